@@ -8,9 +8,28 @@ use std::time::Duration;
 use leptos::*;
 use logging::{log, warn};
 use r_ecipe_s_frontend::api::*;
+use r_ecipe_s_frontend::collections_ls::*;
+use r_ecipe_s_frontend::cook_log_ls::*;
+use r_ecipe_s_frontend::cost_ls::*;
 use r_ecipe_s_frontend::form_component_ls::*;
+use r_ecipe_s_frontend::image_ls::*;
+use r_ecipe_s_frontend::meal_plan_ls::*;
+use r_ecipe_s_frontend::nutrition_ls::*;
+use r_ecipe_s_frontend::pantry_ls::*;
+use r_ecipe_s_frontend::shopping_ls::*;
+use r_ecipe_s_frontend::util::linked_recipe_id;
+use r_ecipe_s_model::cook_log::RecipeOrder;
+use r_ecipe_s_model::diet::{Allergen, Diet, DietaryFilter, DietaryFlags};
+use r_ecipe_s_model::equipment::OVEN;
+use r_ecipe_s_model::image::ImageVariant;
+use r_ecipe_s_model::timing::Timing;
+use r_ecipe_s_model::user::Credentials;
 use r_ecipe_s_model::Recipe;
 
+/// How often the session is refreshed while the app is open, well within the
+/// lifetime of an access token.
+const SESSION_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 fn main() {
     _ = console_log::init_with_level(log::Level::Debug);
     console_error_panic_hook::set_once();
@@ -109,6 +128,13 @@ fn NavBar(
     minilm_action: MiniLmAction,
     api_key: Signal<Option<String>>,
     set_api_key: WriteSignal<Option<String>>,
+    set_refresh_token: WriteSignal<Option<String>>,
+    tag: ReadSignal<Option<String>>,
+    dietary: ReadSignal<DietaryFilter>,
+    max_minutes: ReadSignal<Option<u32>>,
+    without_equipment: ReadSignal<Vec<String>>,
+    section: ReadSignal<Section>,
+    set_section: WriteSignal<Section>,
 ) -> impl IntoView {
     //let spawn_minilm = move || set_minilm.set(Some(spawn_minilm(&origin.get_untracked())));
 
@@ -144,6 +170,10 @@ fn NavBar(
             let vector = get_embedding(Some(minilm), query.clone()).await;
             let x = search(
                 &query,
+                tag.get_untracked().as_deref(),
+                &dietary.get_untracked(),
+                max_minutes.get_untracked(),
+                &without_equipment.get_untracked(),
                 vector.as_ref().map(<Vec<f32> as AsRef<[f32]>>::as_ref),
             )
             .await
@@ -154,8 +184,6 @@ fn NavBar(
     });
     let (searching, set_searching) = create_signal(false);
 
-    let (api_input, set_api_input) = create_signal(false);
-
     let search_view = move || {
         if searching.get() {
             if search_action.pending().get() {
@@ -213,19 +241,16 @@ fn NavBar(
                     </summary>
                     <ul class="dropdown-content bg-base-100 border border-base-content rounded-box w-52 shadow-md shadow-base-300 ">
                     <li>
-                        <button class="btn-sm" on:click = move |_| set_api_input.update(|api_input| *api_input = !*api_input)>
-                            Set API Key
-                        </button>
-                        {
-                            move|| { api_input.get().then(move || {
-                                view! {<input class = "input input-sm input-bordered w-48 ml-2" on:input= move |event| {
-                                        let api_key = event_target_value(&event);
-                                        set_api_key.set(Some(api_key))
-                                    } value = {api_key.get_untracked().unwrap_or_default()}/>
-                                }
-                            })}
-                        }
+                        <SignIn api_key set_api_key set_refresh_token/>
                     </li>
+                    {Section::ALL.into_iter().map(|to| view! {
+                        <li>
+                            <button class="btn-sm" class:active = move || section.get() == to on:click = move |_| set_section.set(to)>
+                                {to.label()}
+                            </button>
+                        </li>
+                    })
+                    .collect_view()}
                     <li>
 
             {
@@ -312,6 +337,35 @@ use leptos_use::storage::use_local_storage;
 fn App() -> impl IntoView {
     let (ai_pref, set_ai_pref, _) = use_local_storage("use_ai", false);
     let (api_key, set_api_key, _) = use_local_storage::<Option<String>, _>("api_key", None);
+    let (refresh_token, set_refresh_token, _) =
+        use_local_storage::<Option<String>, _>("refresh_token", None);
+    // Access tokens expire soon after signing in, so they are swapped for new
+    // ones on opening the app and every so often while it is open.
+    let refresh_action = create_action(move |_: &()| {
+        let refresh_token = refresh_token.get_untracked();
+        async move {
+            let Some(refresh_token) = refresh_token else {
+                return;
+            };
+            match refresh_session(&refresh_token).await {
+                Ok(session) => {
+                    set_api_key.set(Some(session.access_token));
+                    set_refresh_token.set(Some(session.refresh_token));
+                }
+                Err(err @ r_ecipe_s_frontend::api::Error::Http(_)) => {
+                    warn!("Session ended: {err}");
+                    set_api_key.set(None);
+                    set_refresh_token.set(None);
+                }
+                Err(err) => warn!("Failed to refresh session: {err}"),
+            }
+        }
+    });
+    refresh_action.dispatch(());
+    set_interval(
+        move || refresh_action.dispatch(()),
+        SESSION_REFRESH_INTERVAL,
+    );
     let (edit, edit_set) = create_signal(EditModal { state: None });
     let window = web_sys::window().expect("Must be in a windowed i.e. browser setting (You'r not trying to run this in a wasm runtime are you?)");
     let location = window.location();
@@ -351,15 +405,49 @@ fn App() -> impl IntoView {
     minilm_action.dispatch(ai_pref.get());
     provide_context(minilm_action);
 
+    let (tag, set_tag) = create_signal(None::<String>);
+    let (dietary, set_dietary) = create_signal(DietaryFilter::default());
+    let (order, set_order) = create_signal(RecipeOrder::default());
+    let (not_cooked_days, set_not_cooked_days) = create_signal(None::<i64>);
+    let (max_minutes, set_max_minutes) = create_signal(None::<u32>);
+    let (without_equipment, set_without_equipment) = create_signal(Vec::<String>::new());
+    let (section, set_section) = create_signal(Section::Recipes);
+    let open_recipe = move |RecipeWithId { id, data }: RecipeWithId| {
+        edit_set.set(EditModal {
+            state: Some((id, false, Either::Left(data))),
+        })
+    };
+    if let Some(id) = linked_recipe_id() {
+        spawn_local(async move {
+            match get_recipe(id).await {
+                Ok(recipe) => open_recipe(recipe),
+                Err(err) => warn!("Failed to open linked recipe {id}: {err}"),
+            }
+        });
+    }
     let get_page_action = create_action(move |offset| {
         let offset = *offset;
+        let tag = tag.get_untracked();
+        let dietary = dietary.get_untracked();
+        let order = order.get_untracked();
+        let not_cooked_days = not_cooked_days.get_untracked();
+        let max_minutes = max_minutes.get_untracked();
+        let without_equipment = without_equipment.get_untracked();
         async move {
             log!("Getting a page");
             (
                 offset,
-                get_recipes_at_offset(offset)
-                    .await
-                    .map_err(|err| Error::Msg(format!("{err}"))),
+                get_recipes_at_offset(
+                    offset,
+                    tag.as_deref(),
+                    &dietary,
+                    order,
+                    not_cooked_days,
+                    max_minutes,
+                    &without_equipment,
+                )
+                .await
+                .map_err(|err| Error::Msg(format!("{err}"))),
             )
         }
     });
@@ -396,14 +484,333 @@ fn App() -> impl IntoView {
             {move || {
                 let page = get_page_action.value().get();
                 page.map(|(offset, page)|{ view! {
-                    <NavBar offset get_page_action set_edit = edit_set set_ai_pref minilm_action set_api_key api_key/>
-                    <ErrorRecipes offset = offset refresh_action = get_page_action page edit_modal = edit_set api_key/>
+                    <NavBar offset get_page_action set_edit = edit_set set_ai_pref minilm_action set_api_key set_refresh_token api_key tag dietary max_minutes without_equipment section set_section/>
+                    {move || match section.get() {
+                        Section::Recipes => {
+                            let page = page.clone();
+                            view! {
+                                <TagFilter tag set_tag get_page_action/>
+                                <DietFilter dietary set_dietary get_page_action/>
+                                <OrderFilter order set_order not_cooked_days set_not_cooked_days get_page_action/>
+                                <TimeFilter max_minutes set_max_minutes get_page_action/>
+                                <EquipmentFilter without_equipment set_without_equipment get_page_action/>
+                                <ErrorRecipes offset = offset refresh_action = get_page_action page edit_modal = edit_set api_key/>
+                            }.into_view()
+                        }
+                        Section::Collections => view! { <Collections api_key open_recipe/> }.into_view(),
+                        Section::MealPlan => view! { <MealPlanner api_key open_recipe/> }.into_view(),
+                        Section::Shopping => view! { <ShoppingLists api_key/> }.into_view(),
+                        Section::Pantry => view! { <Pantry api_key open_recipe/> }.into_view(),
+                    }}
                 }})
             }}
         </div>
     </div>
         }
 }
+/// Chips for every tag in use, limiting the listed recipes to the chosen tag.
+#[component]
+fn TagFilter(
+    tag: ReadSignal<Option<String>>,
+    set_tag: WriteSignal<Option<String>>,
+    get_page_action: Action<i64, (i64, Result<RecipesResponse, Error>)>,
+) -> impl IntoView {
+    let tags = create_resource(
+        move || get_page_action.version().get(),
+        |_| async move { get_tags().await.unwrap_or_default() },
+    );
+    let choose = move |chosen: Option<String>| {
+        set_tag.set(chosen);
+        get_page_action.dispatch(0);
+    };
+    let chip_class = move |selected: bool| {
+        if selected {
+            "badge badge-primary cursor-pointer"
+        } else {
+            "badge badge-outline cursor-pointer"
+        }
+    };
+
+    view! {
+        <div class = "flex flex-wrap gap-1 my-2">
+            <span class = {chip_class(tag.get_untracked().is_none())} on:click = move |_| choose(None)>
+                "all"
+            </span>
+            {move || tags.get().unwrap_or_default().into_iter()
+                .filter(|TagWithId { recipe_count, .. }| *recipe_count > 0)
+                .map(|TagWithId { data: Tag { name }, recipe_count, .. }| {
+                    let selected = tag.get_untracked().as_deref() == Some(name.as_str());
+                    let chosen = name.clone();
+                    view! {
+                        <span class = {chip_class(selected)} on:click = move |_| choose(Some(chosen.clone()))>
+                            {format!("{name} ({recipe_count})")}
+                        </span>
+                    }
+                })
+                .collect_view()
+            }
+        </div>
+    }
+}
+
+/// Chips limiting the listed and searched recipes to a diet, and to those
+/// free of the chosen allergens.
+#[component]
+fn DietFilter(
+    dietary: ReadSignal<DietaryFilter>,
+    set_dietary: WriteSignal<DietaryFilter>,
+    get_page_action: Action<i64, (i64, Result<RecipesResponse, Error>)>,
+) -> impl IntoView {
+    let change = move |change: &dyn Fn(&mut DietaryFilter)| {
+        set_dietary.update(|dietary| change(dietary));
+        get_page_action.dispatch(0);
+    };
+    let chip_class = move |selected: bool| {
+        if selected {
+            "badge badge-secondary cursor-pointer"
+        } else {
+            "badge badge-outline cursor-pointer"
+        }
+    };
+
+    view! {
+        <div class = "flex flex-wrap gap-1 my-2">
+            {Diet::ALL.into_iter().map(|diet| view! {
+                <span
+                    class = move || chip_class(dietary.get().diet == Some(diet))
+                    on:click = move |_| change(&|dietary| {
+                        dietary.diet = (dietary.diet != Some(diet)).then_some(diet);
+                    })
+                >
+                    {diet.to_string()}
+                </span>
+            })
+            .collect_view()}
+            {Allergen::ALL.into_iter().map(|allergen| view! {
+                <span
+                    class = move || chip_class(dietary.get().free_of.contains(&allergen))
+                    on:click = move |_| change(&|dietary| {
+                        if dietary.free_of.contains(&allergen) {
+                            dietary.free_of.retain(|free_of| *free_of != allergen);
+                        } else {
+                            dietary.free_of.push(allergen);
+                        }
+                    })
+                >
+                    {format!("no {allergen}")}
+                </span>
+            })
+            .collect_view()}
+        </div>
+    }
+}
+
+/// Days without cooking a recipe that make it one not cooked lately.
+const NOT_COOKED_DAYS: i64 = 90;
+
+/// Chips sorting the listed recipes, and limiting them to those not cooked
+/// in the last three months.
+#[component]
+fn OrderFilter(
+    order: ReadSignal<RecipeOrder>,
+    set_order: WriteSignal<RecipeOrder>,
+    not_cooked_days: ReadSignal<Option<i64>>,
+    set_not_cooked_days: WriteSignal<Option<i64>>,
+    get_page_action: Action<i64, (i64, Result<RecipesResponse, Error>)>,
+) -> impl IntoView {
+    let chip_class = move |selected: bool| {
+        if selected {
+            "badge badge-accent cursor-pointer"
+        } else {
+            "badge badge-outline cursor-pointer"
+        }
+    };
+
+    view! {
+        <div class = "flex flex-wrap gap-1 my-2">
+            {RecipeOrder::ALL.into_iter().map(|option| view! {
+                <span
+                    class = move || chip_class(order.get() == option)
+                    on:click = move |_| {
+                        set_order.set(option);
+                        get_page_action.dispatch(0);
+                    }
+                >
+                    {option.label()}
+                </span>
+            })
+            .collect_view()}
+            <span
+                class = move || chip_class(not_cooked_days.get().is_some())
+                on:click = move |_| {
+                    set_not_cooked_days.update(|days| {
+                        *days = days.is_none().then_some(NOT_COOKED_DAYS);
+                    });
+                    get_page_action.dispatch(0);
+                }
+            >
+                "not cooked in 3 months"
+            </span>
+        </div>
+    }
+}
+
+/// Signs in with a username and password, or out again.
+#[component]
+fn SignIn(
+    api_key: Signal<Option<String>>,
+    set_api_key: WriteSignal<Option<String>>,
+    set_refresh_token: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let (open, set_open) = create_signal(false);
+    let (username, set_username) = create_signal(String::new());
+    let (password, set_password) = create_signal(String::new());
+    let (failed, set_failed) = create_signal(None::<String>);
+    let sign_in = create_action(move |credentials: &Credentials| {
+        let credentials = credentials.clone();
+        async move {
+            match login(&credentials).await {
+                Ok(session) => {
+                    set_api_key.set(Some(session.access_token));
+                    set_refresh_token.set(Some(session.refresh_token));
+                    set_password.set(String::new());
+                    set_failed.set(None);
+                    set_open.set(false);
+                }
+                Err(err) => set_failed.set(Some(format!("{err}"))),
+            }
+        }
+    });
+    let sign_out = create_action(move |_: &()| {
+        let api_key = api_key.get_untracked();
+        async move {
+            if let Err(err) = logout(api_key.as_deref()).await {
+                warn!("Failed to sign out: {err}");
+            }
+            set_api_key.set(None);
+            set_refresh_token.set(None);
+        }
+    });
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        sign_in.dispatch(Credentials {
+            username: username.get_untracked(),
+            password: password.get_untracked(),
+        });
+    };
+
+    move || {
+        if api_key.get().is_some() {
+            view! {
+                <button class="btn-sm" on:click = move |_| sign_out.dispatch(())>
+                    Sign out
+                </button>
+            }
+            .into_view()
+        } else {
+            view! {
+                <button class="btn-sm" on:click = move |_| set_open.update(|open| *open = !*open)>
+                    Sign in
+                </button>
+                {move || open.get().then(|| view! {
+                    <form class = "grid gap-1 ml-2" on:submit = on_submit>
+                        <input
+                            class = "input input-sm input-bordered w-48"
+                            placeholder = "username"
+                            autocomplete = "username"
+                            prop:value = username
+                            on:input = move |ev| set_username.set(event_target_value(&ev))
+                        />
+                        <input
+                            type = "password"
+                            class = "input input-sm input-bordered w-48"
+                            placeholder = "password"
+                            autocomplete = "current-password"
+                            prop:value = password
+                            on:input = move |ev| set_password.set(event_target_value(&ev))
+                        />
+                        <button type = "submit" class = "btn btn-sm btn-primary w-48">
+                            {move || if sign_in.pending().get() { "Signing in…" } else { "Sign in" }}
+                        </button>
+                        {move || failed.get().map(|err| view! {
+                            <span class = "text-error text-xs">{err}</span>
+                        })}
+                    </form>
+                })}
+            }
+            .into_view()
+        }
+    }
+}
+
+/// Total times, in minutes, the listed recipes can be limited to.
+const TIME_LIMITS: [u32; 3] = [15, 30, 60];
+
+/// Chips limiting the listed and searched recipes to those quick enough.
+#[component]
+fn TimeFilter(
+    max_minutes: ReadSignal<Option<u32>>,
+    set_max_minutes: WriteSignal<Option<u32>>,
+    get_page_action: Action<i64, (i64, Result<RecipesResponse, Error>)>,
+) -> impl IntoView {
+    view! {
+        <div class = "flex flex-wrap gap-1 my-2">
+            {TIME_LIMITS.into_iter().map(|limit| view! {
+                <span
+                    class = move || if max_minutes.get() == Some(limit) {
+                        "badge badge-accent cursor-pointer"
+                    } else {
+                        "badge badge-outline cursor-pointer"
+                    }
+                    on:click = move |_| {
+                        set_max_minutes.update(|minutes| {
+                            *minutes = (*minutes != Some(limit)).then_some(limit);
+                        });
+                        get_page_action.dispatch(0);
+                    }
+                >
+                    {format!("under {limit} min")}
+                </span>
+            })
+            .collect_view()}
+        </div>
+    }
+}
+
+/// Chip limiting the listed and searched recipes to those needing no oven.
+#[component]
+fn EquipmentFilter(
+    without_equipment: ReadSignal<Vec<String>>,
+    set_without_equipment: WriteSignal<Vec<String>>,
+    get_page_action: Action<i64, (i64, Result<RecipesResponse, Error>)>,
+) -> impl IntoView {
+    let without_oven =
+        move || without_equipment.with(|names| names.iter().any(|name| name == OVEN));
+    view! {
+        <div class = "flex flex-wrap gap-1 my-2">
+            <span
+                class = move || if without_oven() {
+                    "badge badge-accent cursor-pointer"
+                } else {
+                    "badge badge-outline cursor-pointer"
+                }
+                on:click = move |_| {
+                    set_without_equipment.update(|names| {
+                        if names.iter().any(|name| name == OVEN) {
+                            names.retain(|name| name != OVEN);
+                        } else {
+                            names.push(OVEN.to_string());
+                        }
+                    });
+                    get_page_action.dispatch(0);
+                }
+            >
+                "no oven"
+            </span>
+        </div>
+    }
+}
+
 #[component]
 fn TopBar() -> impl IntoView {
     view! {
@@ -444,6 +851,7 @@ fn ErrorRecipes<B: Clone + 'static>(
 use futures_timer::Delay;
 use r_ecipe_s_model::RecipeWithId;
 use r_ecipe_s_model::RecipesResponse;
+use r_ecipe_s_model::{Tag, TagWithId};
 
 #[component]
 pub fn RecipeView<B: Clone + 'static>(
@@ -551,7 +959,17 @@ pub fn RecipeView<B: Clone + 'static>(
                     }
                }
             </div>
-            {button_message}
+            <div class = "flex items-center gap-2">
+                {button_message}
+                <AddToCollection recipe_id = id api_key/>
+                <PlanMeal recipe_id = id api_key/>
+                <ShopForRecipe recipe_id = id api_key/>
+                <CookedThis recipe_id = id api_key/>
+            </div>
+            <RecipeImages recipe_id = id api_key/>
+            <RecipeNutritionPanel recipe_id = id api_key/>
+            <RecipeCostPanel recipe_id = id/>
+            <CookLogPanel recipe_id = id api_key/>
         </div>
     }
 }
@@ -563,6 +981,36 @@ fn Pending() -> impl IntoView {
     }
 }
 
+/// Part of the app shown below the navigation bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Recipes,
+    Collections,
+    MealPlan,
+    Shopping,
+    Pantry,
+}
+
+impl Section {
+    const ALL: [Section; 5] = [
+        Section::Recipes,
+        Section::Collections,
+        Section::MealPlan,
+        Section::Shopping,
+        Section::Pantry,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Section::Recipes => "Recipes",
+            Section::Collections => "Collections",
+            Section::MealPlan => "Meal plan",
+            Section::Shopping => "Shopping",
+            Section::Pantry => "Pantry",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct EditModal {
     state: Option<(
@@ -579,6 +1027,12 @@ async fn put_recipe_action(api_key: Option<&str>) -> Result<RecipeWithId, Error>
         description: "".into(),
         liked: None,
         embedding: None,
+        servings: None,
+        tags: vec![],
+        dietary: DietaryFlags::default(),
+        images: Vec::new(),
+        timing: Timing::default(),
+        equipment: Vec::new(),
     };
     // todo: remove delay
     Delay::new(Duration::from_secs(1)).await;
@@ -603,9 +1057,10 @@ fn Recipes<B: Clone + 'static>(
         recipes
             .into_iter()
             .map(move |RecipeWithId { id, data: recipe }| {
+                let cover = recipe.images.first().copied();
                 let (read_state, write_state) = RecipeState::state();
                 write_state.set(recipe);
-                (id, read_state, write_state)
+                (id, cover, read_state, write_state)
             })
             .collect::<Vec<_>>(),
     );
@@ -614,14 +1069,22 @@ fn Recipes<B: Clone + 'static>(
         <div class = "grid md:grid-cols-2 gap-4 lg:grid-cols-3 sm:grid-cols-1">
             <For
                 each = move || {recipes.get()}
-                key = move |(idx, _, _)| *idx
-                children = move | (id, read_state, write_state)| {
+                key = move |(idx, _, _, _)| *idx
+                children = move | (id, cover, read_state, write_state)| {
                     let click_action = move || {
                         log!("Time to expand");
                         edit_modal.update(|modal| modal.state = Some((id, false, Either::Right((read_state, write_state)))));
                     };
                     view! {
                         <div class = "relative">
+                            {cover.map(|image_id| view! {
+                                <img
+                                    class = "rounded-t-box w-full h-40 object-cover"
+                                    src = recipe_image_url(id, image_id, ImageVariant::Thumbnail)
+                                    loading = "lazy"
+                                    on:dblclick = move |_| click_action()
+                                />
+                            })}
                             <Recipe read_state on:dblclick = move |_| click_action() focus = {false}/>
                             <Delete id offset refresh_action set_recipes api_key/>
                         </div>
@@ -706,7 +1169,7 @@ fn Delete<B: Clone + 'static>(
     id: i64,
     offset: i64,
     refresh_action: Action<i64, B>,
-    set_recipes: WriteSignal<Vec<(i64, RecipeReadState, RecipeWriteState)>>,
+    set_recipes: WriteSignal<Vec<(i64, Option<i64>, RecipeReadState, RecipeWriteState)>>,
     api_key: Signal<Option<String>>,
 ) -> impl IntoView {
     use DeleteStates as Ds;
@@ -745,7 +1208,7 @@ fn Delete<B: Clone + 'static>(
                     set_confirming.set(Ds::Deleted);
                     refresh_action.dispatch(offset);
                     set_recipes
-                        .update(|recipes| recipes.retain(|(recipe_id, _, _)| *recipe_id != id));
+                        .update(|recipes| recipes.retain(|(recipe_id, _, _, _)| *recipe_id != id));
                     view! {
                         <DeleteButton />
                     }
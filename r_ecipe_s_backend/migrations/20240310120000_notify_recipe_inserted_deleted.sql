@@ -0,0 +1,23 @@
+-- New recipes are indexed the same way as updated ones
+CREATE TRIGGER notify_recipe_inserted
+  AFTER INSERT ON recipes
+  FOR EACH ROW
+  WHEN (NEW.searchable = false)
+  EXECUTE PROCEDURE notify_recipe_updated();
+
+-- Deleted recipes are removed from the search indexes
+CREATE OR REPLACE FUNCTION notify_recipe_deleted()
+  RETURNS trigger AS $$
+DECLARE
+BEGIN
+  PERFORM pg_notify(
+    CAST('search_index' AS text),
+    OLD.id::text);
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notify_recipe_deleted
+  AFTER DELETE ON recipes
+  FOR EACH ROW
+  EXECUTE PROCEDURE notify_recipe_deleted();
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS tags
+(
+    id   BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE CHECK (name <> '')
+);
+
+CREATE TABLE IF NOT EXISTS recipe_tags
+(
+    recipe_id BIGINT NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    tag_id    BIGINT NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (recipe_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS recipe_tags_tag_id ON recipe_tags (tag_id);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS collections
+(
+    id          BIGSERIAL PRIMARY KEY,
+    name        TEXT        NOT NULL CHECK (name <> ''),
+    description TEXT        NOT NULL DEFAULT '',
+    created     timestamptz NOT NULL DEFAULT NOW(),
+    updated     timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS collection_recipes
+(
+    collection_id BIGINT NOT NULL REFERENCES collections (id) ON DELETE CASCADE,
+    recipe_id     BIGINT NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    position      BIGINT NOT NULL,
+    note          TEXT,
+    PRIMARY KEY (collection_id, recipe_id)
+);
+
+CREATE INDEX IF NOT EXISTS collection_recipes_recipe_id ON collection_recipes (recipe_id);
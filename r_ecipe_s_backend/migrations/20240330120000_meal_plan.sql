@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS meal_plan
+(
+    id          BIGSERIAL PRIMARY KEY,
+    date        DATE    NOT NULL,
+    slot        TEXT    NOT NULL CHECK (slot IN ('breakfast', 'lunch', 'dinner', 'snack')),
+    -- Kept when the recipe is deleted, with the name it had, so past plans
+    -- still read sensibly.
+    recipe_id   BIGINT REFERENCES recipes (id) ON DELETE SET NULL,
+    recipe_name TEXT    NOT NULL,
+    servings    INTEGER CHECK (servings > 0),
+    note        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS meal_plan_date ON meal_plan (date);
+CREATE INDEX IF NOT EXISTS meal_plan_recipe_id ON meal_plan (recipe_id);
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS shopping_lists
+(
+    id      BIGSERIAL PRIMARY KEY,
+    name    TEXT        NOT NULL CHECK (name <> ''),
+    created timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS shopping_list_items
+(
+    id               BIGSERIAL PRIMARY KEY,
+    shopping_list_id BIGINT  NOT NULL REFERENCES shopping_lists (id) ON DELETE CASCADE,
+    position         BIGINT  NOT NULL,
+    name             TEXT    NOT NULL,
+    -- Amounts that couldn't be added up, as serialised quantities.
+    quantities       JSONB   NOT NULL DEFAULT '[]',
+    aisle            TEXT    NOT NULL,
+    checked          BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS shopping_list_items_list_id ON shopping_list_items (shopping_list_id, position);
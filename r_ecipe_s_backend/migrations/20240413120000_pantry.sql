@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS pantry_items
+(
+    id       BIGSERIAL PRIMARY KEY,
+    name     TEXT        NOT NULL CHECK (name <> ''),
+    -- A serialised quantity.
+    quantity JSONB       NOT NULL,
+    expires  DATE,
+    created  timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS pantry_items_expires ON pantry_items (expires);
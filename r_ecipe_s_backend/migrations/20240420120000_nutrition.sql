@@ -0,0 +1,128 @@
+-- Nutrients per 100 g of a food. Energy is in kcal, sodium in mg and the
+-- rest in grams.
+CREATE TABLE IF NOT EXISTS foods
+(
+    id          BIGSERIAL PRIMARY KEY,
+    name        TEXT             NOT NULL UNIQUE CHECK (name <> ''),
+    -- Other names the food goes by in recipes.
+    aliases     TEXT[]           NOT NULL DEFAULT '{}',
+    kcal        DOUBLE PRECISION NOT NULL,
+    protein     DOUBLE PRECISION NOT NULL,
+    fat         DOUBLE PRECISION NOT NULL,
+    carbs       DOUBLE PRECISION NOT NULL,
+    fibre       DOUBLE PRECISION NOT NULL,
+    sodium      DOUBLE PRECISION NOT NULL,
+    -- Grams per millilitre, for foods measured by volume.
+    density     DOUBLE PRECISION,
+    -- Grams in one piece, for foods counted.
+    unit_weight DOUBLE PRECISION
+);
+
+-- Foods chosen by hand for ingredient names the table doesn't match.
+CREATE TABLE IF NOT EXISTS ingredient_foods
+(
+    -- Lowercase, with whitespace collapsed.
+    ingredient TEXT PRIMARY KEY CHECK (ingredient <> ''),
+    food_id    BIGINT NOT NULL REFERENCES foods (id) ON DELETE CASCADE
+);
+
+-- Common ingredients, approximated from USDA FoodData Central.
+INSERT INTO foods (name, aliases, kcal, protein, fat, carbs, fibre, sodium, density, unit_weight)
+VALUES ('flour', '{plain flour,all-purpose flour,wheat flour}', 364, 10.3, 1.0, 76.3, 2.7, 2, 0.53, NULL),
+       ('sugar', '{caster sugar,granulated sugar,white sugar}', 387, 0, 0, 100, 0, 1, 0.85, NULL),
+       ('brown sugar', '{}', 380, 0.1, 0, 98.1, 0, 28, 0.93, NULL),
+       ('icing sugar', '{powdered sugar,confectioners sugar}', 389, 0, 0, 99.8, 0, 2, 0.56, NULL),
+       ('honey', '{}', 304, 0.3, 0, 82.4, 0.2, 4, 1.42, NULL),
+       ('maple syrup', '{}', 260, 0, 0.1, 67.0, 0, 12, 1.33, NULL),
+       ('butter', '{unsalted butter}', 717, 0.9, 81.1, 0.1, 0, 11, 0.96, NULL),
+       ('olive oil', '{}', 884, 0, 100, 0, 0, 2, 0.92, NULL),
+       ('vegetable oil', '{oil,sunflower oil,canola oil,rapeseed oil}', 884, 0, 100, 0, 0, 0, 0.92, NULL),
+       ('milk', '{whole milk}', 61, 3.2, 3.3, 4.8, 0, 43, 1.03, NULL),
+       ('cream', '{heavy cream,double cream,whipping cream}', 340, 2.8, 36.1, 2.7, 0, 27, 1.0, NULL),
+       ('sour cream', '{}', 198, 2.4, 19.4, 4.6, 0, 31, 1.0, NULL),
+       ('yogurt', '{yoghurt,plain yogurt,greek yogurt}', 61, 3.5, 3.3, 4.7, 0, 46, 1.03, NULL),
+       ('cream cheese', '{}', 342, 5.9, 34.2, 4.1, 0, 321, 1.0, NULL),
+       ('cheddar', '{cheddar cheese}', 403, 24.9, 33.1, 1.3, 0, 621, 0.45, NULL),
+       ('parmesan', '{parmesan cheese,parmigiano reggiano}', 392, 35.8, 25.8, 3.2, 0, 1376, 0.4, NULL),
+       ('mozzarella', '{}', 300, 22.2, 22.4, 2.2, 0, 627, 0.45, NULL),
+       ('feta', '{feta cheese}', 264, 14.2, 21.3, 4.1, 0, 1116, 0.6, NULL),
+       ('egg', '{eggs,large egg}', 143, 12.6, 9.5, 0.7, 0, 142, 1.03, 50),
+       ('salt', '{sea salt,table salt}', 0, 0, 0, 0, 0, 38758, 1.2, NULL),
+       ('black pepper', '{pepper,ground black pepper}', 251, 10.4, 3.3, 64.0, 25.3, 20, 0.5, NULL),
+       ('baking powder', '{}', 53, 0, 0, 27.7, 0.2, 10600, 0.9, NULL),
+       ('baking soda', '{bicarbonate of soda}', 0, 0, 0, 0, 0, 27360, 1.1, NULL),
+       ('yeast', '{dry yeast,instant yeast}', 325, 40.4, 7.6, 41.2, 26.9, 51, 0.6, NULL),
+       ('cornflour', '{cornstarch,corn starch}', 381, 0.3, 0.1, 91.3, 0.9, 9, 0.6, NULL),
+       ('cocoa', '{cocoa powder}', 228, 19.6, 13.7, 57.9, 37.0, 21, 0.42, NULL),
+       ('dark chocolate', '{chocolate}', 598, 7.8, 42.6, 45.9, 10.9, 20, NULL, NULL),
+       ('vanilla extract', '{vanilla}', 288, 0.1, 0.1, 12.7, 0, 9, 0.88, NULL),
+       ('cinnamon', '{ground cinnamon}', 247, 4.0, 1.2, 80.6, 53.1, 10, 0.56, NULL),
+       ('cumin', '{ground cumin}', 375, 17.8, 22.3, 44.2, 10.5, 168, 0.5, NULL),
+       ('paprika', '{smoked paprika}', 282, 14.1, 12.9, 54.0, 34.9, 68, 0.46, NULL),
+       ('mustard', '{}', 60, 3.7, 3.3, 5.8, 4.0, 1104, 1.05, NULL),
+       ('mayonnaise', '{}', 680, 1.0, 74.9, 0.6, 0, 635, 0.91, NULL),
+       ('soy sauce', '{}', 53, 8.1, 0.6, 4.9, 0.8, 5493, 1.15, NULL),
+       ('vinegar', '{}', 21, 0, 0, 0.9, 0, 5, 1.01, NULL),
+       ('tomato paste', '{tomato puree}', 82, 4.3, 0.5, 18.9, 4.1, 59, 1.1, NULL),
+       ('stock', '{broth,chicken stock,vegetable stock,beef stock}', 7, 1.1, 0.2, 0.4, 0, 343, 1.0, NULL),
+       ('water', '{}', 0, 0, 0, 0, 0, 0, 1.0, NULL),
+       ('coconut milk', '{}', 197, 2.0, 21.3, 2.8, 0, 13, 0.97, NULL),
+       ('peanut butter', '{}', 588, 25.1, 50.4, 19.6, 6.0, 459, 1.08, NULL),
+       ('almonds', '{almond}', 579, 21.2, 49.9, 21.6, 12.5, 1, 0.6, NULL),
+       ('walnuts', '{walnut}', 654, 15.2, 65.2, 13.7, 6.7, 2, 0.5, NULL),
+       ('raisins', '{raisin,sultanas}', 299, 3.1, 0.5, 79.2, 3.7, 11, 0.6, NULL),
+       ('oats', '{rolled oats,porridge oats}', 379, 13.2, 6.5, 67.7, 10.1, 6, 0.41, NULL),
+       ('rice', '{white rice,basmati rice,jasmine rice}', 365, 7.1, 0.7, 80.0, 1.3, 5, 0.85, NULL),
+       ('pasta', '{spaghetti,penne,macaroni,fusilli}', 371, 13.0, 1.5, 74.7, 3.2, 6, NULL, NULL),
+       ('egg noodles', '{noodles}', 384, 14.2, 4.4, 71.3, 3.3, 21, NULL, NULL),
+       ('couscous', '{}', 376, 12.8, 0.6, 77.4, 5.0, 10, 0.75, NULL),
+       ('quinoa', '{}', 368, 14.1, 6.1, 64.2, 7.0, 5, 0.72, NULL),
+       ('lentils', '{lentil,red lentils}', 352, 24.6, 1.1, 63.4, 10.7, 6, 0.8, NULL),
+       ('chickpeas', '{chickpea,garbanzo beans}', 139, 7.0, 2.8, 22.5, 6.5, 246, 0.65, NULL),
+       ('black beans', '{kidney beans}', 91, 6.0, 0.3, 16.6, 6.9, 140, 0.7, NULL),
+       ('bread', '{}', 266, 8.9, 3.3, 49.4, 2.7, 491, NULL, 30),
+       ('tortilla', '{flour tortilla,wrap}', 304, 8.0, 7.5, 50.0, 3.5, 600, NULL, 45),
+       ('potato', '{}', 77, 2.0, 0.1, 17.5, 2.2, 6, NULL, 213),
+       ('sweet potato', '{}', 86, 1.6, 0.1, 20.1, 3.0, 55, NULL, 130),
+       ('onion', '{}', 40, 1.1, 0.1, 9.3, 1.7, 4, 0.5, 110),
+       ('spring onion', '{scallion,green onion}', 32, 1.8, 0.2, 7.3, 2.6, 16, NULL, 15),
+       ('leek', '{}', 61, 1.5, 0.3, 14.2, 1.8, 20, NULL, 89),
+       ('garlic', '{garlic clove}', 149, 6.4, 0.5, 33.1, 2.1, 17, 0.6, 3),
+       ('ginger', '{fresh ginger}', 80, 1.8, 0.8, 17.8, 2.0, 13, 0.6, NULL),
+       ('carrot', '{}', 41, 0.9, 0.2, 9.6, 2.8, 69, 0.55, 61),
+       ('celery', '{celery stalk}', 14, 0.7, 0.2, 3.0, 1.6, 80, NULL, 40),
+       ('tomato', '{}', 18, 0.9, 0.2, 3.9, 1.2, 5, NULL, 123),
+       ('tinned tomatoes', '{canned tomatoes,chopped tomatoes,crushed tomatoes}', 32, 1.6, 0.3, 7.3, 1.9, 132, 1.03, NULL),
+       ('bell pepper', '{red pepper,green pepper,yellow pepper}', 31, 1.0, 0.3, 6.0, 2.1, 4, NULL, 119),
+       ('courgette', '{zucchini}', 17, 1.2, 0.3, 3.1, 1.0, 8, NULL, 196),
+       ('cucumber', '{}', 15, 0.7, 0.1, 3.6, 0.5, 2, NULL, 301),
+       ('mushroom', '{}', 22, 3.1, 0.3, 3.3, 1.0, 5, 0.3, 18),
+       ('spinach', '{}', 23, 2.9, 0.4, 3.6, 2.2, 79, 0.13, NULL),
+       ('kale', '{}', 35, 2.9, 1.5, 4.4, 4.1, 53, 0.1, NULL),
+       ('cabbage', '{}', 25, 1.3, 0.1, 5.8, 2.5, 18, 0.37, NULL),
+       ('lettuce', '{}', 15, 1.4, 0.2, 2.9, 1.3, 28, 0.2, NULL),
+       ('broccoli', '{}', 34, 2.8, 0.4, 6.6, 2.6, 33, 0.38, NULL),
+       ('cauliflower', '{}', 25, 1.9, 0.3, 5.0, 2.0, 30, 0.45, NULL),
+       ('peas', '{pea,frozen peas}', 77, 5.2, 0.4, 13.6, 4.5, 108, 0.6, NULL),
+       ('sweetcorn', '{corn}', 86, 3.3, 1.4, 19.0, 2.7, 15, 0.65, NULL),
+       ('avocado', '{}', 160, 2.0, 14.7, 8.5, 6.7, 7, NULL, 150),
+       ('basil', '{fresh basil}', 23, 3.2, 0.6, 2.7, 1.6, 4, 0.1, NULL),
+       ('parsley', '{fresh parsley}', 36, 3.0, 0.8, 6.3, 3.3, 56, 0.1, NULL),
+       ('coriander', '{cilantro,fresh coriander}', 23, 2.1, 0.5, 3.7, 2.8, 46, 0.1, NULL),
+       ('lemon', '{}', 29, 1.1, 0.3, 9.3, 2.8, 2, NULL, 84),
+       ('lemon juice', '{}', 22, 0.4, 0.2, 6.9, 0.3, 1, 1.03, NULL),
+       ('lime', '{}', 30, 0.7, 0.2, 10.5, 2.8, 2, NULL, 67),
+       ('apple', '{}', 52, 0.3, 0.2, 13.8, 2.4, 1, NULL, 182),
+       ('banana', '{}', 89, 1.1, 0.3, 22.8, 2.6, 1, NULL, 118),
+       ('chicken', '{whole chicken}', 215, 18.6, 15.1, 0, 0, 70, NULL, NULL),
+       ('chicken breast', '{chicken breast fillet}', 120, 22.5, 2.6, 0, 0, 45, NULL, 174),
+       ('chicken thigh', '{}', 119, 19.7, 3.9, 0, 0, 86, NULL, 114),
+       ('beef mince', '{ground beef,minced beef}', 254, 17.2, 20.0, 0, 0, 66, NULL, NULL),
+       ('pork', '{pork loin}', 143, 21.2, 5.7, 0, 0, 50, NULL, NULL),
+       ('bacon', '{}', 417, 12.6, 39.7, 1.4, 0, 833, NULL, 28),
+       ('sausage', '{pork sausage}', 268, 13.0, 23.0, 1.5, 0, 750, NULL, 60),
+       ('salmon', '{salmon fillet}', 208, 20.4, 13.4, 0, 0, 59, NULL, 150),
+       ('tuna', '{canned tuna,tinned tuna}', 86, 19.4, 1.0, 0, 0, 247, NULL, NULL),
+       ('prawns', '{prawn,shrimp}', 85, 20.1, 0.5, 0, 0, 119, NULL, 12),
+       ('tofu', '{firm tofu}', 144, 17.3, 8.7, 2.8, 2.3, 14, NULL, NULL)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,8 @@
+-- Worked out from the ingredients whenever a recipe is saved, and for
+-- existing recipes when the server starts.
+ALTER TABLE recipes
+    ADD COLUMN IF NOT EXISTS allergens TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS diets     TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS recipes_allergens ON recipes USING GIN (allergens);
+CREATE INDEX IF NOT EXISTS recipes_diets ON recipes USING GIN (diets);
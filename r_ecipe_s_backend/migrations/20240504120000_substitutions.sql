@@ -0,0 +1,178 @@
+-- What can stand in for an ingredient, and how much of it for how much of
+-- the ingredient.
+CREATE TABLE IF NOT EXISTS substitutions
+(
+    id          BIGSERIAL PRIMARY KEY,
+    -- Lowercase, with whitespace collapsed.
+    ingredient  TEXT        NOT NULL CHECK (ingredient <> ''),
+    -- A serialised quantity of the ingredient.
+    per         JSONB       NOT NULL,
+    -- Serialised ingredients replacing `per` of the ingredient.
+    replacement JSONB       NOT NULL,
+    note        TEXT,
+    created     timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS substitutions_ingredient ON substitutions (ingredient);
+
+-- Common kitchen substitutions.
+INSERT INTO substitutions (ingredient, per, replacement, note)
+VALUES ('egg', '{"Count": 1}',
+        '[{"name": "ground flaxseed", "quantity": {"Tbsp": 1}}, {"name": "water", "quantity": {"Tbsp": 3}}]',
+        'Stir together and leave for 5 minutes to thicken. Best in baking.'),
+       ('egg', '{"Count": 1}',
+        '[{"name": "chia seeds", "quantity": {"Tbsp": 1}}, {"name": "water", "quantity": {"Tbsp": 3}}]',
+        'Leave for 10 minutes to gel.'),
+       ('egg', '{"Count": 1}',
+        '[{"name": "mashed banana", "quantity": {"Cup": 0.25}}]',
+        'Adds sweetness; for cakes and muffins.'),
+       ('egg', '{"Count": 1}',
+        '[{"name": "aquafaba", "quantity": {"Tbsp": 3}}]',
+        'The liquid from a tin of chickpeas. Whips like egg white.'),
+       ('butter', '{"Cup": 1}',
+        '[{"name": "vegetable oil", "quantity": {"Cup": 0.75}}]',
+        'For melted butter; cakes come out denser.'),
+       ('butter', '{"Cup": 1}',
+        '[{"name": "coconut oil", "quantity": {"Cup": 1}}]', NULL),
+       ('butter', '{"Gram": 100}',
+        '[{"name": "vegetable oil", "quantity": {"Ml": 80}}]',
+        'For melted butter; cakes come out denser.'),
+       ('buttermilk', '{"Cup": 1}',
+        '[{"name": "milk", "quantity": {"Cup": 1}}, {"name": "lemon juice", "quantity": {"Tbsp": 1}}]',
+        'Stir the lemon juice into the milk and leave for 5 minutes.'),
+       ('buttermilk', '{"Cup": 1}',
+        '[{"name": "plain yogurt", "quantity": {"Cup": 0.75}}, {"name": "milk", "quantity": {"Cup": 0.25}}]',
+        NULL),
+       ('milk', '{"Cup": 1}',
+        '[{"name": "oat milk", "quantity": {"Cup": 1}}]', NULL),
+       ('milk', '{"Cup": 1}',
+        '[{"name": "water", "quantity": {"Cup": 0.5}}, {"name": "evaporated milk", "quantity": {"Cup": 0.5}}]',
+        NULL),
+       ('double cream', '{"Cup": 1}',
+        '[{"name": "milk", "quantity": {"Cup": 0.75}}, {"name": "butter", "quantity": {"Tbsp": 5}}]',
+        'Melt the butter into the milk. Won''t whip.'),
+       ('heavy cream', '{"Cup": 1}',
+        '[{"name": "milk", "quantity": {"Cup": 0.75}}, {"name": "butter", "quantity": {"Tbsp": 5}}]',
+        'Melt the butter into the milk. Won''t whip.'),
+       ('sour cream', '{"Cup": 1}',
+        '[{"name": "greek yogurt", "quantity": {"Cup": 1}}]', NULL),
+       ('creme fraiche', '{"Cup": 1}',
+        '[{"name": "sour cream", "quantity": {"Cup": 1}}]', NULL),
+       ('self raising flour', '{"Cup": 1}',
+        '[{"name": "plain flour", "quantity": {"Cup": 1}}, {"name": "baking powder", "quantity": {"Tsp": 1.5}}, {"name": "salt", "quantity": {"Tsp": 0.25}}]',
+        NULL),
+       ('self raising flour', '{"Gram": 100}',
+        '[{"name": "plain flour", "quantity": {"Gram": 100}}, {"name": "baking powder", "quantity": {"Tsp": 1}}]',
+        NULL),
+       ('cake flour', '{"Cup": 1}',
+        '[{"name": "plain flour", "quantity": {"Tbsp": 14}}, {"name": "cornflour", "quantity": {"Tbsp": 2}}]',
+        'Sift together twice.'),
+       ('bread flour', '{"Cup": 1}',
+        '[{"name": "plain flour", "quantity": {"Cup": 1}}]', 'Gives a softer crumb.'),
+       ('baking powder', '{"Tsp": 1}',
+        '[{"name": "bicarbonate of soda", "quantity": {"Tsp": 0.25}}, {"name": "cream of tartar", "quantity": {"Tsp": 0.5}}]',
+        NULL),
+       ('cornflour', '{"Tbsp": 1}',
+        '[{"name": "plain flour", "quantity": {"Tbsp": 2}}]', 'For thickening.'),
+       ('cornstarch', '{"Tbsp": 1}',
+        '[{"name": "plain flour", "quantity": {"Tbsp": 2}}]', 'For thickening.'),
+       ('brown sugar', '{"Cup": 1}',
+        '[{"name": "caster sugar", "quantity": {"Cup": 1}}, {"name": "molasses", "quantity": {"Tbsp": 1}}]',
+        NULL),
+       ('caster sugar', '{"Cup": 1}',
+        '[{"name": "granulated sugar", "quantity": {"Cup": 1}}]',
+        'Blitz briefly in a food processor.'),
+       ('icing sugar', '{"Cup": 1}',
+        '[{"name": "caster sugar", "quantity": {"Cup": 1}}, {"name": "cornflour", "quantity": {"Tbsp": 1}}]',
+        'Blitz to a fine powder.'),
+       ('honey', '{"Cup": 1}',
+        '[{"name": "maple syrup", "quantity": {"Cup": 1}}]', NULL),
+       ('maple syrup', '{"Cup": 1}',
+        '[{"name": "honey", "quantity": {"Cup": 1}}]', NULL),
+       ('golden syrup', '{"Cup": 1}',
+        '[{"name": "honey", "quantity": {"Cup": 1}}]', NULL),
+       ('lemon juice', '{"Tbsp": 1}',
+        '[{"name": "lime juice", "quantity": {"Tbsp": 1}}]', NULL),
+       ('lemon juice', '{"Tbsp": 1}',
+        '[{"name": "white wine vinegar", "quantity": {"Tsp": 1.5}}]', NULL),
+       ('white wine', '{"Cup": 1}',
+        '[{"name": "chicken stock", "quantity": {"Cup": 1}}, {"name": "white wine vinegar", "quantity": {"Tbsp": 1}}]',
+        NULL),
+       ('red wine', '{"Cup": 1}',
+        '[{"name": "beef stock", "quantity": {"Cup": 1}}, {"name": "red wine vinegar", "quantity": {"Tbsp": 1}}]',
+        NULL),
+       ('white wine vinegar', '{"Tbsp": 1}',
+        '[{"name": "cider vinegar", "quantity": {"Tbsp": 1}}]', NULL),
+       ('rice vinegar', '{"Tbsp": 1}',
+        '[{"name": "cider vinegar", "quantity": {"Tbsp": 1}}]', NULL),
+       ('soy sauce', '{"Tbsp": 1}',
+        '[{"name": "tamari", "quantity": {"Tbsp": 1}}]', 'Gluten free.'),
+       ('soy sauce', '{"Tbsp": 1}',
+        '[{"name": "coconut aminos", "quantity": {"Tbsp": 1}}]', 'Soy free, and sweeter.'),
+       ('fish sauce', '{"Tbsp": 1}',
+        '[{"name": "soy sauce", "quantity": {"Tbsp": 1}}]', NULL),
+       ('worcestershire sauce', '{"Tbsp": 1}',
+        '[{"name": "soy sauce", "quantity": {"Tbsp": 1}}]', NULL),
+       ('garlic', '{"Other": {"amount": "1", "unit": "cloves"}}',
+        '[{"name": "garlic powder", "quantity": {"Tsp": 0.125}}]', NULL),
+       ('garlic', '{"Count": 1}',
+        '[{"name": "garlic powder", "quantity": {"Tsp": 0.125}}]', 'For one clove.'),
+       ('onion', '{"Count": 1}',
+        '[{"name": "onion powder", "quantity": {"Tbsp": 1}}]', NULL),
+       ('shallot', '{"Count": 1}',
+        '[{"name": "onion", "quantity": {"Count": 0.5}}]', NULL),
+       ('fresh herbs', '{"Tbsp": 1}',
+        '[{"name": "dried herbs", "quantity": {"Tsp": 1}}]', NULL),
+       ('basil', '{"Tbsp": 1}',
+        '[{"name": "dried basil", "quantity": {"Tsp": 1}}]', 'For fresh basil.'),
+       ('parsley', '{"Tbsp": 1}',
+        '[{"name": "dried parsley", "quantity": {"Tsp": 1}}]', 'For fresh parsley.'),
+       ('thyme', '{"Tbsp": 1}',
+        '[{"name": "dried thyme", "quantity": {"Tsp": 1}}]', 'For fresh thyme.'),
+       ('ginger', '{"Tbsp": 1}',
+        '[{"name": "ground ginger", "quantity": {"Tsp": 0.25}}]', 'For fresh grated ginger.'),
+       ('chicken stock', '{"Cup": 1}',
+        '[{"name": "vegetable stock", "quantity": {"Cup": 1}}]', NULL),
+       ('beef stock', '{"Cup": 1}',
+        '[{"name": "mushroom stock", "quantity": {"Cup": 1}}]', NULL),
+       ('tomato passata', '{"Cup": 1}',
+        '[{"name": "tomato puree", "quantity": {"Cup": 0.5}}, {"name": "water", "quantity": {"Cup": 0.5}}]',
+        NULL),
+       ('tinned tomatoes', '{"Gram": 400}',
+        '[{"name": "fresh tomatoes", "quantity": {"Gram": 500}}]', 'Chop and cook down for longer.'),
+       ('parmesan', '{"Gram": 50}',
+        '[{"name": "pecorino", "quantity": {"Gram": 50}}]', NULL),
+       ('parmesan', '{"Gram": 50}',
+        '[{"name": "nutritional yeast", "quantity": {"Tbsp": 3}}]', 'Dairy free.'),
+       ('ricotta', '{"Cup": 1}',
+        '[{"name": "cottage cheese", "quantity": {"Cup": 1}}]', 'Blend smooth first.'),
+       ('mascarpone', '{"Cup": 1}',
+        '[{"name": "cream cheese", "quantity": {"Cup": 1}}]', NULL),
+       ('breadcrumbs', '{"Cup": 1}',
+        '[{"name": "rolled oats", "quantity": {"Cup": 1}}]', NULL),
+       ('rice', '{"Cup": 1}',
+        '[{"name": "quinoa", "quantity": {"Cup": 1}}]', NULL),
+       ('pine nuts', '{"Gram": 50}',
+        '[{"name": "sunflower seeds", "quantity": {"Gram": 50}}]', 'Nut free.'),
+       ('ground almonds', '{"Gram": 100}',
+        '[{"name": "plain flour", "quantity": {"Gram": 80}}]', 'Nut free; bakes drier.'),
+       ('chocolate', '{"Gram": 30}',
+        '[{"name": "cocoa powder", "quantity": {"Tbsp": 3}}, {"name": "butter", "quantity": {"Tbsp": 1}}]',
+        'For unsweetened chocolate.'),
+       ('vanilla extract', '{"Tsp": 1}',
+        '[{"name": "maple syrup", "quantity": {"Tsp": 1}}]', NULL),
+       ('yeast', '{"Gram": 7}',
+        '[{"name": "fresh yeast", "quantity": {"Gram": 14}}]', 'For dried yeast.'),
+       ('mayonnaise', '{"Cup": 1}',
+        '[{"name": "greek yogurt", "quantity": {"Cup": 1}}]', NULL),
+       ('bacon', '{"Gram": 100}',
+        '[{"name": "smoked tofu", "quantity": {"Gram": 100}}, {"name": "smoked paprika", "quantity": {"Tsp": 0.5}}]',
+        'Vegetarian.'),
+       ('chicken', '{"Gram": 500}',
+        '[{"name": "firm tofu", "quantity": {"Gram": 400}}]', 'Vegetarian; press the tofu first.'),
+       ('minced beef', '{"Gram": 500}',
+        '[{"name": "cooked lentils", "quantity": {"Gram": 500}}]', 'Vegetarian.'),
+       ('beef mince', '{"Gram": 500}',
+        '[{"name": "cooked lentils", "quantity": {"Gram": 500}}]', 'Vegetarian.'),
+       ('peanut butter', '{"Tbsp": 1}',
+        '[{"name": "sunflower seed butter", "quantity": {"Tbsp": 1}}]', 'Nut free.');
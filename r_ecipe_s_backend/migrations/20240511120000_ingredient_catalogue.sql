@@ -0,0 +1,121 @@
+-- Ingredients under their canonical names, with the other names recipes
+-- call them by.
+CREATE TABLE IF NOT EXISTS ingredient_catalogue
+(
+    id       BIGSERIAL PRIMARY KEY,
+    name     TEXT   NOT NULL UNIQUE CHECK (name <> ''),
+    -- The plural of the name, where adding an `s` doesn't make it.
+    plural   TEXT,
+    synonyms TEXT[] NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO ingredient_catalogue (name, plural, synonyms)
+VALUES ('spring onion', NULL, '{scallion,green onion,salad onion}'),
+       ('onion', NULL, '{brown onion,yellow onion}'),
+       ('red onion', NULL, '{purple onion}'),
+       ('shallot', NULL, '{eschalot}'),
+       ('garlic', NULL, '{garlic clove}'),
+       ('coriander', NULL, '{cilantro,fresh coriander,coriander leaf,chinese parsley}'),
+       ('ground coriander', NULL, '{coriander powder}'),
+       ('parsley', NULL, '{flat leaf parsley,italian parsley}'),
+       ('aubergine', NULL, '{eggplant,brinjal}'),
+       ('courgette', NULL, '{zucchini}'),
+       ('bell pepper', NULL, '{capsicum,sweet pepper,red pepper,green pepper}'),
+       ('chilli', 'chillies', '{chili,chile,chilli pepper,chili pepper}'),
+       ('chilli flakes', NULL, '{red pepper flakes,crushed red pepper,chili flakes}'),
+       ('rocket', NULL, '{arugula}'),
+       ('beetroot', NULL, '{beet}'),
+       ('swede', NULL, '{rutabaga}'),
+       ('mangetout', NULL, '{snow pea}'),
+       ('sugar snap pea', NULL, '{snap pea}'),
+       ('broad bean', NULL, '{fava bean}'),
+       ('chickpea', NULL, '{garbanzo bean,garbanzo}'),
+       ('haricot bean', NULL, '{navy bean}'),
+       ('butter bean', NULL, '{lima bean}'),
+       ('tomato', 'tomatoes', '{}'),
+       ('potato', 'potatoes', '{}'),
+       ('sweet potato', 'sweet potatoes', '{yam}'),
+       ('mushroom', NULL, '{button mushroom,white mushroom}'),
+       ('spinach', NULL, '{baby spinach,english spinach}'),
+       ('lettuce', NULL, '{}'),
+       ('cabbage', NULL, '{}'),
+       ('carrot', NULL, '{}'),
+       ('celery', NULL, '{celery stick,celery stalk}'),
+       ('celeriac', NULL, '{celery root}'),
+       ('leek', NULL, '{}'),
+       ('lemon', NULL, '{}'),
+       ('lime', NULL, '{}'),
+       ('bay leaf', 'bay leaves', '{}'),
+       ('kaffir lime leaf', 'kaffir lime leaves', '{makrut lime leaf,lime leaf}'),
+       ('ginger', NULL, '{fresh ginger,root ginger,ginger root}'),
+       ('plain flour', NULL, '{all purpose flour,all-purpose flour,ap flour}'),
+       ('self raising flour', NULL, '{self rising flour,self-raising flour,self-rising flour}'),
+       ('strong white flour', NULL, '{bread flour}'),
+       ('wholemeal flour', NULL, '{whole wheat flour}'),
+       ('cornflour', NULL, '{cornstarch,corn starch}'),
+       ('bicarbonate of soda', NULL, '{baking soda,bicarb,bicarb soda,sodium bicarbonate}'),
+       ('baking powder', NULL, '{}'),
+       ('caster sugar', NULL, '{superfine sugar}'),
+       ('icing sugar', NULL, '{powdered sugar,confectioners sugar}'),
+       ('granulated sugar', NULL, '{sugar,white sugar}'),
+       ('brown sugar', NULL, '{light brown sugar,soft brown sugar}'),
+       ('golden syrup', NULL, '{light treacle}'),
+       ('black treacle', NULL, '{molasses}'),
+       ('double cream', NULL, '{heavy cream,heavy whipping cream}'),
+       ('single cream', NULL, '{light cream}'),
+       ('whipping cream', NULL, '{}'),
+       ('natural yogurt', NULL, '{plain yogurt,natural yoghurt,plain yoghurt}'),
+       ('greek yogurt', NULL, '{greek yoghurt}'),
+       ('butter', NULL, '{unsalted butter}'),
+       ('egg', NULL, '{large egg,medium egg}'),
+       ('milk', NULL, '{whole milk,full fat milk}'),
+       ('minced beef', NULL, '{beef mince,ground beef}'),
+       ('minced pork', NULL, '{pork mince,ground pork}'),
+       ('minced lamb', NULL, '{lamb mince,ground lamb}'),
+       ('prawn', NULL, '{shrimp}'),
+       ('streaky bacon', NULL, '{bacon strip}'),
+       ('back bacon', NULL, '{bacon rasher}'),
+       ('chicken breast', NULL, '{chicken breast fillet}'),
+       ('chicken thigh', NULL, '{chicken thigh fillet}'),
+       ('stock cube', NULL, '{bouillon cube}'),
+       ('chicken stock', NULL, '{chicken broth}'),
+       ('vegetable stock', NULL, '{vegetable broth}'),
+       ('beef stock', NULL, '{beef broth}'),
+       ('passata', NULL, '{tomato passata,sieved tomatoes}'),
+       ('tomato puree', NULL, '{tomato paste}'),
+       ('tinned tomatoes', NULL, '{canned tomatoes,chopped tomatoes,tin of tomatoes}'),
+       ('olive oil', NULL, '{}'),
+       ('extra virgin olive oil', NULL, '{evoo}'),
+       ('vegetable oil', NULL, '{neutral oil}'),
+       ('rapeseed oil', NULL, '{canola oil}'),
+       ('soy sauce', NULL, '{light soy sauce,soya sauce}'),
+       ('dark soy sauce', NULL, '{}'),
+       ('fish sauce', NULL, '{nam pla}'),
+       ('rice vinegar', NULL, '{rice wine vinegar}'),
+       ('cider vinegar', NULL, '{apple cider vinegar}'),
+       ('dark chocolate', NULL, '{bittersweet chocolate,plain chocolate}'),
+       ('cocoa powder', NULL, '{cocoa,unsweetened cocoa}'),
+       ('vanilla extract', NULL, '{vanilla essence}'),
+       ('sultana', NULL, '{golden raisin}'),
+       ('raisin', NULL, '{}'),
+       ('rolled oats', NULL, '{porridge oats,old fashioned oats}'),
+       ('breadcrumbs', 'breadcrumbs', '{bread crumbs}'),
+       ('panko', 'panko', '{panko breadcrumbs}'),
+       ('spaghetti', 'spaghetti', '{}'),
+       ('basmati rice', NULL, '{}'),
+       ('arborio rice', NULL, '{risotto rice}'),
+       ('parmesan', NULL, '{parmigiano reggiano,parmesan cheese}'),
+       ('cheddar', NULL, '{cheddar cheese,mature cheddar}'),
+       ('mozzarella', NULL, '{mozzarella cheese}'),
+       ('cumin', NULL, '{ground cumin}'),
+       ('paprika', NULL, '{sweet paprika}'),
+       ('smoked paprika', NULL, '{pimenton}'),
+       ('cayenne pepper', NULL, '{cayenne}'),
+       ('black pepper', NULL, '{ground black pepper,pepper}'),
+       ('salt', NULL, '{sea salt,table salt,kosher salt}'),
+       ('mixed spice', NULL, '{pumpkin pie spice}'),
+       ('allspice', NULL, '{pimento}'),
+       ('star anise', 'star anise', '{}'),
+       ('clove', NULL, '{whole clove}'),
+       ('cinnamon', NULL, '{ground cinnamon}'),
+       ('nutmeg', NULL, '{ground nutmeg}');
@@ -0,0 +1,15 @@
+-- What amounts of ingredients in the catalogue cost to buy, kept over time
+-- so that the latest price is used and old ones stay for reference.
+CREATE TABLE IF NOT EXISTS prices
+(
+    id           BIGSERIAL PRIMARY KEY,
+    catalogue_id BIGINT           NOT NULL REFERENCES ingredient_catalogue (id) ON DELETE CASCADE,
+    -- A serialised quantity, the amount the price is for.
+    per          JSONB            NOT NULL,
+    price        DOUBLE PRECISION NOT NULL CHECK (price >= 0),
+    currency     TEXT             NOT NULL CHECK (currency <> ''),
+    date         DATE             NOT NULL DEFAULT CURRENT_DATE,
+    created      timestamptz      NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS prices_catalogue_id ON prices (catalogue_id, date);
@@ -0,0 +1,17 @@
+-- Each time a recipe was cooked, with how it went.
+CREATE TABLE IF NOT EXISTS cook_log
+(
+    id         BIGSERIAL PRIMARY KEY,
+    recipe_id  BIGINT      NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    cooked     DATE        NOT NULL DEFAULT CURRENT_DATE,
+    cook       TEXT,
+    rating     SMALLINT CHECK (rating BETWEEN 1 AND 5),
+    notes      TEXT,
+    photo      BYTEA,
+    -- The content type the photo was uploaded with.
+    photo_type TEXT,
+    created    timestamptz NOT NULL DEFAULT NOW(),
+    CHECK ((photo IS NULL) = (photo_type IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS cook_log_recipe_id ON cook_log (recipe_id, cooked);
@@ -0,0 +1,14 @@
+-- Images uploaded for recipes. The files themselves are kept in the image
+-- store, under keys made from the recipe and image ids.
+CREATE TABLE IF NOT EXISTS recipe_images
+(
+    id           BIGSERIAL PRIMARY KEY,
+    recipe_id    BIGINT      NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    -- Images are shown in this order, the first being the recipe's cover.
+    position     INTEGER     NOT NULL DEFAULT 0,
+    -- The content type of the original upload.
+    content_type TEXT        NOT NULL,
+    created      timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS recipe_images_recipe_id ON recipe_images (recipe_id, position);
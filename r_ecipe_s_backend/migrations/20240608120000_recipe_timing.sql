@@ -0,0 +1,12 @@
+-- Times are in minutes. The total is worked out from the prep and cook
+-- times when a recipe is saved without one.
+ALTER TABLE recipes
+    ADD COLUMN IF NOT EXISTS prep_minutes  INTEGER CHECK (prep_minutes >= 0),
+    ADD COLUMN IF NOT EXISTS cook_minutes  INTEGER CHECK (cook_minutes >= 0),
+    ADD COLUMN IF NOT EXISTS total_minutes INTEGER CHECK (total_minutes >= 0),
+    ADD COLUMN IF NOT EXISTS oven_degrees  INTEGER CHECK (oven_degrees >= 0),
+    ADD COLUMN IF NOT EXISTS oven_unit     TEXT CHECK (oven_unit IN ('celsius', 'fahrenheit')),
+    ADD CONSTRAINT recipes_oven_temperature
+        CHECK ((oven_degrees IS NULL) = (oven_unit IS NULL));
+
+CREATE INDEX IF NOT EXISTS recipes_total_minutes ON recipes (total_minutes);
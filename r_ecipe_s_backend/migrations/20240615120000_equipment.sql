@@ -0,0 +1,53 @@
+-- Kitchen equipment under catalogue names, with the other names recipes call
+-- it by. Equipment a recipe names that isn't in the catalogue is added to it.
+CREATE TABLE IF NOT EXISTS equipment
+(
+    id       BIGSERIAL PRIMARY KEY,
+    name     TEXT   NOT NULL UNIQUE CHECK (name <> ''),
+    synonyms TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE IF NOT EXISTS recipe_equipment
+(
+    recipe_id    BIGINT NOT NULL REFERENCES recipes (id) ON DELETE CASCADE,
+    equipment_id BIGINT NOT NULL REFERENCES equipment (id) ON DELETE CASCADE,
+    PRIMARY KEY (recipe_id, equipment_id)
+);
+
+CREATE INDEX IF NOT EXISTS recipe_equipment_equipment_id ON recipe_equipment (equipment_id);
+
+INSERT INTO equipment (name, synonyms)
+VALUES ('oven', '{}'),
+       ('stand mixer', '{kitchen mixer,kitchenaid}'),
+       ('hand mixer', '{electric whisk,electric mixer}'),
+       ('food processor', '{}'),
+       ('blender', '{liquidiser,liquidizer}'),
+       ('stick blender', '{immersion blender,hand blender}'),
+       ('dutch oven', '{casserole dish,cast iron pot,cocotte}'),
+       ('sous-vide', '{sous vide,immersion circulator,sous-vide circulator}'),
+       ('slow cooker', '{crock pot,crockpot}'),
+       ('pressure cooker', '{instant pot,multicooker}'),
+       ('air fryer', '{}'),
+       ('microwave', '{microwave oven}'),
+       ('grill', '{barbecue,bbq}'),
+       ('wok', '{}'),
+       ('cast iron skillet', '{cast iron pan}'),
+       ('baking sheet', '{baking tray,sheet pan}'),
+       ('loaf tin', '{loaf pan}'),
+       ('muffin tin', '{muffin tray,muffin pan}'),
+       ('springform tin', '{springform pan}'),
+       ('rolling pin', '{}'),
+       ('pasta machine', '{pasta roller}'),
+       ('mandoline', '{mandolin slicer}'),
+       ('thermometer', '{probe thermometer,meat thermometer,sugar thermometer}'),
+       ('kitchen scale', '{scales,digital scale}')
+ON CONFLICT (name) DO NOTHING;
+
+-- Recipes with an oven temperature need an oven.
+INSERT INTO recipe_equipment (recipe_id, equipment_id)
+SELECT recipes.id, equipment.id
+FROM recipes, equipment
+WHERE recipes.oven_degrees IS NOT NULL AND equipment.name = 'oven'
+ON CONFLICT DO NOTHING;
+
+UPDATE recipes SET searchable = false WHERE oven_degrees IS NOT NULL;
@@ -0,0 +1,21 @@
+-- Accounts signing in with a password, hashed with argon2.
+CREATE TABLE IF NOT EXISTS users
+(
+    id            BIGSERIAL PRIMARY KEY,
+    username      TEXT        NOT NULL UNIQUE CHECK (username <> ''),
+    password_hash TEXT        NOT NULL,
+    created       timestamptz NOT NULL DEFAULT NOW()
+);
+
+-- Signed in sessions, each with one valid refresh token at a time. The
+-- generation goes up with every refresh, so an older token can't be reused.
+CREATE TABLE IF NOT EXISTS sessions
+(
+    id         BIGSERIAL PRIMARY KEY,
+    user_id    BIGINT      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    generation BIGINT      NOT NULL DEFAULT 0,
+    created    timestamptz NOT NULL DEFAULT NOW(),
+    expires    timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS sessions_user_id ON sessions (user_id);
@@ -0,0 +1,11 @@
+-- Tokens for reading the meal plan calendar feed, kept so that they can be
+-- revoked. A user has at most one unexpired token at a time.
+CREATE TABLE IF NOT EXISTS feed_tokens
+(
+    id      BIGSERIAL PRIMARY KEY,
+    user_id BIGINT      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created timestamptz NOT NULL DEFAULT NOW(),
+    expires timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS feed_tokens_user_id ON feed_tokens (user_id);
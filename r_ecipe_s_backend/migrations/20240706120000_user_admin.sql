@@ -0,0 +1,5 @@
+-- Admins can add accounts and replace the whole library. The first account,
+-- created on start up, is one.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS admin BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE users SET admin = TRUE WHERE id = (SELECT MIN(id) FROM users);
@@ -0,0 +1,3 @@
+-- Photos are kept in the image store, photo_type saying there is one. Those
+-- still in the table are moved there on start up.
+ALTER TABLE cook_log DROP CONSTRAINT IF EXISTS cook_log_check;
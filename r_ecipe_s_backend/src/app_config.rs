@@ -27,6 +27,10 @@ pub struct DBConfig {
 pub struct HTTPConfig {
     pub host: String,
     pub port: u16,
+    /// Where people reach the app, e.g. `https://recipes.example.com`, for
+    /// links in what it serves.
+    #[serde(default)]
+    pub public_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,10 +52,33 @@ pub struct VectorSearchConfig {
     pub port: u16,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageConfig {
+    /// Directory uploaded recipe images are kept in.
+    pub path: String,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            path: "images".to_string(),
+        }
+    }
+}
+
 impl HTTPConfig {
     pub fn connection_string(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// The public URL without a trailing slash, or the address listened on
+    /// when there isn't one.
+    pub fn base_url(&self) -> String {
+        match &self.public_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("http://{}", self.connection_string()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +87,7 @@ pub struct AppConfig {
     pub db_config: DBConfig,
     pub search_config: SearchConfig,
     pub vector_search_config: VectorSearchConfig,
+    pub image_config: ImageConfig,
 }
 
 impl AppConfig {
@@ -97,6 +125,10 @@ impl AppConfig {
         } else {
             info!("getting server host from file");
         }
+        if let Ok(public_url) = std::env::var("R_ECIPE_S_PUBLIC_URL") {
+            info!("getting public url from env: {public_url}");
+            http_config.public_url = Some(public_url);
+        }
 
         let mut search_config = conf.get::<SearchConfig>("search")?;
         if let Ok(api_key) = std::env::var("R_ECIPE_S_SEARCH_API_KEY") {
@@ -120,11 +152,18 @@ impl AppConfig {
             vector_search_config.host = host;
         }
 
+        let mut image_config = conf.get::<ImageConfig>("images").unwrap_or_default();
+        if let Ok(path) = std::env::var("R_ECIPE_S_IMAGE_PATH") {
+            info!("getting image path from env");
+            image_config.path = path;
+        }
+
         Ok(AppConfig {
             http_config,
             db_config,
             search_config,
             vector_search_config,
+            image_config,
         })
     }
 }
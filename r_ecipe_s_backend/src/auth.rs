@@ -1,13 +1,32 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use async_trait::async_trait;
 use axum::{
-    extract::{FromRequest, RequestParts},
-    headers::{authorization::Bearer, Authorization, Header, SetCookie},
-    http::{HeaderMap, StatusCode},
+    extract::{Extension, FromRequest, RequestParts},
+    headers::{authorization::Bearer, Authorization},
+    http::StatusCode,
     response::{IntoResponse, Response},
     Json, TypedHeader,
 };
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use r_ecipe_s_model::serde_json;
+use r_ecipe_s_model::user::{Session, User};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use time::{Duration, OffsetDateTime};
+
+/// How long an access token is good for. Signing out ends the session, but
+/// access tokens already handed out stay valid until they expire.
+pub const ACCESS_TOKEN_LIFETIME: Duration = Duration::minutes(15);
+/// How long a session lasts without being refreshed.
+pub const REFRESH_TOKEN_LIFETIME: Duration = Duration::days(30);
+/// How long a calendar app can read the meal plan feed with one token,
+/// unless it's revoked first.
+pub const FEED_TOKEN_LIFETIME: Duration = Duration::days(365);
+/// The shortest secret tokens are signed with, in bytes.
+pub const MIN_SECRET_LENGTH: usize = 32;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
@@ -17,64 +36,228 @@ pub enum AuthError {
     MissingCredentials,
     #[error("Incorrect Authentication Token")]
     InvalidToken,
+    #[error("Failed to create token")]
+    TokenCreation,
+    #[error("Failed to hash password")]
+    Hashing,
+    #[error("Authentication isn't set up")]
+    Unconfigured,
+    #[error("Only admins can do that")]
+    Forbidden,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = match self {
+            AuthError::WrongCredentials => (StatusCode::UNAUTHORIZED, "Wrong credentials"),
+            AuthError::MissingCredentials => (StatusCode::UNAUTHORIZED, "Missing credentials"),
+            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
+            AuthError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error"),
+            AuthError::Hashing => (StatusCode::INTERNAL_SERVER_ERROR, "Password hashing error"),
+            AuthError::Unconfigured => (StatusCode::INTERNAL_SERVER_ERROR, "Auth not configured"),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, "Admins only"),
+        };
+        let body = Json(serde_json::json!({
+            "error": error_message,
+        }));
+        (status, body).into_response()
+    }
 }
 
-pub struct BearerValidation {
-    hard_coded_secret: String, // yolo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TokenKind {
+    Access,
+    Refresh,
+    /// Only reads the meal plan calendar feed, as calendar apps keep it.
+    Feed,
 }
 
-impl BearerValidation {
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Claims {
+    /// The user's id.
+    pub(crate) sub: i64,
+    pub(crate) name: String,
+    /// Missing from tokens issued before there were admins.
+    #[serde(default)]
+    pub(crate) admin: bool,
+    /// The session's id, or the feed token's for a feed token.
+    pub(crate) sid: i64,
+    /// The session's generation when the token was issued.
+    pub(crate) generation: i64,
+    pub(crate) kind: TokenKind,
+    pub(crate) iat: i64,
+    pub(crate) exp: i64,
+}
+
+/// Signs and checks session tokens with the server's secret.
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl JwtKeys {
     pub fn new(secret: &str) -> Self {
-        BearerValidation {
-            hard_coded_secret: secret.into(),
+        JwtKeys {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
         }
     }
 
-    pub(crate) fn authorise(
+    fn issue(
         &self,
-        BearerToken(bearer_token): BearerToken,
-    ) -> Result<(), AuthError> {
-        if bearer_token == (self.hard_coded_secret) {
-            Ok(())
+        user: &User,
+        session_id: i64,
+        generation: i64,
+        kind: TokenKind,
+        lifetime: Duration,
+    ) -> Result<String, AuthError> {
+        let now = OffsetDateTime::now_utc();
+        let claims = Claims {
+            sub: user.id,
+            name: user.username.clone(),
+            admin: user.admin,
+            sid: session_id,
+            generation,
+            kind,
+            iat: now.unix_timestamp(),
+            exp: (now + lifetime).unix_timestamp(),
+        };
+        encode(&Header::default(), &claims, &self.encoding).map_err(|_| AuthError::TokenCreation)
+    }
+
+    /// A new access and refresh token for the session at its generation.
+    pub(crate) fn session(
+        &self,
+        user: User,
+        session_id: i64,
+        generation: i64,
+    ) -> Result<Session, AuthError> {
+        let access_token = self.issue(
+            &user,
+            session_id,
+            generation,
+            TokenKind::Access,
+            ACCESS_TOKEN_LIFETIME,
+        )?;
+        let refresh_token = self.issue(
+            &user,
+            session_id,
+            generation,
+            TokenKind::Refresh,
+            REFRESH_TOKEN_LIFETIME,
+        )?;
+        Ok(Session {
+            access_token,
+            refresh_token,
+            expires_in: ACCESS_TOKEN_LIFETIME.whole_seconds(),
+            user,
+        })
+    }
+
+    /// A token for reading the meal plan calendar feed as the user, good
+    /// until the stored feed token `feed_token_id` expires or is revoked.
+    pub(crate) fn feed_token(
+        &self,
+        user: &AuthUser,
+        feed_token_id: i64,
+        expires: OffsetDateTime,
+    ) -> Result<String, AuthError> {
+        let user = User {
+            id: user.id,
+            username: user.username.clone(),
+            admin: user.admin,
+        };
+        let lifetime = expires - OffsetDateTime::now_utc();
+        self.issue(&user, feed_token_id, 0, TokenKind::Feed, lifetime)
+    }
+
+    /// The claims of an unexpired token of the kind signed with these keys.
+    pub(crate) fn verify(&self, token: &str, kind: TokenKind) -> Result<Claims, AuthError> {
+        let claims = decode::<Claims>(token, &self.decoding, &Validation::default())
+            .map_err(|_| AuthError::InvalidToken)?
+            .claims;
+        if claims.kind == kind {
+            Ok(claims)
         } else {
             Err(AuthError::InvalidToken)
         }
     }
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AuthError::WrongCredentials => (StatusCode::UNAUTHORIZED, "Wrong credentials"),
-            AuthError::MissingCredentials => (StatusCode::BAD_REQUEST, "Missing credentials"),
-            AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Invalid token"),
-        };
-        let body = Json(serde_json::json!({
-            "error": error_message,
-        }));
-        (status, body).into_response()
-    }
+pub(crate) fn hash_password(password: &str) -> Result<String, AuthError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| AuthError::Hashing)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct BearerToken(String);
+pub(crate) fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// A hash to check passwords against when the username is unknown, so that
+/// failing to sign in takes as long either way and doesn't give usernames away.
+pub(crate) fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password("").unwrap_or_default())
+}
+
+/// The signed in user, from the access token in the `Authorization` header.
+/// Needs the [`JwtKeys`] added to the router as an extension.
+///
+/// Only the token's signature and expiry are checked, not its session, so
+/// requests don't each go to the database. An access token thus keeps working
+/// for the rest of its [`ACCESS_TOKEN_LIFETIME`] after signing out, and so do
+/// the username and admin flag it was issued with. Refreshing does check the
+/// session, which bounds that window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthUser {
+    pub id: i64,
+    pub username: String,
+    pub admin: bool,
+    pub session_id: i64,
+}
+
+impl AuthUser {
+    /// Fails unless the user is an admin.
+    pub fn require_admin(&self) -> Result<(), AuthError> {
+        if self.admin {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden)
+        }
+    }
+}
 
 #[async_trait]
-impl<B> FromRequest<B> for BearerToken
+impl<B> FromRequest<B> for AuthUser
 where
     B: Send,
 {
     type Rejection = AuthError;
 
     async fn from_request(req: &mut RequestParts<B>) -> std::result::Result<Self, Self::Rejection> {
-        // Extract the token from the authorization header
+        let Extension(keys) = Extension::<Arc<JwtKeys>>::from_request(req)
+            .await
+            .map_err(|_| AuthError::Unconfigured)?;
         let TypedHeader(Authorization(bearer)) =
             TypedHeader::<Authorization<Bearer>>::from_request(req)
                 .await
-                .map_err(|_| AuthError::InvalidToken)?;
-        // Decode the user data
-        let token_data = bearer.token().to_string();
-
-        Ok(BearerToken(token_data))
+                .map_err(|_| AuthError::MissingCredentials)?;
+        let claims = keys.verify(bearer.token(), TokenKind::Access)?;
+        Ok(AuthUser {
+            id: claims.sub,
+            username: claims.name,
+            admin: claims.admin,
+            session_id: claims.sid,
+        })
     }
 }
@@ -0,0 +1,390 @@
+use crate::{
+    app_config::SearchConfig,
+    auth::AuthUser,
+    db::DbAccess,
+    recipe_service::{self, RecipeAccess},
+    search_indexer::{set_synonyms, R_ECIPE_S_INDEX_NAME},
+};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use meilisearch_sdk::client::Client;
+use r_ecipe_s_model::catalogue::{
+    link_ingredient, propose_matches, CatalogueEntry, CatalogueEntryWithId, CatalogueMatch,
+};
+use r_ecipe_s_model::shopping::ingredient_key;
+use r_ecipe_s_model::Ingredient;
+use sqlx::types::Json;
+use sqlx::PgExecutor;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Search error: {0}")]
+    Search(#[from] meilisearch_sdk::errors::Error),
+    #[error("Missing catalogue entry with id: {0}")]
+    Missing(i64),
+    #[error("Catalogue entries need a name")]
+    EmptyName,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Search(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyName => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait CatalogueService {
+    type ServiceType;
+    fn bind_catalogue_routes(
+        self,
+        catalogue_access: &Arc<CatalogueAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        search_config: &SearchConfig,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> CatalogueService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_catalogue_routes(
+        self,
+        catalogue_access: &Arc<CatalogueAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        search_config: &SearchConfig,
+    ) -> Self::ServiceType {
+        let url = search_config.http_url();
+        let search_client = Arc::new(Client::new(url, Some(search_config.api_key.clone())));
+
+        self.route(
+            "/catalogue",
+            get({
+                let catalogue_access = catalogue_access.clone();
+                || get_catalogue(catalogue_access)
+            })
+            .put({
+                let catalogue_access = catalogue_access.clone();
+                let search_client = search_client.clone();
+                |form, user| put_entry(form, user, catalogue_access, search_client)
+            }),
+        )
+        .route(
+            "/catalogue/reconcile",
+            get({
+                let catalogue_access = catalogue_access.clone();
+                let recipe_access = recipe_access.clone();
+                || get_proposals(catalogue_access, recipe_access)
+            })
+            .post({
+                let catalogue_access = catalogue_access.clone();
+                |form, user| post_reconcile(form, user, catalogue_access)
+            }),
+        )
+        .route(
+            "/catalogue/:id",
+            get({
+                let catalogue_access = catalogue_access.clone();
+                |path| get_entry(path, catalogue_access)
+            })
+            .post({
+                let catalogue_access = catalogue_access.clone();
+                let search_client = search_client.clone();
+                |path, user, form| post_entry(path, user, form, catalogue_access, search_client)
+            })
+            .delete({
+                let catalogue_access = catalogue_access.clone();
+                |path, user| delete_entry(path, user, catalogue_access, search_client)
+            }),
+        )
+    }
+}
+
+pub struct CatalogueAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct CatalogueEntryRep {
+    id: i64,
+    name: String,
+    plural: Option<String>,
+    synonyms: Vec<String>,
+}
+
+impl CatalogueEntryRep {
+    fn model_with_id(self) -> CatalogueEntryWithId {
+        CatalogueEntryWithId {
+            id: self.id,
+            data: CatalogueEntry {
+                name: self.name,
+                plural: self.plural,
+                synonyms: self.synonyms,
+            },
+        }
+    }
+}
+
+impl CatalogueAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        CatalogueAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<CatalogueEntryWithId>> {
+        Ok(Self::get_all_pool(self.db_access.get_pool()).await?)
+    }
+
+    pub(crate) async fn get_all_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+    ) -> sqlx::Result<Vec<CatalogueEntryWithId>> {
+        Ok(sqlx::query_as!(
+            CatalogueEntryRep,
+            "SELECT id, name, plural, synonyms FROM ingredient_catalogue ORDER BY name"
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(CatalogueEntryRep::model_with_id)
+        .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<CatalogueEntryWithId>> {
+        Ok(sqlx::query_as!(
+            CatalogueEntryRep,
+            "SELECT id, name, plural, synonyms FROM ingredient_catalogue WHERE id = $1",
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(CatalogueEntryRep::model_with_id))
+    }
+
+    async fn insert(&self, entry: &CatalogueEntry) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO ingredient_catalogue (name, plural, synonyms)
+                VALUES ($1, $2, $3)
+                RETURNING id
+            "#,
+            entry.name,
+            entry.plural,
+            &entry.synonyms[..]
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    async fn update(&self, id: i64, entry: &CatalogueEntry) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                UPDATE ingredient_catalogue
+                SET name = $2, plural = $3, synonyms = $4
+                WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            entry.name,
+            entry.plural,
+            &entry.synonyms[..]
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            "DELETE FROM ingredient_catalogue WHERE id = $1 RETURNING id",
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Links recipes' ingredients to the entries matched to them, so the
+    /// recipes are indexed again. Returns how many ingredients were linked.
+    async fn link(&self, matches: &[CatalogueMatch]) -> Result<usize> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let mut recipe_ids = matches
+            .iter()
+            .map(|catalogue_match| catalogue_match.recipe_id)
+            .collect::<Vec<_>>();
+        recipe_ids.sort_unstable();
+        recipe_ids.dedup();
+        let recipes = sqlx::query!(
+            r#"
+                SELECT id, ingredients as "ingredients: Json<Vec<Ingredient>>"
+                FROM recipes
+                WHERE id = ANY($1)
+                FOR UPDATE
+            "#,
+            &recipe_ids[..]
+        )
+        .fetch_all(transaction.as_mut())
+        .await?;
+        let mut linked = 0;
+        for recipe in recipes {
+            let mut ingredients = recipe.ingredients.0;
+            let recipe_linked = matches
+                .iter()
+                .filter(|catalogue_match| catalogue_match.recipe_id == recipe.id)
+                .filter(|catalogue_match| link_ingredient(&mut ingredients, catalogue_match))
+                .count();
+            if recipe_linked == 0 {
+                continue;
+            }
+            sqlx::query!(
+                "UPDATE recipes SET ingredients = $2, searchable = false WHERE id = $1",
+                recipe.id,
+                Json(&ingredients) as _
+            )
+            .execute(transaction.as_mut())
+            .await?;
+            linked += recipe_linked;
+        }
+        transaction.commit().await?;
+        Ok(linked)
+    }
+}
+
+fn normalise(entry: CatalogueEntry) -> Result<CatalogueEntry> {
+    let name = ingredient_key(&entry.name);
+    if name.is_empty() {
+        return Err(Error::EmptyName);
+    }
+    let plural = entry
+        .plural
+        .map(|plural| ingredient_key(&plural))
+        .filter(|plural| !plural.is_empty());
+    let mut synonyms = entry
+        .synonyms
+        .iter()
+        .map(|synonym| ingredient_key(synonym))
+        .filter(|synonym| !synonym.is_empty() && *synonym != name)
+        .collect::<Vec<_>>();
+    synonyms.sort();
+    synonyms.dedup();
+    Ok(CatalogueEntry {
+        name,
+        plural,
+        synonyms,
+    })
+}
+
+/// Hands the catalogue's names to the search index after it changes.
+async fn update_synonyms(catalogue_access: &CatalogueAccess, search_client: &Client) -> Result<()> {
+    let catalogue = catalogue_access.get_all().await?;
+    set_synonyms(&search_client.index(R_ECIPE_S_INDEX_NAME), &catalogue).await?;
+    Ok(())
+}
+
+pub(crate) async fn get_catalogue(
+    catalogue_access: Arc<CatalogueAccess>,
+) -> Result<HttpJson<Vec<CatalogueEntryWithId>>> {
+    Ok(catalogue_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_entry(
+    Path(id): Path<i64>,
+    catalogue_access: Arc<CatalogueAccess>,
+) -> Result<HttpJson<CatalogueEntryWithId>> {
+    let entry = catalogue_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(entry.into())
+}
+
+pub(crate) async fn put_entry(
+    HttpJson(entry): HttpJson<CatalogueEntry>,
+    _user: AuthUser,
+    catalogue_access: Arc<CatalogueAccess>,
+    search_client: Arc<Client>,
+) -> Result<HttpJson<i64>> {
+    let id = catalogue_access.insert(&normalise(entry)?).await?;
+    update_synonyms(&catalogue_access, &search_client).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_entry(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(entry): HttpJson<CatalogueEntry>,
+    catalogue_access: Arc<CatalogueAccess>,
+    search_client: Arc<Client>,
+) -> Result<HttpJson<i64>> {
+    let id = catalogue_access
+        .update(id, &normalise(entry)?)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    update_synonyms(&catalogue_access, &search_client).await?;
+    Ok(id.into())
+}
+
+/// Removes an entry. Ingredients linked to it are matched by name again.
+pub(crate) async fn delete_entry(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    catalogue_access: Arc<CatalogueAccess>,
+    search_client: Arc<Client>,
+) -> Result<HttpJson<()>> {
+    catalogue_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    update_synonyms(&catalogue_access, &search_client).await?;
+    Ok(().into())
+}
+
+/// Entries proposed for every recipe ingredient not yet linked to one.
+pub(crate) async fn get_proposals(
+    catalogue_access: Arc<CatalogueAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<Vec<CatalogueMatch>>> {
+    let catalogue = catalogue_access.get_all().await?;
+    let recipes = recipe_access.get_all_recipes().await?;
+    Ok(propose_matches(&recipes, &catalogue).into())
+}
+
+/// Accepts proposed matches, linking the ingredients to their entries.
+/// Returns how many were linked; ingredients renamed since are skipped.
+pub(crate) async fn post_reconcile(
+    HttpJson(matches): HttpJson<Vec<CatalogueMatch>>,
+    _user: AuthUser,
+    catalogue_access: Arc<CatalogueAccess>,
+) -> Result<HttpJson<usize>> {
+    let catalogue = catalogue_access.get_all().await?;
+    if let Some(missing) = matches.iter().find(|catalogue_match| {
+        !catalogue
+            .iter()
+            .any(|entry| entry.id == catalogue_match.catalogue_id)
+    }) {
+        return Err(Error::Missing(missing.catalogue_id));
+    }
+    Ok(catalogue_access.link(&matches).await?.into())
+}
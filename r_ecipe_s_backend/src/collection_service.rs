@@ -0,0 +1,554 @@
+use crate::{
+    auth::AuthUser,
+    db::DbAccess,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::{Collection, CollectionEntry, CollectionWithId, RecipeWithId};
+use sqlx::{Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Missing collection with id: {0}")]
+    Missing(i64),
+    #[error("Missing recipe with id: {0}")]
+    MissingRecipe(i64),
+    #[error("Recipe {0} is not in the collection")]
+    NotInCollection(i64),
+    #[error("Collection names can't be empty")]
+    EmptyName,
+    #[error("A new order must list every recipe in the collection exactly once")]
+    InvalidOrder,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Missing(_) | Error::MissingRecipe(_) | Error::NotInCollection(_) => {
+                http::StatusCode::NOT_FOUND
+            }
+            Error::EmptyName | Error::InvalidOrder => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait CollectionService {
+    type ServiceType;
+    fn bind_collection_routes(
+        self,
+        collection_access: &Arc<CollectionAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> CollectionService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_collection_routes(
+        self,
+        collection_access: &Arc<CollectionAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/collections",
+            get({
+                let collection_access = collection_access.clone();
+                || get_collections(collection_access)
+            })
+            .put({
+                let collection_access = collection_access.clone();
+                |form, user| put_collection(form, user, collection_access)
+            }),
+        )
+        .route(
+            "/collections/:id",
+            get({
+                let collection_access = collection_access.clone();
+                |path| get_collection(path, collection_access)
+            })
+            .post({
+                let collection_access = collection_access.clone();
+                |path, user, form| post_collection(path, user, form, collection_access)
+            })
+            .delete({
+                let collection_access = collection_access.clone();
+                |path, user| delete_collection(path, user, collection_access)
+            }),
+        )
+        .route(
+            "/collections/:id/order",
+            post({
+                let collection_access = collection_access.clone();
+                |path, user, form| reorder_collection(path, user, form, collection_access)
+            }),
+        )
+        .route(
+            "/collections/:id/recipes",
+            get({
+                let collection_access = collection_access.clone();
+                let recipe_access = recipe_access.clone();
+                |path| get_collection_recipes(path, collection_access, recipe_access)
+            })
+            .put({
+                let collection_access = collection_access.clone();
+                |path, user, form| put_collection_entry(path, user, form, collection_access)
+            }),
+        )
+        .route(
+            "/collections/:id/recipes/:recipe_id",
+            delete({
+                let collection_access = collection_access.clone();
+                |path, user| delete_collection_entry(path, user, collection_access)
+            }),
+        )
+    }
+}
+
+pub struct CollectionAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct CollectionRep {
+    id: i64,
+    name: String,
+    description: String,
+}
+
+struct EntryRep {
+    collection_id: i64,
+    recipe_id: i64,
+    note: Option<String>,
+}
+
+impl EntryRep {
+    fn model(self) -> CollectionEntry {
+        CollectionEntry {
+            recipe_id: self.recipe_id,
+            note: self.note,
+        }
+    }
+}
+
+/// Trims names and notes, dropping blank notes and repeated recipes.
+fn normalise(collection: Collection) -> Result<Collection> {
+    let name = collection.name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::EmptyName);
+    }
+    let mut seen = HashSet::new();
+    let entries = collection
+        .entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.recipe_id))
+        .map(normalise_entry)
+        .collect();
+    Ok(Collection {
+        name,
+        description: collection.description.trim().to_string(),
+        entries,
+    })
+}
+
+fn normalise_entry(entry: CollectionEntry) -> CollectionEntry {
+    CollectionEntry {
+        recipe_id: entry.recipe_id,
+        note: entry
+            .note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty()),
+    }
+}
+
+impl CollectionAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        CollectionAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<CollectionWithId>> {
+        let pool = self.db_access.get_pool();
+        let collections = sqlx::query_as!(
+            CollectionRep,
+            "SELECT id, name, description FROM collections ORDER BY name, id"
+        )
+        .fetch_all(pool)
+        .await?;
+        let entries = sqlx::query_as!(
+            EntryRep,
+            r#"
+                SELECT collection_id, recipe_id, note
+                FROM collection_recipes
+                ORDER BY collection_id, position
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        let mut entries_by_collection: HashMap<i64, Vec<CollectionEntry>> = HashMap::new();
+        for entry in entries {
+            entries_by_collection
+                .entry(entry.collection_id)
+                .or_default()
+                .push(entry.model());
+        }
+        Ok(collections
+            .into_iter()
+            .map(|rep| CollectionWithId {
+                id: rep.id,
+                data: Collection {
+                    name: rep.name,
+                    description: rep.description,
+                    entries: entries_by_collection.remove(&rep.id).unwrap_or_default(),
+                },
+            })
+            .collect())
+    }
+
+    pub(crate) async fn get_by_id(&self, id: i64) -> Result<Option<CollectionWithId>> {
+        let pool = self.db_access.get_pool();
+        let Some(rep) = sqlx::query_as!(
+            CollectionRep,
+            "SELECT id, name, description FROM collections WHERE id = $1",
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let entries = sqlx::query_as!(
+            EntryRep,
+            r#"
+                SELECT collection_id, recipe_id, note
+                FROM collection_recipes
+                WHERE collection_id = $1
+                ORDER BY position
+            "#,
+            id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(Some(CollectionWithId {
+            id: rep.id,
+            data: Collection {
+                name: rep.name,
+                description: rep.description,
+                entries: entries.into_iter().map(EntryRep::model).collect(),
+            },
+        }))
+    }
+
+    /// Replaces the collection's entries, numbering them in the given order.
+    async fn set_entries(
+        transaction: &mut Transaction<'_, Postgres>,
+        id: i64,
+        entries: &[CollectionEntry],
+    ) -> Result<()> {
+        let recipe_ids = entries
+            .iter()
+            .map(|entry| entry.recipe_id)
+            .collect::<Vec<_>>();
+        let notes = entries
+            .iter()
+            .map(|entry| entry.note.clone().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let missing = sqlx::query!(
+            r#"
+                SELECT entry.recipe_id as "recipe_id!"
+                FROM UNNEST($1::bigint[]) AS entry(recipe_id)
+                WHERE NOT EXISTS (SELECT 1 FROM recipes WHERE recipes.id = entry.recipe_id)
+                LIMIT 1
+            "#,
+            &recipe_ids[..]
+        )
+        .fetch_optional(transaction.as_mut())
+        .await?;
+        if let Some(missing) = missing {
+            return Err(Error::MissingRecipe(missing.recipe_id));
+        }
+        sqlx::query!(
+            "DELETE FROM collection_recipes WHERE collection_id = $1",
+            id
+        )
+        .execute(transaction.as_mut())
+        .await?;
+        sqlx::query!(
+            r#"
+                INSERT INTO collection_recipes (collection_id, recipe_id, position, note)
+                SELECT $1, entry.recipe_id, entry.position, NULLIF(entry.note, '')
+                FROM UNNEST($2::bigint[], $3::text[])
+                    WITH ORDINALITY AS entry(recipe_id, note, position)
+            "#,
+            id,
+            &recipe_ids[..],
+            &notes[..]
+        )
+        .execute(transaction.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    async fn insert(&self, collection: &Collection) -> Result<i64> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO collections (name, description)
+                VALUES ($1, $2)
+                RETURNING id
+            "#,
+            collection.name,
+            collection.description
+        )
+        .fetch_one(transaction.as_mut())
+        .await?;
+        Self::set_entries(&mut transaction, rec.id, &collection.entries).await?;
+        transaction.commit().await?;
+        Ok(rec.id)
+    }
+
+    async fn update(&self, id: i64, collection: &Collection) -> Result<Option<i64>> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let Some(rec) = sqlx::query!(
+            r#"
+                UPDATE collections
+                SET name = $2, description = $3, updated = NOW()
+                WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            collection.name,
+            collection.description
+        )
+        .fetch_optional(transaction.as_mut())
+        .await?
+        else {
+            return Ok(None);
+        };
+        Self::set_entries(&mut transaction, rec.id, &collection.entries).await?;
+        transaction.commit().await?;
+        Ok(Some(rec.id))
+    }
+
+    /// Moves the collection's recipes into the order of `recipe_ids`, which
+    /// must hold each of them once.
+    async fn reorder(&self, id: i64, recipe_ids: &[i64]) -> Result<()> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        sqlx::query!("SELECT id FROM collections WHERE id = $1 FOR UPDATE", id)
+            .fetch_optional(transaction.as_mut())
+            .await?
+            .ok_or(Error::Missing(id))?;
+        let current = sqlx::query!(
+            "SELECT recipe_id FROM collection_recipes WHERE collection_id = $1",
+            id
+        )
+        .fetch_all(transaction.as_mut())
+        .await?
+        .into_iter()
+        .map(|rec| rec.recipe_id)
+        .collect::<HashSet<_>>();
+        let requested = recipe_ids.iter().copied().collect::<HashSet<_>>();
+        if requested.len() != recipe_ids.len() || requested != current {
+            return Err(Error::InvalidOrder);
+        }
+        sqlx::query!(
+            r#"
+                UPDATE collection_recipes
+                SET position = entry.position
+                FROM UNNEST($2::bigint[]) WITH ORDINALITY AS entry(recipe_id, position)
+                WHERE collection_recipes.collection_id = $1
+                    AND collection_recipes.recipe_id = entry.recipe_id
+            "#,
+            id,
+            recipe_ids
+        )
+        .execute(transaction.as_mut())
+        .await?;
+        sqlx::query!("UPDATE collections SET updated = NOW() WHERE id = $1", id)
+            .execute(transaction.as_mut())
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Adds a recipe to the end of the collection, or updates its note if it
+    /// is already there.
+    async fn add_entry(&self, id: i64, entry: &CollectionEntry) -> Result<()> {
+        let pool = self.db_access.get_pool();
+        let exists = sqlx::query!(
+            r#"
+                SELECT
+                    EXISTS (SELECT 1 FROM collections WHERE id = $1) as "collection!",
+                    EXISTS (SELECT 1 FROM recipes WHERE id = $2) as "recipe!"
+            "#,
+            id,
+            entry.recipe_id
+        )
+        .fetch_one(pool)
+        .await?;
+        if !exists.collection {
+            return Err(Error::Missing(id));
+        }
+        if !exists.recipe {
+            return Err(Error::MissingRecipe(entry.recipe_id));
+        }
+        sqlx::query!(
+            r#"
+                INSERT INTO collection_recipes (collection_id, recipe_id, position, note)
+                SELECT $1, $2, COALESCE(MAX(position), 0) + 1, $3
+                FROM collection_recipes
+                WHERE collection_id = $1
+                ON CONFLICT (collection_id, recipe_id) DO UPDATE SET note = EXCLUDED.note
+            "#,
+            id,
+            entry.recipe_id,
+            entry.note
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_entry(&self, id: i64, recipe_id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                DELETE FROM collection_recipes
+                WHERE collection_id = $1 AND recipe_id = $2
+                RETURNING recipe_id
+            "#,
+            id,
+            recipe_id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.recipe_id))
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM collections WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+pub(crate) async fn get_collections(
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<Vec<CollectionWithId>>> {
+    Ok(collection_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_collection(
+    Path(id): Path<i64>,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<CollectionWithId>> {
+    let collection = collection_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(collection.into())
+}
+
+/// The collection's recipes in order.
+pub(crate) async fn get_collection_recipes(
+    Path(id): Path<i64>,
+    collection_access: Arc<CollectionAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<Vec<RecipeWithId>>> {
+    collection_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(recipe_access.get_by_collection(id).await?.into())
+}
+
+pub(crate) async fn put_collection(
+    HttpJson(collection): HttpJson<Collection>,
+    _user: AuthUser,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = collection_access.insert(&normalise(collection)?).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_collection(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(collection): HttpJson<Collection>,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = collection_access
+        .update(id, &normalise(collection)?)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_collection(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<()>> {
+    collection_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
+
+/// Takes the collection's recipe ids in their new order.
+pub(crate) async fn reorder_collection(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(recipe_ids): HttpJson<Vec<i64>>,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<()>> {
+    collection_access.reorder(id, &recipe_ids).await?;
+    Ok(().into())
+}
+
+pub(crate) async fn put_collection_entry(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(entry): HttpJson<CollectionEntry>,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<()>> {
+    collection_access
+        .add_entry(id, &normalise_entry(entry))
+        .await?;
+    Ok(().into())
+}
+
+pub(crate) async fn delete_collection_entry(
+    Path((id, recipe_id)): Path<(i64, i64)>,
+    _user: AuthUser,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<HttpJson<()>> {
+    collection_access
+        .remove_entry(id, recipe_id)
+        .await?
+        .ok_or(Error::NotInCollection(recipe_id))?;
+    Ok(().into())
+}
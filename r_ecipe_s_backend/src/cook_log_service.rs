@@ -0,0 +1,550 @@
+use crate::{
+    auth::AuthUser,
+    db::DbAccess,
+    image_service::{recipe_images_prefix, sniff_content_type},
+    image_store::ImageStore,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::{DefaultBodyLimit, Path},
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::cook_log::{is_rating, CookLogEntry, CookLogEntryWithId, CookingStats};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+use time::Date;
+use tracing::log::warn;
+
+/// Photos straight off a phone camera can be several megabytes.
+const PHOTO_LIMIT: usize = 16 * 1024 * 1024;
+const PHOTO_VARIANT: &str = "photo";
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Missing cook log entry with id: {0}")]
+    Missing(i64),
+    #[error("No photo of cook log entry with id: {0}")]
+    MissingPhoto(i64),
+    #[error("Ratings are from 1 to 5, not {0}")]
+    InvalidRating(u8),
+    #[error("Only PNG, JPEG, GIF and WebP photos can be uploaded")]
+    NotAnImage,
+    #[error("Error with image storage: {0}")]
+    Storage(#[from] std::io::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::MissingPhoto(_) => http::StatusCode::NOT_FOUND,
+            Error::InvalidRating(_) => http::StatusCode::BAD_REQUEST,
+            Error::NotAnImage => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::Storage(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait CookLogService {
+    type ServiceType;
+    fn bind_cook_log_routes(
+        self,
+        cook_log_access: &Arc<CookLogAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError> CookLogService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Bytes> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+{
+    type ServiceType = Self;
+    fn bind_cook_log_routes(
+        self,
+        cook_log_access: &Arc<CookLogAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/cook-log",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                || get_cook_log(cook_log_access)
+            })
+            .put({
+                let cook_log_access = cook_log_access.clone();
+                let recipe_access = recipe_access.clone();
+                |form, user| put_cook_log_entry(form, user, cook_log_access, recipe_access)
+            }),
+        )
+        .route(
+            "/cook-log/stats",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                || get_all_stats(cook_log_access)
+            }),
+        )
+        .route(
+            "/cook-log/:id",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                |path| get_cook_log_entry(path, cook_log_access)
+            })
+            .post({
+                let cook_log_access = cook_log_access.clone();
+                let recipe_access = recipe_access.clone();
+                let image_store = image_store.clone();
+                |path, user, form| {
+                    post_cook_log_entry(
+                        path,
+                        user,
+                        form,
+                        cook_log_access,
+                        recipe_access,
+                        image_store,
+                    )
+                }
+            })
+            .delete({
+                let cook_log_access = cook_log_access.clone();
+                let image_store = image_store.clone();
+                |path, user| delete_cook_log_entry(path, user, cook_log_access, image_store)
+            }),
+        )
+        .route(
+            "/cook-log/:id/photo",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                let image_store = image_store.clone();
+                |path| get_photo(path, cook_log_access, image_store)
+            })
+            .put({
+                let cook_log_access = cook_log_access.clone();
+                let image_store = image_store.clone();
+                |path, user, photo| put_photo(path, user, photo, cook_log_access, image_store)
+            })
+            .layer(DefaultBodyLimit::max(PHOTO_LIMIT)),
+        )
+        .route(
+            "/recipes/:id/cook-log",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                |path| get_recipe_cook_log(path, cook_log_access)
+            }),
+        )
+        .route(
+            "/recipes/:id/cook-stats",
+            get({
+                let cook_log_access = cook_log_access.clone();
+                |path| get_recipe_stats(path, cook_log_access)
+            }),
+        )
+    }
+}
+
+pub struct CookLogAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct CookLogRep {
+    id: i64,
+    recipe_id: i64,
+    cooked: Date,
+    cook: Option<String>,
+    rating: Option<i16>,
+    notes: Option<String>,
+    photo: bool,
+}
+
+impl CookLogRep {
+    fn model_with_id(self) -> CookLogEntryWithId {
+        CookLogEntryWithId {
+            id: self.id,
+            data: CookLogEntry {
+                recipe_id: self.recipe_id,
+                cooked: self.cooked,
+                cook: self.cook,
+                rating: self.rating.and_then(|rating| u8::try_from(rating).ok()),
+                notes: self.notes,
+            },
+            photo: self.photo,
+        }
+    }
+}
+
+struct StoredPhotoRep {
+    id: i64,
+    recipe_id: i64,
+    photo: Option<Vec<u8>>,
+}
+
+impl CookLogAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        CookLogAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    /// Every entry, the most recently cooked first, or only those of one
+    /// recipe.
+    async fn get_all(&self, recipe_id: Option<i64>) -> Result<Vec<CookLogEntryWithId>> {
+        Ok(sqlx::query_as!(
+            CookLogRep,
+            r#"
+                SELECT id, recipe_id, cooked, cook, rating, notes, photo_type IS NOT NULL as "photo!"
+                FROM cook_log
+                WHERE $1::int8 IS NULL OR recipe_id = $1
+                ORDER BY cooked DESC, id DESC
+            "#,
+            recipe_id
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(CookLogRep::model_with_id)
+        .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<CookLogEntryWithId>> {
+        Ok(sqlx::query_as!(
+            CookLogRep,
+            r#"
+                SELECT id, recipe_id, cooked, cook, rating, notes, photo_type IS NOT NULL as "photo!"
+                FROM cook_log
+                WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(CookLogRep::model_with_id))
+    }
+
+    /// The stats of every recipe that has been cooked, or of only one.
+    async fn get_stats(&self, recipe_id: Option<i64>) -> Result<Vec<CookingStats>> {
+        Ok(sqlx::query_as!(
+            CookingStats,
+            r#"
+                SELECT recipe_id, COUNT(id) as "times_cooked!", MAX(cooked) as last_cooked,
+                    AVG(rating)::float8 as average_rating
+                FROM cook_log
+                WHERE $1::int8 IS NULL OR recipe_id = $1
+                GROUP BY recipe_id
+                ORDER BY recipe_id
+            "#,
+            recipe_id
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?)
+    }
+
+    async fn insert(&self, entry: &CookLogEntry) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO cook_log (recipe_id, cooked, cook, rating, notes)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id
+            "#,
+            entry.recipe_id,
+            entry.cooked,
+            entry.cook,
+            entry.rating.map(i16::from),
+            entry.notes
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    async fn update(&self, id: i64, entry: &CookLogEntry) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                UPDATE cook_log
+                SET recipe_id = $2, cooked = $3, cook = $4, rating = $5, notes = $6
+                WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            entry.recipe_id,
+            entry.cooked,
+            entry.cook,
+            entry.rating.map(i16::from),
+            entry.notes
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Deletes the entry, giving the recipe it was of.
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM cook_log WHERE id = $1 RETURNING recipe_id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.recipe_id))
+    }
+
+    /// The recipe the entry is of and its photo's content type, if it has
+    /// one.
+    async fn get_photo_type(&self, id: i64) -> Result<Option<(i64, Option<String>)>> {
+        let rec = sqlx::query!(
+            "SELECT recipe_id, photo_type FROM cook_log WHERE id = $1",
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| (rec.recipe_id, rec.photo_type)))
+    }
+
+    async fn set_photo_type(&self, id: i64, photo_type: Option<&str>) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            "UPDATE cook_log SET photo = NULL, photo_type = $2 WHERE id = $1 RETURNING id",
+            id,
+            photo_type
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Moves photos kept in the table by earlier versions into the image
+    /// store, dropping any that aren't a supported image. Gives how many were
+    /// moved.
+    pub async fn move_photos(&self, image_store: &dyn ImageStore) -> Result<usize> {
+        let photos = sqlx::query_as!(
+            StoredPhotoRep,
+            "SELECT id, recipe_id, photo FROM cook_log WHERE photo IS NOT NULL"
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?;
+        let mut moved = 0;
+        for StoredPhotoRep {
+            id,
+            recipe_id,
+            photo,
+        } in photos
+        {
+            let photo = photo.unwrap_or_default();
+            let photo_type = sniff_content_type(&photo);
+            match photo_type {
+                Some(_) => {
+                    image_store.put(&photo_key(recipe_id, id), &photo).await?;
+                    moved += 1;
+                }
+                None => warn!("Dropped the photo of cook log entry {id}, as it isn't an image"),
+            }
+            self.set_photo_type(id, photo_type).await?;
+        }
+        Ok(moved)
+    }
+}
+
+/// Where the photo of an entry of the recipe is kept, alongside the
+/// recipe's images so that it goes when they do.
+fn photo_prefix(recipe_id: i64, id: i64) -> String {
+    format!("{}/cook-log/{id}", recipe_images_prefix(recipe_id))
+}
+
+fn photo_key(recipe_id: i64, id: i64) -> String {
+    format!("{}/{PHOTO_VARIANT}", photo_prefix(recipe_id, id))
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn normalise(entry: CookLogEntry) -> Result<CookLogEntry> {
+    if let Some(rating) = entry.rating.filter(|rating| !is_rating(*rating)) {
+        return Err(Error::InvalidRating(rating));
+    }
+    Ok(CookLogEntry {
+        cook: non_empty(entry.cook),
+        notes: non_empty(entry.notes),
+        ..entry
+    })
+}
+
+async fn check_recipe(recipe_access: &RecipeAccess, id: i64) -> Result<()> {
+    recipe_access
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| recipe_service::Error::Missing {
+            item_type: "recipe".to_string(),
+            id,
+        })?;
+    Ok(())
+}
+
+/// Every time anything was cooked, the most recent first.
+pub(crate) async fn get_cook_log(
+    cook_log_access: Arc<CookLogAccess>,
+) -> Result<HttpJson<Vec<CookLogEntryWithId>>> {
+    Ok(cook_log_access.get_all(None).await?.into())
+}
+
+pub(crate) async fn get_recipe_cook_log(
+    Path(id): Path<i64>,
+    cook_log_access: Arc<CookLogAccess>,
+) -> Result<HttpJson<Vec<CookLogEntryWithId>>> {
+    Ok(cook_log_access.get_all(Some(id)).await?.into())
+}
+
+/// The stats of every recipe that has been cooked.
+pub(crate) async fn get_all_stats(
+    cook_log_access: Arc<CookLogAccess>,
+) -> Result<HttpJson<Vec<CookingStats>>> {
+    Ok(cook_log_access.get_stats(None).await?.into())
+}
+
+/// The recipe's stats, which are empty if it hasn't been cooked.
+pub(crate) async fn get_recipe_stats(
+    Path(id): Path<i64>,
+    cook_log_access: Arc<CookLogAccess>,
+) -> Result<HttpJson<CookingStats>> {
+    let stats = cook_log_access
+        .get_stats(Some(id))
+        .await?
+        .pop()
+        .unwrap_or(CookingStats {
+            recipe_id: id,
+            ..CookingStats::default()
+        });
+    Ok(stats.into())
+}
+
+pub(crate) async fn get_cook_log_entry(
+    Path(id): Path<i64>,
+    cook_log_access: Arc<CookLogAccess>,
+) -> Result<HttpJson<CookLogEntryWithId>> {
+    let entry = cook_log_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(entry.into())
+}
+
+pub(crate) async fn put_cook_log_entry(
+    HttpJson(entry): HttpJson<CookLogEntry>,
+    user: AuthUser,
+    cook_log_access: Arc<CookLogAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<i64>> {
+    let mut entry = normalise(entry)?;
+    // Whoever is signed in cooked it, unless someone else is named.
+    entry.cook = entry.cook.or(Some(user.username));
+    check_recipe(&recipe_access, entry.recipe_id).await?;
+    Ok(cook_log_access.insert(&entry).await?.into())
+}
+
+/// Updates the entry, moving its photo along if it's now of another recipe.
+pub(crate) async fn post_cook_log_entry(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(entry): HttpJson<CookLogEntry>,
+    cook_log_access: Arc<CookLogAccess>,
+    recipe_access: Arc<RecipeAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<HttpJson<i64>> {
+    let entry = normalise(entry)?;
+    check_recipe(&recipe_access, entry.recipe_id).await?;
+    let (recipe_id, photo_type) = cook_log_access
+        .get_photo_type(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    let id = cook_log_access
+        .update(id, &entry)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    if photo_type.is_some() && recipe_id != entry.recipe_id {
+        if let Some(photo) = image_store.get(&photo_key(recipe_id, id)).await? {
+            image_store
+                .put(&photo_key(entry.recipe_id, id), &photo)
+                .await?;
+        }
+        image_store
+            .delete_prefix(&photo_prefix(recipe_id, id))
+            .await?;
+    }
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_cook_log_entry(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    cook_log_access: Arc<CookLogAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<HttpJson<()>> {
+    let recipe_id = cook_log_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    image_store
+        .delete_prefix(&photo_prefix(recipe_id, id))
+        .await?;
+    Ok(().into())
+}
+
+/// The entry's photo. It was checked to be an image when uploaded, and
+/// browsers are told not to take it for anything else.
+pub(crate) async fn get_photo(
+    Path(id): Path<i64>,
+    cook_log_access: Arc<CookLogAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<Response> {
+    let (recipe_id, photo_type) = cook_log_access
+        .get_photo_type(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    let photo_type = photo_type.ok_or(Error::MissingPhoto(id))?;
+    let photo = image_store
+        .get(&photo_key(recipe_id, id))
+        .await?
+        .ok_or(Error::MissingPhoto(id))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, photo_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+        ],
+        photo,
+    )
+        .into_response())
+}
+
+/// Replaces the entry's photo with the request body, which must be a PNG,
+/// JPEG, GIF or WebP image.
+pub(crate) async fn put_photo(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    photo: Bytes,
+    cook_log_access: Arc<CookLogAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<HttpJson<()>> {
+    let photo_type = sniff_content_type(&photo).ok_or(Error::NotAnImage)?;
+    let (recipe_id, _) = cook_log_access
+        .get_photo_type(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    image_store.put(&photo_key(recipe_id, id), &photo).await?;
+    cook_log_access
+        .set_photo_type(id, Some(photo_type))
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
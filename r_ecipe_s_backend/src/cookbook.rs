@@ -0,0 +1,304 @@
+use crate::document::Paper;
+use crate::pdf::{text_width, truncate, wrap, Font, PdfDocument};
+use r_ecipe_s_model::{method::list_item, RecipeWithId};
+use std::collections::BTreeMap;
+
+const MARGIN: f32 = 56.0;
+const FOOTER: f32 = 28.0;
+const LEADING: f32 = 1.35;
+const TITLE_SIZE: f32 = 20.0;
+const HEADING_SIZE: f32 = 13.0;
+const SUBHEADING_SIZE: f32 = 11.5;
+const BODY_SIZE: f32 = 10.5;
+const INDENT: f32 = 14.0;
+
+/// Lays out text top to bottom, starting new pages as they fill up.
+struct Flow {
+    document: PdfDocument,
+    page: usize,
+    y: f32,
+}
+
+impl Flow {
+    fn new(document: PdfDocument) -> Self {
+        Flow {
+            document,
+            page: 0,
+            y: 0.0,
+        }
+    }
+
+    fn width(&self) -> f32 {
+        self.document.width() - 2.0 * MARGIN
+    }
+
+    fn top(&self) -> f32 {
+        self.document.height() - MARGIN
+    }
+
+    fn new_page(&mut self) -> usize {
+        self.page = self.document.add_page();
+        self.y = self.top();
+        self.page
+    }
+
+    /// Moves down by `height`, first starting a new page if it doesn't fit.
+    fn advance(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+        self.y -= height;
+    }
+
+    fn space(&mut self, height: f32) {
+        if self.y < self.top() {
+            self.y = (self.y - height).max(MARGIN);
+        }
+    }
+
+    /// Writes wrapped text indented by `indent`, with `marker` such as a
+    /// bullet hanging in the indent of its first line.
+    fn text(&mut self, text: &str, font: Font, size: f32, indent: f32, marker: Option<&str>) {
+        let width = self.width() - indent;
+        let lines = wrap(text, font, size, width);
+        for (index, line) in lines.iter().enumerate() {
+            self.advance(size * LEADING);
+            let page = self.document.page(self.page);
+            if let (0, Some(marker)) = (index, marker) {
+                page.text(
+                    MARGIN + indent - INDENT,
+                    self.y,
+                    Font::Regular,
+                    size,
+                    marker,
+                );
+            }
+            page.text(MARGIN + indent, self.y, font, size, line);
+        }
+    }
+
+    fn heading(&mut self, text: &str, size: f32) {
+        self.space(size * 0.6);
+        // Keep a heading together with at least two lines of what follows.
+        if self.y - size * LEADING - 2.0 * BODY_SIZE * LEADING < MARGIN {
+            self.new_page();
+        }
+        self.text(text, Font::Bold, size, 0.0, None);
+        self.space(size * 0.25);
+    }
+
+    /// Writes `left` and `right` on one line, `right` aligned to the margin,
+    /// and returns the line's baseline.
+    fn row(&mut self, left: &str, font: Font, right: &str) -> f32 {
+        let right_width = text_width(right, Font::Regular, BODY_SIZE);
+        let left = truncate(left, font, BODY_SIZE, self.width() - right_width - INDENT);
+        self.advance(BODY_SIZE * LEADING);
+        let right_x = self.document.width() - MARGIN - right_width;
+        let page = self.document.page(self.page);
+        page.text(MARGIN, self.y, font, BODY_SIZE, &left);
+        page.text(right_x, self.y, Font::Regular, BODY_SIZE, right);
+        self.y
+    }
+}
+
+/// Removes the markdown emphasis marks that would otherwise be printed.
+fn plain(text: &str) -> String {
+    text.replace("**", "").replace("__", "").replace('`', "")
+}
+
+fn write_method(flow: &mut Flow, description: &str) {
+    let mut step = 0;
+    for line in description.lines().map(str::trim) {
+        if line.is_empty() {
+            flow.space(BODY_SIZE * 0.5);
+        } else if line.starts_with('#') {
+            step = 0;
+            flow.heading(&plain(line.trim_start_matches('#').trim()), SUBHEADING_SIZE);
+        } else if let Some(item) = list_item(line) {
+            let marker = if line.starts_with(|c: char| c.is_ascii_digit()) {
+                step += 1;
+                format!("{step}.")
+            } else {
+                "•".to_string()
+            };
+            flow.text(
+                &plain(item),
+                Font::Regular,
+                BODY_SIZE,
+                INDENT,
+                Some(&marker),
+            );
+        } else {
+            flow.text(&plain(line), Font::Regular, BODY_SIZE, 0.0, None);
+        }
+    }
+}
+
+fn write_recipe(flow: &mut Flow, recipe: &RecipeWithId) {
+    let recipe = &recipe.data;
+    flow.text(recipe.name.trim(), Font::Bold, TITLE_SIZE, 0.0, None);
+    if let Some(servings) = recipe.servings {
+        flow.text(
+            &format!("Serves {servings}"),
+            Font::Italic,
+            BODY_SIZE,
+            0.0,
+            None,
+        );
+    }
+    let timing = recipe
+        .timing
+        .summary()
+        .into_iter()
+        .map(|(label, value)| format!("{label} {value}"))
+        .collect::<Vec<_>>();
+    if !timing.is_empty() {
+        flow.text(&timing.join(" · "), Font::Italic, BODY_SIZE, 0.0, None);
+    }
+    if !recipe.equipment.is_empty() {
+        flow.text(
+            &format!("Equipment: {}", recipe.equipment.join(", ")),
+            Font::Italic,
+            BODY_SIZE,
+            0.0,
+            None,
+        );
+    }
+    flow.space(BODY_SIZE);
+    if !recipe.ingredients.is_empty() {
+        flow.heading("Ingredients", HEADING_SIZE);
+        for ingredient in &recipe.ingredients {
+            let line = ingredient.to_string();
+            flow.text(&line, Font::Regular, BODY_SIZE, INDENT, Some("•"));
+        }
+    }
+    if !recipe.description.trim().is_empty() {
+        flow.heading("Method", HEADING_SIZE);
+        write_method(flow, &recipe.description);
+    }
+}
+
+fn write_cover(flow: &mut Flow, title: &str, subtitle: &str) {
+    let page = flow.new_page();
+    let width = flow.document.width();
+    let mut y = flow.document.height() * 0.62;
+    for line in wrap(title, Font::Bold, 30.0, flow.width()) {
+        let x = (width - text_width(&line, Font::Bold, 30.0)) / 2.0;
+        flow.document.page(page).text(x, y, Font::Bold, 30.0, &line);
+        y -= 30.0 * LEADING;
+    }
+    let rule = width / 4.0;
+    flow.document
+        .page(page)
+        .line((rule, y + 8.0), (width - rule, y + 8.0), 0.8);
+    let x = (width - text_width(subtitle, Font::Italic, 13.0)) / 2.0;
+    flow.document
+        .page(page)
+        .text(x, y - 16.0, Font::Italic, 13.0, subtitle);
+}
+
+fn page_list(pages: &[usize]) -> String {
+    pages
+        .iter()
+        .map(|page| page.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders recipes as a cookbook: a cover, a table of contents linking to each
+/// recipe, every recipe starting on its own page and an index of ingredients
+/// with the pages that use them.
+pub(crate) fn cookbook_pdf(
+    title: &str,
+    subtitle: &str,
+    recipes: &[RecipeWithId],
+    paper: Paper,
+) -> std::io::Result<Vec<u8>> {
+    let mut flow = Flow::new(PdfDocument::new(title, paper.points()));
+    write_cover(&mut flow, title, subtitle);
+
+    // The contents come before the recipes but list their pages, so room is
+    // left for them and they are written once the recipes are laid out.
+    let row_height = BODY_SIZE * LEADING;
+    let contents_rows = recipes.len() + 1;
+    let first_rows = ((flow.top() - MARGIN - TITLE_SIZE * 2.0) / row_height) as usize;
+    let rows_per_page = ((flow.top() - MARGIN) / row_height) as usize;
+    let contents_pages = 1 + contents_rows
+        .saturating_sub(first_rows)
+        .div_ceil(rows_per_page);
+    let contents_start = flow.document.page_count();
+    for _ in 0..contents_pages {
+        flow.new_page();
+    }
+
+    let mut recipe_pages = Vec::with_capacity(recipes.len());
+    let mut index: BTreeMap<String, (String, Vec<usize>)> = BTreeMap::new();
+    for recipe in recipes {
+        let page = flow.new_page();
+        recipe_pages.push(page);
+        write_recipe(&mut flow, recipe);
+        for ingredient in &recipe.data.ingredients {
+            let name = ingredient.name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let (_, pages) = index
+                .entry(name.to_lowercase())
+                .or_insert_with(|| (name.to_string(), Vec::new()));
+            if pages.last() != Some(&(page + 1)) {
+                pages.push(page + 1);
+            }
+        }
+    }
+
+    let index_page = flow.new_page();
+    flow.text("Index of ingredients", Font::Bold, TITLE_SIZE, 0.0, None);
+    flow.space(BODY_SIZE);
+    let mut letter = None;
+    for (key, (name, pages)) in &index {
+        let initial = key.chars().next().map(|c| c.to_uppercase().to_string());
+        if initial != letter {
+            flow.heading(initial.as_deref().unwrap_or_default(), HEADING_SIZE);
+            letter = initial;
+        }
+        let pages = page_list(pages);
+        let pages_width = text_width(&pages, Font::Regular, BODY_SIZE);
+        if pages_width > flow.width() / 2.0 {
+            flow.text(name, Font::Regular, BODY_SIZE, 0.0, None);
+            flow.text(&pages, Font::Regular, BODY_SIZE, INDENT, None);
+        } else {
+            flow.row(name, Font::Regular, &pages);
+        }
+    }
+
+    flow.page = contents_start;
+    flow.y = flow.top();
+    flow.text("Contents", Font::Bold, TITLE_SIZE, 0.0, None);
+    flow.space(TITLE_SIZE * 2.0 - TITLE_SIZE * LEADING);
+    let entries = recipes
+        .iter()
+        .map(|recipe| recipe.data.name.trim())
+        .zip(recipe_pages.iter().copied())
+        .chain([("Index of ingredients", index_page)]);
+    for (name, page) in entries {
+        if flow.y - row_height < MARGIN {
+            flow.page += 1;
+            flow.y = flow.top();
+        }
+        let y = flow.row(name, Font::Regular, &(page + 1).to_string());
+        let right = flow.document.width() - MARGIN;
+        flow.document
+            .page(flow.page)
+            .link((MARGIN, y - 3.0), (right, y + BODY_SIZE), page);
+    }
+
+    let width = flow.document.width();
+    for page in 1..flow.document.page_count() {
+        let number = (page + 1).to_string();
+        let x = (width - text_width(&number, Font::Regular, 9.0)) / 2.0;
+        flow.document
+            .page(page)
+            .text(x, FOOTER, Font::Regular, 9.0, &number);
+    }
+    flow.document.to_bytes()
+}
@@ -0,0 +1,150 @@
+use crate::{
+    auth::AuthUser,
+    collection_service::{self, CollectionAccess},
+    cookbook::cookbook_pdf,
+    document::Paper,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::HttpBody,
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::tags::normalise_tag;
+use serde::Deserialize;
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+const PDF_CONTENT_TYPE: &str = "application/pdf";
+const DEFAULT_TITLE: &str = "Cookbook";
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("{0}")]
+    Collection(#[from] collection_service::Error),
+    #[error("Failed to write cookbook: {0}")]
+    Pdf(#[from] std::io::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::Recipe(err) => return err.into_response(),
+            Error::Collection(err) => return err.into_response(),
+            Error::Pdf(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait CookbookService {
+    type ServiceType;
+    fn bind_cookbook_routes(
+        self,
+        recipe_access: &Arc<RecipeAccess>,
+        collection_access: &Arc<CollectionAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> CookbookService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_cookbook_routes(
+        self,
+        recipe_access: &Arc<RecipeAccess>,
+        collection_access: &Arc<CollectionAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/cookbook",
+            post({
+                let recipe_access = recipe_access.clone();
+                let collection_access = collection_access.clone();
+                |user, request| create_cookbook(user, request, recipe_access, collection_access)
+            }),
+        )
+    }
+}
+
+/// Recipes to put in a cookbook, in order, or the recipes of a collection or
+/// with a tag. When none of these are given the whole library goes in.
+#[derive(Deserialize, Debug, Default)]
+pub struct CookbookRequest {
+    title: Option<String>,
+    #[serde(default)]
+    ids: Vec<i64>,
+    collection: Option<i64>,
+    tag: Option<String>,
+    #[serde(default)]
+    paper: Paper,
+}
+
+/// Generates a PDF cookbook of the requested recipes.
+pub(crate) async fn create_cookbook(
+    _user: AuthUser,
+    HttpJson(request): HttpJson<CookbookRequest>,
+    recipe_access: Arc<RecipeAccess>,
+    collection_access: Arc<CollectionAccess>,
+) -> Result<Response> {
+    let collection = match request.collection {
+        Some(id) => Some(
+            collection_access
+                .get_by_id(id)
+                .await?
+                .ok_or(collection_service::Error::Missing(id))?,
+        ),
+        None => None,
+    };
+    let tag = request.tag.as_deref().and_then(normalise_tag);
+    let recipes = match (&collection, &tag) {
+        _ if !request.ids.is_empty() => recipe_access.get_by_ids(&request.ids).await?,
+        (Some(collection), _) => recipe_access.get_by_collection(collection.id).await?,
+        (None, Some(tag)) => recipe_access.get_by_tag(tag).await?,
+        (None, None) => recipe_access.get_all_recipes().await?,
+    };
+    let title = request
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .or(collection
+            .as_ref()
+            .map(|collection| collection.data.name.as_str()))
+        .unwrap_or(DEFAULT_TITLE);
+    let date = OffsetDateTime::now_utc().date();
+    let subtitle = format!(
+        "{} {}, {} {} {}",
+        recipes.len(),
+        if recipes.len() == 1 {
+            "recipe"
+        } else {
+            "recipes"
+        },
+        date.day(),
+        date.month(),
+        date.year()
+    );
+    let body = cookbook_pdf(title, &subtitle, &recipes, request.paper)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, PDF_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"cookbook.pdf\"".to_string(),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,99 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use r_ecipe_s_model::{document::recipe_to_markdown, Recipe};
+use serde::Deserialize;
+
+pub(crate) const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+pub(crate) const HTML_EXTENSION: &str = "html";
+
+/// Paper size printed documents are laid out for.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Paper {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl Paper {
+    /// Width and height in points.
+    pub(crate) fn points(self) -> (f32, f32) {
+        match self {
+            Paper::A4 => (595.28, 841.89),
+            Paper::Letter => (612.0, 792.0),
+        }
+    }
+
+    fn css_size(self) -> &'static str {
+        match self {
+            Paper::A4 => "A4",
+            Paper::Letter => "letter",
+        }
+    }
+}
+
+const STYLE: &str = "
+body { font-family: Georgia, 'Times New Roman', serif; font-size: 11pt; line-height: 1.45; color: #222; max-width: 42em; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 22pt; margin: 0 0 0.2em; border-bottom: 1px solid #999; padding-bottom: 0.2em; }
+h2 { font-size: 14pt; margin: 1.2em 0 0.4em; }
+h3 { font-size: 12pt; margin: 1em 0 0.3em; }
+ul { columns: 2; column-gap: 2em; }
+li { break-inside: avoid; margin-bottom: 0.2em; }
+ol li { margin-bottom: 0.5em; }
+@media print { body { margin: 0; padding: 0; max-width: none; } a { color: inherit; text-decoration: none; } }
+";
+
+/// Whether a link or image address could run script when followed.
+fn is_script_url(url: &str) -> bool {
+    let scheme = url.trim_start().split(':').next().unwrap_or_default();
+    url.contains(':')
+        && ["javascript", "vbscript", "data"]
+            .iter()
+            .any(|script| scheme.eq_ignore_ascii_case(script))
+}
+
+/// Renders markdown written by users, so any HTML in it is shown as text
+/// rather than run, and links that would run script go nowhere.
+pub(crate) fn markdown_to_html(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) => Event::Text(html),
+        Event::Start(Tag::Link(kind, url, title)) if is_script_url(&url) => {
+            Event::Start(Tag::Link(kind, "".into(), title))
+        }
+        Event::Start(Tag::Image(kind, url, title)) if is_script_url(&url) => {
+            Event::Start(Tag::Image(kind, "".into(), title))
+        }
+        event => event,
+    });
+
+    let mut html_output = String::with_capacity(markdown.len() * 2);
+    html::push_html(&mut html_output, parser);
+    html_output
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a recipe as a self-contained HTML page, styled inline so it can be
+/// printed or emailed without the app.
+pub(crate) fn recipe_to_html(recipe: &Recipe, paper: Paper) -> String {
+    let body = markdown_to_html(&recipe_to_markdown(recipe));
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n@page {{ size: {size}; margin: 18mm; }}{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        title = escape_html(recipe.name.trim()),
+        size = paper.css_size(),
+    )
+}
@@ -0,0 +1,296 @@
+use crate::{auth::AuthUser, db::DbAccess};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::equipment::{Equipment, EquipmentWithId};
+use r_ecipe_s_model::tags::{normalise_tag, normalise_tags};
+use sqlx::PgExecutor;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("Missing equipment with id: {0}")]
+    Missing(i64),
+    #[error("Equipment names can't be empty")]
+    EmptyName,
+    #[error("Equipment named {0} already exists")]
+    Conflict(String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyName => http::StatusCode::BAD_REQUEST,
+            Error::Conflict(_) => http::StatusCode::CONFLICT,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait EquipmentService {
+    type ServiceType;
+    fn bind_equipment_routes(self, equipment_access: &Arc<EquipmentAccess>) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> EquipmentService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_equipment_routes(self, equipment_access: &Arc<EquipmentAccess>) -> Self::ServiceType {
+        self.route(
+            "/equipment",
+            get({
+                let equipment_access = equipment_access.clone();
+                || get_all_equipment(equipment_access)
+            })
+            .put({
+                let equipment_access = equipment_access.clone();
+                |form, user| put_equipment(form, user, equipment_access)
+            }),
+        )
+        .route(
+            "/equipment/:id",
+            get({
+                let equipment_access = equipment_access.clone();
+                |path| get_equipment(path, equipment_access)
+            })
+            .post({
+                let equipment_access = equipment_access.clone();
+                |path, user, form| post_equipment(path, user, form, equipment_access)
+            })
+            .delete({
+                let equipment_access = equipment_access.clone();
+                |path, user| delete_equipment(path, user, equipment_access)
+            }),
+        )
+    }
+}
+
+pub struct EquipmentAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct EquipmentRep {
+    id: i64,
+    name: String,
+    synonyms: Vec<String>,
+    recipe_count: i64,
+}
+
+impl EquipmentRep {
+    fn model_with_id(self) -> EquipmentWithId {
+        EquipmentWithId {
+            id: self.id,
+            data: Equipment {
+                name: self.name,
+                synonyms: self.synonyms,
+            },
+            recipe_count: self.recipe_count,
+        }
+    }
+}
+
+impl EquipmentAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        EquipmentAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<EquipmentWithId>> {
+        Ok(Self::get_all_pool(self.db_access.get_pool()).await?)
+    }
+
+    pub(crate) async fn get_all_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+    ) -> sqlx::Result<Vec<EquipmentWithId>> {
+        let equipment = sqlx::query_as!(
+            EquipmentRep,
+            r#"
+                SELECT
+                    equipment.id,
+                    equipment.name,
+                    equipment.synonyms,
+                    COUNT(recipe_equipment.recipe_id) as "recipe_count!"
+                FROM equipment
+                LEFT JOIN recipe_equipment ON recipe_equipment.equipment_id = equipment.id
+                GROUP BY equipment.id
+                ORDER BY equipment.name
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(equipment
+            .into_iter()
+            .map(EquipmentRep::model_with_id)
+            .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<EquipmentWithId>> {
+        let equipment = sqlx::query_as!(
+            EquipmentRep,
+            r#"
+                SELECT
+                    equipment.id,
+                    equipment.name,
+                    equipment.synonyms,
+                    COUNT(recipe_equipment.recipe_id) as "recipe_count!"
+                FROM equipment
+                LEFT JOIN recipe_equipment ON recipe_equipment.equipment_id = equipment.id
+                WHERE equipment.id = $1
+                GROUP BY equipment.id
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(equipment.map(EquipmentRep::model_with_id))
+    }
+
+    /// Adds the equipment to the catalogue, or gives the existing entry with
+    /// that name the synonyms, returning its id.
+    async fn insert(&self, equipment: &Equipment) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO equipment (name, synonyms) VALUES ($1, $2)
+                ON CONFLICT (name) DO UPDATE SET synonyms = EXCLUDED.synonyms
+                RETURNING id
+            "#,
+            equipment.name,
+            &equipment.synonyms[..],
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    /// Renames the entry and replaces its synonyms, queueing its recipes to
+    /// be indexed again.
+    async fn update(&self, id: i64, equipment: &Equipment) -> Result<Option<i64>> {
+        let existing = sqlx::query!(
+            "SELECT id FROM equipment WHERE name = $1 AND id <> $2",
+            equipment.name,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        if existing.is_some() {
+            return Err(Error::Conflict(equipment.name.clone()));
+        }
+        let rec = sqlx::query!(
+            r#"
+                WITH reindexed AS (
+                    UPDATE recipes SET searchable = false
+                    WHERE id IN (
+                        SELECT recipe_id FROM recipe_equipment WHERE equipment_id = $1
+                    )
+                )
+                UPDATE equipment SET name = $2, synonyms = $3 WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            equipment.name,
+            &equipment.synonyms[..],
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Deletes the entry, removing it from its recipes, which are queued to
+    /// be indexed again.
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                WITH reindexed AS (
+                    UPDATE recipes SET searchable = false
+                    WHERE id IN (
+                        SELECT recipe_id FROM recipe_equipment WHERE equipment_id = $1
+                    )
+                )
+                DELETE FROM equipment WHERE id = $1
+                RETURNING id
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+/// The equipment with its name and synonyms normalised as tags are.
+fn normalised(equipment: Equipment) -> Result<Equipment> {
+    let name = normalise_tag(&equipment.name).ok_or(Error::EmptyName)?;
+    let synonyms = normalise_tags(&equipment.synonyms)
+        .into_iter()
+        .filter(|synonym| *synonym != name)
+        .collect();
+    Ok(Equipment { name, synonyms })
+}
+
+pub(crate) async fn get_all_equipment(
+    equipment_access: Arc<EquipmentAccess>,
+) -> Result<HttpJson<Vec<EquipmentWithId>>> {
+    Ok(equipment_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_equipment(
+    Path(id): Path<i64>,
+    equipment_access: Arc<EquipmentAccess>,
+) -> Result<HttpJson<EquipmentWithId>> {
+    let equipment = equipment_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(equipment.into())
+}
+
+pub(crate) async fn put_equipment(
+    HttpJson(equipment): HttpJson<Equipment>,
+    _user: AuthUser,
+    equipment_access: Arc<EquipmentAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = equipment_access.insert(&normalised(equipment)?).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_equipment(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(equipment): HttpJson<Equipment>,
+    equipment_access: Arc<EquipmentAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = equipment_access
+        .update(id, &normalised(equipment)?)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_equipment(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    equipment_access: Arc<EquipmentAccess>,
+) -> Result<HttpJson<()>> {
+    equipment_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
@@ -0,0 +1,255 @@
+use crate::document::{recipe_to_html, Paper, HTML_CONTENT_TYPE, HTML_EXTENSION};
+use crate::recipe_service::{self, RecipeAccess};
+use axum::{
+    body::{HttpBody, StreamBody},
+    extract::{Path, Query},
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::{stream, StreamExt};
+use r_ecipe_s_model::{
+    archive::{ArchiveMetadata, ARCHIVE_CONTENT_TYPE},
+    cooklang::{to_cooklang, COOKLANG_EXTENSION},
+    document::{recipe_to_markdown, MARKDOWN_CONTENT_TYPE, MARKDOWN_EXTENSION},
+    serde_json, RecipeWithId,
+};
+use serde::Deserialize;
+use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+const TAR_CONTENT_TYPE: &str = "application/x-tar";
+const COOKLANG_ARCHIVE_NAME: &str = "recipes-cooklang.tar";
+const LIBRARY_ARCHIVE_NAME: &str = "r_ecipe_s-library.jsonl";
+const EXPORT_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Failed to write archive: {0}")]
+    Archive(#[from] std::io::Error),
+    #[error("Failed to serialise archive: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::Recipe(err) => return err.into_response(),
+            Error::Archive(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Serde(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait ExportService {
+    type ServiceType;
+    fn bind_export_routes(self, recipe_access: &Arc<RecipeAccess>) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> ExportService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_export_routes(self, recipe_access: &Arc<RecipeAccess>) -> Self::ServiceType {
+        self.route(
+            "/export",
+            get({
+                let recipe_access = recipe_access.clone();
+                |options| export_library(options, recipe_access)
+            }),
+        )
+        .route(
+            "/recipes/:id/export",
+            get({
+                let recipe_access = recipe_access.clone();
+                |path, options| export_recipe(path, options, recipe_access)
+            }),
+        )
+        .route(
+            "/recipes/export/cooklang",
+            get({
+                let recipe_access = recipe_access.clone();
+                || export_cooklang(recipe_access)
+            }),
+        )
+    }
+}
+
+/// A file name for the recipe that is safe in any archive, e.g.
+/// `12-lemon-drizzle-cake`.
+fn file_stem(recipe: &RecipeWithId) -> String {
+    let slug = recipe
+        .data
+        .name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    match slug.as_str() {
+        "" => recipe.id.to_string(),
+        slug => format!("{}-{slug}", recipe.id),
+    }
+}
+
+/// Exports every recipe as a tarball holding one `.cook` file per recipe.
+pub(crate) async fn export_cooklang(recipe_access: Arc<RecipeAccess>) -> Result<Response> {
+    let recipes = recipe_access.get_all_recipes().await?;
+    let mut archive = tar::Builder::new(Vec::new());
+    for recipe in &recipes {
+        let contents = to_cooklang(&recipe.data);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        let path = format!("{}.{COOKLANG_EXTENSION}", file_stem(recipe));
+        archive.append_data(&mut header, path, contents.as_bytes())?;
+    }
+    let body = archive.into_inner()?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, TAR_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{COOKLANG_ARCHIVE_NAME}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Md,
+    Html,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DocumentOptions {
+    #[serde(default)]
+    format: DocumentFormat,
+    servings: Option<u32>,
+    #[serde(default)]
+    paper: Paper,
+}
+
+/// Exports a recipe as a markdown or printable HTML document, with the
+/// ingredients scaled to `servings` when given. Markdown is offered as a
+/// download, HTML is shown inline so it can be printed from the browser.
+pub(crate) async fn export_recipe(
+    Path(id): Path<i64>,
+    Query(options): Query<DocumentOptions>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<Response> {
+    let recipe =
+        recipe_access
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| recipe_service::Error::Missing {
+                item_type: "recipe".to_string(),
+                id,
+            })?;
+    let file_stem = file_stem(&recipe);
+    let recipe = match options.servings {
+        Some(servings) if servings > 0 => recipe.data.scaled(servings),
+        _ => recipe.data,
+    };
+
+    let (content_type, disposition, body) = match options.format {
+        DocumentFormat::Md => (
+            MARKDOWN_CONTENT_TYPE,
+            format!("attachment; filename=\"{file_stem}.{MARKDOWN_EXTENSION}\""),
+            recipe_to_markdown(&recipe),
+        ),
+        DocumentFormat::Html => (
+            HTML_CONTENT_TYPE,
+            format!("inline; filename=\"{file_stem}.{HTML_EXTENSION}\""),
+            recipe_to_html(&recipe, options.paper),
+        ),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExportOptions {
+    #[serde(default)]
+    embeddings: bool,
+}
+
+fn archive_line(recipe: &RecipeWithId) -> Result<String> {
+    let mut line = serde_json::to_string(recipe)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Streams the whole library as an archive: a line of [`ArchiveMetadata`]
+/// followed by one [`RecipeWithId`] per line. Embeddings are left out unless
+/// asked for, as they make up most of the archive's size.
+pub(crate) async fn export_library(
+    Query(options): Query<ExportOptions>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<Response> {
+    let metadata = ArchiveMetadata::new(
+        OffsetDateTime::now_utc().unix_timestamp(),
+        recipe_access.count().await?,
+        options.embeddings,
+    );
+    let mut metadata = serde_json::to_string(&metadata)?;
+    metadata.push('\n');
+
+    let embeddings = options.embeddings;
+    let pages = stream::try_unfold(Some(0), move |after_id| {
+        let recipe_access = recipe_access.clone();
+        async move {
+            let Some(after_id) = after_id else {
+                return Ok(None);
+            };
+            let mut page = recipe_access
+                .get_page_after(after_id, EXPORT_PAGE_SIZE)
+                .await?;
+            let next = match page.last() {
+                Some(last) if page.len() as i64 == EXPORT_PAGE_SIZE => Some(last.id),
+                _ => None,
+            };
+            if !embeddings {
+                page.iter_mut()
+                    .for_each(|recipe| recipe.data.embedding = None);
+            }
+            let lines = page.iter().map(archive_line).collect::<Result<String>>()?;
+            Ok::<_, Error>(Some((lines, next)))
+        }
+    });
+    let body = stream::once(async { Ok(metadata) }).chain(pages);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, ARCHIVE_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{LIBRARY_ARCHIVE_NAME}\""),
+            ),
+        ],
+        StreamBody::new(body),
+    )
+        .into_response())
+}
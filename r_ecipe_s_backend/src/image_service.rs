@@ -0,0 +1,432 @@
+use crate::{
+    auth::AuthUser,
+    db::DbAccess,
+    image_store::ImageStore,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        DefaultBodyLimit, Multipart, Path,
+    },
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json as HttpJson, Router,
+};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use r_ecipe_s_model::image::ImageVariant;
+use std::io::Cursor;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+/// Several full size photos can be uploaded at once.
+const UPLOAD_LIMIT: usize = 64 * 1024 * 1024;
+/// Longest side, in pixels, of the grid thumbnails.
+const THUMBNAIL_SIZE: u32 = 480;
+/// Longest side, in pixels, of the images shown with a recipe.
+const LARGE_SIZE: u32 = 1600;
+const WEBP_CONTENT_TYPE: &str = "image/webp";
+/// An image's variants never change, as a new upload gets a new id.
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Where the images of every recipe are kept.
+pub(crate) const RECIPE_IMAGES_PREFIX: &str = "recipes";
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Failed to read multipart upload: {0}")]
+    MultipartUpload(#[from] MultipartRejection),
+    #[error("Failed to read multipart upload: {0}")]
+    Multipart(#[from] MultipartError),
+    #[error("Not a supported image: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("Only PNG, JPEG, GIF and WebP images can be uploaded")]
+    UnsupportedFormat,
+    #[error("Error with image storage: {0}")]
+    Storage(#[from] std::io::Error),
+    #[error("Failed to process image: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[error("Missing image {image_id} of recipe {id}")]
+    Missing { id: i64, image_id: i64 },
+    #[error("Unknown image variant: {0}")]
+    UnknownVariant(String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::MultipartUpload(err) => return err.into_response(),
+            Error::Multipart(_) => http::StatusCode::BAD_REQUEST,
+            Error::Image(_) => http::StatusCode::BAD_REQUEST,
+            Error::UnsupportedFormat => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::Storage(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Task(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Missing { .. } => http::StatusCode::NOT_FOUND,
+            Error::UnknownVariant(_) => http::StatusCode::NOT_FOUND,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait ImageService {
+    type ServiceType;
+    fn bind_image_routes(
+        self,
+        image_access: &Arc<ImageAccess>,
+        image_store: &Arc<dyn ImageStore>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError> ImageService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Bytes> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+{
+    type ServiceType = Self;
+    fn bind_image_routes(
+        self,
+        image_access: &Arc<ImageAccess>,
+        image_store: &Arc<dyn ImageStore>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/recipes/:id/images",
+            get({
+                let image_access = image_access.clone();
+                |path| get_images(path, image_access)
+            })
+            .post({
+                let image_access = image_access.clone();
+                let image_store = image_store.clone();
+                let recipe_access = recipe_access.clone();
+                |path, user, upload| {
+                    upload_images(path, user, upload, image_access, image_store, recipe_access)
+                }
+            })
+            .layer(DefaultBodyLimit::max(UPLOAD_LIMIT)),
+        )
+        .route(
+            "/recipes/:id/images/:image_id",
+            axum::routing::delete({
+                let image_access = image_access.clone();
+                let image_store = image_store.clone();
+                |path, user| delete_image(path, user, image_access, image_store)
+            }),
+        )
+        .route(
+            "/recipes/:id/images/:image_id/cover",
+            post({
+                let image_access = image_access.clone();
+                |path, user| make_cover(path, user, image_access)
+            }),
+        )
+        .route(
+            "/recipes/:id/images/:image_id/:variant",
+            get({
+                let image_access = image_access.clone();
+                let image_store = image_store.clone();
+                |path| get_image(path, image_access, image_store)
+            }),
+        )
+    }
+}
+
+pub struct ImageAccess {
+    db_access: Arc<DbAccess>,
+}
+
+impl ImageAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        ImageAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self, recipe_id: i64) -> Result<Vec<i64>> {
+        Ok(sqlx::query!(
+            r#"
+                SELECT id FROM recipe_images
+                WHERE recipe_id = $1
+                ORDER BY position, id
+            "#,
+            recipe_id
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(|rec| rec.id)
+        .collect())
+    }
+
+    /// The content type the image was uploaded with.
+    async fn get_content_type(&self, recipe_id: i64, id: i64) -> Result<Option<String>> {
+        let rec = sqlx::query!(
+            "SELECT content_type FROM recipe_images WHERE recipe_id = $1 AND id = $2",
+            recipe_id,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.content_type))
+    }
+
+    /// Adds an image after the recipe's others. The recipe is indexed again,
+    /// for search results to show it.
+    async fn insert(&self, recipe_id: i64, content_type: &str) -> Result<i64> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO recipe_images (recipe_id, position, content_type)
+                VALUES (
+                    $1,
+                    COALESCE((SELECT MAX(position) + 1 FROM recipe_images WHERE recipe_id = $1), 0),
+                    $2
+                )
+                RETURNING id
+            "#,
+            recipe_id,
+            content_type
+        )
+        .fetch_one(transaction.as_mut())
+        .await?;
+        Self::reindex(transaction.as_mut(), recipe_id).await?;
+        transaction.commit().await?;
+        Ok(rec.id)
+    }
+
+    /// Moves the image before the recipe's others.
+    async fn make_cover(&self, recipe_id: i64, id: i64) -> Result<Option<i64>> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let rec = sqlx::query!(
+            r#"
+                UPDATE recipe_images
+                SET position = (SELECT MIN(position) - 1 FROM recipe_images WHERE recipe_id = $1)
+                WHERE recipe_id = $1 AND id = $2
+                RETURNING id
+            "#,
+            recipe_id,
+            id
+        )
+        .fetch_optional(transaction.as_mut())
+        .await?;
+        Self::reindex(transaction.as_mut(), recipe_id).await?;
+        transaction.commit().await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    async fn delete(&self, recipe_id: i64, id: i64) -> Result<Option<i64>> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let rec = sqlx::query!(
+            "DELETE FROM recipe_images WHERE recipe_id = $1 AND id = $2 RETURNING id",
+            recipe_id,
+            id
+        )
+        .fetch_optional(transaction.as_mut())
+        .await?;
+        Self::reindex(transaction.as_mut(), recipe_id).await?;
+        transaction.commit().await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    async fn reindex(connection: &mut sqlx::PgConnection, recipe_id: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE recipes SET searchable = false WHERE id = $1",
+            recipe_id
+        )
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Where the variant of an image is kept in the store.
+fn image_key(recipe_id: i64, id: i64, variant: ImageVariant) -> String {
+    format!("{}/{variant}", image_prefix(recipe_id, id))
+}
+
+fn image_prefix(recipe_id: i64, id: i64) -> String {
+    format!("{}/{id}", recipe_images_prefix(recipe_id))
+}
+
+/// Where all of a recipe's images are kept.
+pub(crate) fn recipe_images_prefix(recipe_id: i64) -> String {
+    format!("{RECIPE_IMAGES_PREFIX}/{recipe_id}")
+}
+
+/// An uploaded image with its resized WebP variants.
+struct ProcessedImage {
+    content_type: &'static str,
+    original: Bytes,
+    thumbnail: Vec<u8>,
+    large: Vec<u8>,
+}
+
+fn content_type(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some(WEBP_CONTENT_TYPE),
+        _ => None,
+    }
+}
+
+/// The image as a WebP whose longest side is at most `size`. Smaller images
+/// are kept at their size.
+fn webp(image: &DynamicImage, size: u32) -> Result<Vec<u8>> {
+    let rgba = image.to_rgba8();
+    let (width, height) = (rgba.width(), rgba.height());
+    let longest = width.max(height);
+    let resized = if longest > size {
+        let scale = |side: u32| ((u64::from(side) * u64::from(size)) / u64::from(longest)).max(1);
+        image::imageops::resize(
+            &rgba,
+            scale(width) as u32,
+            scale(height) as u32,
+            FilterType::Triangle,
+        )
+    } else {
+        rgba
+    };
+    let mut data = Vec::new();
+    DynamicImage::ImageRgba8(resized).write_to(&mut Cursor::new(&mut data), ImageFormat::WebP)?;
+    Ok(data)
+}
+
+/// The content type of a PNG, JPEG, GIF or WebP image, going by its data
+/// rather than what it was uploaded as.
+pub(crate) fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    image::guess_format(data).ok().and_then(content_type)
+}
+
+fn process(original: Bytes) -> Result<ProcessedImage> {
+    let content_type = sniff_content_type(&original).ok_or(Error::UnsupportedFormat)?;
+    let image = image::load_from_memory(&original)?;
+    Ok(ProcessedImage {
+        content_type,
+        thumbnail: webp(&image, THUMBNAIL_SIZE)?,
+        large: webp(&image, LARGE_SIZE)?,
+        original,
+    })
+}
+
+/// Ids of the recipe's images, the cover first.
+pub(crate) async fn get_images(
+    Path(id): Path<i64>,
+    image_access: Arc<ImageAccess>,
+) -> Result<HttpJson<Vec<i64>>> {
+    Ok(image_access.get_all(id).await?.into())
+}
+
+/// Adds every file of a multipart upload as an image of the recipe, after
+/// checking they're all images. Returns the new images' ids.
+pub(crate) async fn upload_images(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    mut upload: Multipart,
+    image_access: Arc<ImageAccess>,
+    image_store: Arc<dyn ImageStore>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<Vec<i64>>> {
+    recipe_access
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| recipe_service::Error::Missing {
+            item_type: "recipe".to_string(),
+            id,
+        })?;
+    let mut images = Vec::new();
+    while let Some(field) = upload.next_field().await? {
+        let original = field.bytes().await?;
+        images.push(tokio::task::spawn_blocking(move || process(original)).await??);
+    }
+    let mut ids = Vec::new();
+    for image in images {
+        let image_id = image_access.insert(id, image.content_type).await?;
+        let variants = [
+            (ImageVariant::Original, image.original.as_ref()),
+            (ImageVariant::Thumbnail, image.thumbnail.as_slice()),
+            (ImageVariant::Large, image.large.as_slice()),
+        ];
+        for (variant, data) in variants {
+            if let Err(err) = image_store
+                .put(&image_key(id, image_id, variant), data)
+                .await
+            {
+                image_access.delete(id, image_id).await?;
+                image_store
+                    .delete_prefix(&image_prefix(id, image_id))
+                    .await?;
+                return Err(err.into());
+            }
+        }
+        ids.push(image_id);
+    }
+    Ok(ids.into())
+}
+
+pub(crate) async fn get_image(
+    Path((id, image_id, variant)): Path<(i64, i64, String)>,
+    image_access: Arc<ImageAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<Response> {
+    let variant = ImageVariant::parse(&variant).ok_or(Error::UnknownVariant(variant))?;
+    let original_type = image_access
+        .get_content_type(id, image_id)
+        .await?
+        .ok_or(Error::Missing { id, image_id })?;
+    let data = image_store
+        .get(&image_key(id, image_id, variant))
+        .await?
+        .ok_or(Error::Missing { id, image_id })?;
+    let content_type = match variant {
+        ImageVariant::Original => original_type,
+        ImageVariant::Thumbnail | ImageVariant::Large => WEBP_CONTENT_TYPE.to_string(),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, IMMUTABLE_CACHE_CONTROL.to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+pub(crate) async fn make_cover(
+    Path((id, image_id)): Path<(i64, i64)>,
+    _user: AuthUser,
+    image_access: Arc<ImageAccess>,
+) -> Result<HttpJson<()>> {
+    image_access
+        .make_cover(id, image_id)
+        .await?
+        .ok_or(Error::Missing { id, image_id })?;
+    Ok(().into())
+}
+
+pub(crate) async fn delete_image(
+    Path((id, image_id)): Path<(i64, i64)>,
+    _user: AuthUser,
+    image_access: Arc<ImageAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<HttpJson<()>> {
+    image_access
+        .delete(id, image_id)
+        .await?
+        .ok_or(Error::Missing { id, image_id })?;
+    image_store
+        .delete_prefix(&image_prefix(id, image_id))
+        .await?;
+    Ok(().into())
+}
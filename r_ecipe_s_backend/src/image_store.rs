@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Somewhere to keep uploaded images, by `/` separated keys such as
+/// `recipes/12/34/thumbnail`. Keys only come from the server, never straight
+/// from a request.
+#[async_trait]
+pub trait ImageStore: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> io::Result<()>;
+    /// What is stored under the key, or `None` when nothing is.
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+    /// Removes everything stored under keys starting with `prefix/`.
+    async fn delete_prefix(&self, prefix: &str) -> io::Result<()>;
+}
+
+/// Keeps images as files in a directory on the local filesystem.
+pub struct LocalImageStore {
+    root: PathBuf,
+}
+
+impl LocalImageStore {
+    /// Creates the directory if needed. Its absolute path is kept, so the
+    /// store still works after the server changes its working directory.
+    pub fn new(root: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::create_dir_all(&root)?;
+        Ok(LocalImageStore {
+            root: root.as_ref().canonicalize()?,
+        })
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid image key: {}", key.display()),
+            ));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+fn not_found_as_none<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[async_trait]
+impl ImageStore for LocalImageStore {
+    async fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, data).await
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        not_found_as_none(tokio::fs::read(self.path(key)?).await)
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> io::Result<()> {
+        not_found_as_none(tokio::fs::remove_dir_all(self.path(prefix)?).await)?;
+        Ok(())
+    }
+}
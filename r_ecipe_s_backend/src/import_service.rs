@@ -0,0 +1,575 @@
+use crate::{
+    auth::{AuthError, AuthUser},
+    image_service::RECIPE_IMAGES_PREFIX,
+    image_store::ImageStore,
+    recipe_service::{self, RecipeAccess},
+};
+use async_trait::async_trait;
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        rejection::BytesRejection,
+        DefaultBodyLimit, FromRequest, Multipart, Query, RequestParts,
+    },
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::post,
+    BoxError, Json as HttpJson, Router,
+};
+use flate2::read::GzDecoder;
+use r_ecipe_s_model::{
+    archive::{read_archive, ArchiveError, ArchiveImportReport, ImportMode},
+    component::Component,
+    cooklang::{parse_cooklang, COOKLANG_EXTENSION},
+    mealie::{mealie_records, recipe_from_mealie},
+    paprika::{recipe_from_paprika, PAPRIKA_RECIPE_EXTENSION},
+    schema_org::{recipe_from_document, JsonLdError},
+    serde_json, ImportOutcome, ImportReport, ImportResult, Recipe, RecipeWithId,
+};
+use serde::Deserialize;
+use sqlx::{Acquire, Postgres, Transaction};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+const MULTIPART_CONTENT_TYPE: &str = "multipart/form-data";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_MAGIC: [u8; 2] = *b"PK";
+const JSON_EXTENSION: &str = "json";
+/// Exports of whole libraries, with embeddings or photos, are far larger than
+/// the default 2 MB request limit.
+const ARCHIVE_LIMIT: usize = 256 * 1024 * 1024;
+/// The most that compressed uploads may unpack to, in all. Recipes are text,
+/// so even whole libraries are far smaller than this.
+const MAX_UNPACKED: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Error with authentication: {0}")]
+    Auth(#[from] AuthError),
+    #[error("Failed to import JSON-LD recipe: {0}")]
+    JsonLd(#[from] JsonLdError),
+    #[error("Failed to read upload: {0}")]
+    Body(#[from] BytesRejection),
+    #[error("Failed to read multipart upload: {0}")]
+    MultipartUpload(#[from] MultipartRejection),
+    #[error("Failed to read multipart upload: {0}")]
+    Multipart(#[from] MultipartError),
+    #[error("Failed to read tar archive: {0}")]
+    Archive(#[from] std::io::Error),
+    #[error("Failed to read library archive: {0}")]
+    LibraryArchive(#[from] ArchiveError),
+    #[error("Failed to read zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Failed to read JSON export: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Upload unpacks to more than {} MB", MAX_UNPACKED / 1024 / 1024)]
+    TooLarge,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::Recipe(err) => return err.into_response(),
+            Error::Auth(err) => return err.into_response(),
+            Error::JsonLd(_) => http::StatusCode::BAD_REQUEST,
+            Error::Body(err) => return err.into_response(),
+            Error::MultipartUpload(err) => return err.into_response(),
+            Error::Multipart(_) => http::StatusCode::BAD_REQUEST,
+            Error::Archive(_) => http::StatusCode::BAD_REQUEST,
+            Error::LibraryArchive(_) => http::StatusCode::BAD_REQUEST,
+            Error::Zip(_) => http::StatusCode::BAD_REQUEST,
+            Error::Json(_) => http::StatusCode::BAD_REQUEST,
+            Error::TooLarge => http::StatusCode::PAYLOAD_TOO_LARGE,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait ImportService {
+    type ServiceType;
+    fn bind_import_routes(
+        self,
+        recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError> ImportService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Bytes> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+{
+    type ServiceType = Self;
+    fn bind_import_routes(
+        self,
+        recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/recipes/import/jsonld",
+            post({
+                let recipe_access = recipe_access.clone();
+                |user, document| import_json_ld(user, document, recipe_access)
+            }),
+        )
+        .route(
+            "/recipes/import/cooklang",
+            post({
+                let recipe_access = recipe_access.clone();
+                |user, files| import_cooklang(user, files, recipe_access)
+            })
+            .layer(DefaultBodyLimit::max(ARCHIVE_LIMIT)),
+        )
+        .route(
+            "/recipes/import/paprika",
+            post({
+                let recipe_access = recipe_access.clone();
+                |user, export| import_paprika(user, export, recipe_access)
+            })
+            .layer(DefaultBodyLimit::max(ARCHIVE_LIMIT)),
+        )
+        .route(
+            "/recipes/import/mealie",
+            post({
+                let recipe_access = recipe_access.clone();
+                |user, export| import_mealie(user, export, recipe_access)
+            })
+            .layer(DefaultBodyLimit::max(ARCHIVE_LIMIT)),
+        )
+        .route(
+            "/import",
+            post({
+                let recipe_access = recipe_access.clone();
+                let image_store = image_store.clone();
+                |user, options, archive| {
+                    import_library(user, options, archive, recipe_access, image_store)
+                }
+            })
+            .layer(DefaultBodyLimit::max(ARCHIVE_LIMIT)),
+        )
+    }
+}
+
+/// Reads all of `reader`, unless that takes what has been unpacked so far,
+/// `unpacked`, past [`MAX_UNPACKED`]. Gives the result of reading otherwise.
+fn read_unpacked(reader: impl Read, unpacked: &mut u64) -> Result<std::io::Result<Vec<u8>>> {
+    let mut contents = Vec::new();
+    let read = reader
+        .take(MAX_UNPACKED - *unpacked + 1)
+        .read_to_end(&mut contents);
+    *unpacked += contents.len() as u64;
+    if *unpacked > MAX_UNPACKED {
+        return Err(Error::TooLarge);
+    }
+    Ok(read.map(|_| contents))
+}
+
+/// Uploaded `.cook` files as pairs of file name and contents, read either from
+/// a multipart form or from a request body holding a tarball, optionally
+/// gzipped. Other files in a tarball are ignored.
+pub(crate) struct CooklangFiles(Vec<(String, String)>);
+
+#[async_trait]
+impl<B> FromRequest<B> for CooklangFiles
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self> {
+        let is_multipart = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(MULTIPART_CONTENT_TYPE));
+        if is_multipart {
+            let mut multipart = Multipart::from_request(req).await?;
+            let mut files = Vec::new();
+            while let Some(field) = multipart.next_field().await? {
+                let name = field
+                    .file_name()
+                    .or(field.name())
+                    .unwrap_or_default()
+                    .to_string();
+                files.push((name, field.text().await?));
+            }
+            return Ok(CooklangFiles(files));
+        }
+
+        let body = Bytes::from_request(req).await?;
+        let tarball = if body.starts_with(&GZIP_MAGIC) {
+            Bytes::from(read_unpacked(GzDecoder::new(body.as_ref()), &mut 0)??)
+        } else {
+            body
+        };
+        let mut archive = tar::Archive::new(tarball.as_ref());
+        let mut files = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let is_cooklang = path.ends_with(&format!(".{COOKLANG_EXTENSION}"));
+            if !entry.header().entry_type().is_file() || !is_cooklang {
+                continue;
+            }
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            files.push((path, String::from_utf8_lossy(&contents).into_owned()));
+        }
+        Ok(CooklangFiles(files))
+    }
+}
+
+/// Imports a recipe from a JSON-LD document, or from a saved HTML page that
+/// embeds schema.org Recipe JSON-LD.
+pub(crate) async fn import_json_ld(
+    _user: AuthUser,
+    document: String,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<RecipeWithId>> {
+    let recipe = recipe_from_document(&document)?;
+    let id = recipe_access.insert(&recipe).await?;
+
+    Ok(RecipeWithId { id, data: recipe }.into())
+}
+
+/// Adds each converted recipe to the library, reporting the ones that
+/// couldn't be converted or saved without failing the rest of the import.
+async fn import_recipes<E: ToString>(
+    recipe_access: &RecipeAccess,
+    recipes: Vec<(String, std::result::Result<Recipe, E>)>,
+) -> ImportReport {
+    let mut results = Vec::with_capacity(recipes.len());
+    for (source, recipe) in recipes {
+        let outcome = match recipe {
+            Ok(recipe) => match recipe_access.insert(&recipe).await {
+                Ok(id) => ImportOutcome::Imported {
+                    id,
+                    name: recipe.name,
+                },
+                Err(err) => ImportOutcome::Failed {
+                    error: err.to_string(),
+                },
+            },
+            Err(err) => ImportOutcome::Failed {
+                error: err.to_string(),
+            },
+        };
+        results.push(ImportResult { source, outcome });
+    }
+    ImportReport { results }
+}
+
+/// Imports `.cook` files in bulk.
+pub(crate) async fn import_cooklang(
+    _user: AuthUser,
+    CooklangFiles(files): CooklangFiles,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<ImportReport>> {
+    let recipes = files
+        .into_iter()
+        .map(|(source, contents)| {
+            let recipe = parse_cooklang(&contents, Some(&source));
+            (source, recipe)
+        })
+        .collect();
+
+    Ok(import_recipes(&recipe_access, recipes).await.into())
+}
+
+/// The files in a zip archive with the given extension, as pairs of file name
+/// and contents. What they unpack to is added to `unpacked`.
+fn zip_files(
+    archive: &[u8],
+    extension: &str,
+    unpacked: &mut u64,
+) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() || !file.name().ends_with(&format!(".{extension}")) {
+            continue;
+        }
+        let name = file.name().to_string();
+        let contents = read_unpacked(&mut file, unpacked)??;
+        files.push((name, contents));
+    }
+    Ok(files)
+}
+
+/// Unpacks one gzipped Paprika recipe, counting its size towards `unpacked`.
+/// Only going over [`MAX_UNPACKED`] fails the whole import.
+fn paprika_recipe(
+    contents: &[u8],
+    unpacked: &mut u64,
+) -> Result<std::result::Result<Recipe, String>> {
+    Ok(read_unpacked(GzDecoder::new(contents), unpacked)?
+        .map_err(|err| format!("Not a gzipped Paprika recipe: {err}"))
+        .and_then(|json| paprika_record(&json)))
+}
+
+fn paprika_record(json: &[u8]) -> std::result::Result<Recipe, String> {
+    let record = serde_json::from_slice(json).map_err(|err| err.to_string())?;
+    recipe_from_paprika(&record).map_err(|err| err.to_string())
+}
+
+/// Imports a Paprika `.paprikarecipes` export, a zip archive of gzipped JSON
+/// recipes, or a single gzipped `.paprikarecipe`.
+pub(crate) async fn import_paprika(
+    _user: AuthUser,
+    export: Bytes,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<ImportReport>> {
+    let mut unpacked = 0;
+    let files = if export.starts_with(&ZIP_MAGIC) {
+        zip_files(&export, PAPRIKA_RECIPE_EXTENSION, &mut unpacked)?
+    } else {
+        vec![(
+            format!("recipe.{PAPRIKA_RECIPE_EXTENSION}"),
+            export.to_vec(),
+        )]
+    };
+    let mut recipes = Vec::new();
+    for (source, contents) in files {
+        let recipe = paprika_recipe(&contents, &mut unpacked)?;
+        recipes.push((source, recipe));
+    }
+
+    Ok(import_recipes(&recipe_access, recipes).await.into())
+}
+
+/// Imports a Mealie JSON export, either a single JSON document or a zip
+/// archive of them. Each recipe is reported under its file and position.
+pub(crate) async fn import_mealie(
+    _user: AuthUser,
+    export: Bytes,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<ImportReport>> {
+    let documents = if export.starts_with(&ZIP_MAGIC) {
+        zip_files(&export, JSON_EXTENSION, &mut 0)?
+            .into_iter()
+            .map(|(source, contents)| (source, serde_json::from_slice(&contents)))
+            .collect()
+    } else {
+        vec![(
+            format!("export.{JSON_EXTENSION}"),
+            Ok(serde_json::from_slice(&export)?),
+        )]
+    };
+    let mut recipes = Vec::new();
+    for (source, document) in documents {
+        let document: serde_json::Value = match document {
+            Ok(document) => document,
+            Err(err) => {
+                recipes.push((source, Err(err.to_string())));
+                continue;
+            }
+        };
+        for (index, record) in mealie_records(&document).into_iter().enumerate() {
+            let recipe = recipe_from_mealie(record).map_err(|err| err.to_string());
+            recipes.push((format!("{source} #{}", index + 1), recipe));
+        }
+    }
+
+    Ok(import_recipes(&recipe_access, recipes).await.into())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LibraryImportOptions {
+    #[serde(default)]
+    mode: ImportMode,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Adds an archived recipe under a new id, or when merging updates the
+/// library's recipe of the same name, keeping its embedding if the archive
+/// has none.
+/// Saves an archived recipe in a savepoint of the import's transaction, so a
+/// recipe that fails is rolled back and reported without aborting the rest.
+async fn import_archived_recipe(
+    transaction: &mut Transaction<'_, Postgres>,
+    mode: ImportMode,
+    recipe: Recipe,
+) -> recipe_service::Result<ImportOutcome> {
+    let mut savepoint = transaction.begin().await?;
+    match save_archived_recipe(&mut savepoint, mode, recipe).await {
+        Ok(outcome) => {
+            savepoint.commit().await?;
+            Ok(outcome)
+        }
+        Err(err) => {
+            savepoint.rollback().await?;
+            Ok(ImportOutcome::Failed {
+                error: err.to_string(),
+            })
+        }
+    }
+}
+
+async fn save_archived_recipe(
+    transaction: &mut Transaction<'_, Postgres>,
+    mode: ImportMode,
+    mut recipe: Recipe,
+) -> recipe_service::Result<ImportOutcome> {
+    let existing = match mode {
+        ImportMode::Merge => {
+            RecipeAccess::find_id_by_name_pool(transaction.as_mut(), &recipe.name).await?
+        }
+        ImportMode::Replace => None,
+    };
+    let Some(id) = existing else {
+        let id = RecipeAccess::insert_pool(transaction.as_mut(), &recipe).await?;
+        return Ok(ImportOutcome::Imported {
+            id,
+            name: recipe.name,
+        });
+    };
+    if recipe.embedding.is_none() {
+        recipe.embedding = RecipeAccess::get_by_id_pool(transaction.as_mut(), id)
+            .await?
+            .and_then(|existing| existing.data.embedding);
+    }
+    RecipeAccess::update_pool(transaction.as_mut(), id, &recipe).await?;
+    Ok(ImportOutcome::Updated {
+        id,
+        name: recipe.name,
+    })
+}
+
+/// Imports a library archive written by `GET /export` in a single
+/// transaction. Archived ids are never reused: every recipe is given an id in
+/// this library and the report maps one onto the other. A dry run reports the
+/// same results and then rolls the transaction back. Replacing the library
+/// also removes the images of the recipes it replaced, and only admins can
+/// do it.
+pub(crate) async fn import_library(
+    user: AuthUser,
+    Query(options): Query<LibraryImportOptions>,
+    archive: String,
+    recipe_access: Arc<RecipeAccess>,
+    image_store: Arc<dyn ImageStore>,
+) -> Result<HttpJson<ArchiveImportReport>> {
+    if options.mode == ImportMode::Replace {
+        user.require_admin()?;
+    }
+    let (metadata, lines) = read_archive(&archive)?;
+
+    let mut transaction = recipe_access.begin().await?;
+    let removed = match options.mode {
+        ImportMode::Replace => RecipeAccess::delete_all_pool(transaction.as_mut()).await?,
+        ImportMode::Merge => 0,
+    };
+    let mut results = Vec::with_capacity(lines.len());
+    // Components refer to archived ids, so recipes are first saved without
+    // them and given them back once every recipe has its id in the library.
+    let mut ids = HashMap::new();
+    let mut with_components = Vec::new();
+    for (number, line) in lines {
+        let result = match line {
+            Ok(RecipeWithId { id, mut data }) => {
+                let ingredients = data.ingredients.clone();
+                data.ingredients
+                    .iter_mut()
+                    .for_each(|ingredient| ingredient.component = None);
+                let outcome = import_archived_recipe(&mut transaction, options.mode, data).await?;
+                if let ImportOutcome::Imported { id: new_id, .. }
+                | ImportOutcome::Updated { id: new_id, .. } = &outcome
+                {
+                    ids.insert(id, *new_id);
+                    if ingredients
+                        .iter()
+                        .any(|ingredient| ingredient.component.is_some())
+                    {
+                        with_components.push((id, *new_id, ingredients));
+                    }
+                }
+                ImportResult {
+                    source: format!("recipe {id}"),
+                    outcome,
+                }
+            }
+            Err(err) => ImportResult {
+                source: format!("line {number}"),
+                outcome: ImportOutcome::Failed {
+                    error: err.to_string(),
+                },
+            },
+        };
+        results.push(result);
+    }
+    for (archived_id, id, mut ingredients) in with_components {
+        // Components missing from the archive are kept as plain ingredients.
+        for ingredient in &mut ingredients {
+            ingredient.component = ingredient.component.and_then(|component| {
+                Some(Component {
+                    recipe_id: *ids.get(&component.recipe_id)?,
+                    ..component
+                })
+            });
+        }
+        let Some(recipe) = RecipeAccess::get_by_id_pool(transaction.as_mut(), id).await? else {
+            continue;
+        };
+        let recipe = Recipe {
+            ingredients,
+            ..recipe.data
+        };
+        let mut savepoint = transaction
+            .begin()
+            .await
+            .map_err(recipe_service::Error::from)?;
+        match RecipeAccess::update_pool(savepoint.as_mut(), id, &recipe).await {
+            Ok(_) => savepoint
+                .commit()
+                .await
+                .map_err(recipe_service::Error::from)?,
+            Err(err) => {
+                savepoint
+                    .rollback()
+                    .await
+                    .map_err(recipe_service::Error::from)?;
+                results.push(ImportResult {
+                    source: format!("components of recipe {archived_id}"),
+                    outcome: ImportOutcome::Failed {
+                        error: err.to_string(),
+                    },
+                });
+            }
+        }
+    }
+    if options.dry_run {
+        transaction
+            .rollback()
+            .await
+            .map_err(recipe_service::Error::from)?;
+    } else {
+        transaction
+            .commit()
+            .await
+            .map_err(recipe_service::Error::from)?;
+        if options.mode == ImportMode::Replace {
+            image_store
+                .delete_prefix(RECIPE_IMAGES_PREFIX)
+                .await
+                .map_err(recipe_service::Error::from)?;
+        }
+    }
+
+    Ok(ArchiveImportReport {
+        metadata,
+        mode: options.mode,
+        dry_run: options.dry_run,
+        removed,
+        results,
+    }
+    .into())
+}
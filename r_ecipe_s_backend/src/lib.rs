@@ -1,5 +1,25 @@
 pub mod app_config;
 pub mod auth;
+pub mod catalogue_service;
+pub mod collection_service;
+pub mod cook_log_service;
+pub mod cookbook;
+pub mod cookbook_service;
 pub mod db;
+pub mod document;
+pub mod equipment_service;
+pub mod export_service;
+pub mod image_service;
+pub mod image_store;
+pub mod import_service;
+pub mod meal_plan_service;
+pub mod nutrition_service;
+pub mod pantry_service;
+pub mod pdf;
+pub mod price_service;
 pub mod recipe_service;
 pub mod search_indexer;
+pub mod shopping_service;
+pub mod substitution_service;
+pub mod tag_service;
+pub mod user_service;
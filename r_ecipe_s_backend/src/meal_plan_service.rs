@@ -0,0 +1,485 @@
+use crate::{
+    app_config::HTTPConfig,
+    auth::{AuthError, AuthUser, JwtKeys, TokenKind},
+    db::DbAccess,
+    recipe_service::{self, stored_servings, RecipeAccess},
+    user_service::{self, UserAccess},
+};
+use axum::{
+    body::HttpBody,
+    extract::{Path, Query},
+    http::{self, header},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::calendar::{meal_plan_to_ics, CALENDAR_CONTENT_TYPE, CALENDAR_EXTENSION};
+use r_ecipe_s_model::plan::{
+    PlanRange, PlannedMeal, PlannedMealWithId, PlannedRecipe, MAX_PLAN_DAYS,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+use time::{Date, Duration, OffsetDateTime};
+
+/// Days before today that the calendar feed goes back.
+const FEED_DAYS_BEFORE: i64 = 28;
+/// Days after today that the calendar feed looks ahead.
+const FEED_DAYS_AFTER: i64 = 182;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Error with authentication: {0}")]
+    Auth(#[from] AuthError),
+    #[error("{0}")]
+    User(#[from] user_service::Error),
+    #[error("Missing planned meal with id: {0}")]
+    Missing(i64),
+    #[error("Missing recipe with id: {0}")]
+    MissingRecipe(i64),
+    #[error("A planned meal needs a recipe")]
+    NoRecipe,
+    #[error("Dates must be in order and at most {MAX_PLAN_DAYS} days apart")]
+    InvalidRange,
+    #[error("{0}")]
+    UnknownSlot(String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) | Error::UnknownSlot(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Auth(_) => http::StatusCode::UNAUTHORIZED,
+            Error::User(err) => return err.into_response(),
+            Error::Missing(_) | Error::MissingRecipe(_) => http::StatusCode::NOT_FOUND,
+            Error::NoRecipe | Error::InvalidRange => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait MealPlanService {
+    type ServiceType;
+    fn bind_meal_plan_routes(
+        self,
+        meal_plan_access: &Arc<MealPlanAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        user_access: &Arc<UserAccess>,
+        jwt_keys: &Arc<JwtKeys>,
+        http_config: &HTTPConfig,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> MealPlanService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_meal_plan_routes(
+        self,
+        meal_plan_access: &Arc<MealPlanAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        user_access: &Arc<UserAccess>,
+        jwt_keys: &Arc<JwtKeys>,
+        http_config: &HTTPConfig,
+    ) -> Self::ServiceType {
+        let base_url: Arc<str> = http_config.base_url().into();
+        self.route(
+            "/meal-plan",
+            get({
+                let meal_plan_access = meal_plan_access.clone();
+                |range| get_meal_plan(range, meal_plan_access)
+            })
+            .put({
+                let meal_plan_access = meal_plan_access.clone();
+                let recipe_access = recipe_access.clone();
+                |form, user| put_planned_meal(form, user, meal_plan_access, recipe_access)
+            }),
+        )
+        .route(
+            "/meal-plan.ics",
+            get({
+                let meal_plan_access = meal_plan_access.clone();
+                let recipe_access = recipe_access.clone();
+                let user_access = user_access.clone();
+                let jwt_keys = jwt_keys.clone();
+                let base_url = base_url.clone();
+                |query| {
+                    get_meal_plan_calendar(
+                        query,
+                        meal_plan_access,
+                        recipe_access,
+                        user_access,
+                        jwt_keys,
+                        base_url,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/meal-plan/recipes",
+            get({
+                let meal_plan_access = meal_plan_access.clone();
+                let recipe_access = recipe_access.clone();
+                |range| get_planned_recipes(range, meal_plan_access, recipe_access)
+            }),
+        )
+        .route(
+            "/meal-plan/:id",
+            get({
+                let meal_plan_access = meal_plan_access.clone();
+                |path| get_planned_meal(path, meal_plan_access)
+            })
+            .post({
+                let meal_plan_access = meal_plan_access.clone();
+                let recipe_access = recipe_access.clone();
+                |path, user, form| {
+                    post_planned_meal(path, user, form, meal_plan_access, recipe_access)
+                }
+            })
+            .delete({
+                let meal_plan_access = meal_plan_access.clone();
+                |path, user| delete_planned_meal(path, user, meal_plan_access)
+            }),
+        )
+    }
+}
+
+pub struct MealPlanAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct PlannedMealRep {
+    id: i64,
+    date: Date,
+    slot: String,
+    recipe_id: Option<i64>,
+    recipe_name: String,
+    servings: Option<i32>,
+    note: Option<String>,
+}
+
+impl PlannedMealRep {
+    fn model_with_id(self) -> Result<PlannedMealWithId> {
+        Ok(PlannedMealWithId {
+            id: self.id,
+            data: PlannedMeal {
+                date: self.date,
+                slot: self.slot.parse().map_err(Error::UnknownSlot)?,
+                recipe_id: self.recipe_id,
+                recipe_name: self.recipe_name,
+                servings: self.servings.map(|servings| servings as u32),
+                note: self.note,
+            },
+        })
+    }
+}
+
+impl MealPlanAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        MealPlanAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    /// Meals planned in the range, by day and through the day. Meals show the
+    /// current name of their recipe, or the name it had if it was deleted.
+    pub(crate) async fn get_range(&self, range: PlanRange) -> Result<Vec<PlannedMealWithId>> {
+        sqlx::query_as!(
+            PlannedMealRep,
+            r#"
+                SELECT
+                    meal_plan.id,
+                    meal_plan.date,
+                    meal_plan.slot,
+                    meal_plan.recipe_id,
+                    COALESCE(recipes.name, meal_plan.recipe_name) as "recipe_name!",
+                    meal_plan.servings,
+                    meal_plan.note
+                FROM meal_plan
+                LEFT JOIN recipes ON recipes.id = meal_plan.recipe_id
+                WHERE meal_plan.date BETWEEN $1 AND $2
+                ORDER BY
+                    meal_plan.date,
+                    array_position(ARRAY['breakfast', 'lunch', 'dinner', 'snack'], meal_plan.slot),
+                    meal_plan.id
+            "#,
+            range.from,
+            range.to
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(PlannedMealRep::model_with_id)
+        .collect()
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<PlannedMealWithId>> {
+        sqlx::query_as!(
+            PlannedMealRep,
+            r#"
+                SELECT
+                    meal_plan.id,
+                    meal_plan.date,
+                    meal_plan.slot,
+                    meal_plan.recipe_id,
+                    COALESCE(recipes.name, meal_plan.recipe_name) as "recipe_name!",
+                    meal_plan.servings,
+                    meal_plan.note
+                FROM meal_plan
+                LEFT JOIN recipes ON recipes.id = meal_plan.recipe_id
+                WHERE meal_plan.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(PlannedMealRep::model_with_id)
+        .transpose()
+    }
+
+    async fn insert(&self, meal: &PlannedMeal, recipe_id: i64, recipe_name: &str) -> Result<i64> {
+        let servings = stored_servings(meal.servings)?;
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO meal_plan (date, slot, recipe_id, recipe_name, servings, note)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+            "#,
+            meal.date,
+            meal.slot.as_str(),
+            recipe_id,
+            recipe_name,
+            servings,
+            meal.note
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    /// Updates the meal, keeping the recipe name it has when no new one is
+    /// given.
+    async fn update(
+        &self,
+        id: i64,
+        meal: &PlannedMeal,
+        recipe_name: Option<&str>,
+    ) -> Result<Option<i64>> {
+        let servings = stored_servings(meal.servings)?;
+        let rec = sqlx::query!(
+            r#"
+                UPDATE meal_plan
+                SET
+                    date = $2,
+                    slot = $3,
+                    recipe_id = $4,
+                    recipe_name = COALESCE($5, recipe_name),
+                    servings = $6,
+                    note = $7
+                WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            meal.date,
+            meal.slot.as_str(),
+            meal.recipe_id,
+            recipe_name,
+            servings,
+            meal.note
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM meal_plan WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+fn valid_range(range: PlanRange) -> Result<PlanRange> {
+    range.is_valid().then_some(range).ok_or(Error::InvalidRange)
+}
+
+/// Drops blank notes and servings of zero.
+fn normalise(meal: PlannedMeal) -> PlannedMeal {
+    PlannedMeal {
+        servings: meal.servings.filter(|servings| *servings > 0),
+        note: meal
+            .note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty()),
+        ..meal
+    }
+}
+
+async fn recipe_name(recipe_access: &RecipeAccess, recipe_id: i64) -> Result<String> {
+    let recipe = recipe_access
+        .get_by_id(recipe_id)
+        .await?
+        .ok_or(Error::MissingRecipe(recipe_id))?;
+    Ok(recipe.data.name)
+}
+
+/// Meals planned in the range alongside their recipes.
+pub(crate) async fn planned_recipes(
+    meal_plan_access: &MealPlanAccess,
+    recipe_access: &RecipeAccess,
+    range: PlanRange,
+) -> Result<Vec<PlannedRecipe>> {
+    let meals = meal_plan_access.get_range(valid_range(range)?).await?;
+    let mut recipe_ids = meals
+        .iter()
+        .filter_map(|meal| meal.data.recipe_id)
+        .collect::<Vec<_>>();
+    recipe_ids.sort_unstable();
+    recipe_ids.dedup();
+    let recipes = recipe_access
+        .get_by_ids(&recipe_ids)
+        .await?
+        .into_iter()
+        .map(|recipe| (recipe.id, recipe))
+        .collect::<HashMap<_, _>>();
+    Ok(meals
+        .into_iter()
+        .map(|meal| PlannedRecipe {
+            recipe: meal
+                .data
+                .recipe_id
+                .and_then(|recipe_id| recipes.get(&recipe_id).cloned()),
+            meal,
+        })
+        .collect())
+}
+
+pub(crate) async fn get_meal_plan(
+    Query(range): Query<PlanRange>,
+    meal_plan_access: Arc<MealPlanAccess>,
+) -> Result<HttpJson<Vec<PlannedMealWithId>>> {
+    Ok(meal_plan_access
+        .get_range(valid_range(range)?)
+        .await?
+        .into())
+}
+
+pub(crate) async fn get_planned_recipes(
+    Query(range): Query<PlanRange>,
+    meal_plan_access: Arc<MealPlanAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<Vec<PlannedRecipe>>> {
+    Ok(planned_recipes(&meal_plan_access, &recipe_access, range)
+        .await?
+        .into())
+}
+
+/// Calendar apps subscribing to the feed can't send headers, so a feed token
+/// comes in the query.
+#[derive(Debug, Deserialize)]
+pub(crate) struct FeedQuery {
+    token: String,
+}
+
+/// The meal plan from a few weeks back to months ahead as an iCalendar feed.
+pub(crate) async fn get_meal_plan_calendar(
+    Query(FeedQuery { token }): Query<FeedQuery>,
+    meal_plan_access: Arc<MealPlanAccess>,
+    recipe_access: Arc<RecipeAccess>,
+    user_access: Arc<UserAccess>,
+    jwt_keys: Arc<JwtKeys>,
+    base_url: Arc<str>,
+) -> Result<Response> {
+    let claims = jwt_keys.verify(&token, TokenKind::Feed)?;
+    if !user_access
+        .is_feed_token_valid(claims.sid, claims.sub)
+        .await?
+    {
+        return Err(AuthError::InvalidToken.into());
+    }
+    let now = OffsetDateTime::now_utc();
+    let range = PlanRange {
+        from: now.date() - Duration::days(FEED_DAYS_BEFORE),
+        to: now.date() + Duration::days(FEED_DAYS_AFTER),
+    };
+    let planned = planned_recipes(&meal_plan_access, &recipe_access, range).await?;
+    let calendar = meal_plan_to_ics(&planned, |id| format!("{base_url}/?recipe={id}"), now);
+    Ok((
+        [
+            (header::CONTENT_TYPE, CALENDAR_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"meal-plan.{CALENDAR_EXTENSION}\""),
+            ),
+        ],
+        calendar,
+    )
+        .into_response())
+}
+
+pub(crate) async fn get_planned_meal(
+    Path(id): Path<i64>,
+    meal_plan_access: Arc<MealPlanAccess>,
+) -> Result<HttpJson<PlannedMealWithId>> {
+    let meal = meal_plan_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(meal.into())
+}
+
+pub(crate) async fn put_planned_meal(
+    HttpJson(meal): HttpJson<PlannedMeal>,
+    _user: AuthUser,
+    meal_plan_access: Arc<MealPlanAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<i64>> {
+    let meal = normalise(meal);
+    let recipe_id = meal.recipe_id.ok_or(Error::NoRecipe)?;
+    let name = recipe_name(&recipe_access, recipe_id).await?;
+    let id = meal_plan_access.insert(&meal, recipe_id, &name).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_planned_meal(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(meal): HttpJson<PlannedMeal>,
+    meal_plan_access: Arc<MealPlanAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<i64>> {
+    let meal = normalise(meal);
+    let name = match meal.recipe_id {
+        Some(recipe_id) => Some(recipe_name(&recipe_access, recipe_id).await?),
+        None => None,
+    };
+    let id = meal_plan_access
+        .update(id, &meal, name.as_deref())
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_planned_meal(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    meal_plan_access: Arc<MealPlanAccess>,
+) -> Result<HttpJson<()>> {
+    meal_plan_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
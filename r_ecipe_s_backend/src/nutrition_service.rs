@@ -0,0 +1,314 @@
+use crate::{
+    auth::AuthUser,
+    db::DbAccess,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::nutrition::{
+    recipe_nutrition, Food, FoodWithId, IngredientMapping, Nutrients, RecipeNutrition,
+};
+use r_ecipe_s_model::shopping::ingredient_key;
+use r_ecipe_s_model::Recipe;
+use sqlx::PgExecutor;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Missing food with id: {0}")]
+    MissingFood(i64),
+    #[error("No food is mapped to ingredient: {0}")]
+    MissingMapping(String),
+    #[error("Mappings need an ingredient name")]
+    EmptyIngredient,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::MissingFood(_) => http::StatusCode::NOT_FOUND,
+            Error::MissingMapping(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyIngredient => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait NutritionService {
+    type ServiceType;
+    fn bind_nutrition_routes(
+        self,
+        nutrition_access: &Arc<NutritionAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> NutritionService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_nutrition_routes(
+        self,
+        nutrition_access: &Arc<NutritionAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/foods",
+            get({
+                let nutrition_access = nutrition_access.clone();
+                || get_foods(nutrition_access)
+            }),
+        )
+        .route(
+            "/recipes/:id/nutrition",
+            get({
+                let nutrition_access = nutrition_access.clone();
+                let recipe_access = recipe_access.clone();
+                |path| get_recipe_nutrition(path, nutrition_access, recipe_access)
+            }),
+        )
+        .route(
+            "/nutrition/mappings",
+            get({
+                let nutrition_access = nutrition_access.clone();
+                || get_mappings(nutrition_access)
+            })
+            .put({
+                let nutrition_access = nutrition_access.clone();
+                let recipe_access = recipe_access.clone();
+                |form, user| put_mapping(form, user, nutrition_access, recipe_access)
+            }),
+        )
+        .route(
+            "/nutrition/mappings/:ingredient",
+            delete({
+                let nutrition_access = nutrition_access.clone();
+                let recipe_access = recipe_access.clone();
+                |path, user| delete_mapping(path, user, nutrition_access, recipe_access)
+            }),
+        )
+    }
+}
+
+pub struct NutritionAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct FoodRep {
+    id: i64,
+    name: String,
+    aliases: Vec<String>,
+    kcal: f64,
+    protein: f64,
+    fat: f64,
+    carbs: f64,
+    fibre: f64,
+    sodium: f64,
+    density: Option<f64>,
+    unit_weight: Option<f64>,
+}
+
+impl FoodRep {
+    fn model_with_id(self) -> FoodWithId {
+        FoodWithId {
+            id: self.id,
+            data: Food {
+                name: self.name,
+                aliases: self.aliases,
+                per_100g: Nutrients {
+                    kcal: self.kcal,
+                    protein: self.protein,
+                    fat: self.fat,
+                    carbs: self.carbs,
+                    fibre: self.fibre,
+                    sodium: self.sodium,
+                },
+                density: self.density,
+                unit_weight: self.unit_weight,
+            },
+        }
+    }
+}
+
+impl NutritionAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        NutritionAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_foods(&self) -> Result<Vec<FoodWithId>> {
+        Ok(Self::get_foods_pool(self.db_access.get_pool()).await?)
+    }
+
+    pub(crate) async fn get_foods_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+    ) -> sqlx::Result<Vec<FoodWithId>> {
+        Ok(sqlx::query_as!(
+            FoodRep,
+            r#"
+                SELECT id, name, aliases, kcal, protein, fat, carbs, fibre, sodium, density, unit_weight
+                FROM foods
+                ORDER BY name
+            "#
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(FoodRep::model_with_id)
+        .collect())
+    }
+
+    async fn food_exists(&self, id: i64) -> Result<bool> {
+        let rec = sqlx::query!("SELECT id FROM foods WHERE id = $1", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.is_some())
+    }
+
+    async fn get_mappings(&self) -> Result<Vec<IngredientMapping>> {
+        Ok(Self::get_mappings_pool(self.db_access.get_pool()).await?)
+    }
+
+    pub(crate) async fn get_mappings_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+    ) -> sqlx::Result<Vec<IngredientMapping>> {
+        sqlx::query_as!(
+            IngredientMapping,
+            "SELECT ingredient, food_id FROM ingredient_foods ORDER BY ingredient"
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn set_mapping(&self, mapping: &IngredientMapping) -> Result<()> {
+        sqlx::query!(
+            r#"
+                INSERT INTO ingredient_foods (ingredient, food_id)
+                VALUES ($1, $2)
+                ON CONFLICT (ingredient) DO UPDATE SET food_id = excluded.food_id
+            "#,
+            mapping.ingredient,
+            mapping.food_id
+        )
+        .execute(self.db_access.get_pool())
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_mapping(&self, ingredient: &str) -> Result<Option<String>> {
+        let rec = sqlx::query!(
+            "DELETE FROM ingredient_foods WHERE ingredient = $1 RETURNING ingredient",
+            ingredient
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.ingredient))
+    }
+}
+
+/// Mappings keyed by ingredient, for matching ingredients to foods.
+pub(crate) fn food_mappings(mappings: Vec<IngredientMapping>) -> HashMap<String, i64> {
+    mappings
+        .into_iter()
+        .map(|mapping| (mapping.ingredient, mapping.food_id))
+        .collect()
+}
+
+pub(crate) async fn get_foods(
+    nutrition_access: Arc<NutritionAccess>,
+) -> Result<HttpJson<Vec<FoodWithId>>> {
+    Ok(nutrition_access.get_foods().await?.into())
+}
+
+/// The recipe's nutrients, component recipes included, with the ingredients
+/// that couldn't be counted.
+pub(crate) async fn get_recipe_nutrition(
+    Path(id): Path<i64>,
+    nutrition_access: Arc<NutritionAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<RecipeNutrition>> {
+    let recipe =
+        recipe_access
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| recipe_service::Error::Missing {
+                item_type: "recipe".to_string(),
+                id,
+            })?;
+    let recipe = Recipe {
+        ingredients: recipe_access
+            .expand_components(&recipe.data.ingredients)
+            .await?,
+        ..recipe.data
+    };
+    let foods = nutrition_access.get_foods().await?;
+    let mappings = food_mappings(nutrition_access.get_mappings().await?);
+    Ok(recipe_nutrition(&recipe, &foods, &mappings).into())
+}
+
+pub(crate) async fn get_mappings(
+    nutrition_access: Arc<NutritionAccess>,
+) -> Result<HttpJson<Vec<IngredientMapping>>> {
+    Ok(nutrition_access.get_mappings().await?.into())
+}
+
+/// Maps an ingredient name, and every spelling of it differing only in
+/// case or spacing, to a food. Recipes' dietary flags are worked out again
+/// to take the food into account.
+pub(crate) async fn put_mapping(
+    HttpJson(mapping): HttpJson<IngredientMapping>,
+    _user: AuthUser,
+    nutrition_access: Arc<NutritionAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<()>> {
+    let ingredient = ingredient_key(&mapping.ingredient);
+    if ingredient.is_empty() {
+        return Err(Error::EmptyIngredient);
+    }
+    if !nutrition_access.food_exists(mapping.food_id).await? {
+        return Err(Error::MissingFood(mapping.food_id));
+    }
+    nutrition_access
+        .set_mapping(&IngredientMapping {
+            ingredient,
+            food_id: mapping.food_id,
+        })
+        .await?;
+    recipe_access.classify_all().await?;
+    Ok(().into())
+}
+
+pub(crate) async fn delete_mapping(
+    Path(ingredient): Path<String>,
+    _user: AuthUser,
+    nutrition_access: Arc<NutritionAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<()>> {
+    let ingredient = ingredient_key(&ingredient);
+    nutrition_access
+        .delete_mapping(&ingredient)
+        .await?
+        .ok_or(Error::MissingMapping(ingredient))?;
+    recipe_access.classify_all().await?;
+    Ok(().into())
+}
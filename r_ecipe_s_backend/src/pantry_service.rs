@@ -0,0 +1,421 @@
+use crate::{
+    app_config::SearchConfig,
+    auth::AuthUser,
+    catalogue_service::CatalogueAccess,
+    db::DbAccess,
+    recipe_service::{self, RecipeAccess, RecipeFilter},
+};
+use axum::{
+    body::HttpBody,
+    extract::{Path, Query},
+    http,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json as HttpJson, Router,
+};
+use meilisearch_sdk::client::Client;
+use qdrant_client::prelude::QdrantClient;
+use r_ecipe_s_model::catalogue::rename_like;
+use r_ecipe_s_model::pantry::{
+    use_ingredients, uses_item, CookedRecipe, ExpiringItem, PantryItem, PantryItemWithId, PantryUse,
+};
+use r_ecipe_s_model::plan::MAX_PLAN_DAYS;
+use r_ecipe_s_model::{Ingredient, Quantity};
+use serde::Deserialize;
+use sqlx::types::Json;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+use time::{Date, Duration, OffsetDateTime};
+
+/// Days ahead the expiry report looks when not told otherwise.
+const DEFAULT_EXPIRY_DAYS: i64 = 7;
+/// Recipes suggested for each expiring item.
+const RECIPES_PER_ITEM: usize = 5;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Missing pantry item with id: {0}")]
+    Missing(i64),
+    #[error("Pantry items need a name")]
+    EmptyName,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyName => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait PantryService {
+    type ServiceType;
+    fn bind_pantry_routes(
+        self,
+        pantry_access: &Arc<PantryAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        search_config: &SearchConfig,
+        vector_client: &Arc<QdrantClient>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> PantryService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_pantry_routes(
+        self,
+        pantry_access: &Arc<PantryAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+        search_config: &SearchConfig,
+        vector_client: &Arc<QdrantClient>,
+    ) -> Self::ServiceType {
+        let url = search_config.http_url();
+        let search_client = Arc::new(Client::new(url, Some(search_config.api_key.clone())));
+
+        self.route(
+            "/pantry",
+            get({
+                let pantry_access = pantry_access.clone();
+                || get_pantry(pantry_access)
+            })
+            .put({
+                let pantry_access = pantry_access.clone();
+                |form, user| put_pantry_item(form, user, pantry_access)
+            }),
+        )
+        .route(
+            "/pantry/cook",
+            post({
+                let pantry_access = pantry_access.clone();
+                let recipe_access = recipe_access.clone();
+                |form, user| cook_from_pantry(form, user, pantry_access, recipe_access)
+            }),
+        )
+        .route(
+            "/pantry/expiring",
+            get({
+                let pantry_access = pantry_access.clone();
+                let vector_client = Arc::clone(vector_client);
+                |query| get_expiring(query, pantry_access, search_client, vector_client)
+            }),
+        )
+        .route(
+            "/pantry/:id",
+            get({
+                let pantry_access = pantry_access.clone();
+                |path| get_pantry_item(path, pantry_access)
+            })
+            .post({
+                let pantry_access = pantry_access.clone();
+                |path, user, form| post_pantry_item(path, user, form, pantry_access)
+            })
+            .delete({
+                let pantry_access = pantry_access.clone();
+                |path, user| delete_pantry_item(path, user, pantry_access)
+            }),
+        )
+    }
+}
+
+pub struct PantryAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct PantryItemRep {
+    id: i64,
+    name: String,
+    quantity: Json<Quantity>,
+    expires: Option<Date>,
+}
+
+impl PantryItemRep {
+    fn model_with_id(self) -> PantryItemWithId {
+        PantryItemWithId {
+            id: self.id,
+            data: PantryItem {
+                name: self.name,
+                quantity: self.quantity.0,
+                expires: self.expires,
+            },
+        }
+    }
+}
+
+impl PantryAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        PantryAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    /// Every item, those expiring first at the top.
+    async fn get_all(&self) -> Result<Vec<PantryItemWithId>> {
+        Ok(sqlx::query_as!(
+            PantryItemRep,
+            r#"
+                SELECT id, name, quantity as "quantity: Json<Quantity>", expires
+                FROM pantry_items
+                ORDER BY expires NULLS LAST, name, id
+            "#
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(PantryItemRep::model_with_id)
+        .collect())
+    }
+
+    /// Items that expire on or before `date`, including those already past.
+    async fn get_expiring_by(&self, date: Date) -> Result<Vec<PantryItemWithId>> {
+        Ok(sqlx::query_as!(
+            PantryItemRep,
+            r#"
+                SELECT id, name, quantity as "quantity: Json<Quantity>", expires
+                FROM pantry_items
+                WHERE expires <= $1
+                ORDER BY expires, name, id
+            "#,
+            date
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(PantryItemRep::model_with_id)
+        .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<PantryItemWithId>> {
+        Ok(sqlx::query_as!(
+            PantryItemRep,
+            r#"
+                SELECT id, name, quantity as "quantity: Json<Quantity>", expires
+                FROM pantry_items
+                WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(PantryItemRep::model_with_id))
+    }
+
+    async fn insert(&self, item: &PantryItem) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO pantry_items (name, quantity, expires)
+                VALUES ($1, $2, $3)
+                RETURNING id
+            "#,
+            item.name,
+            Json(&item.quantity) as _,
+            item.expires
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    async fn update(&self, id: i64, item: &PantryItem) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                UPDATE pantry_items
+                SET name = $2, quantity = $3, expires = $4
+                WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            item.name,
+            Json(&item.quantity) as _,
+            item.expires
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Takes `ingredients` out of the pantry, saving the new quantities of
+    /// what was used and removing what ran out. The stock is locked until then,
+    /// so that cooking twice at once can't use the same items twice.
+    async fn take(&self, ingredients: &[Ingredient]) -> Result<PantryUse> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let stock = sqlx::query_as!(
+            PantryItemRep,
+            r#"
+                SELECT id, name, quantity as "quantity: Json<Quantity>", expires
+                FROM pantry_items
+                ORDER BY expires NULLS LAST, name, id
+                FOR UPDATE
+            "#
+        )
+        .fetch_all(transaction.as_mut())
+        .await?
+        .into_iter()
+        .map(PantryItemRep::model_with_id)
+        .collect::<Vec<_>>();
+        let catalogue = CatalogueAccess::get_all_pool(transaction.as_mut()).await?;
+        let names = stock
+            .iter()
+            .map(|item| item.data.name.as_str())
+            .collect::<Vec<_>>();
+        let ingredients = rename_like(ingredients, &names, &catalogue);
+        let pantry_use = use_ingredients(&stock, &ingredients);
+        for item in &pantry_use.updated {
+            sqlx::query!(
+                "UPDATE pantry_items SET quantity = $2 WHERE id = $1",
+                item.id,
+                Json(&item.data.quantity) as _
+            )
+            .execute(transaction.as_mut())
+            .await?;
+        }
+        let used_up = pantry_use
+            .used_up
+            .iter()
+            .map(|item| item.id)
+            .collect::<Vec<_>>();
+        sqlx::query!("DELETE FROM pantry_items WHERE id = ANY($1)", &used_up[..])
+            .execute(transaction.as_mut())
+            .await?;
+        transaction.commit().await?;
+        Ok(pantry_use)
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM pantry_items WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+fn normalise(item: PantryItem) -> Result<PantryItem> {
+    let name = item.name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::EmptyName);
+    }
+    Ok(PantryItem { name, ..item })
+}
+
+pub(crate) async fn get_pantry(
+    pantry_access: Arc<PantryAccess>,
+) -> Result<HttpJson<Vec<PantryItemWithId>>> {
+    Ok(pantry_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_pantry_item(
+    Path(id): Path<i64>,
+    pantry_access: Arc<PantryAccess>,
+) -> Result<HttpJson<PantryItemWithId>> {
+    let item = pantry_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(item.into())
+}
+
+pub(crate) async fn put_pantry_item(
+    HttpJson(item): HttpJson<PantryItem>,
+    _user: AuthUser,
+    pantry_access: Arc<PantryAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = pantry_access.insert(&normalise(item)?).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_pantry_item(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(item): HttpJson<PantryItem>,
+    pantry_access: Arc<PantryAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = pantry_access
+        .update(id, &normalise(item)?)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_pantry_item(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    pantry_access: Arc<PantryAccess>,
+) -> Result<HttpJson<()>> {
+    pantry_access.delete(id).await?.ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
+
+/// Takes the ingredients of a cooked recipe and its component recipes, scaled
+/// to the servings cooked, out of the pantry.
+pub(crate) async fn cook_from_pantry(
+    HttpJson(cooked): HttpJson<CookedRecipe>,
+    _user: AuthUser,
+    pantry_access: Arc<PantryAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<PantryUse>> {
+    let recipe = recipe_access
+        .get_by_id(cooked.recipe_id)
+        .await?
+        .ok_or_else(|| recipe_service::Error::Missing {
+            item_type: "recipe".to_string(),
+            id: cooked.recipe_id,
+        })?;
+    let recipe = match cooked.servings.filter(|servings| *servings > 0) {
+        Some(servings) => recipe.data.scaled(servings),
+        None => recipe.data,
+    };
+    let ingredients = recipe_access.expand_components(&recipe.ingredients).await?;
+    Ok(pantry_access.take(&ingredients).await?.into())
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExpiringQuery {
+    days: Option<i64>,
+}
+
+/// Items expiring within the coming days, each with the recipes best
+/// matching it in the hybrid search that call for it.
+pub(crate) async fn get_expiring(
+    Query(ExpiringQuery { days }): Query<ExpiringQuery>,
+    pantry_access: Arc<PantryAccess>,
+    search_client: Arc<Client>,
+    vector_client: Arc<QdrantClient>,
+) -> Result<HttpJson<Vec<ExpiringItem>>> {
+    let days = days.unwrap_or(DEFAULT_EXPIRY_DAYS).clamp(0, MAX_PLAN_DAYS);
+    let by = OffsetDateTime::now_utc().date() + Duration::days(days);
+    let catalogue = CatalogueAccess::get_all_pool(pantry_access.db_access.get_pool()).await?;
+    let mut report = Vec::new();
+    for item in pantry_access.get_expiring_by(by).await? {
+        let recipes = recipe_service::hybrid_search(
+            &search_client,
+            &vector_client,
+            &item.data.name,
+            &RecipeFilter::default(),
+            None,
+        )
+        .await?
+        .into_iter()
+        .filter(|recipe| {
+            let names = [item.data.name.as_str()];
+            let ingredients = rename_like(&recipe.data.ingredients, &names, &catalogue);
+            uses_item(&ingredients, &item.data)
+        })
+        .take(RECIPES_PER_ITEM)
+        .collect();
+        report.push(ExpiringItem { item, recipes });
+    }
+    Ok(report.into())
+}
@@ -0,0 +1,349 @@
+use flate2::{write::ZlibEncoder, Compression};
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Fonts of the standard PDF set, which every viewer provides, so nothing has
+/// to be embedded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Font {
+    Regular,
+    Bold,
+    Italic,
+}
+
+impl Font {
+    const ALL: [Font; 3] = [Font::Regular, Font::Bold, Font::Italic];
+
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+        }
+    }
+
+    fn base_font(self) -> &'static str {
+        match self {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+            Font::Italic => "Helvetica-Oblique",
+        }
+    }
+
+    /// Glyph widths of printable ASCII in thousandths of the font size, from
+    /// the Adobe font metrics. The oblique face shares the regular widths.
+    fn ascii_widths(self) -> &'static [u16; 95] {
+        match self {
+            Font::Regular | Font::Italic => &HELVETICA_WIDTHS,
+            Font::Bold => &HELVETICA_BOLD_WIDTHS,
+        }
+    }
+}
+
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[rustfmt::skip]
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Width assumed for characters outside printable ASCII.
+const DEFAULT_WIDTH: u16 = 556;
+
+/// Maps a character onto the WinAnsi encoding used by the standard fonts.
+/// Characters it can't represent are printed as `?`.
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '™' => 0x99,
+        '\t' => b' ',
+        _ => b'?',
+    }
+}
+
+pub(crate) fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let widths = font.ascii_widths();
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => u32::from(widths[c as usize - 32]),
+            _ => u32::from(DEFAULT_WIDTH),
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+/// Breaks text into lines no wider than `width`, breaking words that don't fit
+/// on a line of their own.
+pub(crate) fn wrap(text: &str, font: Font, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = match line.as_str() {
+            "" => word.to_string(),
+            line => format!("{line} {word}"),
+        };
+        if text_width(&candidate, font, size) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if text_width(&line, font, size) > width && line.chars().count() > 1 {
+                let last = line.pop().unwrap_or(c);
+                lines.push(std::mem::take(&mut line));
+                line.push(last);
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Shortens text with an ellipsis so it fits in `width`.
+pub(crate) fn truncate(text: &str, font: Font, size: f32, width: f32) -> String {
+    if text_width(text, font, size) <= width {
+        return text.to_string();
+    }
+    let mut truncated = text.to_string();
+    while !truncated.is_empty() && text_width(&format!("{truncated}…"), font, size) > width {
+        truncated.pop();
+    }
+    format!("{}…", truncated.trim_end())
+}
+
+fn pdf_string(text: &str) -> String {
+    let mut string = String::with_capacity(text.len() + 2);
+    string.push('(');
+    for byte in text.chars().map(win_ansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                string.push('\\');
+                string.push(byte as char);
+            }
+            0x20..=0x7e => string.push(byte as char),
+            byte => {
+                let _ = write!(string, "\\{byte:03o}");
+            }
+        }
+    }
+    string.push(')');
+    string
+}
+
+/// A link from an area of a page, in points from the bottom left corner, to
+/// another page.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    rect: [f32; 4],
+    page: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Page {
+    content: String,
+    links: Vec<Link>,
+}
+
+impl Page {
+    /// Draws a line of text with its baseline starting at `x`, `y`.
+    pub(crate) fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        let _ = writeln!(
+            self.content,
+            "BT /{} {size:.1} Tf {x:.2} {y:.2} Td {} Tj ET",
+            font.resource(),
+            pdf_string(text)
+        );
+    }
+
+    pub(crate) fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32) {
+        let _ = writeln!(
+            self.content,
+            "{width:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            from.0, from.1, to.0, to.1
+        );
+    }
+
+    /// Makes the area between the corners `from` and `to` link to the page at
+    /// index `page` of the document.
+    pub(crate) fn link(&mut self, from: (f32, f32), to: (f32, f32), page: usize) {
+        self.links.push(Link {
+            rect: [from.0, from.1, to.0, to.1],
+            page,
+        });
+    }
+}
+
+/// A PDF document of pages of the same size, written by hand as only text,
+/// rules and internal links are needed.
+#[derive(Debug)]
+pub(crate) struct PdfDocument {
+    title: String,
+    width: f32,
+    height: f32,
+    pages: Vec<Page>,
+}
+
+impl PdfDocument {
+    pub(crate) fn new(title: &str, (width, height): (f32, f32)) -> Self {
+        PdfDocument {
+            title: title.to_string(),
+            width,
+            height,
+            pages: Vec::new(),
+        }
+    }
+
+    pub(crate) fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub(crate) fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Adds a page and returns its index.
+    pub(crate) fn add_page(&mut self) -> usize {
+        self.pages.push(Page::default());
+        self.pages.len() - 1
+    }
+
+    pub(crate) fn page(&mut self, index: usize) -> &mut Page {
+        &mut self.pages[index]
+    }
+
+    pub(crate) fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+        // Objects are numbered: catalog, page tree, info, fonts, then a page
+        // followed by its content stream for every page.
+        const CATALOG: usize = 1;
+        const PAGES: usize = 2;
+        const INFO: usize = 3;
+        const FIRST_FONT: usize = 4;
+        let first_page = FIRST_FONT + Font::ALL.len();
+        let page_id = |index: usize| first_page + 2 * index;
+
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        objects.push(format!("<< /Type /Catalog /Pages {PAGES} 0 R >>").into_bytes());
+        let kids = (0..self.pages.len())
+            .map(|index| format!("{} 0 R", page_id(index)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{kids}] /Count {} /MediaBox [0 0 {:.2} {:.2}] >>",
+                self.pages.len(),
+                self.width,
+                self.height
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            format!(
+                "<< /Title {} /Producer (r_ecipe_s) >>",
+                pdf_string(&self.title)
+            )
+            .into_bytes(),
+        );
+        for font in Font::ALL {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font.base_font()
+                )
+                .into_bytes(),
+            );
+        }
+        let fonts = Font::ALL
+            .iter()
+            .enumerate()
+            .map(|(offset, font)| format!("/{} {} 0 R", font.resource(), FIRST_FONT + offset))
+            .collect::<Vec<_>>()
+            .join(" ");
+        for (index, page) in self.pages.iter().enumerate() {
+            let annotations = page
+                .links
+                .iter()
+                .map(|link| {
+                    let [x1, y1, x2, y2] = link.rect;
+                    format!(
+                        "<< /Type /Annot /Subtype /Link /Rect [{x1:.2} {y1:.2} {x2:.2} {y2:.2}] /Border [0 0 0] /Dest [{} 0 R /Fit] >>",
+                        page_id(link.page)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent {PAGES} 0 R /Resources << /Font << {fonts} >> >> /Contents {} 0 R /Annots [{annotations}] >>",
+                    page_id(index) + 1
+                )
+                .into_bytes(),
+            );
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(page.content.as_bytes())?;
+            let content = encoder.finish()?;
+            let mut stream = format!(
+                "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+                content.len()
+            )
+            .into_bytes();
+            stream.extend(content);
+            stream.extend(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj", index + 1)?;
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1)?;
+        for offset in offsets {
+            writeln!(pdf, "{offset:010} 00000 n ")?;
+        }
+        write!(
+            pdf,
+            "trailer\n<< /Size {} /Root {CATALOG} 0 R /Info {INFO} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )?;
+        Ok(pdf)
+    }
+}
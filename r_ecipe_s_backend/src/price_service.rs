@@ -0,0 +1,309 @@
+use crate::{
+    auth::AuthUser,
+    catalogue_service::CatalogueAccess,
+    db::DbAccess,
+    nutrition_service::{food_mappings, NutritionAccess},
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::cost::{CostEstimate, Price, PriceBook, PriceWithId};
+use r_ecipe_s_model::Quantity;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+use time::Date;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("Missing price with id: {0}")]
+    Missing(i64),
+    #[error("Missing catalogue entry with id: {0}")]
+    MissingEntry(i64),
+    #[error("Prices need a currency")]
+    EmptyCurrency,
+    #[error("Prices need to be for a positive amount and not negative")]
+    InvalidPrice,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::MissingEntry(_) => http::StatusCode::BAD_REQUEST,
+            Error::EmptyCurrency => http::StatusCode::BAD_REQUEST,
+            Error::InvalidPrice => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait PriceService {
+    type ServiceType;
+    fn bind_price_routes(
+        self,
+        price_access: &Arc<PriceAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> PriceService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_price_routes(
+        self,
+        price_access: &Arc<PriceAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/prices",
+            get({
+                let price_access = price_access.clone();
+                || get_prices(price_access)
+            })
+            .put({
+                let price_access = price_access.clone();
+                |form, user| put_price(form, user, price_access)
+            }),
+        )
+        .route(
+            "/prices/:id",
+            get({
+                let price_access = price_access.clone();
+                |path| get_price(path, price_access)
+            })
+            .delete({
+                let price_access = price_access.clone();
+                |path, user| delete_price(path, user, price_access)
+            }),
+        )
+        .route(
+            "/recipes/:id/cost",
+            get({
+                let price_access = price_access.clone();
+                let recipe_access = recipe_access.clone();
+                |path| get_recipe_cost(path, price_access, recipe_access)
+            }),
+        )
+    }
+}
+
+pub struct PriceAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct PriceRep {
+    id: i64,
+    catalogue_id: i64,
+    per: Json<Quantity>,
+    price: f64,
+    currency: String,
+    date: Date,
+}
+
+impl PriceRep {
+    fn model_with_id(self) -> PriceWithId {
+        PriceWithId {
+            id: self.id,
+            data: Price {
+                catalogue_id: self.catalogue_id,
+                per: self.per.0,
+                price: self.price,
+                currency: self.currency,
+                date: self.date,
+            },
+        }
+    }
+}
+
+impl PriceAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        PriceAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<PriceWithId>> {
+        Ok(sqlx::query_as!(
+            PriceRep,
+            r#"
+                SELECT id, catalogue_id, per as "per: Json<Quantity>", price, currency, date
+                FROM prices
+                ORDER BY catalogue_id, date DESC, id DESC
+            "#
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(PriceRep::model_with_id)
+        .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<PriceWithId>> {
+        Ok(sqlx::query_as!(
+            PriceRep,
+            r#"
+                SELECT id, catalogue_id, per as "per: Json<Quantity>", price, currency, date
+                FROM prices
+                WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(PriceRep::model_with_id))
+    }
+
+    /// The catalogue and prices, along with the nutrition table's weights for
+    /// converting between units.
+    pub(crate) async fn get_price_book_pool(pool: &PgPool) -> sqlx::Result<PriceBook> {
+        let prices = sqlx::query_as!(
+            PriceRep,
+            r#"
+                SELECT id, catalogue_id, per as "per: Json<Quantity>", price, currency, date
+                FROM prices
+            "#
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(PriceRep::model_with_id)
+        .collect();
+        Ok(PriceBook {
+            catalogue: CatalogueAccess::get_all_pool(pool).await?,
+            prices,
+            foods: NutritionAccess::get_foods_pool(pool).await?,
+            mappings: food_mappings(NutritionAccess::get_mappings_pool(pool).await?),
+        })
+    }
+
+    async fn entry_exists(&self, catalogue_id: i64) -> Result<bool> {
+        let rec = sqlx::query!(
+            "SELECT id FROM ingredient_catalogue WHERE id = $1",
+            catalogue_id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.is_some())
+    }
+
+    async fn insert(&self, price: &Price) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO prices (catalogue_id, per, price, currency, date)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id
+            "#,
+            price.catalogue_id,
+            Json(&price.per) as _,
+            price.price,
+            price.currency,
+            price.date
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM prices WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+fn normalise(price: Price) -> Result<Price> {
+    if !(price.price >= 0.0 && price.per.value() > 0.0) {
+        return Err(Error::InvalidPrice);
+    }
+    let currency = price.currency.trim().to_uppercase();
+    if currency.is_empty() {
+        return Err(Error::EmptyCurrency);
+    }
+    Ok(Price { currency, ..price })
+}
+
+/// Every price, the latest first for each catalogue entry.
+pub(crate) async fn get_prices(
+    price_access: Arc<PriceAccess>,
+) -> Result<HttpJson<Vec<PriceWithId>>> {
+    Ok(price_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_price(
+    Path(id): Path<i64>,
+    price_access: Arc<PriceAccess>,
+) -> Result<HttpJson<PriceWithId>> {
+    let price = price_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(price.into())
+}
+
+/// Records a price. Earlier prices of the entry are kept, and the one with
+/// the latest date is used for estimates.
+pub(crate) async fn put_price(
+    HttpJson(price): HttpJson<Price>,
+    _user: AuthUser,
+    price_access: Arc<PriceAccess>,
+) -> Result<HttpJson<i64>> {
+    let price = normalise(price)?;
+    if !price_access.entry_exists(price.catalogue_id).await? {
+        return Err(Error::MissingEntry(price.catalogue_id));
+    }
+    Ok(price_access.insert(&price).await?.into())
+}
+
+pub(crate) async fn delete_price(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    price_access: Arc<PriceAccess>,
+) -> Result<HttpJson<()>> {
+    price_access.delete(id).await?.ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
+
+/// What the recipe costs to make, component recipes included, in all and per
+/// serving, with the ingredients that couldn't be priced.
+pub(crate) async fn get_recipe_cost(
+    Path(id): Path<i64>,
+    price_access: Arc<PriceAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<CostEstimate>> {
+    let recipe =
+        recipe_access
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| recipe_service::Error::Missing {
+                item_type: "recipe".to_string(),
+                id,
+            })?;
+    let ingredients = recipe_access
+        .expand_components(&recipe.data.ingredients)
+        .await?;
+    let price_book = PriceAccess::get_price_book_pool(price_access.db_access.get_pool()).await?;
+    Ok(price_book
+        .estimate(&ingredients, recipe.data.servings)
+        .into())
+}
@@ -1,13 +1,22 @@
 use crate::{
     app_config::{SearchConfig, VectorSearchConfig},
-    auth::{AuthError, BearerToken, BearerValidation},
+    auth::AuthUser,
     db::DbAccess,
-    search_indexer::{RECIPES_VEC_COLLECTION_NAME, R_ECIPE_S_INDEX_NAME},
+    equipment_service::EquipmentAccess,
+    image_service::recipe_images_prefix,
+    image_store::ImageStore,
+    nutrition_service::{food_mappings, NutritionAccess},
+    search_indexer::{
+        ALLERGENS_FILTER_ATTRIBUTE, ALLERGENS_PAYLOAD_KEY, DIETS_FILTER_ATTRIBUTE,
+        DIETS_PAYLOAD_KEY, EQUIPMENT_FILTER_ATTRIBUTE, EQUIPMENT_PAYLOAD_KEY,
+        RECIPES_VEC_COLLECTION_NAME, R_ECIPE_S_INDEX_NAME, TAGS_FILTER_ATTRIBUTE, TAGS_PAYLOAD_KEY,
+        TOTAL_MINUTES_FILTER_ATTRIBUTE, TOTAL_MINUTES_PAYLOAD_KEY,
+    },
 };
 use axum::{
     body::HttpBody,
     extract::{Path, Query},
-    http,
+    http::{self, header},
     response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json as HttpJson, Router,
@@ -16,10 +25,20 @@ use futures_util::{StreamExt, TryStreamExt};
 use qdrant_client::{
     prelude::{QdrantClient, QdrantClientConfig},
     qdrant::{
-        point_id::PointIdOptions, with_payload_selector::SelectorOptions, PointId, SearchPoints,
-        Value, WithPayloadSelector,
+        point_id::PointIdOptions, with_payload_selector::SelectorOptions, Condition, Filter,
+        PointId, Range, SearchPoints, Value, WithPayloadSelector,
     },
 };
+use r_ecipe_s_model::component::{
+    check_components, component_ids, expand_components, ComponentError,
+};
+use r_ecipe_s_model::cook_log::RecipeOrder;
+use r_ecipe_s_model::cooklang::{to_cooklang, COOKLANG_EXTENSION};
+use r_ecipe_s_model::diet::{dietary_flags, DietaryFilter, DietaryFlags};
+use r_ecipe_s_model::equipment::{normalise_equipment, split_equipment};
+use r_ecipe_s_model::schema_org::{recipe_to_json_ld, JSON_LD_CONTENT_TYPE};
+use r_ecipe_s_model::tags::{normalise_tag, normalise_tags};
+use r_ecipe_s_model::timing::{Temperature, TemperatureUnit, Timing, TimingError};
 use r_ecipe_s_model::{
     serde_json, Ingredient, Recipe, RecipeWithId, RecipesResponse, SearchQuery, SearchResponse,
     SearchResult,
@@ -27,7 +46,8 @@ use r_ecipe_s_model::{
 use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
 use sqlx::{types::time::OffsetDateTime, PgExecutor};
-use sqlx::{FromRow, Postgres, Transaction};
+use sqlx::{FromRow, PgConnection, Postgres, Transaction};
+use time::{Date, Duration};
 use tracing::log::{error, info};
 
 use meilisearch_sdk::client::Client;
@@ -38,6 +58,8 @@ use std::{
 };
 use thiserror::Error as ThisError;
 const MAX_PAGE_SIZE: i64 = 100;
+const JSON_LD_EXTENSION: &str = "jsonld";
+pub(crate) const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
 
 #[derive(Debug, ThisError)]
 pub enum Error {
@@ -53,14 +75,24 @@ pub enum Error {
     Missing { item_type: String, id: i64 },
     #[error("Incorrect page size: {0}. Must be between 1 and 100")]
     IncorrectPageSize(i64),
-    #[error("Error with authentication: {0}")]
-    Auth(#[from] AuthError),
     #[error("Search error: {0}")]
     Search(#[from] meilisearch_sdk::errors::Error),
     #[error("Resource with Id {0} not found")]
     NotFoundId(i64),
     #[error("Error with vector DB {0}.")]
     Vector(String),
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+    #[error("Invalid times or temperature: {0}")]
+    Timing(#[from] TimingError),
+    #[error("Invalid components: {0}")]
+    Component(#[from] ComponentError),
+    #[error("Recipe {id} is a component of recipes {used_by:?}")]
+    ComponentInUse { id: i64, used_by: Vec<i64> },
+    #[error("Invalid servings: {0}. Must be between 1 and {max}", max = i32::MAX)]
+    InvalidServings(u32),
+    #[error("Error with image storage: {0}")]
+    Storage(#[from] std::io::Error),
 }
 
 impl Error {
@@ -69,7 +101,19 @@ impl Error {
     }
 }
 
-type Result<T> = std::result::Result<T, Error>;
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+/// Servings as stored, at least one and fitting the column.
+pub(crate) fn stored_servings(servings: Option<u32>) -> Result<Option<i32>> {
+    servings
+        .map(|servings| {
+            i32::try_from(servings)
+                .ok()
+                .filter(|servings| *servings > 0)
+                .ok_or(Error::InvalidServings(servings))
+        })
+        .transpose()
+}
 struct RecipeId {
     id: i64,
 }
@@ -80,13 +124,18 @@ impl IntoResponse for Error {
             Error::Fail => http::StatusCode::INTERNAL_SERVER_ERROR,
             Error::Serde(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
             Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
-            Error::ParseInt(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ParseInt(_) => http::StatusCode::BAD_REQUEST,
             Error::Missing { .. } => http::StatusCode::NOT_FOUND,
             Error::IncorrectPageSize(_) => http::StatusCode::BAD_REQUEST,
-            Error::Auth(_) => http::StatusCode::UNAUTHORIZED,
             Error::Search(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
             Error::NotFoundId(_) => http::StatusCode::NOT_FOUND,
             Error::Vector(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::UnknownFormat(_) => http::StatusCode::BAD_REQUEST,
+            Error::Timing(_) => http::StatusCode::BAD_REQUEST,
+            Error::Component(_) => http::StatusCode::BAD_REQUEST,
+            Error::ComponentInUse { .. } => http::StatusCode::CONFLICT,
+            Error::InvalidServings(_) => http::StatusCode::BAD_REQUEST,
+            Error::Storage(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
         };
         (error_code, format!("{self}")).into_response()
     }
@@ -97,9 +146,9 @@ pub trait RecipeService {
     fn bind_recipe_routes(
         self,
         recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
         search_config: &SearchConfig,
         vector_client: &Arc<QdrantClient>,
-        bearer_validation: &Arc<BearerValidation>,
     ) -> Self::ServiceType;
 }
 
@@ -113,9 +162,9 @@ where
     fn bind_recipe_routes(
         self,
         recipe_access: &Arc<RecipeAccess>,
+        image_store: &Arc<dyn ImageStore>,
         search_config: &SearchConfig,
         vector_client: &Arc<QdrantClient>,
-        bearer_validation: &Arc<BearerValidation>,
     ) -> Self::ServiceType {
         let url = search_config.http_url();
         let search_client = Arc::new(Client::new(url, Some(search_config.api_key.clone())));
@@ -128,37 +177,32 @@ where
             })
             .put({
                 let recipe_access = recipe_access.clone();
-                let bearer_validation = bearer_validation.clone();
-                |form, bearer_auth| put_recipe(form, bearer_auth, recipe_access, bearer_validation)
+                |form, user| put_recipe(form, user, recipe_access)
             }),
         )
         .route(
             "/recipes/:id",
             delete({
                 let recipe_access = recipe_access.clone();
-                let bearer_validation = bearer_validation.clone();
-                |path, bearer_auth| {
-                    delete_recipe(path, bearer_auth, recipe_access, bearer_validation)
-                }
+                let image_store = image_store.clone();
+                |path, user| delete_recipe(path, user, recipe_access, image_store)
             })
             .post({
                 let recipe_access = recipe_access.clone();
-                let bearer_validation = bearer_validation.clone();
-                |path, bearer_auth, form_data| {
-                    post_recipe(
-                        path,
-                        bearer_auth,
-                        form_data,
-                        recipe_access,
-                        bearer_validation,
-                    )
-                }
+                |path, user, form_data| post_recipe(path, user, form_data, recipe_access)
             })
             .get({
                 let recipe_access = recipe_access.clone();
                 |path| get_recipe(path, recipe_access)
             }),
         )
+        .route(
+            "/recipes/:id/ingredients",
+            get({
+                let recipe_access = recipe_access.clone();
+                |path, query| get_expanded_ingredients(path, query, recipe_access)
+            }),
+        )
         .route(
             "/recipes/search",
             post({
@@ -185,6 +229,17 @@ pub struct RecipeRep {
     liked: Option<bool>,
     searchable: bool,
     embedding: Option<Vec<f32>>,
+    servings: Option<i32>,
+    prep_minutes: Option<i32>,
+    cook_minutes: Option<i32>,
+    total_minutes: Option<i32>,
+    oven_degrees: Option<i32>,
+    oven_unit: Option<String>,
+    allergens: Vec<String>,
+    diets: Vec<String>,
+    tags: Vec<String>,
+    images: Vec<i64>,
+    equipment: Vec<String>,
 }
 
 impl RecipeRep {
@@ -195,6 +250,25 @@ impl RecipeRep {
             description: self.description,
             liked: self.liked,
             embedding: self.embedding,
+            servings: self.servings.map(|servings| servings as u32),
+            tags: self.tags,
+            dietary: DietaryFlags::from_names(&self.allergens, &self.diets),
+            images: self.images,
+            timing: Timing {
+                prep_minutes: self.prep_minutes.map(|minutes| minutes as u32),
+                cook_minutes: self.cook_minutes.map(|minutes| minutes as u32),
+                total_minutes: self.total_minutes.map(|minutes| minutes as u32),
+                oven: self
+                    .oven_degrees
+                    .zip(self.oven_unit)
+                    .and_then(|(degrees, unit)| {
+                        Some(Temperature {
+                            degrees: degrees as u32,
+                            unit: TemperatureUnit::parse(&unit)?,
+                        })
+                    }),
+            },
+            equipment: self.equipment,
         }
     }
 
@@ -205,6 +279,20 @@ impl RecipeRep {
     }
 }
 
+/// Which recipes to list.
+#[derive(Debug, Default)]
+pub(crate) struct RecipeFilter {
+    /// Only recipes with this tag.
+    pub(crate) tag: Option<String>,
+    pub(crate) dietary: DietaryFilter,
+    /// Only recipes not cooked since this day, or never.
+    pub(crate) not_cooked_since: Option<Date>,
+    /// Only recipes taking at most this many minutes in total.
+    pub(crate) max_minutes: Option<u32>,
+    /// Only recipes needing none of this equipment.
+    pub(crate) without_equipment: Vec<String>,
+}
+
 const EMPTY_RECIPE_LIST: &[RecipeWithId] = &[];
 
 impl RecipeAccess {
@@ -223,7 +311,32 @@ impl RecipeAccess {
                     description, 
                     liked,
                     searchable,
-                    embedding
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
                 FROM recipes
                 WHERE searchable = false
                 ORDER BY id 
@@ -281,11 +394,32 @@ impl RecipeAccess {
         Ok(res)
     }
 
-    async fn get_all(&self, page: i64, page_size: i64) -> Result<RecipesResponse> {
+    /// A page of the recipes passing the filter, in the given order.
+    async fn get_all(
+        &self,
+        page: i64,
+        page_size: i64,
+        filter: &RecipeFilter,
+        order: RecipeOrder,
+    ) -> Result<RecipesResponse> {
         if (page_size <= 0) || (page_size > MAX_PAGE_SIZE) {
             return Err(Error::IncorrectPageSize(page_size));
         }
         let offset = page * page_size;
+        let RecipeFilter {
+            tag,
+            dietary,
+            not_cooked_since,
+            max_minutes,
+            without_equipment,
+        } = filter;
+        let max_minutes = max_minutes.map(|minutes| minutes.min(i32::MAX as u32) as i32);
+        let diet = dietary.diet.map(|diet| diet.to_string());
+        let free_of = dietary
+            .free_of
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
         let data = sqlx::query_as!(
             RecipeRep,
             r#"
@@ -296,14 +430,76 @@ impl RecipeAccess {
                     description, 
                     liked,
                     searchable,
-                    embedding
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
                 FROM recipes
-                ORDER BY updated DESC
+                LEFT JOIN LATERAL (
+                    SELECT MAX(cooked) as last_cooked, COUNT(id) as times_cooked,
+                        AVG(rating) as average_rating
+                    FROM cook_log
+                    WHERE cook_log.recipe_id = recipes.id
+                ) stats ON TRUE
+                WHERE ($3::text IS NULL OR EXISTS (
+                    SELECT 1 FROM recipe_tags
+                    JOIN tags ON tags.id = recipe_tags.tag_id
+                    WHERE recipe_tags.recipe_id = recipes.id AND tags.name = $3
+                ))
+                    AND ($4::text IS NULL OR $4 = ANY(diets))
+                    AND NOT allergens && $5
+                    AND ($7::date IS NULL OR NOT EXISTS (
+                        SELECT 1 FROM cook_log
+                        WHERE cook_log.recipe_id = recipes.id AND cooked >= $7
+                    ))
+                    AND ($8::int IS NULL OR total_minutes <= $8)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                            AND equipment.name = ANY($9)
+                    )
+                ORDER BY
+                    CASE WHEN $6 = 'name' THEN name END ASC,
+                    CASE WHEN $6 = 'last_cooked' THEN stats.last_cooked END DESC NULLS LAST,
+                    CASE WHEN $6 = 'least_recently_cooked' THEN stats.last_cooked END ASC NULLS FIRST,
+                    CASE WHEN $6 = 'most_cooked' THEN stats.times_cooked END DESC,
+                    CASE WHEN $6 = 'rating' THEN stats.average_rating END DESC NULLS LAST,
+                    updated DESC
                 OFFSET $1
                 LIMIT $2
             "#,
             offset,
-            page_size
+            page_size,
+            tag.as_deref(),
+            diet,
+            &free_of[..],
+            order.as_str(),
+            *not_cooked_since,
+            max_minutes,
+            &without_equipment[..]
         )
         .fetch(self.db_access.get_pool())
         .map(
@@ -316,9 +512,33 @@ impl RecipeAccess {
         .await;
         let count = sqlx::query!(
             r#"
-                SELECT GREATEST (((COUNT(id) - 1)::int8 / $1),  0::int8)::int8 as count  FROM recipes;
+                SELECT GREATEST (((COUNT(id) - 1)::int8 / $1),  0::int8)::int8 as count  FROM recipes
+                WHERE ($2::text IS NULL OR EXISTS (
+                    SELECT 1 FROM recipe_tags
+                    JOIN tags ON tags.id = recipe_tags.tag_id
+                    WHERE recipe_tags.recipe_id = recipes.id AND tags.name = $2
+                ))
+                    AND ($3::text IS NULL OR $3 = ANY(diets))
+                    AND NOT allergens && $4
+                    AND ($5::date IS NULL OR NOT EXISTS (
+                        SELECT 1 FROM cook_log
+                        WHERE cook_log.recipe_id = recipes.id AND cooked >= $5
+                    ))
+                    AND ($6::int IS NULL OR total_minutes <= $6)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                            AND equipment.name = ANY($7)
+                    );
             "#,
-            page_size
+            page_size,
+            tag.as_deref(),
+            diet,
+            &free_of[..],
+            *not_cooked_since,
+            max_minutes,
+            &without_equipment[..]
         )
         .fetch_one(self.db_access.get_pool())
         .await?
@@ -332,19 +552,311 @@ impl RecipeAccess {
         })
     }
 
-    async fn update(&self, id: i64, recipe: &Recipe) -> Result<Option<i64>> {
+    pub(crate) async fn get_all_recipes(&self) -> Result<Vec<RecipeWithId>> {
+        sqlx::query_as!(
+            RecipeRep,
+            r#"
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                ORDER BY id
+            "#
+        )
+        .fetch(self.db_access.get_pool())
+        .map(
+            |rep_res: std::result::Result<RecipeRep, _>| -> Result<RecipeWithId> {
+                let recipe = rep_res?;
+                Ok(recipe.model_with_id())
+            },
+        )
+        .try_collect::<Vec<_>>()
+        .await
+    }
+
+    /// The recipes with the given ids, in the order asked for.
+    pub(crate) async fn get_by_ids(&self, ids: &[i64]) -> Result<Vec<RecipeWithId>> {
+        let recipes = sqlx::query_as!(
+            RecipeRep,
+            r#"
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                WHERE id = ANY($1)
+            "#,
+            ids
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?;
+        let mut recipes = recipes
+            .into_iter()
+            .map(|rep| (rep.id, rep.model_with_id()))
+            .collect::<HashMap<_, _>>();
+        ids.iter()
+            .map(|id| {
+                recipes.remove(id).ok_or_else(|| Error::Missing {
+                    item_type: "recipe".to_string(),
+                    id: *id,
+                })
+            })
+            .collect()
+    }
+
+    /// Every recipe with the given tag, by name.
+    pub(crate) async fn get_by_tag(&self, tag: &str) -> Result<Vec<RecipeWithId>> {
         sqlx::query_as!(
+            RecipeRep,
+            r#"
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                WHERE EXISTS (
+                    SELECT 1 FROM recipe_tags
+                    JOIN tags ON tags.id = recipe_tags.tag_id
+                    WHERE recipe_tags.recipe_id = recipes.id AND tags.name = $1
+                )
+                ORDER BY name
+            "#,
+            tag
+        )
+        .fetch(self.db_access.get_pool())
+        .map(
+            |rep_res: std::result::Result<RecipeRep, _>| -> Result<RecipeWithId> {
+                let recipe = rep_res?;
+                Ok(recipe.model_with_id())
+            },
+        )
+        .try_collect::<Vec<_>>()
+        .await
+    }
+
+    /// Every recipe in the collection, in its order.
+    pub(crate) async fn get_by_collection(&self, collection_id: i64) -> Result<Vec<RecipeWithId>> {
+        sqlx::query_as!(
+            RecipeRep,
+            r#"
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                JOIN collection_recipes ON collection_recipes.recipe_id = recipes.id
+                WHERE collection_recipes.collection_id = $1
+                ORDER BY collection_recipes.position
+            "#,
+            collection_id
+        )
+        .fetch(self.db_access.get_pool())
+        .map(
+            |rep_res: std::result::Result<RecipeRep, _>| -> Result<RecipeWithId> {
+                let recipe = rep_res?;
+                Ok(recipe.model_with_id())
+            },
+        )
+        .try_collect::<Vec<_>>()
+        .await
+    }
+
+    async fn update(&self, id: i64, recipe: &Recipe) -> Result<Option<i64>> {
+        let mut connection = self.db_access.get_pool().acquire().await?;
+        Self::update_pool(&mut connection, id, recipe).await
+    }
+
+    /// Saves the recipe, working out its dietary flags from the ingredients
+    /// again, its total time if not given and whether it needs an oven. The
+    /// flags of recipes using it as a component are worked out again too.
+    pub(crate) async fn update_pool(
+        connection: &mut PgConnection,
+        id: i64,
+        recipe: &Recipe,
+    ) -> Result<Option<i64>> {
+        recipe.timing.validate()?;
+        let components = Self::get_components_pool(&mut *connection, &recipe.ingredients).await?;
+        check_components(Some(id), &recipe.ingredients, &components)?;
+        let timing = recipe.timing.with_total();
+        let servings = stored_servings(recipe.servings)?;
+        let tags = normalise_tags(&recipe.tags);
+        let equipment_catalogue = EquipmentAccess::get_all_pool(&mut *connection).await?;
+        let equipment = normalise_equipment(&recipe.equipment, &timing, &equipment_catalogue);
+        let dietary =
+            Self::classify_pool(&mut *connection, &recipe.ingredients, &components).await?;
+        let updated = sqlx::query_as!(
             RecipeId,
             r#"
-                UPDATE recipes SET 
-                    name = $1,
-                    ingredients = $2, 
-                    description = $3,
-                    liked = $4,
-                    updated = $5,
-                    searchable = false,
-                    embedding = $6
-                where id = $7 RETURNING id as "id!: i64"
+                WITH updated AS (
+                    UPDATE recipes SET 
+                        name = $1,
+                        ingredients = $2, 
+                        description = $3,
+                        liked = $4,
+                        updated = $5,
+                        searchable = false,
+                        embedding = $6,
+                        servings = $7,
+                        allergens = $10,
+                        diets = $11,
+                        prep_minutes = $12,
+                        cook_minutes = $13,
+                        total_minutes = $14,
+                        oven_degrees = $15,
+                        oven_unit = $16
+                    where id = $8 RETURNING id
+                ), new_tags AS (
+                    INSERT INTO tags (name)
+                    SELECT UNNEST($9::text[]) WHERE EXISTS (SELECT 1 FROM updated)
+                    ON CONFLICT (name) DO NOTHING
+                    RETURNING id
+                ), recipe_tag_ids AS (
+                    SELECT id FROM new_tags
+                    UNION SELECT id FROM tags WHERE name = ANY($9)
+                ), removed AS (
+                    DELETE FROM recipe_tags
+                    WHERE recipe_id IN (SELECT id FROM updated)
+                        AND tag_id NOT IN (SELECT id FROM recipe_tag_ids)
+                ), added AS (
+                    INSERT INTO recipe_tags (recipe_id, tag_id)
+                    SELECT updated.id, recipe_tag_ids.id FROM updated, recipe_tag_ids
+                    ON CONFLICT DO NOTHING
+                ), new_equipment AS (
+                    INSERT INTO equipment (name)
+                    SELECT UNNEST($17::text[]) WHERE EXISTS (SELECT 1 FROM updated)
+                    ON CONFLICT (name) DO NOTHING
+                    RETURNING id
+                ), recipe_equipment_ids AS (
+                    SELECT id FROM new_equipment
+                    UNION SELECT id FROM equipment WHERE name = ANY($17)
+                ), removed_equipment AS (
+                    DELETE FROM recipe_equipment
+                    WHERE recipe_id IN (SELECT id FROM updated)
+                        AND equipment_id NOT IN (SELECT id FROM recipe_equipment_ids)
+                ), added_equipment AS (
+                    INSERT INTO recipe_equipment (recipe_id, equipment_id)
+                    SELECT updated.id, recipe_equipment_ids.id
+                    FROM updated, recipe_equipment_ids
+                    ON CONFLICT DO NOTHING
+                )
+                SELECT id as "id!: i64" FROM updated
             "#,
             recipe.name,
             sqlx::types::Json(recipe.ingredients.clone()) as _,
@@ -354,16 +866,45 @@ impl RecipeAccess {
             (&recipe.embedding)
                 .as_ref()
                 .map(|arr| <Vec<f32> as AsRef<[f32]>>::as_ref(arr)),
+            servings,
             id,
+            &tags[..],
+            &dietary.allergen_names()[..],
+            &dietary.diet_names()[..],
+            timing.prep_minutes.map(|minutes| minutes as i32),
+            timing.cook_minutes.map(|minutes| minutes as i32),
+            timing.total_minutes.map(|minutes| minutes as i32),
+            timing.oven.map(|oven| oven.degrees as i32),
+            timing.oven.map(|oven| oven.unit.as_str()),
+            &equipment[..],
         )
-        .fetch_optional(self.db_access.get_pool())
-        .await
-        .map(|opt| opt.map(|recipe_id| recipe_id.id))
-        .map_err(|err| err.into())
+        .fetch_optional(&mut *connection)
+        .await?
+        .map(|recipe_id| recipe_id.id);
+        if updated.is_some() {
+            Self::classify_dependants_pool(connection, id).await?;
+        }
+        Ok(updated)
     }
 
     async fn delete(&self, id: i64) -> Result<()> {
         let now = OffsetDateTime::now_utc();
+        let used_by = sqlx::query!(
+            r#"
+                SELECT DISTINCT id FROM recipes, json_array_elements(ingredients) ingredient
+                WHERE (ingredient->'component'->>'recipe_id')::bigint = $1
+                ORDER BY id
+            "#,
+            id,
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?;
+        if !used_by.is_empty() {
+            return Err(Error::ComponentInUse {
+                id,
+                used_by: used_by.into_iter().map(|rec| rec.id).collect(),
+            });
+        }
         sqlx::query!(
             r#"
                 DELETE FROM recipes WHERE id = $1
@@ -378,40 +919,318 @@ impl RecipeAccess {
         .ok_or(Error::NotFoundId(id))
     }
 
-    async fn insert(&self, recipe: &Recipe) -> Result<i64> {
+    pub(crate) async fn insert(&self, recipe: &Recipe) -> Result<i64> {
+        let mut connection = self.db_access.get_pool().acquire().await?;
+        Self::insert_pool(&mut connection, recipe).await
+    }
+
+    pub(crate) async fn insert_pool(connection: &mut PgConnection, recipe: &Recipe) -> Result<i64> {
+        recipe.timing.validate()?;
+        let components = Self::get_components_pool(&mut *connection, &recipe.ingredients).await?;
+        check_components(None, &recipe.ingredients, &components)?;
+        let timing = recipe.timing.with_total();
+        let servings = stored_servings(recipe.servings)?;
         let now = OffsetDateTime::now_utc();
+        let tags = normalise_tags(&recipe.tags);
+        let equipment_catalogue = EquipmentAccess::get_all_pool(&mut *connection).await?;
+        let equipment = normalise_equipment(&recipe.equipment, &timing, &equipment_catalogue);
+        let dietary =
+            Self::classify_pool(&mut *connection, &recipe.ingredients, &components).await?;
         let rec = sqlx::query!(
             r#"
-                INSERT INTO recipes (
-                    name,
-                    ingredients,
-                    description,
-                    liked,
-                    created,
-                    updated,
-                    searchable
-                ) VALUES (
-                    $1,
-                    $2,
-                    $3,
-                    $4,
-                    $5,
-                    $5,
-                    false
-                ) RETURNING id
+                WITH inserted AS (
+                    INSERT INTO recipes (
+                        name,
+                        ingredients,
+                        description,
+                        liked,
+                        created,
+                        updated,
+                        searchable,
+                        servings,
+                        allergens,
+                        diets,
+                        prep_minutes,
+                        cook_minutes,
+                        total_minutes,
+                        oven_degrees,
+                        oven_unit
+                    ) VALUES (
+                        $1,
+                        $2,
+                        $3,
+                        $4,
+                        $5,
+                        $5,
+                        false,
+                        $6,
+                        $8,
+                        $9,
+                        $10,
+                        $11,
+                        $12,
+                        $13,
+                        $14
+                    ) RETURNING id
+                ), new_tags AS (
+                    INSERT INTO tags (name)
+                    SELECT UNNEST($7::text[])
+                    ON CONFLICT (name) DO NOTHING
+                    RETURNING id
+                ), added AS (
+                    INSERT INTO recipe_tags (recipe_id, tag_id)
+                    SELECT inserted.id, recipe_tag_ids.id
+                    FROM inserted, (
+                        SELECT id FROM new_tags
+                        UNION SELECT id FROM tags WHERE name = ANY($7)
+                    ) recipe_tag_ids
+                ), new_equipment AS (
+                    INSERT INTO equipment (name)
+                    SELECT UNNEST($15::text[])
+                    ON CONFLICT (name) DO NOTHING
+                    RETURNING id
+                ), added_equipment AS (
+                    INSERT INTO recipe_equipment (recipe_id, equipment_id)
+                    SELECT inserted.id, recipe_equipment_ids.id
+                    FROM inserted, (
+                        SELECT id FROM new_equipment
+                        UNION SELECT id FROM equipment WHERE name = ANY($15)
+                    ) recipe_equipment_ids
+                )
+                SELECT id as "id!" FROM inserted
             "#,
             recipe.name,
             sqlx::types::Json(recipe.ingredients.clone()) as _,
             recipe.description,
             recipe.liked,
-            now
+            now,
+            servings,
+            &tags[..],
+            &dietary.allergen_names()[..],
+            &dietary.diet_names()[..],
+            timing.prep_minutes.map(|minutes| minutes as i32),
+            timing.cook_minutes.map(|minutes| minutes as i32),
+            timing.total_minutes.map(|minutes| minutes as i32),
+            timing.oven.map(|oven| oven.degrees as i32),
+            timing.oven.map(|oven| oven.unit.as_str()),
+            &equipment[..],
         )
-        .fetch_one(self.db_access.get_pool())
+        .fetch_one(&mut *connection)
         .await?;
         Ok(rec.id)
     }
 
-    async fn get_by_id_pool<'a, P: PgExecutor<'a>>(
+    /// Works out dietary flags from the ingredients, including those of their
+    /// component recipes, and the foods in the nutrition table they go by.
+    async fn classify_pool(
+        connection: &mut PgConnection,
+        ingredients: &[Ingredient],
+        components: &HashMap<i64, Recipe>,
+    ) -> Result<DietaryFlags> {
+        let ingredients = expand_components(ingredients, components)?;
+        let foods = NutritionAccess::get_foods_pool(&mut *connection).await?;
+        let mappings = food_mappings(NutritionAccess::get_mappings_pool(&mut *connection).await?);
+        Ok(dietary_flags(&ingredients, &foods, &mappings))
+    }
+
+    /// Works out the dietary flags of every recipe using the one with `id` as
+    /// a component, directly or through others, again. Those whose flags
+    /// change are indexed again.
+    async fn classify_dependants_pool(connection: &mut PgConnection, id: i64) -> Result<()> {
+        let dependants = sqlx::query!(
+            r#"
+                WITH RECURSIVE dependants (id) AS (
+                    SELECT $1::bigint
+                    UNION
+                    SELECT recipes.id
+                    FROM dependants, recipes, json_array_elements(recipes.ingredients) ingredient
+                    WHERE (ingredient->'component'->>'recipe_id')::bigint = dependants.id
+                )
+                SELECT id, ingredients as "ingredients: Json<Vec<Ingredient>>"
+                FROM recipes
+                WHERE id IN (SELECT id FROM dependants) AND id <> $1
+                ORDER BY id
+                FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_all(&mut *connection)
+        .await?;
+        for dependant in dependants {
+            let components =
+                Self::get_components_pool(&mut *connection, &dependant.ingredients).await?;
+            let dietary =
+                Self::classify_pool(&mut *connection, &dependant.ingredients, &components).await?;
+            sqlx::query!(
+                r#"
+                    UPDATE recipes SET allergens = $2, diets = $3, searchable = false
+                    WHERE id = $1 AND (allergens <> $2 OR diets <> $3)
+                "#,
+                dependant.id,
+                &dietary.allergen_names()[..],
+                &dietary.diet_names()[..],
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Works out every recipe's dietary flags again, as after the foods that
+    /// ingredients are mapped to change. Recipes whose flags change are
+    /// indexed again. Returns how many changed.
+    pub async fn classify_all(&self) -> Result<u64> {
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let foods = NutritionAccess::get_foods_pool(transaction.as_mut()).await?;
+        let mappings =
+            food_mappings(NutritionAccess::get_mappings_pool(transaction.as_mut()).await?);
+        let recipes = sqlx::query!(
+            r#"
+                SELECT id, ingredients as "ingredients: Json<Vec<Ingredient>>", servings
+                FROM recipes
+                FOR UPDATE
+            "#
+        )
+        .fetch_all(transaction.as_mut())
+        .await?
+        .into_iter()
+        .map(|rec| {
+            let recipe = Recipe {
+                ingredients: rec.ingredients.0,
+                servings: rec
+                    .servings
+                    .and_then(|servings| u32::try_from(servings).ok()),
+                ..Recipe::default()
+            };
+            (rec.id, recipe)
+        })
+        .collect::<HashMap<_, _>>();
+        let mut changed = 0;
+        for (id, recipe) in &recipes {
+            // Recipes saved before components were checked keep their own
+            // ingredients if theirs can't be expanded.
+            let ingredients = expand_components(&recipe.ingredients, &recipes)
+                .unwrap_or_else(|_| recipe.ingredients.clone());
+            let dietary = dietary_flags(&ingredients, &foods, &mappings);
+            changed += sqlx::query!(
+                r#"
+                    UPDATE recipes SET allergens = $2, diets = $3, searchable = false
+                    WHERE id = $1 AND (allergens <> $2 OR diets <> $3)
+                "#,
+                id,
+                &dietary.allergen_names()[..],
+                &dietary.diet_names()[..],
+            )
+            .execute(transaction.as_mut())
+            .await?
+            .rows_affected();
+        }
+        transaction.commit().await?;
+        Ok(changed)
+    }
+
+    /// The id of the oldest recipe with this name, ignoring case.
+    pub(crate) async fn find_id_by_name_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+        name: &str,
+    ) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                SELECT id FROM recipes
+                WHERE LOWER(name) = LOWER($1)
+                ORDER BY id
+                LIMIT 1
+            "#,
+            name,
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    pub(crate) async fn delete_all_pool<'a, P: PgExecutor<'a>>(pool: P) -> Result<u64> {
+        let deleted = sqlx::query!("DELETE FROM recipes")
+            .execute(pool)
+            .await?
+            .rows_affected();
+        Ok(deleted)
+    }
+
+    pub(crate) async fn begin(&self) -> Result<Transaction<'_, Postgres>> {
+        Ok(self.db_access.get_pool().begin().await?)
+    }
+
+    pub(crate) async fn count(&self) -> Result<i64> {
+        let count = sqlx::query!(r#"SELECT COUNT(id) as "count!" FROM recipes"#)
+            .fetch_one(self.db_access.get_pool())
+            .await?
+            .count;
+        Ok(count)
+    }
+
+    /// Up to `limit` recipes with ids greater than `after_id`, in id order, so
+    /// that the whole library can be read page by page while it changes.
+    pub(crate) async fn get_page_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<RecipeWithId>> {
+        sqlx::query_as!(
+            RecipeRep,
+            r#"
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                WHERE id > $1
+                ORDER BY id
+                LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch(self.db_access.get_pool())
+        .map(
+            |rep_res: std::result::Result<RecipeRep, _>| -> Result<RecipeWithId> {
+                let recipe = rep_res?;
+                Ok(recipe.model_with_id())
+            },
+        )
+        .try_collect::<Vec<_>>()
+        .await
+    }
+
+    pub(crate) async fn get_by_id_pool<'a, P: PgExecutor<'a>>(
         pool: P,
         id: i64,
     ) -> Result<Option<RecipeWithId>> {
@@ -425,7 +1244,32 @@ impl RecipeAccess {
                     description, 
                     liked,
                     searchable,
-                    embedding
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
                 FROM recipes
                 WHERE id = $1
             "#,
@@ -441,6 +1285,84 @@ impl RecipeAccess {
         Self::get_by_id_pool(self.db_access.get_pool(), id).await
     }
 
+    /// Every recipe the ingredients use as a component, and those recipes'
+    /// components in turn, by id.
+    pub(crate) async fn get_components_pool<'a, P: PgExecutor<'a>>(
+        pool: P,
+        ingredients: &[Ingredient],
+    ) -> Result<HashMap<i64, Recipe>> {
+        let ids = component_ids(ingredients);
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let recipes = sqlx::query_as!(
+            RecipeRep,
+            r#"
+                WITH RECURSIVE components (id) AS (
+                    SELECT UNNEST($1::bigint[])
+                    UNION
+                    SELECT (ingredient->'component'->>'recipe_id')::bigint
+                    FROM components
+                    JOIN recipes ON recipes.id = components.id,
+                    json_array_elements(recipes.ingredients) ingredient
+                    WHERE ingredient->'component' IS NOT NULL
+                )
+                SELECT
+                    id, 
+                    name, 
+                    ingredients as "ingredients: Json<Vec<Ingredient>>", 
+                    description, 
+                    liked,
+                    searchable,
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
+                FROM recipes
+                WHERE id IN (SELECT id FROM components)
+            "#,
+            &ids[..],
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(recipes
+            .into_iter()
+            .map(|rep| (rep.id, rep.model()))
+            .collect())
+    }
+
+    /// The ingredients with every component recipe replaced by its own
+    /// ingredients, scaled to the portion used.
+    pub(crate) async fn expand_components(
+        &self,
+        ingredients: &[Ingredient],
+    ) -> Result<Vec<Ingredient>> {
+        let components = Self::get_components_pool(self.db_access.get_pool(), ingredients).await?;
+        Ok(expand_components(ingredients, &components)?)
+    }
+
     pub(crate) async fn get_by_id_for_update(
         &self,
         id: i64,
@@ -457,7 +1379,32 @@ impl RecipeAccess {
                     description, 
                     liked,
                     searchable,
-                    embedding
+                    embedding,
+                    servings,
+                    prep_minutes,
+                    cook_minutes,
+                    total_minutes,
+                    oven_degrees,
+                    oven_unit,
+                    allergens,
+                    diets,
+                    ARRAY(
+                        SELECT tags.name FROM recipe_tags
+                        JOIN tags ON tags.id = recipe_tags.tag_id
+                        WHERE recipe_tags.recipe_id = recipes.id
+                        ORDER BY tags.name
+                    ) as "tags!",
+                    ARRAY(
+                        SELECT recipe_images.id FROM recipe_images
+                        WHERE recipe_images.recipe_id = recipes.id
+                        ORDER BY recipe_images.position, recipe_images.id
+                    ) as "images!",
+                    ARRAY(
+                        SELECT equipment.name FROM recipe_equipment
+                        JOIN equipment ON equipment.id = recipe_equipment.equipment_id
+                        WHERE recipe_equipment.recipe_id = recipes.id
+                        ORDER BY equipment.name
+                    ) as "equipment!"
                 FROM recipes
                 WHERE id = $1
                 FOR UPDATE
@@ -482,55 +1429,135 @@ impl RecipeAccess {
 #[derive(Deserialize, Debug)]
 pub struct Paging {
     offset: Option<i64>,
+    tag: Option<String>,
+    diet: Option<String>,
+    /// Comma separated allergens.
+    free_of: Option<String>,
+    order: Option<String>,
+    /// Only recipes not cooked in this many days, or never.
+    not_cooked_days: Option<i64>,
+    /// Only recipes taking at most this many minutes in total.
+    max_minutes: Option<u32>,
+    /// Comma separated equipment to do without.
+    without_equipment: Option<String>,
 }
 
 const PAGE_SIZE: i64 = 9;
+/// Looking further back than this finds the same recipes, and keeps the date
+/// in range.
+const MAX_NOT_COOKED_DAYS: i64 = 100 * 366;
 
 pub(crate) async fn get_all(
     recipe_access: Arc<RecipeAccess>,
     page: Query<Paging>,
 ) -> Result<HttpJson<RecipesResponse>> {
-    let page = page.offset;
-    let data = recipe_access.get_all(page.unwrap_or(0), PAGE_SIZE).await?;
+    let filter = RecipeFilter {
+        tag: page.tag.as_deref().and_then(normalise_tag),
+        dietary: DietaryFilter::from_query(page.diet.as_deref(), page.free_of.as_deref()),
+        not_cooked_since: page
+            .not_cooked_days
+            .map(|days| days.clamp(0, MAX_NOT_COOKED_DAYS))
+            .map(|days| OffsetDateTime::now_utc().date() - Duration::days(days)),
+        max_minutes: page.max_minutes,
+        without_equipment: page
+            .without_equipment
+            .as_deref()
+            .map(split_equipment)
+            .unwrap_or_default(),
+    };
+    let order = page
+        .order
+        .as_deref()
+        .and_then(RecipeOrder::parse)
+        .unwrap_or_default();
+    let data = recipe_access
+        .get_all(page.offset.unwrap_or(0), PAGE_SIZE, &filter, order)
+        .await?;
 
     //let body = serde_json::to_string(&data)?;
 
     Ok(data.into())
 }
 
+#[derive(Deserialize, Debug)]
+pub struct IngredientsQuery {
+    servings: Option<u32>,
+}
+
+/// Everything that goes into a recipe, with its component recipes expanded
+/// into their ingredients, scaled to `servings` when given.
+pub(crate) async fn get_expanded_ingredients(
+    Path(id): Path<i64>,
+    Query(IngredientsQuery { servings }): Query<IngredientsQuery>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<Vec<Ingredient>>> {
+    let recipe = recipe_access
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| Error::Missing {
+            item_type: "recipe".to_string(),
+            id,
+        })?;
+    let recipe = match servings.filter(|servings| *servings > 0) {
+        Some(servings) => recipe.data.scaled(servings),
+        None => recipe.data,
+    };
+    Ok(recipe_access
+        .expand_components(&recipe.ingredients)
+        .await?
+        .into())
+}
+
+/// Serves a recipe as JSON, or in an export format when the id carries an
+/// extension, e.g. `/recipes/12.jsonld` or `/recipes/12.cook`.
 pub(crate) async fn get_recipe(
-    path: Path<i64>,
+    Path(path): Path<String>,
     recipe_access: Arc<RecipeAccess>,
-) -> Result<HttpJson<RecipeWithId>> {
-    let id = *path;
+) -> Result<Response> {
+    let (id, extension) = match path.split_once('.') {
+        Some((id, extension)) => (id, Some(extension)),
+        None => (path.as_str(), None),
+    };
+    let id = id.parse::<i64>()?;
     let data_option = recipe_access.get_by_id(id).await?;
     let data = data_option.ok_or_else(|| Error::Missing {
         item_type: "recipe".to_string(),
         id,
     })?;
 
-    Ok(data.into())
+    match extension {
+        None => Ok(HttpJson(data).into_response()),
+        Some(JSON_LD_EXTENSION) => {
+            let body = serde_json::to_string(&recipe_to_json_ld(&data.data))?;
+            Ok(([(header::CONTENT_TYPE, JSON_LD_CONTENT_TYPE)], body).into_response())
+        }
+        Some(COOKLANG_EXTENSION) => {
+            let body = to_cooklang(&data.data);
+            Ok(([(header::CONTENT_TYPE, TEXT_CONTENT_TYPE)], body).into_response())
+        }
+        Some(other) => Err(Error::UnknownFormat(other.to_string())),
+    }
 }
 
 pub(crate) async fn delete_recipe(
     path: Path<i64>,
-    bearer_auth: BearerToken,
+    _user: AuthUser,
     recipe_access: Arc<RecipeAccess>,
-    bearer_validation: Arc<BearerValidation>,
+    image_store: Arc<dyn ImageStore>,
 ) -> Result<HttpJson<()>> {
-    bearer_validation.authorise(bearer_auth)?;
     let id = recipe_access.delete(*path).await?;
+    image_store
+        .delete_prefix(&recipe_images_prefix(*path))
+        .await?;
 
     Ok(id.into())
 }
 
 pub(crate) async fn put_recipe(
     form: HttpJson<Recipe>,
-    bearer_auth: BearerToken,
+    _user: AuthUser,
     recipe_access: Arc<RecipeAccess>,
-    bearer_validation: Arc<BearerValidation>,
 ) -> Result<HttpJson<i64>> {
-    bearer_validation.authorise(bearer_auth)?;
     let id = recipe_access.insert(&form).await?;
 
     Ok(id.into())
@@ -538,12 +1565,10 @@ pub(crate) async fn put_recipe(
 
 pub(crate) async fn post_recipe(
     path: Path<i64>,
-    bearer_auth: BearerToken,
+    _user: AuthUser,
     form: HttpJson<Recipe>,
     recipe_access: Arc<RecipeAccess>,
-    bearer_validation: Arc<BearerValidation>,
 ) -> Result<HttpJson<i64>> {
-    bearer_validation.authorise(bearer_auth)?;
     let id = *path;
 
     let recipe = recipe_access
@@ -563,12 +1588,98 @@ pub(crate) async fn search_recipe(
     search_query: Query<SearchQuery>,
     form: HttpJson<Option<Vec<f32>>>,
 ) -> Result<HttpJson<SearchResponse>> {
-    let data = form.0;
-    let vector_results = match data {
-        Some(query) => {
+    let tag = search_query.tag.as_deref().and_then(normalise_tag);
+    let dietary = DietaryFilter::from_query(
+        search_query.diet.as_deref(),
+        search_query.free_of.as_deref(),
+    );
+    let filter = RecipeFilter {
+        tag,
+        dietary,
+        not_cooked_since: None,
+        max_minutes: search_query.max_minutes,
+        without_equipment: search_query
+            .without_equipment
+            .as_deref()
+            .map(split_equipment)
+            .unwrap_or_default(),
+    };
+    let results = hybrid_search(
+        &search_client,
+        &vector_client,
+        &search_query.query,
+        &filter,
+        form.0,
+    )
+    .await?
+    .into_iter()
+    .map(|recipe| SearchResult { recipe })
+    .collect();
+    Ok(SearchResponse { results }.into())
+}
+
+/// `value` quoted for a Meilisearch filter, with backslashes escaped before
+/// quotes so that neither can end the string early.
+fn filter_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Ranks recipes matching `query` by full text search combined with, when
+/// the query's embedding is given, vector similarity. Recipes found only by
+/// vector similarity come without their ingredients. The search indexes don't
+/// know when recipes were cooked, so `not_cooked_since` isn't applied.
+pub(crate) async fn hybrid_search(
+    search_client: &Client,
+    vector_client: &QdrantClient,
+    query: &str,
+    filter: &RecipeFilter,
+    vector: Option<Vec<f32>>,
+) -> Result<Vec<RecipeWithId>> {
+    let RecipeFilter {
+        tag,
+        dietary,
+        max_minutes,
+        without_equipment,
+        ..
+    } = filter;
+    let vector_results = match vector {
+        Some(vector) => {
+            let must = tag
+                .iter()
+                .map(|tag| Condition::matches(TAGS_PAYLOAD_KEY, tag.clone()))
+                .chain(
+                    dietary
+                        .diet
+                        .map(|diet| Condition::matches(DIETS_PAYLOAD_KEY, diet.to_string())),
+                )
+                .chain(max_minutes.map(|minutes| {
+                    Condition::range(
+                        TOTAL_MINUTES_PAYLOAD_KEY,
+                        Range {
+                            lte: Some(f64::from(minutes)),
+                            ..Default::default()
+                        },
+                    )
+                }))
+                .collect::<Vec<_>>();
+            let must_not = dietary
+                .free_of
+                .iter()
+                .map(|allergen| Condition::matches(ALLERGENS_PAYLOAD_KEY, allergen.to_string()))
+                .chain(
+                    without_equipment
+                        .iter()
+                        .map(|name| Condition::matches(EQUIPMENT_PAYLOAD_KEY, name.clone())),
+                )
+                .collect::<Vec<_>>();
             let request = SearchPoints {
                 collection_name: RECIPES_VEC_COLLECTION_NAME.into(),
-                vector: query,
+                vector,
+                filter: (!must.is_empty() || !must_not.is_empty()).then(|| Filter {
+                    must,
+                    must_not,
+                    ..Default::default()
+                }),
                 limit: 10,
                 score_threshold: Some(0.25),
                 with_payload: Some(WithPayloadSelector {
@@ -587,9 +1698,34 @@ pub(crate) async fn search_recipe(
     };
 
     let index = search_client.index(R_ECIPE_S_INDEX_NAME);
-    let mut query = index.search();
-    let (search_ids, mut results) = query
-        .with_query(&search_query.query)
+    let filter = tag
+        .iter()
+        .map(|tag| format!("{TAGS_FILTER_ATTRIBUTE} = {}", filter_string(tag)))
+        .chain(
+            dietary
+                .diet
+                .map(|diet| format!("{DIETS_FILTER_ATTRIBUTE} = \"{diet}\"")),
+        )
+        .chain(
+            dietary
+                .free_of
+                .iter()
+                .map(|allergen| format!("NOT {ALLERGENS_FILTER_ATTRIBUTE} = \"{allergen}\"")),
+        )
+        .chain(max_minutes.map(|minutes| format!("{TOTAL_MINUTES_FILTER_ATTRIBUTE} <= {minutes}")))
+        .chain(
+            without_equipment
+                .iter()
+                .map(|name| format!("NOT {EQUIPMENT_FILTER_ATTRIBUTE} = {}", filter_string(name))),
+        )
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let mut search = index.search();
+    search.with_query(query);
+    if !filter.is_empty() {
+        search.with_filter(&filter);
+    }
+    let (search_ids, mut results) = search
         .execute::<RecipeWithId>()
         .await?
         .hits
@@ -628,6 +1764,12 @@ pub(crate) async fn search_recipe(
                     description,
                     liked: None,
                     embedding: None,
+                    servings: None,
+                    tags: Vec::new(),
+                    dietary: DietaryFlags::default(),
+                    images: Vec::new(),
+                    timing: Timing::default(),
+                    equipment: Vec::new(),
                 };
                 let id = num as i64;
                 let score = point.score;
@@ -662,9 +1804,5 @@ pub(crate) async fn search_recipe(
         })
         .collect::<Vec<_>>();
     all.sort_by(|(_, score), (_, score_2)| score_2.partial_cmp(score).unwrap());
-    let results = all
-        .into_iter()
-        .map(|(recipe, _)| SearchResult { recipe })
-        .collect();
-    Ok(SearchResponse { results }.into())
+    Ok(all.into_iter().map(|(recipe, _)| recipe).collect())
 }
@@ -7,8 +7,8 @@ use meilisearch_sdk::{client::Client, MeilisearchError};
 use qdrant_client::{
     prelude::{QdrantClient, QdrantClientConfig},
     qdrant::{
-        vectors_config::Config, CreateCollection, Distance, PointId, PointStruct, VectorParams,
-        VectorsConfig,
+        points_selector::PointsSelectorOneOf, vectors_config::Config, CreateCollection, Distance,
+        PointId, PointStruct, PointsIdsList, PointsSelector, VectorParams, VectorsConfig,
     },
 };
 use sqlx::postgres::{PgListener, PgNotification};
@@ -22,8 +22,10 @@ use tracing::warn;
 use crate::db::DbAccess;
 use crate::{
     app_config::{SearchConfig, VectorSearchConfig},
+    catalogue_service::CatalogueAccess,
     recipe_service::{self, RecipeAccess},
 };
+use r_ecipe_s_model::catalogue::{synonyms, CatalogueEntryWithId};
 
 #[derive(Debug, ThisError)]
 pub enum Error {
@@ -108,6 +110,18 @@ type Result<T> = std::result::Result<T, ContextError>;
 
 pub(crate) const R_ECIPE_S_INDEX_NAME: &str = "r_ecipe_s";
 pub(crate) const RECIPES_VEC_COLLECTION_NAME: &str = "recipes";
+pub(crate) const TAGS_PAYLOAD_KEY: &str = "tags";
+/// Recipes are indexed as [`RecipeWithId`](r_ecipe_s_model::RecipeWithId), so
+/// their tags are nested under `data`.
+pub(crate) const TAGS_FILTER_ATTRIBUTE: &str = "data.tags";
+pub(crate) const ALLERGENS_PAYLOAD_KEY: &str = "allergens";
+pub(crate) const ALLERGENS_FILTER_ATTRIBUTE: &str = "data.dietary.allergens";
+pub(crate) const DIETS_PAYLOAD_KEY: &str = "diets";
+pub(crate) const DIETS_FILTER_ATTRIBUTE: &str = "data.dietary.diets";
+pub(crate) const TOTAL_MINUTES_PAYLOAD_KEY: &str = "total_minutes";
+pub(crate) const TOTAL_MINUTES_FILTER_ATTRIBUTE: &str = "data.timing.total_minutes";
+pub(crate) const EQUIPMENT_PAYLOAD_KEY: &str = "equipment";
+pub(crate) const EQUIPMENT_FILTER_ATTRIBUTE: &str = "data.equipment";
 
 #[derive(Clone)]
 struct SearchIndexer {
@@ -146,7 +160,7 @@ impl CanIndex {
         info!("INDEX");
         let (recipe, transaction) = self.indexer.recipe_access.get_by_id_for_update(id).await?;
         let Some(mut recipe) = recipe else {
-            return Ok(());
+            return self.remove(id).await;
         };
         let embedding = recipe.data.embedding.take();
 
@@ -161,8 +175,30 @@ impl CanIndex {
                         "description".into(),
                         Value::from(recipe.data.description.clone()),
                     ),
+                    (
+                        TAGS_PAYLOAD_KEY.into(),
+                        Value::from(recipe.data.tags.clone()),
+                    ),
+                    (
+                        ALLERGENS_PAYLOAD_KEY.into(),
+                        Value::from(recipe.data.dietary.allergen_names()),
+                    ),
+                    (
+                        DIETS_PAYLOAD_KEY.into(),
+                        Value::from(recipe.data.dietary.diet_names()),
+                    ),
+                    (
+                        EQUIPMENT_PAYLOAD_KEY.into(),
+                        Value::from(recipe.data.equipment.clone()),
+                    ),
                 ]
                 .into_iter()
+                .chain(recipe.data.timing.total_minutes.map(|minutes| {
+                    (
+                        TOTAL_MINUTES_PAYLOAD_KEY.into(),
+                        Value::from(i64::from(minutes)),
+                    )
+                }))
                 .collect::<HashMap<_, _>>(),
                 vectors: Some(embedding.into()),
             };
@@ -188,6 +224,37 @@ impl CanIndex {
             .await?;
         Ok(())
     }
+
+    /// Removes a deleted recipe from both search indexes.
+    async fn remove(&self, id: i64) -> Result<()> {
+        info!("REMOVE");
+        let points = PointsSelector {
+            points_selector_one_of: Some(PointsSelectorOneOf::Points(PointsIdsList {
+                ids: vec![PointId::from(id as u64)],
+            })),
+        };
+        self.indexer
+            .vector_client
+            .delete_points(RECIPES_VEC_COLLECTION_NAME, &points, None)
+            .await
+            .map_err(Error::qdrant)?;
+        self.index
+            .delete_document(id)
+            .await?
+            .wait_for_completion(&self.indexer.search_client, None, None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Makes searching for any name of an ingredient in the catalogue find the
+/// recipes calling it by its other names.
+pub(crate) async fn set_synonyms(
+    index: &Index,
+    catalogue: &[CatalogueEntryWithId],
+) -> std::result::Result<(), MeiliError> {
+    index.set_synonyms(&synonyms(catalogue)).await?;
+    Ok(())
 }
 
 async fn process_notification(can_index: &CanIndex, not: PgNotification) -> Result<()> {
@@ -274,6 +341,23 @@ pub async fn index_loop(
         Err(err) => return Err(err.into()),
         Ok(_) => (),
     };
+    can_index
+        .index
+        .set_filterable_attributes([
+            TAGS_FILTER_ATTRIBUTE,
+            ALLERGENS_FILTER_ATTRIBUTE,
+            DIETS_FILTER_ATTRIBUTE,
+            TOTAL_MINUTES_FILTER_ATTRIBUTE,
+            EQUIPMENT_FILTER_ATTRIBUTE,
+        ])
+        .await
+        .context("Failed to make tags, dietary flags, total time and equipment filterable")?;
+    let catalogue = CatalogueAccess::get_all_pool(db_access.get_pool())
+        .await
+        .context("Failed to get the ingredient catalogue")?;
+    set_synonyms(&can_index.index, &catalogue)
+        .await
+        .context("Failed to set ingredient synonyms")?;
     info!("Creating listener");
     let mut listener = PgListener::connect_with(db_access.get_pool())
         .await
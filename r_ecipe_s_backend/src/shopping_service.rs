@@ -0,0 +1,459 @@
+use crate::{
+    auth::AuthUser,
+    catalogue_service::CatalogueAccess,
+    db::DbAccess,
+    meal_plan_service::{self, MealPlanAccess},
+    price_service::PriceAccess,
+    recipe_service::{self, RecipeAccess},
+};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::catalogue::canonicalise;
+use r_ecipe_s_model::cost::CostEstimate;
+use r_ecipe_s_model::shopping::{
+    shopping_items, ShoppingItem, ShoppingItemWithId, ShoppingList, ShoppingListRequest,
+    ShoppingListWithId,
+};
+use r_ecipe_s_model::{serde_json, Ingredient, Quantity};
+use sqlx::types::Json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("Error serialising quantities: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("{0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("{0}")]
+    MealPlan(#[from] meal_plan_service::Error),
+    #[error("Missing shopping list with id: {0}")]
+    Missing(i64),
+    #[error("Missing item {item_id} on shopping list {id}")]
+    MissingItem { id: i64, item_id: i64 },
+    #[error("A shopping list needs recipes or a date range")]
+    NothingToBuy,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) | Error::Serde(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Recipe(err) => return err.into_response(),
+            Error::MealPlan(err) => return err.into_response(),
+            Error::Missing(_) | Error::MissingItem { .. } => http::StatusCode::NOT_FOUND,
+            Error::NothingToBuy => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait ShoppingService {
+    type ServiceType;
+    fn bind_shopping_routes(
+        self,
+        shopping_access: &Arc<ShoppingAccess>,
+        meal_plan_access: &Arc<MealPlanAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> ShoppingService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_shopping_routes(
+        self,
+        shopping_access: &Arc<ShoppingAccess>,
+        meal_plan_access: &Arc<MealPlanAccess>,
+        recipe_access: &Arc<RecipeAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/shopping-lists",
+            get({
+                let shopping_access = shopping_access.clone();
+                || get_shopping_lists(shopping_access)
+            })
+            .put({
+                let shopping_access = shopping_access.clone();
+                let meal_plan_access = meal_plan_access.clone();
+                let recipe_access = recipe_access.clone();
+                |form, user| {
+                    put_shopping_list(form, user, shopping_access, meal_plan_access, recipe_access)
+                }
+            }),
+        )
+        .route(
+            "/shopping-lists/:id",
+            get({
+                let shopping_access = shopping_access.clone();
+                |path| get_shopping_list(path, shopping_access)
+            })
+            .delete({
+                let shopping_access = shopping_access.clone();
+                |path, user| delete_shopping_list(path, user, shopping_access)
+            }),
+        )
+        .route(
+            "/shopping-lists/:id/cost",
+            get({
+                let shopping_access = shopping_access.clone();
+                |path| get_shopping_list_cost(path, shopping_access)
+            }),
+        )
+        .route(
+            "/shopping-lists/:id/items/:item_id",
+            post({
+                let shopping_access = shopping_access.clone();
+                |path, user, form| check_shopping_item(path, user, form, shopping_access)
+            }),
+        )
+    }
+}
+
+pub struct ShoppingAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct ShoppingListRep {
+    id: i64,
+    name: String,
+}
+
+struct ItemRep {
+    id: i64,
+    shopping_list_id: i64,
+    name: String,
+    quantities: Json<Vec<Quantity>>,
+    aisle: String,
+    checked: bool,
+}
+
+impl ItemRep {
+    fn model_with_id(self) -> ShoppingItemWithId {
+        ShoppingItemWithId {
+            id: self.id,
+            data: ShoppingItem {
+                name: self.name,
+                quantities: self.quantities.0,
+                aisle: self.aisle,
+                checked: self.checked,
+            },
+        }
+    }
+}
+
+impl ShoppingAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        ShoppingAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    /// Every shopping list, newest first.
+    async fn get_all(&self) -> Result<Vec<ShoppingListWithId>> {
+        let pool = self.db_access.get_pool();
+        let lists = sqlx::query_as!(
+            ShoppingListRep,
+            "SELECT id, name FROM shopping_lists ORDER BY created DESC, id DESC"
+        )
+        .fetch_all(pool)
+        .await?;
+        let items = sqlx::query_as!(
+            ItemRep,
+            r#"
+                SELECT
+                    id,
+                    shopping_list_id,
+                    name,
+                    quantities as "quantities: Json<Vec<Quantity>>",
+                    aisle,
+                    checked
+                FROM shopping_list_items
+                ORDER BY shopping_list_id, position
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        let mut items_by_list: HashMap<i64, Vec<ShoppingItemWithId>> = HashMap::new();
+        for item in items {
+            items_by_list
+                .entry(item.shopping_list_id)
+                .or_default()
+                .push(item.model_with_id());
+        }
+        Ok(lists
+            .into_iter()
+            .map(|rep| ShoppingListWithId {
+                id: rep.id,
+                data: ShoppingList {
+                    name: rep.name,
+                    items: items_by_list.remove(&rep.id).unwrap_or_default(),
+                },
+            })
+            .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShoppingListWithId>> {
+        let pool = self.db_access.get_pool();
+        let Some(rep) = sqlx::query_as!(
+            ShoppingListRep,
+            "SELECT id, name FROM shopping_lists WHERE id = $1",
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let items = sqlx::query_as!(
+            ItemRep,
+            r#"
+                SELECT
+                    id,
+                    shopping_list_id,
+                    name,
+                    quantities as "quantities: Json<Vec<Quantity>>",
+                    aisle,
+                    checked
+                FROM shopping_list_items
+                WHERE shopping_list_id = $1
+                ORDER BY position
+            "#,
+            id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(Some(ShoppingListWithId {
+            id: rep.id,
+            data: ShoppingList {
+                name: rep.name,
+                items: items.into_iter().map(ItemRep::model_with_id).collect(),
+            },
+        }))
+    }
+
+    /// Saves a new list with the items in the given order.
+    async fn insert(&self, name: &str, items: &[ShoppingItem]) -> Result<i64> {
+        let names = items
+            .iter()
+            .map(|item| item.name.clone())
+            .collect::<Vec<_>>();
+        let quantities = items
+            .iter()
+            .map(|item| serde_json::to_value(&item.quantities))
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let aisles = items
+            .iter()
+            .map(|item| item.aisle.clone())
+            .collect::<Vec<_>>();
+        let mut transaction = self.db_access.get_pool().begin().await?;
+        let rec = sqlx::query!(
+            "INSERT INTO shopping_lists (name) VALUES ($1) RETURNING id",
+            name
+        )
+        .fetch_one(transaction.as_mut())
+        .await?;
+        sqlx::query!(
+            r#"
+                INSERT INTO shopping_list_items (shopping_list_id, position, name, quantities, aisle)
+                SELECT $1, item.position, item.name, item.quantities, item.aisle
+                FROM UNNEST($2::text[], $3::jsonb[], $4::text[])
+                    WITH ORDINALITY AS item(name, quantities, aisle, position)
+            "#,
+            rec.id,
+            &names[..],
+            &quantities[..],
+            &aisles[..]
+        )
+        .execute(transaction.as_mut())
+        .await?;
+        transaction.commit().await?;
+        Ok(rec.id)
+    }
+
+    async fn set_checked(&self, id: i64, item_id: i64, checked: bool) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                UPDATE shopping_list_items
+                SET checked = $3
+                WHERE shopping_list_id = $1 AND id = $2
+                RETURNING id
+            "#,
+            id,
+            item_id,
+            checked
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM shopping_lists WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+/// The ingredients of every requested recipe and planned meal, scaled to
+/// the servings asked for and with component recipes expanded, along with a
+/// name for the list.
+async fn requested_ingredients(
+    request: &ShoppingListRequest,
+    meal_plan_access: &MealPlanAccess,
+    recipe_access: &RecipeAccess,
+) -> Result<(String, Vec<Ingredient>)> {
+    let mut recipe_ids = request
+        .recipes
+        .iter()
+        .map(|entry| entry.recipe_id)
+        .collect::<Vec<_>>();
+    recipe_ids.sort_unstable();
+    recipe_ids.dedup();
+    let recipes = recipe_access
+        .get_by_ids(&recipe_ids)
+        .await?
+        .into_iter()
+        .map(|recipe| (recipe.id, recipe.data))
+        .collect::<HashMap<_, _>>();
+    let mut names = Vec::new();
+    let mut ingredients = Vec::new();
+    for entry in &request.recipes {
+        let recipe =
+            recipes
+                .get(&entry.recipe_id)
+                .ok_or_else(|| recipe_service::Error::Missing {
+                    item_type: "recipe".to_string(),
+                    id: entry.recipe_id,
+                })?;
+        let recipe = match entry.servings.filter(|servings| *servings > 0) {
+            Some(servings) => recipe.scaled(servings),
+            None => recipe.clone(),
+        };
+        names.push(recipe.name);
+        ingredients.extend(recipe_access.expand_components(&recipe.ingredients).await?);
+    }
+    if let Some(range) = request.range {
+        let planned =
+            meal_plan_service::planned_recipes(meal_plan_access, recipe_access, range).await?;
+        for planned in planned {
+            let Some(recipe) = planned.recipe else {
+                continue;
+            };
+            let recipe = match planned.meal.data.servings {
+                Some(servings) => recipe.data.scaled(servings),
+                None => recipe.data,
+            };
+            ingredients.extend(recipe_access.expand_components(&recipe.ingredients).await?);
+        }
+        names.insert(0, format!("Meals from {} to {}", range.from, range.to));
+    }
+    let name = request
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| names.join(", "));
+    Ok((name, ingredients))
+}
+
+pub(crate) async fn get_shopping_lists(
+    shopping_access: Arc<ShoppingAccess>,
+) -> Result<HttpJson<Vec<ShoppingListWithId>>> {
+    Ok(shopping_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_shopping_list(
+    Path(id): Path<i64>,
+    shopping_access: Arc<ShoppingAccess>,
+) -> Result<HttpJson<ShoppingListWithId>> {
+    let list = shopping_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(list.into())
+}
+
+/// An estimate of what everything on the list costs to buy.
+pub(crate) async fn get_shopping_list_cost(
+    Path(id): Path<i64>,
+    shopping_access: Arc<ShoppingAccess>,
+) -> Result<HttpJson<CostEstimate>> {
+    let list = shopping_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    let price_book = PriceAccess::get_price_book_pool(shopping_access.db_access.get_pool()).await?;
+    Ok(price_book.estimate(&list.data.ingredients(), None).into())
+}
+
+pub(crate) async fn put_shopping_list(
+    HttpJson(request): HttpJson<ShoppingListRequest>,
+    _user: AuthUser,
+    shopping_access: Arc<ShoppingAccess>,
+    meal_plan_access: Arc<MealPlanAccess>,
+    recipe_access: Arc<RecipeAccess>,
+) -> Result<HttpJson<i64>> {
+    if request.recipes.is_empty() && request.range.is_none() {
+        return Err(Error::NothingToBuy);
+    }
+    let (name, ingredients) =
+        requested_ingredients(&request, &meal_plan_access, &recipe_access).await?;
+    let name = if name.is_empty() {
+        "Shopping list".to_string()
+    } else {
+        name
+    };
+    // Ingredients in the catalogue are bought under their canonical names,
+    // so that `scallions` and `spring onions` end up on one line.
+    let catalogue = CatalogueAccess::get_all_pool(shopping_access.db_access.get_pool()).await?;
+    let id = shopping_access
+        .insert(
+            &name,
+            &shopping_items(canonicalise(ingredients, &catalogue)),
+        )
+        .await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn check_shopping_item(
+    Path((id, item_id)): Path<(i64, i64)>,
+    _user: AuthUser,
+    HttpJson(checked): HttpJson<bool>,
+    shopping_access: Arc<ShoppingAccess>,
+) -> Result<HttpJson<()>> {
+    shopping_access
+        .set_checked(id, item_id, checked)
+        .await?
+        .ok_or(Error::MissingItem { id, item_id })?;
+    Ok(().into())
+}
+
+pub(crate) async fn delete_shopping_list(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    shopping_access: Arc<ShoppingAccess>,
+) -> Result<HttpJson<()>> {
+    shopping_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
@@ -0,0 +1,280 @@
+use crate::{auth::AuthUser, db::DbAccess};
+use axum::{
+    body::HttpBody,
+    extract::{Path, Query},
+    http,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::shopping::ingredient_key;
+use r_ecipe_s_model::substitution::{
+    suggest_substitutes, Substitution, SubstitutionWithId, SuggestedSubstitute,
+};
+use r_ecipe_s_model::{Ingredient, Quantity};
+use serde::Deserialize;
+use sqlx::types::Json;
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("Missing substitution with id: {0}")]
+    Missing(i64),
+    #[error("Substitutions need an ingredient name")]
+    EmptyIngredient,
+    #[error("Substitutions need at least one replacement ingredient")]
+    EmptyReplacement,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyIngredient => http::StatusCode::BAD_REQUEST,
+            Error::EmptyReplacement => http::StatusCode::BAD_REQUEST,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait SubstitutionService {
+    type ServiceType;
+    fn bind_substitution_routes(
+        self,
+        substitution_access: &Arc<SubstitutionAccess>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> SubstitutionService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_substitution_routes(
+        self,
+        substitution_access: &Arc<SubstitutionAccess>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/substitutions",
+            get({
+                let substitution_access = substitution_access.clone();
+                |query| get_substitutions(query, substitution_access)
+            })
+            .put({
+                let substitution_access = substitution_access.clone();
+                |form, user| put_substitution(form, user, substitution_access)
+            }),
+        )
+        .route(
+            "/substitutions/suggest",
+            post({
+                let substitution_access = substitution_access.clone();
+                |form| suggest(form, substitution_access)
+            }),
+        )
+        .route(
+            "/substitutions/:id",
+            get({
+                let substitution_access = substitution_access.clone();
+                |path| get_substitution(path, substitution_access)
+            })
+            .delete({
+                let substitution_access = substitution_access.clone();
+                |path, user| delete_substitution(path, user, substitution_access)
+            }),
+        )
+    }
+}
+
+pub struct SubstitutionAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct SubstitutionRep {
+    id: i64,
+    ingredient: String,
+    per: Json<Quantity>,
+    replacement: Json<Vec<Ingredient>>,
+    note: Option<String>,
+}
+
+impl SubstitutionRep {
+    fn model_with_id(self) -> SubstitutionWithId {
+        SubstitutionWithId {
+            id: self.id,
+            data: Substitution {
+                ingredient: self.ingredient,
+                per: self.per.0,
+                replacement: self.replacement.0,
+                note: self.note,
+            },
+        }
+    }
+}
+
+impl SubstitutionAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        SubstitutionAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<SubstitutionWithId>> {
+        Ok(sqlx::query_as!(
+            SubstitutionRep,
+            r#"
+                SELECT id, ingredient, per as "per: Json<Quantity>",
+                    replacement as "replacement: Json<Vec<Ingredient>>", note
+                FROM substitutions
+                ORDER BY ingredient, id
+            "#
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?
+        .into_iter()
+        .map(SubstitutionRep::model_with_id)
+        .collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<SubstitutionWithId>> {
+        Ok(sqlx::query_as!(
+            SubstitutionRep,
+            r#"
+                SELECT id, ingredient, per as "per: Json<Quantity>",
+                    replacement as "replacement: Json<Vec<Ingredient>>", note
+                FROM substitutions
+                WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?
+        .map(SubstitutionRep::model_with_id))
+    }
+
+    async fn insert(&self, substitution: &Substitution) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO substitutions (ingredient, per, replacement, note)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id
+            "#,
+            substitution.ingredient,
+            Json(&substitution.per) as _,
+            Json(&substitution.replacement) as _,
+            substitution.note
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!("DELETE FROM substitutions WHERE id = $1 RETURNING id", id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+fn normalise(substitution: Substitution) -> Result<Substitution> {
+    let ingredient = ingredient_key(&substitution.ingredient);
+    if ingredient.is_empty() {
+        return Err(Error::EmptyIngredient);
+    }
+    let replacement = substitution
+        .replacement
+        .into_iter()
+        .map(|replacement| Ingredient {
+            name: replacement.name.trim().to_string(),
+            ..replacement
+        })
+        .filter(|replacement| !replacement.name.is_empty())
+        .collect::<Vec<_>>();
+    if replacement.is_empty() {
+        return Err(Error::EmptyReplacement);
+    }
+    let note = substitution
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    Ok(Substitution {
+        ingredient,
+        replacement,
+        note,
+        ..substitution
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SubstitutionQuery {
+    ingredient: Option<String>,
+}
+
+/// Every substitution, or those for one ingredient.
+pub(crate) async fn get_substitutions(
+    Query(SubstitutionQuery { ingredient }): Query<SubstitutionQuery>,
+    substitution_access: Arc<SubstitutionAccess>,
+) -> Result<HttpJson<Vec<SubstitutionWithId>>> {
+    let substitutions = substitution_access.get_all().await?;
+    Ok(match ingredient {
+        Some(ingredient) => substitutions
+            .into_iter()
+            .filter(|substitution| substitution.data.applies_to(&ingredient))
+            .collect::<Vec<_>>(),
+        None => substitutions,
+    }
+    .into())
+}
+
+pub(crate) async fn get_substitution(
+    Path(id): Path<i64>,
+    substitution_access: Arc<SubstitutionAccess>,
+) -> Result<HttpJson<SubstitutionWithId>> {
+    let substitution = substitution_access
+        .get_by_id(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(substitution.into())
+}
+
+/// Substitutes for an ingredient, with the amounts needed to replace its
+/// quantity.
+pub(crate) async fn suggest(
+    HttpJson(ingredient): HttpJson<Ingredient>,
+    substitution_access: Arc<SubstitutionAccess>,
+) -> Result<HttpJson<Vec<SuggestedSubstitute>>> {
+    let substitutions = substitution_access.get_all().await?;
+    Ok(suggest_substitutes(&ingredient, &substitutions).into())
+}
+
+pub(crate) async fn put_substitution(
+    HttpJson(substitution): HttpJson<Substitution>,
+    _user: AuthUser,
+    substitution_access: Arc<SubstitutionAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = substitution_access
+        .insert(&normalise(substitution)?)
+        .await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_substitution(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    substitution_access: Arc<SubstitutionAccess>,
+) -> Result<HttpJson<()>> {
+    substitution_access
+        .delete(id)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
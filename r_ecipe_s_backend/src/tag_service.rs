@@ -0,0 +1,253 @@
+use crate::{auth::AuthUser, db::DbAccess};
+use axum::{
+    body::HttpBody,
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::{tags::normalise_tag, Tag, TagWithId};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("Missing tag with id: {0}")]
+    Missing(i64),
+    #[error("Tag names can't be empty")]
+    EmptyName,
+    #[error("A tag named {0} already exists")]
+    Conflict(String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyName => http::StatusCode::BAD_REQUEST,
+            Error::Conflict(_) => http::StatusCode::CONFLICT,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait TagService {
+    type ServiceType;
+    fn bind_tag_routes(self, tag_access: &Arc<TagAccess>) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> TagService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_tag_routes(self, tag_access: &Arc<TagAccess>) -> Self::ServiceType {
+        self.route(
+            "/tags",
+            get({
+                let tag_access = tag_access.clone();
+                || get_tags(tag_access)
+            })
+            .put({
+                let tag_access = tag_access.clone();
+                |form, user| put_tag(form, user, tag_access)
+            }),
+        )
+        .route(
+            "/tags/:id",
+            get({
+                let tag_access = tag_access.clone();
+                |path| get_tag(path, tag_access)
+            })
+            .post({
+                let tag_access = tag_access.clone();
+                |path, user, form| post_tag(path, user, form, tag_access)
+            })
+            .delete({
+                let tag_access = tag_access.clone();
+                |path, user| delete_tag(path, user, tag_access)
+            }),
+        )
+    }
+}
+
+pub struct TagAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct TagRep {
+    id: i64,
+    name: String,
+    recipe_count: i64,
+}
+
+impl TagRep {
+    fn model_with_id(self) -> TagWithId {
+        TagWithId {
+            id: self.id,
+            data: Tag { name: self.name },
+            recipe_count: self.recipe_count,
+        }
+    }
+}
+
+impl TagAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        TagAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<TagWithId>> {
+        let tags = sqlx::query_as!(
+            TagRep,
+            r#"
+                SELECT
+                    tags.id,
+                    tags.name,
+                    COUNT(recipe_tags.recipe_id) as "recipe_count!"
+                FROM tags
+                LEFT JOIN recipe_tags ON recipe_tags.tag_id = tags.id
+                GROUP BY tags.id
+                ORDER BY tags.name
+            "#
+        )
+        .fetch_all(self.db_access.get_pool())
+        .await?;
+        Ok(tags.into_iter().map(TagRep::model_with_id).collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<TagWithId>> {
+        let tag = sqlx::query_as!(
+            TagRep,
+            r#"
+                SELECT
+                    tags.id,
+                    tags.name,
+                    COUNT(recipe_tags.recipe_id) as "recipe_count!"
+                FROM tags
+                LEFT JOIN recipe_tags ON recipe_tags.tag_id = tags.id
+                WHERE tags.id = $1
+                GROUP BY tags.id
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(tag.map(TagRep::model_with_id))
+    }
+
+    /// Creates the tag, or returns the id of the existing tag with that name.
+    async fn insert(&self, name: &str) -> Result<i64> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO tags (name) VALUES ($1)
+                ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name
+                RETURNING id
+            "#,
+            name
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.id)
+    }
+
+    /// Renames the tag and queues its recipes to be indexed again.
+    async fn rename(&self, id: i64, name: &str) -> Result<Option<i64>> {
+        let existing = sqlx::query!("SELECT id FROM tags WHERE name = $1 AND id <> $2", name, id)
+            .fetch_optional(self.db_access.get_pool())
+            .await?;
+        if existing.is_some() {
+            return Err(Error::Conflict(name.to_string()));
+        }
+        let rec = sqlx::query!(
+            r#"
+                WITH reindexed AS (
+                    UPDATE recipes SET searchable = false
+                    WHERE id IN (SELECT recipe_id FROM recipe_tags WHERE tag_id = $1)
+                )
+                UPDATE tags SET name = $2 WHERE id = $1
+                RETURNING id
+            "#,
+            id,
+            name
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Deletes the tag, removing it from its recipes, which are queued to be
+    /// indexed again.
+    async fn delete(&self, id: i64) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                WITH reindexed AS (
+                    UPDATE recipes SET searchable = false
+                    WHERE id IN (SELECT recipe_id FROM recipe_tags WHERE tag_id = $1)
+                )
+                DELETE FROM tags WHERE id = $1
+                RETURNING id
+            "#,
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+}
+
+fn tag_name(tag: &Tag) -> Result<String> {
+    normalise_tag(&tag.name).ok_or(Error::EmptyName)
+}
+
+pub(crate) async fn get_tags(tag_access: Arc<TagAccess>) -> Result<HttpJson<Vec<TagWithId>>> {
+    Ok(tag_access.get_all().await?.into())
+}
+
+pub(crate) async fn get_tag(
+    Path(id): Path<i64>,
+    tag_access: Arc<TagAccess>,
+) -> Result<HttpJson<TagWithId>> {
+    let tag = tag_access.get_by_id(id).await?.ok_or(Error::Missing(id))?;
+    Ok(tag.into())
+}
+
+pub(crate) async fn put_tag(
+    HttpJson(tag): HttpJson<Tag>,
+    _user: AuthUser,
+    tag_access: Arc<TagAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = tag_access.insert(&tag_name(&tag)?).await?;
+    Ok(id.into())
+}
+
+pub(crate) async fn post_tag(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    HttpJson(tag): HttpJson<Tag>,
+    tag_access: Arc<TagAccess>,
+) -> Result<HttpJson<i64>> {
+    let id = tag_access
+        .rename(id, &tag_name(&tag)?)
+        .await?
+        .ok_or(Error::Missing(id))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn delete_tag(
+    Path(id): Path<i64>,
+    _user: AuthUser,
+    tag_access: Arc<TagAccess>,
+) -> Result<HttpJson<()>> {
+    tag_access.delete(id).await?.ok_or(Error::Missing(id))?;
+    Ok(().into())
+}
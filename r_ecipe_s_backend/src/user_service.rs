@@ -0,0 +1,419 @@
+use crate::{
+    auth::{
+        dummy_password_hash, hash_password, verify_password, AuthError, AuthUser, JwtKeys,
+        TokenKind, FEED_TOKEN_LIFETIME, REFRESH_TOKEN_LIFETIME,
+    },
+    db::DbAccess,
+};
+use axum::{
+    body::HttpBody,
+    http,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json as HttpJson, Router,
+};
+use r_ecipe_s_model::user::{Credentials, RefreshRequest, Session, User};
+use std::sync::Arc;
+use thiserror::Error as ThisError;
+use time::OffsetDateTime;
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("Database Error: {0}")]
+    DB(#[from] sqlx::Error),
+    #[error("Error with authentication: {0}")]
+    Auth(#[from] AuthError),
+    #[error("Missing user with id: {0}")]
+    Missing(i64),
+    #[error("Usernames and passwords can't be empty")]
+    EmptyCredentials,
+    #[error("User named {0} already exists")]
+    Conflict(String),
+    #[error("Failed to run password hashing: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let error_code = match self {
+            Error::DB(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Auth(err) => return err.into_response(),
+            Error::Missing(_) => http::StatusCode::NOT_FOUND,
+            Error::EmptyCredentials => http::StatusCode::BAD_REQUEST,
+            Error::Conflict(_) => http::StatusCode::CONFLICT,
+            Error::Task(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (error_code, format!("{self}")).into_response()
+    }
+}
+
+pub trait UserService {
+    type ServiceType;
+    fn bind_user_routes(
+        self,
+        user_access: &Arc<UserAccess>,
+        jwt_keys: &Arc<JwtKeys>,
+    ) -> Self::ServiceType;
+}
+
+impl<T, HttpError, Data> UserService for Router<T>
+where
+    T: HttpBody<Error = HttpError, Data = Data> + Send + 'static,
+    HttpError: Sync + Send + std::error::Error + 'static,
+    Data: Send + 'static,
+{
+    type ServiceType = Self;
+    fn bind_user_routes(
+        self,
+        user_access: &Arc<UserAccess>,
+        jwt_keys: &Arc<JwtKeys>,
+    ) -> Self::ServiceType {
+        self.route(
+            "/auth/login",
+            post({
+                let user_access = user_access.clone();
+                let jwt_keys = jwt_keys.clone();
+                |form| login(form, user_access, jwt_keys)
+            }),
+        )
+        .route(
+            "/auth/refresh",
+            post({
+                let user_access = user_access.clone();
+                let jwt_keys = jwt_keys.clone();
+                |form| refresh(form, user_access, jwt_keys)
+            }),
+        )
+        .route(
+            "/auth/logout",
+            post({
+                let user_access = user_access.clone();
+                |user| logout(user, user_access)
+            }),
+        )
+        .route(
+            "/auth/feed-token",
+            post({
+                let user_access = user_access.clone();
+                let jwt_keys = jwt_keys.clone();
+                |user| post_feed_token(user, user_access, jwt_keys)
+            })
+            .delete({
+                let user_access = user_access.clone();
+                |user| delete_feed_token(user, user_access)
+            }),
+        )
+        .route(
+            "/users",
+            put({
+                let user_access = user_access.clone();
+                |user, form| put_user(user, form, user_access)
+            }),
+        )
+        .route(
+            "/users/me",
+            get({
+                let user_access = user_access.clone();
+                |user| get_me(user, user_access)
+            }),
+        )
+    }
+}
+
+pub struct UserAccess {
+    db_access: Arc<DbAccess>,
+}
+
+struct UserRep {
+    id: i64,
+    username: String,
+    password_hash: String,
+    admin: bool,
+}
+
+impl UserAccess {
+    pub fn new(db_access: &Arc<DbAccess>) -> Self {
+        UserAccess {
+            db_access: Arc::clone(db_access),
+        }
+    }
+
+    async fn get_by_name(&self, username: &str) -> Result<Option<UserRep>> {
+        let user = sqlx::query_as!(
+            UserRep,
+            "SELECT id, username, password_hash, admin FROM users WHERE username = $1",
+            username
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(user)
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            "SELECT id, username, admin FROM users WHERE id = $1",
+            id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(user)
+    }
+
+    /// Adds the user with the already hashed password, or gives `None` if the
+    /// name is taken.
+    async fn insert(
+        &self,
+        username: &str,
+        password_hash: &str,
+        admin: bool,
+    ) -> Result<Option<i64>> {
+        let rec = sqlx::query!(
+            r#"
+                INSERT INTO users (username, password_hash, admin) VALUES ($1, $2, $3)
+                ON CONFLICT (username) DO NOTHING
+                RETURNING id
+            "#,
+            username,
+            password_hash,
+            admin
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        Ok(rec.map(|rec| rec.id))
+    }
+
+    /// Creates the user as an admin if there are none yet, so there is someone
+    /// to sign in as. Gives whether it did.
+    pub async fn ensure_user(&self, username: String, password: String) -> Result<bool> {
+        let Credentials { username, password } = normalised(Credentials { username, password })?;
+        let existing = sqlx::query!(r#"SELECT EXISTS (SELECT 1 FROM users) as "exists!""#)
+            .fetch_one(self.db_access.get_pool())
+            .await?;
+        if existing.exists {
+            return Ok(false);
+        }
+        let password_hash = tokio::task::spawn_blocking(move || hash_password(&password)).await??;
+        Ok(self
+            .insert(&username, &password_hash, true)
+            .await?
+            .is_some())
+    }
+
+    /// Starts a session for the user, clearing out their expired ones, and
+    /// gives its id and generation.
+    async fn insert_session(&self, user_id: i64) -> Result<(i64, i64)> {
+        let expires = OffsetDateTime::now_utc() + REFRESH_TOKEN_LIFETIME;
+        let rec = sqlx::query!(
+            r#"
+                WITH expired AS (
+                    DELETE FROM sessions WHERE user_id = $1 AND expires < NOW()
+                )
+                INSERT INTO sessions (user_id, expires) VALUES ($1, $2)
+                RETURNING id, generation
+            "#,
+            user_id,
+            expires
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok((rec.id, rec.generation))
+    }
+
+    /// Moves an unexpired session on from `generation` to the next one,
+    /// giving it. A token from an older generation means it was used twice,
+    /// perhaps by someone else, so the session is ended instead.
+    async fn refresh_session(&self, id: i64, user_id: i64, generation: i64) -> Result<Option<i64>> {
+        let expires = OffsetDateTime::now_utc() + REFRESH_TOKEN_LIFETIME;
+        let rec = sqlx::query!(
+            r#"
+                UPDATE sessions SET generation = generation + 1, expires = $4
+                WHERE id = $1 AND user_id = $2 AND generation = $3 AND expires > NOW()
+                RETURNING generation
+            "#,
+            id,
+            user_id,
+            generation,
+            expires
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        if rec.is_none() {
+            self.delete_session(id).await?;
+        }
+        Ok(rec.map(|rec| rec.generation))
+    }
+
+    async fn delete_session(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM sessions WHERE id = $1", id)
+            .execute(self.db_access.get_pool())
+            .await?;
+        Ok(())
+    }
+
+    /// The user's unexpired feed token, started if they don't have one yet,
+    /// giving its id and when it expires.
+    async fn feed_token(&self, user_id: i64) -> Result<(i64, OffsetDateTime)> {
+        let existing = sqlx::query!(
+            r#"
+                SELECT id, expires FROM feed_tokens
+                WHERE user_id = $1 AND expires > NOW()
+                ORDER BY id DESC
+                LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(self.db_access.get_pool())
+        .await?;
+        if let Some(rec) = existing {
+            return Ok((rec.id, rec.expires));
+        }
+        let expires = OffsetDateTime::now_utc() + FEED_TOKEN_LIFETIME;
+        let rec = sqlx::query!(
+            r#"
+                WITH expired AS (
+                    DELETE FROM feed_tokens WHERE user_id = $1 AND expires < NOW()
+                )
+                INSERT INTO feed_tokens (user_id, expires) VALUES ($1, $2)
+                RETURNING id, expires
+            "#,
+            user_id,
+            expires
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok((rec.id, rec.expires))
+    }
+
+    /// Whether the feed token is the user's and hasn't expired or been
+    /// revoked.
+    pub(crate) async fn is_feed_token_valid(&self, id: i64, user_id: i64) -> Result<bool> {
+        let rec = sqlx::query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM feed_tokens WHERE id = $1 AND user_id = $2 AND expires > NOW()
+                ) as "exists!"
+            "#,
+            id,
+            user_id
+        )
+        .fetch_one(self.db_access.get_pool())
+        .await?;
+        Ok(rec.exists)
+    }
+
+    async fn delete_feed_tokens(&self, user_id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM feed_tokens WHERE user_id = $1", user_id)
+            .execute(self.db_access.get_pool())
+            .await?;
+        Ok(())
+    }
+}
+
+/// The credentials with the username trimmed, both required.
+fn normalised(Credentials { username, password }: Credentials) -> Result<Credentials> {
+    let username = username.trim().to_string();
+    if username.is_empty() || password.is_empty() {
+        return Err(Error::EmptyCredentials);
+    }
+    Ok(Credentials { username, password })
+}
+
+pub(crate) async fn login(
+    HttpJson(credentials): HttpJson<Credentials>,
+    user_access: Arc<UserAccess>,
+    jwt_keys: Arc<JwtKeys>,
+) -> Result<HttpJson<Session>> {
+    let Credentials { username, password } =
+        normalised(credentials).map_err(|_| AuthError::WrongCredentials)?;
+    let user = user_access.get_by_name(&username).await?;
+    let password_hash = user.as_ref().map(|user| user.password_hash.clone());
+    let verified = tokio::task::spawn_blocking(move || {
+        let password_hash = match &password_hash {
+            Some(password_hash) => password_hash,
+            None => dummy_password_hash(),
+        };
+        verify_password(&password, password_hash)
+    })
+    .await?;
+    let user = match user {
+        Some(user) if verified => user,
+        _ => return Err(AuthError::WrongCredentials.into()),
+    };
+    let (session_id, generation) = user_access.insert_session(user.id).await?;
+    let user = User {
+        id: user.id,
+        username: user.username,
+        admin: user.admin,
+    };
+    Ok(jwt_keys.session(user, session_id, generation)?.into())
+}
+
+pub(crate) async fn refresh(
+    HttpJson(RefreshRequest { refresh_token }): HttpJson<RefreshRequest>,
+    user_access: Arc<UserAccess>,
+    jwt_keys: Arc<JwtKeys>,
+) -> Result<HttpJson<Session>> {
+    let claims = jwt_keys.verify(&refresh_token, TokenKind::Refresh)?;
+    let generation = user_access
+        .refresh_session(claims.sid, claims.sub, claims.generation)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    let user = user_access
+        .get_by_id(claims.sub)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    Ok(jwt_keys.session(user, claims.sid, generation)?.into())
+}
+
+pub(crate) async fn logout(user: AuthUser, user_access: Arc<UserAccess>) -> Result<HttpJson<()>> {
+    user_access.delete_session(user.session_id).await?;
+    Ok(().into())
+}
+
+/// A token for the meal plan calendar feed, which calendar apps can't sign
+/// in to. The same feed token is given until it expires or is revoked.
+pub(crate) async fn post_feed_token(
+    user: AuthUser,
+    user_access: Arc<UserAccess>,
+    jwt_keys: Arc<JwtKeys>,
+) -> Result<HttpJson<String>> {
+    let (id, expires) = user_access.feed_token(user.id).await?;
+    Ok(jwt_keys.feed_token(&user, id, expires)?.into())
+}
+
+/// Revokes the user's feed token, so calendar apps can no longer read the
+/// meal plan with it.
+pub(crate) async fn delete_feed_token(
+    user: AuthUser,
+    user_access: Arc<UserAccess>,
+) -> Result<HttpJson<()>> {
+    user_access.delete_feed_tokens(user.id).await?;
+    Ok(().into())
+}
+
+/// Adds an account, which only admins can do. New accounts aren't admins.
+pub(crate) async fn put_user(
+    user: AuthUser,
+    HttpJson(credentials): HttpJson<Credentials>,
+    user_access: Arc<UserAccess>,
+) -> Result<HttpJson<i64>> {
+    user.require_admin()?;
+    let Credentials { username, password } = normalised(credentials)?;
+    let password_hash = tokio::task::spawn_blocking(move || hash_password(&password)).await??;
+    let id = user_access
+        .insert(&username, &password_hash, false)
+        .await?
+        .ok_or(Error::Conflict(username))?;
+    Ok(id.into())
+}
+
+pub(crate) async fn get_me(user: AuthUser, user_access: Arc<UserAccess>) -> Result<HttpJson<User>> {
+    let user = user_access
+        .get_by_id(user.id)
+        .await?
+        .ok_or(Error::Missing(user.id))?;
+    Ok(user.into())
+}
@@ -1,6 +1,23 @@
 use gloo_net::http::{self, QueryParams};
 use leptos::logging::warn;
-use r_ecipe_s_model::{Recipe, RecipeWithId, RecipesResponse, SearchResponse};
+use r_ecipe_s_model::catalogue::CatalogueEntryWithId;
+use r_ecipe_s_model::cook_log::{CookLogEntry, CookLogEntryWithId, CookingStats, RecipeOrder};
+use r_ecipe_s_model::cost::CostEstimate;
+use r_ecipe_s_model::diet::DietaryFilter;
+use r_ecipe_s_model::equipment::EquipmentWithId;
+use r_ecipe_s_model::image::ImageVariant;
+use r_ecipe_s_model::nutrition::{FoodWithId, IngredientMapping, RecipeNutrition};
+use r_ecipe_s_model::pantry::{
+    CookedRecipe, ExpiringItem, PantryItem, PantryItemWithId, PantryUse,
+};
+use r_ecipe_s_model::plan::{PlanRange, PlannedMeal, PlannedRecipe};
+use r_ecipe_s_model::shopping::{ShoppingListRequest, ShoppingListWithId};
+use r_ecipe_s_model::substitution::SuggestedSubstitute;
+use r_ecipe_s_model::user::{Credentials, RefreshRequest, Session};
+use r_ecipe_s_model::{
+    Collection, CollectionEntry, CollectionWithId, Ingredient, Recipe, RecipeWithId,
+    RecipesResponse, SearchResponse, TagWithId,
+};
 use serde::de::DeserializeOwned;
 use std::future::Future;
 use std::pin::Pin;
@@ -13,7 +30,7 @@ pub enum Error {
     Serde(#[from] serde_json::Error),
     #[error("Bad response: {0}")]
     Http(String),
-    #[error("Action not allowed with configuring API token")]
+    #[error("Sign in to do that")]
     Forbidden,
 }
 trait HttpErr {
@@ -43,6 +60,18 @@ impl HttpErr for http::Response {
     }
 }
 
+/// Query parameters narrowing recipes to the dietary filter.
+fn dietary_query(dietary: &DietaryFilter) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(diet) = dietary.diet {
+        query.push(("diet", diet.to_string()));
+    }
+    if !dietary.free_of.is_empty() {
+        query.push(("free_of", dietary.free_of_query()));
+    }
+    query
+}
+
 pub async fn put_recipe(recipe: &Recipe, token: Option<&str>) -> Result<i64, Error> {
     let token = token.ok_or(Error::Forbidden)?;
     http::Request::put("/api/v1/recipes")
@@ -91,14 +120,99 @@ pub async fn download(origin: &str, host: &str, static_file: &str) -> Result<Vec
     }
 }
 
-pub async fn get_recipes_at_offset(offset: i64) -> Result<RecipesResponse, Error> {
-    http::Request::get(&format!("/api/v1/recipes?offset={offset}"))
+pub async fn get_recipes_at_offset(
+    offset: i64,
+    tag: Option<&str>,
+    dietary: &DietaryFilter,
+    order: RecipeOrder,
+    not_cooked_days: Option<i64>,
+    max_minutes: Option<u32>,
+    without_equipment: &[String],
+) -> Result<RecipesResponse, Error> {
+    let mut request = http::Request::get("/api/v1/recipes")
+        .query([("offset", offset.to_string()), ("order", order.to_string())]);
+    if let Some(tag) = tag {
+        request = request.query([("tag", tag)]);
+    }
+    if let Some(days) = not_cooked_days {
+        request = request.query([("not_cooked_days", days.to_string())]);
+    }
+    if let Some(minutes) = max_minutes {
+        request = request.query([("max_minutes", minutes.to_string())]);
+    }
+    if !without_equipment.is_empty() {
+        request = request.query([("without_equipment", without_equipment.join(","))]);
+    }
+    request
+        .query(dietary_query(dietary))
         .send()
         .await?
         .http_ok_json::<RecipesResponse>()
         .await
 }
 
+pub async fn get_recipe(id: i64) -> Result<RecipeWithId, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{id}"))
+        .send()
+        .await?
+        .http_ok_json::<RecipeWithId>()
+        .await
+}
+
+pub async fn login(credentials: &Credentials) -> Result<Session, Error> {
+    http::Request::post("/api/v1/auth/login")
+        .header("Content-Type", "application/json")
+        .body(&serde_json::to_string(credentials)?)?
+        .send()
+        .await?
+        .http_ok_json::<Session>()
+        .await
+}
+
+/// A new pair of tokens for the session, as the access token expires.
+pub async fn refresh_session(refresh_token: &str) -> Result<Session, Error> {
+    let request = RefreshRequest {
+        refresh_token: refresh_token.to_string(),
+    };
+    http::Request::post("/api/v1/auth/refresh")
+        .header("Content-Type", "application/json")
+        .body(&serde_json::to_string(&request)?)?
+        .send()
+        .await?
+        .http_ok_json::<Session>()
+        .await
+}
+
+pub async fn logout(token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post("/api/v1/auth/logout")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+/// A long lived token for [`meal_plan_feed_url`].
+pub async fn get_feed_token(token: Option<&str>) -> Result<String, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post("/api/v1/auth/feed-token")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<String>()
+        .await
+}
+
+/// Address of the meal plan calendar feed, for calendar apps to subscribe
+/// to. A feed token goes in the query as they can't send headers.
+pub fn meal_plan_feed_url(origin: &str, token: &str) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("token", token)
+        .finish();
+    format!("{origin}/api/v1/meal-plan.ics?{query}")
+}
+
 pub async fn delete_recipe(id: i64, token: Option<&str>) -> Result<(), Error> {
     let token = token.ok_or(Error::Forbidden)?;
     http::Request::delete(&format!("/api/v1/recipes/{id}"))
@@ -108,10 +222,502 @@ pub async fn delete_recipe(id: i64, token: Option<&str>) -> Result<(), Error> {
         .http_ok_json::<()>()
         .await
 }
-pub async fn search(query: &str, vector: Option<&[f32]>) -> Result<SearchResponse, Error> {
-    http::Request::post(&format!("/api/v1/recipes/search"))
+pub async fn get_tags() -> Result<Vec<TagWithId>, Error> {
+    http::Request::get("/api/v1/tags")
+        .send()
+        .await?
+        .http_ok_json::<Vec<TagWithId>>()
+        .await
+}
+
+pub async fn get_collections() -> Result<Vec<CollectionWithId>, Error> {
+    http::Request::get("/api/v1/collections")
+        .send()
+        .await?
+        .http_ok_json::<Vec<CollectionWithId>>()
+        .await
+}
+
+pub async fn get_collection_recipes(id: i64) -> Result<Vec<RecipeWithId>, Error> {
+    http::Request::get(&format!("/api/v1/collections/{id}/recipes"))
+        .send()
+        .await?
+        .http_ok_json::<Vec<RecipeWithId>>()
+        .await
+}
+
+pub async fn put_collection(collection: &Collection, token: Option<&str>) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/collections")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(collection)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn update_collection(
+    id: i64,
+    collection: &Collection,
+    token: Option<&str>,
+) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!("/api/v1/collections/{id}"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(collection)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn delete_collection(id: i64, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/collections/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn reorder_collection(
+    id: i64,
+    recipe_ids: &[i64],
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!("/api/v1/collections/{id}/order"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(recipe_ids)?)?
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn add_to_collection(
+    id: i64,
+    entry: &CollectionEntry,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put(&format!("/api/v1/collections/{id}/recipes"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(entry)?)?
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn remove_from_collection(
+    id: i64,
+    recipe_id: i64,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/collections/{id}/recipes/{recipe_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn get_planned_recipes(range: PlanRange) -> Result<Vec<PlannedRecipe>, Error> {
+    http::Request::get("/api/v1/meal-plan/recipes")
+        .query([
+            ("from", range.from.to_string()),
+            ("to", range.to.to_string()),
+        ])
+        .send()
+        .await?
+        .http_ok_json::<Vec<PlannedRecipe>>()
+        .await
+}
+
+pub async fn plan_meal(meal: &PlannedMeal, token: Option<&str>) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/meal-plan")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(meal)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn update_planned_meal(
+    id: i64,
+    meal: &PlannedMeal,
+    token: Option<&str>,
+) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!("/api/v1/meal-plan/{id}"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(meal)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn delete_planned_meal(id: i64, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/meal-plan/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn get_shopping_lists() -> Result<Vec<ShoppingListWithId>, Error> {
+    http::Request::get("/api/v1/shopping-lists")
+        .send()
+        .await?
+        .http_ok_json::<Vec<ShoppingListWithId>>()
+        .await
+}
+
+pub async fn get_shopping_list_cost(id: i64) -> Result<CostEstimate, Error> {
+    http::Request::get(&format!("/api/v1/shopping-lists/{id}/cost"))
+        .send()
+        .await?
+        .http_ok_json::<CostEstimate>()
+        .await
+}
+
+pub async fn put_shopping_list(
+    request: &ShoppingListRequest,
+    token: Option<&str>,
+) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/shopping-lists")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(request)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn check_shopping_item(
+    id: i64,
+    item_id: i64,
+    checked: bool,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!("/api/v1/shopping-lists/{id}/items/{item_id}"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(&checked)?)?
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn delete_shopping_list(id: i64, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/shopping-lists/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn get_pantry() -> Result<Vec<PantryItemWithId>, Error> {
+    http::Request::get("/api/v1/pantry")
+        .send()
+        .await?
+        .http_ok_json::<Vec<PantryItemWithId>>()
+        .await
+}
+
+pub async fn get_expiring(days: i64) -> Result<Vec<ExpiringItem>, Error> {
+    http::Request::get("/api/v1/pantry/expiring")
+        .query([("days", days.to_string())])
+        .send()
+        .await?
+        .http_ok_json::<Vec<ExpiringItem>>()
+        .await
+}
+
+pub async fn put_pantry_item(item: &PantryItem, token: Option<&str>) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/pantry")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(item)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn update_pantry_item(
+    id: i64,
+    item: &PantryItem,
+    token: Option<&str>,
+) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!("/api/v1/pantry/{id}"))
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(item)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn delete_pantry_item(id: i64, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/pantry/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn cook_from_pantry(
+    cooked: &CookedRecipe,
+    token: Option<&str>,
+) -> Result<PantryUse, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post("/api/v1/pantry/cook")
         .header("Content-Type", "application/json")
-        .query([("query", query)])
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(cooked)?)?
+        .send()
+        .await?
+        .http_ok_json::<PantryUse>()
+        .await
+}
+
+pub async fn get_catalogue() -> Result<Vec<CatalogueEntryWithId>, Error> {
+    http::Request::get("/api/v1/catalogue")
+        .send()
+        .await?
+        .http_ok_json::<Vec<CatalogueEntryWithId>>()
+        .await
+}
+
+pub async fn get_equipment() -> Result<Vec<EquipmentWithId>, Error> {
+    http::Request::get("/api/v1/equipment")
+        .send()
+        .await?
+        .http_ok_json::<Vec<EquipmentWithId>>()
+        .await
+}
+
+pub async fn get_foods() -> Result<Vec<FoodWithId>, Error> {
+    http::Request::get("/api/v1/foods")
+        .send()
+        .await?
+        .http_ok_json::<Vec<FoodWithId>>()
+        .await
+}
+
+pub async fn get_recipe_nutrition(id: i64) -> Result<RecipeNutrition, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{id}/nutrition"))
+        .send()
+        .await?
+        .http_ok_json::<RecipeNutrition>()
+        .await
+}
+
+pub async fn get_recipe_cost(id: i64) -> Result<CostEstimate, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{id}/cost"))
+        .send()
+        .await?
+        .http_ok_json::<CostEstimate>()
+        .await
+}
+
+pub async fn get_recipe_cook_log(id: i64) -> Result<Vec<CookLogEntryWithId>, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{id}/cook-log"))
+        .send()
+        .await?
+        .http_ok_json::<Vec<CookLogEntryWithId>>()
+        .await
+}
+
+pub async fn get_recipe_cook_stats(id: i64) -> Result<CookingStats, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{id}/cook-stats"))
+        .send()
+        .await?
+        .http_ok_json::<CookingStats>()
+        .await
+}
+
+pub async fn log_cook(entry: &CookLogEntry, token: Option<&str>) -> Result<i64, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/cook-log")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(entry)?)?
+        .send()
+        .await?
+        .http_ok_json::<i64>()
+        .await
+}
+
+pub async fn delete_cook_log_entry(id: i64, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/cook-log/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+/// Address of the photo of a cook log entry.
+pub fn cook_photo_url(id: i64) -> String {
+    format!("/api/v1/cook-log/{id}/photo")
+}
+
+pub async fn put_cook_photo(
+    id: i64,
+    photo: web_sys::File,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put(&cook_photo_url(id))
+        .header("Content-Type", &photo.type_())
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(photo)?
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn get_recipe_images(recipe_id: i64) -> Result<Vec<i64>, Error> {
+    http::Request::get(&format!("/api/v1/recipes/{recipe_id}/images"))
+        .send()
+        .await?
+        .http_ok_json::<Vec<i64>>()
+        .await
+}
+
+/// Address of a size of an image of a recipe.
+pub fn recipe_image_url(recipe_id: i64, image_id: i64, variant: ImageVariant) -> String {
+    format!("/api/v1/recipes/{recipe_id}/images/{image_id}/{variant}")
+}
+
+/// Uploads the files as images of the recipe, returning their ids.
+pub async fn upload_recipe_images(
+    recipe_id: i64,
+    files: &[web_sys::File],
+    token: Option<&str>,
+) -> Result<Vec<i64>, Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    let form = web_sys::FormData::new()
+        .map_err(|err| Error::Http(format!("Failed to create form: {err:?}")))?;
+    for file in files {
+        form.append_with_blob_and_filename("image", file, &file.name())
+            .map_err(|err| Error::Http(format!("Failed to add {}: {err:?}", file.name())))?;
+    }
+    http::Request::post(&format!("/api/v1/recipes/{recipe_id}/images"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(form)?
+        .send()
+        .await?
+        .http_ok_json::<Vec<i64>>()
+        .await
+}
+
+pub async fn make_recipe_image_cover(
+    recipe_id: i64,
+    image_id: i64,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::post(&format!(
+        "/api/v1/recipes/{recipe_id}/images/{image_id}/cover"
+    ))
+    .header("Authorization", &format!("Bearer {token}"))
+    .send()
+    .await?
+    .http_ok_json::<()>()
+    .await
+}
+
+pub async fn delete_recipe_image(
+    recipe_id: i64,
+    image_id: i64,
+    token: Option<&str>,
+) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::delete(&format!("/api/v1/recipes/{recipe_id}/images/{image_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn map_ingredient(mapping: &IngredientMapping, token: Option<&str>) -> Result<(), Error> {
+    let token = token.ok_or(Error::Forbidden)?;
+    http::Request::put("/api/v1/nutrition/mappings")
+        .header("Content-Type", "application/json")
+        .header("Authorization", &format!("Bearer {token}"))
+        .body(&serde_json::to_string(mapping)?)?
+        .send()
+        .await?
+        .http_ok_json::<()>()
+        .await
+}
+
+pub async fn suggest_substitutes(
+    ingredient: &Ingredient,
+) -> Result<Vec<SuggestedSubstitute>, Error> {
+    http::Request::post("/api/v1/substitutions/suggest")
+        .header("Content-Type", "application/json")
+        .body(&serde_json::to_string(ingredient)?)?
+        .send()
+        .await?
+        .http_ok_json::<Vec<SuggestedSubstitute>>()
+        .await
+}
+
+pub async fn search(
+    query: &str,
+    tag: Option<&str>,
+    dietary: &DietaryFilter,
+    max_minutes: Option<u32>,
+    without_equipment: &[String],
+    vector: Option<&[f32]>,
+) -> Result<SearchResponse, Error> {
+    let mut request = http::Request::post(&format!("/api/v1/recipes/search"))
+        .header("Content-Type", "application/json")
+        .query([("query", query)]);
+    if let Some(tag) = tag {
+        request = request.query([("tag", tag)]);
+    }
+    if let Some(minutes) = max_minutes {
+        request = request.query([("max_minutes", minutes.to_string())]);
+    }
+    if !without_equipment.is_empty() {
+        request = request.query([("without_equipment", without_equipment.join(","))]);
+    }
+    request
+        .query(dietary_query(dietary))
         .body(serde_json::to_string(&vector)?)?
         .send()
         .await?
@@ -0,0 +1,326 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::{Collection, CollectionEntry, CollectionWithId, RecipeWithId};
+use std::collections::HashMap;
+
+use crate::api::*;
+
+#[derive(Debug, Clone)]
+enum CollectionEdit {
+    Details { name: String, description: String },
+    Order(Vec<i64>),
+    Note(CollectionEntry),
+    Remove(i64),
+    Delete,
+}
+
+/// Lists the collections beside the recipes of the chosen one, in order.
+#[component]
+pub fn Collections<F: Fn(RecipeWithId) + Copy + 'static>(
+    api_key: Signal<Option<String>>,
+    open_recipe: F,
+) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let collections = create_resource(
+        move || refresh.get(),
+        |_| async move {
+            get_collections().await.unwrap_or_else(|err| {
+                warn!("Failed to get collections: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let (selected, set_selected) = create_signal(None::<i64>);
+    let (new_name, set_new_name) = create_signal(String::new());
+    let create = create_action(move |name: &String| {
+        let collection = Collection {
+            name: name.clone(),
+            ..Collection::default()
+        };
+        let api_key = api_key.get_untracked();
+        async move {
+            match put_collection(&collection, api_key.as_deref()).await {
+                Ok(id) => {
+                    set_selected.set(Some(id));
+                    set_new_name.set(String::new());
+                }
+                Err(err) => warn!("Failed to create collection: {err}"),
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+    let chosen = move || {
+        let selected = selected.get()?;
+        collections
+            .get()?
+            .into_iter()
+            .find(|collection| collection.id == selected)
+    };
+
+    view! {
+        <div class = "grid grid-cols-1 md:grid-cols-3 gap-4">
+            <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300">
+                <div class = "card-body p-4">
+                    <h2 class = "card-title">Collections</h2>
+                    <ul class = "menu p-0">
+                        {move || collections.get().unwrap_or_default().into_iter().map(|CollectionWithId { id, data }| {
+                            let active = move || if selected.get() == Some(id) { "active" } else { "" };
+                            view! {
+                                <li>
+                                    <a class = active on:click = move |_| set_selected.set(Some(id))>
+                                        {data.name}
+                                        <span class = "badge badge-sm">{data.entries.len()}</span>
+                                    </a>
+                                </li>
+                            }
+                        })
+                        .collect_view()}
+                    </ul>
+                    <div class = "join w-full">
+                        <input
+                            class = "input input-bordered input-primary input-sm bg-base-300 join-item w-full"
+                            placeholder = "new collection"
+                            prop:value = new_name
+                            on:input = move |ev| set_new_name.set(event_target_value(&ev))
+                        />
+                        <button class = "btn btn-primary btn-sm join-item" on:click = move |_| {
+                            let name = new_name.get_untracked();
+                            if !name.trim().is_empty() {
+                                create.dispatch(name);
+                            }
+                        }>"add"</button>
+                    </div>
+                </div>
+            </div>
+            <div class = "md:col-span-2">
+                {move || chosen().map(|collection| view! {
+                    <CollectionDetail collection api_key refresh set_refresh set_selected open_recipe />
+                })}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn CollectionDetail<F: Fn(RecipeWithId) + Copy + 'static>(
+    collection: CollectionWithId,
+    api_key: Signal<Option<String>>,
+    refresh: ReadSignal<usize>,
+    set_refresh: WriteSignal<usize>,
+    set_selected: WriteSignal<Option<i64>>,
+    open_recipe: F,
+) -> impl IntoView {
+    let CollectionWithId {
+        id,
+        data: Collection {
+            name,
+            description,
+            entries,
+        },
+    } = collection;
+    let recipes = create_resource(
+        move || refresh.get(),
+        move |_| async move {
+            get_collection_recipes(id)
+                .await
+                .unwrap_or_else(|err| {
+                    warn!("Failed to get recipes of collection {id}: {err}");
+                    Vec::new()
+                })
+                .into_iter()
+                .map(|recipe| (recipe.id, recipe))
+                .collect::<HashMap<_, _>>()
+        },
+    );
+    // Saving the details resends the entries, which are current as the view is
+    // rebuilt after every edit.
+    let current_entries = entries.clone();
+    let edit = create_action(move |edit: &CollectionEdit| {
+        let edit = edit.clone();
+        let entries = current_entries.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                CollectionEdit::Details { name, description } => {
+                    let collection = Collection {
+                        name,
+                        description,
+                        entries,
+                    };
+                    update_collection(id, &collection, api_key)
+                        .await
+                        .map(|_| ())
+                }
+                CollectionEdit::Order(recipe_ids) => {
+                    reorder_collection(id, &recipe_ids, api_key).await
+                }
+                CollectionEdit::Note(entry) => add_to_collection(id, &entry, api_key).await,
+                CollectionEdit::Remove(recipe_id) => {
+                    remove_from_collection(id, recipe_id, api_key).await
+                }
+                CollectionEdit::Delete => {
+                    let result = delete_collection(id, api_key).await;
+                    if result.is_ok() {
+                        set_selected.set(None);
+                    }
+                    result
+                }
+            };
+            if let Err(err) = result {
+                warn!("Failed to edit collection {id}: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    let order = entries
+        .iter()
+        .map(|entry| entry.recipe_id)
+        .collect::<Vec<_>>();
+    let count = entries.len();
+    let rows = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, CollectionEntry { recipe_id, note })| {
+            let moved = |to: usize| {
+                let mut order = order.clone();
+                order.swap(index, to);
+                order
+            };
+            let (up, down) = (
+                moved(index.saturating_sub(1)),
+                moved((index + 1).min(count - 1)),
+            );
+            let recipe = move || {
+                recipes
+                    .get()
+                    .and_then(|recipes| recipes.get(&recipe_id).cloned())
+            };
+            view! {
+                <li class = "flex flex-wrap items-center gap-2 py-1">
+                    <div class = "join">
+                        <button
+                            class = "btn btn-xs join-item"
+                            disabled = {index == 0}
+                            on:click = move |_| edit.dispatch(CollectionEdit::Order(up.clone()))
+                        >"↑"</button>
+                        <button
+                            class = "btn btn-xs join-item"
+                            disabled = {index + 1 == count}
+                            on:click = move |_| edit.dispatch(CollectionEdit::Order(down.clone()))
+                        >"↓"</button>
+                    </div>
+                    <a class = "link link-hover flex-1" on:click = move |_| {
+                        if let Some(recipe) = recipe() {
+                            open_recipe(recipe);
+                        }
+                    }>
+                        {move || recipe().map(|recipe| recipe.data.name).unwrap_or_default()}
+                    </a>
+                    <input
+                        class = "input input-bordered input-primary input-xs bg-base-300 w-1/3"
+                        placeholder = "note"
+                        value = {note}
+                        on:change = move |ev| edit.dispatch(CollectionEdit::Note(CollectionEntry {
+                            recipe_id,
+                            note: Some(event_target_value(&ev)),
+                        }))
+                    />
+                    <button
+                        class = "btn btn-circle btn-primary btn-xs"
+                        on:click = move |_| edit.dispatch(CollectionEdit::Remove(recipe_id))
+                    >"×"</button>
+                </li>
+            }
+        })
+        .collect_view();
+
+    let (name, set_name) = create_signal(name);
+    let (description, set_description) = create_signal(description);
+    let save_details = move |_| {
+        edit.dispatch(CollectionEdit::Details {
+            name: name.get_untracked(),
+            description: description.get_untracked(),
+        })
+    };
+
+    view! {
+        <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300">
+            <div class = "card-body p-4">
+                <input
+                    class = "input input-ghost text-xl font-bold w-full"
+                    prop:value = name
+                    on:input = move |ev| set_name.set(event_target_value(&ev))
+                    on:change = save_details
+                />
+                <textarea
+                    class = "textarea textarea-ghost w-full"
+                    placeholder = "description"
+                    prop:value = description
+                    on:input = move |ev| set_description.set(event_target_value(&ev))
+                    on:change = save_details
+                />
+                {(count == 0).then(|| view! {
+                    <p class = "italic">"Add recipes to this collection from their page."</p>
+                })}
+                <ol class = "list-decimal list-inside">
+                    {rows}
+                </ol>
+                <div class = "card-actions justify-end">
+                    {move || edit.pending().get().then(|| view! {
+                        <div class = "loading loading-infinity loading-secondary" />
+                    })}
+                    <button class = "btn btn-xs btn-primary" on:click = move |_| edit.dispatch(CollectionEdit::Delete)>
+                        "delete collection"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Picks a collection to add a recipe to.
+#[component]
+pub fn AddToCollection(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let add = create_action(move |collection_id: &i64| {
+        let collection_id = *collection_id;
+        let api_key = api_key.get_untracked();
+        async move {
+            let entry = CollectionEntry {
+                recipe_id,
+                note: None,
+            };
+            add_to_collection(collection_id, &entry, api_key.as_deref())
+                .await
+                .map_err(|err| format!("{err}"))
+        }
+    });
+    let collections = create_resource(
+        move || add.version().get(),
+        |_| async move { get_collections().await.unwrap_or_default() },
+    );
+
+    view! {
+        <select
+            class = "select select-bordered select-primary select-xs bg-base-300"
+            on:change = move |ev| {
+                if let Ok(collection_id) = event_target_value(&ev).parse::<i64>() {
+                    add.dispatch(collection_id);
+                }
+            }
+        >
+            <option value = "" selected>"add to collection"</option>
+            {move || collections.get().unwrap_or_default().into_iter().map(|CollectionWithId { id, data }| {
+                let added = data.entries.iter().any(|entry| entry.recipe_id == recipe_id);
+                view! {
+                    <option value = {id.to_string()} disabled = added>{data.name}</option>
+                }
+            })
+            .collect_view()}
+        </select>
+        {move || add.value().get().and_then(Result::err).map(|err| view! {
+            <span class = "text-error text-xs ml-2">{err}</span>
+        })}
+    }
+}
@@ -0,0 +1,188 @@
+use leptos::html::Input;
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::cook_log::{CookLogEntry, CookLogEntryWithId, CookingStats, MAX_RATING};
+
+use crate::api::*;
+use crate::meal_plan_ls::{parse_date, today};
+
+#[derive(Debug, Clone)]
+enum CookLogEdit {
+    Log(CookLogEntry, Option<web_sys::File>),
+    Delete(i64),
+}
+
+fn stars(rating: u8) -> String {
+    (1..=MAX_RATING)
+        .map(|star| if star <= rating { '★' } else { '☆' })
+        .collect()
+}
+
+fn summary(stats: &CookingStats) -> String {
+    let mut summary = match stats.times_cooked {
+        0 => return "Not cooked yet.".to_string(),
+        1 => "Cooked once".to_string(),
+        times => format!("Cooked {times} times"),
+    };
+    if let Some(last_cooked) = stats.last_cooked {
+        summary.push_str(&format!(", last on {last_cooked}"));
+    }
+    if let Some(rating) = stats.average_rating {
+        summary.push_str(&format!(", rated {rating:.1} of {MAX_RATING}"));
+    }
+    summary.push('.');
+    summary
+}
+
+/// When the recipe was cooked and how it went, with a form to log cooking
+/// it again.
+#[component]
+pub fn CookLogPanel(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let entries = create_resource(
+        move || refresh.get(),
+        move |_| async move {
+            get_recipe_cook_log(recipe_id).await.unwrap_or_else(|err| {
+                warn!("Failed to get cook log: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let stats = create_resource(
+        move || refresh.get(),
+        move |_| async move {
+            get_recipe_cook_stats(recipe_id)
+                .await
+                .map_err(|err| warn!("Failed to get cooking stats: {err}"))
+                .ok()
+        },
+    );
+    let edit = create_action(move |edit: &CookLogEdit| {
+        let edit = edit.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                CookLogEdit::Log(entry, photo) => match log_cook(&entry, api_key).await {
+                    Ok(id) => match photo {
+                        Some(photo) => put_cook_photo(id, photo, api_key).await,
+                        None => Ok(()),
+                    },
+                    Err(err) => Err(err),
+                },
+                CookLogEdit::Delete(id) => delete_cook_log_entry(id, api_key).await,
+            };
+            if let Err(err) = result {
+                warn!("Failed to change cook log: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    let (cooked, set_cooked) = create_signal(today());
+    let (cook, set_cook) = create_signal(String::new());
+    let (rating, set_rating) = create_signal(None::<u8>);
+    let (notes, set_notes) = create_signal(String::new());
+    let photo_input = create_node_ref::<Input>();
+    let log = move || {
+        let photo = photo_input
+            .get_untracked()
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0));
+        edit.dispatch(CookLogEdit::Log(
+            CookLogEntry {
+                recipe_id,
+                cooked: cooked.get_untracked(),
+                cook: Some(cook.get_untracked()),
+                rating: rating.get_untracked(),
+                notes: Some(notes.get_untracked()),
+            },
+            photo,
+        ));
+        set_notes.set(String::new());
+        set_rating.set(None);
+        if let Some(input) = photo_input.get_untracked() {
+            input.set_value("");
+        }
+    };
+
+    let row = move |CookLogEntryWithId { id, data, photo }: CookLogEntryWithId| {
+        view! {
+            <li class = "flex flex-col gap-1 py-1">
+                <div class = "flex items-center gap-2">
+                    <span>{data.cooked.to_string()}</span>
+                    {data.cook.map(|cook| view! { <span class = "opacity-60">{cook}</span> })}
+                    {data.rating.map(|rating| view! { <span class = "text-warning">{stars(rating)}</span> })}
+                    <button class = "btn btn-circle btn-ghost btn-xs ml-auto" on:click = move |_| edit.dispatch(CookLogEdit::Delete(id))>
+                        "×"
+                    </button>
+                </div>
+                {data.notes.map(|notes| view! { <p class = "text-sm">{notes}</p> })}
+                {photo.then(|| view! {
+                    <img class = "rounded max-h-48 object-contain" src = cook_photo_url(id) loading = "lazy"/>
+                })}
+            </li>
+        }
+    };
+
+    view! {
+        <div class = "collapse collapse-arrow bg-base-200">
+            <input type = "checkbox"/>
+            <div class = "collapse-title font-bold">
+                "Cooked"
+                {move || (entries.loading().get() || edit.pending().get()).then(|| view! {
+                    <span class = "loading loading-infinity loading-secondary loading-xs ml-2" />
+                })}
+            </div>
+            <div class = "collapse-content">
+                <p class = "text-sm">{move || stats.get().flatten().map(|stats| summary(&stats))}</p>
+                <div class = "flex flex-wrap gap-1 my-2">
+                    <input
+                        type = "date"
+                        class = "input input-bordered input-primary input-xs bg-base-300"
+                        value = {cooked.get_untracked().to_string()}
+                        on:change = move |ev| {
+                            if let Some(date) = parse_date(&event_target_value(&ev)) {
+                                set_cooked.set(date);
+                            }
+                        }
+                    />
+                    <input
+                        class = "input input-bordered input-primary input-xs bg-base-300"
+                        placeholder = "cook"
+                        prop:value = cook
+                        on:input = move |ev| set_cook.set(event_target_value(&ev))
+                    />
+                    <select
+                        class = "select select-bordered select-primary select-xs bg-base-300"
+                        on:change = move |ev| set_rating.set(event_target_value(&ev).parse().ok())
+                    >
+                        <option value = "" selected = move || rating.get().is_none()>"rating"</option>
+                        {(1..=MAX_RATING).map(|option| view! {
+                            <option value = option.to_string() selected = move || rating.get() == Some(option)>
+                                {stars(option)}
+                            </option>
+                        })
+                        .collect_view()}
+                    </select>
+                    <textarea
+                        class = "textarea textarea-bordered textarea-primary textarea-xs bg-base-300 w-full"
+                        placeholder = "notes"
+                        prop:value = notes
+                        on:input = move |ev| set_notes.set(event_target_value(&ev))
+                    />
+                    <input
+                        type = "file"
+                        accept = "image/*"
+                        class = "file-input file-input-bordered file-input-primary file-input-xs bg-base-300"
+                        node_ref = photo_input
+                    />
+                    <button class = "btn btn-primary btn-xs" on:click = move |_| log()>"log"</button>
+                </div>
+                <ul>
+                    {move || entries.get().unwrap_or_default().into_iter().map(row).collect_view()}
+                </ul>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,123 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::cost::{Cost, CostEstimate, Unpriced, UnpricedIngredient};
+
+use crate::api::*;
+
+/// The costs in each currency, e.g. `GBP 4.20 + EUR 1.10`.
+fn format_costs(costs: &[Cost]) -> String {
+    costs
+        .iter()
+        .map(|cost| format!("{} {:.2}", cost.currency, cost.amount))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn unpriced_row(UnpricedIngredient { name, reason }: UnpricedIngredient) -> impl IntoView {
+    let reason = match reason {
+        Unpriced::NoPrice => "no price",
+        Unpriced::NoConversion => "amount can't be converted",
+    };
+    view! {
+        <li class = "flex items-center gap-2">
+            <span>{name}</span>
+            <span class = "text-xs opacity-60">{reason}</span>
+        </li>
+    }
+}
+
+/// What the recipe costs to make, per serving and in total, from the latest
+/// prices.
+#[component]
+pub fn RecipeCostPanel(recipe_id: i64) -> impl IntoView {
+    let estimate = create_resource(
+        || (),
+        move |_| async move {
+            get_recipe_cost(recipe_id)
+                .await
+                .map_err(|err| warn!("Failed to get cost: {err}"))
+                .ok()
+        },
+    );
+
+    let summary = |estimate: &CostEstimate| {
+        if estimate.total.is_empty() {
+            return view! { <p class = "italic">"Nothing priced."</p> }.into_view();
+        }
+        view! {
+            <table class = "table table-xs">
+                <tbody>
+                    {estimate.per_serving.as_ref().map(|per_serving| view! {
+                        <tr>
+                            <td>"per serving"</td>
+                            <td>{format_costs(per_serving)}</td>
+                        </tr>
+                    })}
+                    <tr>
+                        <td>"total"</td>
+                        <td>{format_costs(&estimate.total)}</td>
+                    </tr>
+                </tbody>
+            </table>
+        }
+        .into_view()
+    };
+
+    view! {
+        <div class = "collapse collapse-arrow bg-base-200">
+            <input type = "checkbox"/>
+            <div class = "collapse-title font-bold">
+                "Cost"
+                {move || estimate.loading().get().then(|| view! {
+                    <span class = "loading loading-infinity loading-secondary loading-xs ml-2" />
+                })}
+            </div>
+            <div class = "collapse-content">
+                {move || estimate.get().flatten().map(|estimate| view! {
+                    {summary(&estimate)}
+                    {(!estimate.unpriced.is_empty()).then(|| view! {
+                        <h3 class = "font-bold mt-2">"Not priced"</h3>
+                        <ul class = "text-sm">
+                            {estimate.unpriced.clone().into_iter().map(unpriced_row).collect_view()}
+                        </ul>
+                    })}
+                })}
+            </div>
+        </div>
+    }
+}
+
+/// A line estimating what everything on the shopping list costs.
+#[component]
+pub fn ShoppingListCost(list_id: i64) -> impl IntoView {
+    let estimate = create_resource(
+        || (),
+        move |_| async move {
+            get_shopping_list_cost(list_id)
+                .await
+                .map_err(|err| warn!("Failed to get cost of shopping list {list_id}: {err}"))
+                .ok()
+        },
+    );
+
+    move || {
+        estimate
+            .get()
+            .flatten()
+            .filter(|estimate| !estimate.total.is_empty())
+            .map(|estimate| {
+                let unpriced = estimate.unpriced.len();
+                view! {
+                    <p class = "text-sm">
+                        "Estimated total: "
+                        <span class = "font-bold">{format_costs(&estimate.total)}</span>
+                        {(unpriced > 0).then(|| view! {
+                            <span class = "opacity-60">
+                                {format!(" ({unpriced} items not priced)")}
+                            </span>
+                        })}
+                    </p>
+                }
+            })
+    }
+}
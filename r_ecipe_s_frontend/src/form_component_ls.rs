@@ -1,18 +1,29 @@
-use leptos::logging::log;
+use leptos::logging::{log, warn};
 use leptos::*;
-use r_ecipe_s_model::{Ingredient, Quantity, Recipe, COUNT, GRAM, MATCHERS, ML, TSP};
+use r_ecipe_s_model::catalogue::{lookup, CatalogueEntryWithId};
+use r_ecipe_s_model::component::{Component, Portion};
+use r_ecipe_s_model::diet::DietaryFlags;
+use r_ecipe_s_model::ingredient_parser::parse_ingredients;
+use r_ecipe_s_model::scaling::{scale_factor, DEFAULT_SERVINGS};
+use r_ecipe_s_model::tags::{normalise_tag, split_tags};
+use r_ecipe_s_model::timing::{Temperature, TemperatureUnit, Timing};
+use r_ecipe_s_model::{
+    Ingredient, Quantity, Recipe, COUNT, CUP, GRAM, KG, L, MATCHERS, ML, TBSP, TSP,
+};
 use uuid::Uuid;
 use web_sys::Event;
 
-use std::num::ParseIntError;
+use std::num::ParseFloatError;
 use std::str::FromStr;
 
+use crate::api::{get_catalogue, get_equipment};
+use crate::substitution_ls::Substitutes;
 use crate::util::markdown_to_html;
 
 #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum QuantityError {
     #[error("Error reading number for quantity: {0}")]
-    ParseNumber(#[from] ParseIntError),
+    ParseNumber(#[from] ParseFloatError),
     #[error("Invalid quantiyt type provided: {0}")]
     InvalidType(String),
 }
@@ -22,14 +33,7 @@ type QuantityRes = QtyRes<Quantity>;
 
 #[component]
 pub fn Quantity<S: SignalWith<Value = Quantity> + 'static>(quantity: S) -> impl IntoView {
-    let formatted_quantity = move || {
-        quantity.with(|quantity| match quantity {
-            Quantity::Count(count) => format!("{count}"),
-            Quantity::Tsp(count) => format!("{count} tsp."),
-            Quantity::Gram(count) => format!("{count} g"),
-            Quantity::Ml(count) => format!("{count} ml"),
-        })
-    };
+    let formatted_quantity = move || quantity.with(|quantity| quantity.to_string());
 
     view! {
         {move || formatted_quantity()}
@@ -37,20 +41,24 @@ pub fn Quantity<S: SignalWith<Value = Quantity> + 'static>(quantity: S) -> impl
 }
 
 //
-pub fn quantity_from_symbol(symbol: &str, current_num: QtyRes<usize>) -> QuantityRes {
+pub fn quantity_from_symbol(symbol: &str, current_num: QtyRes<f64>) -> QuantityRes {
     let current_num = current_num?;
     match symbol {
         COUNT => Ok(Quantity::Count(current_num)),
         GRAM => Ok(Quantity::Gram(current_num)),
+        KG => Ok(Quantity::Kg(current_num)),
         TSP => Ok(Quantity::Tsp(current_num)),
+        TBSP => Ok(Quantity::Tbsp(current_num)),
+        CUP => Ok(Quantity::Cup(current_num)),
         ML => Ok(Quantity::Ml(current_num)),
+        L => Ok(Quantity::L(current_num)),
         invalid => Err(QuantityError::InvalidType(invalid.to_string())),
     }
 }
 
-pub fn quantity_handler(ev: &Event) -> QtyRes<usize> {
+pub fn quantity_handler(ev: &Event) -> QtyRes<f64> {
     let value = event_target_value(ev);
-    let number = usize::from_str(&value)?;
+    let number = f64::from_str(&value)?;
     Ok(number)
 }
 
@@ -74,7 +82,7 @@ pub fn QuantityForm<F: Fn(Quantity) + 'static>(
         let quantity = quantity_from_symbol(quant_type.get().as_str(), value.get());
         match quantity {
             Ok(quantity) => {
-                set_quantity.set(quantity);
+                set_quantity.set(quantity.clone());
                 log!("Blop");
                 set_quantity_val(quantity);
                 log!("Blip");
@@ -129,30 +137,216 @@ pub fn QuantityForm<F: Fn(Quantity) + 'static>(
     }
 }
 
+/// Edits a quantity imported as free text, e.g. from Cooklang, keeping its
+/// amount and unit as written.
+#[component]
+fn OtherQuantityForm(
+    amount: String,
+    unit: String,
+    set_ingredient: WriteSignal<Ingredient>,
+) -> impl IntoView {
+    let amount_input = move |ev: Event| {
+        let value = event_target_value(&ev);
+        set_ingredient.update(|ingr| {
+            if let Quantity::Other { amount, .. } = &mut ingr.quantity {
+                *amount = value;
+            }
+        });
+    };
+    let unit_input = move |ev: Event| {
+        let value = event_target_value(&ev);
+        set_ingredient.update(|ingr| {
+            if let Quantity::Other { unit, .. } = &mut ingr.quantity {
+                *unit = value;
+            }
+        });
+    };
+
+    view! {
+        <input
+            type = "text"
+            class = "input input-xs input-bordered input-primary py-0 px-1 bg-base-300 w-1/5 max-w-xs join-item"
+            on:input = amount_input
+            value = amount
+        />
+        <input
+            type = "text"
+            class = "input input-xs input-bordered input-primary py-0 px-1 bg-base-300 w-2/5 max-w-xs join-item"
+            placeholder = "unit"
+            on:input = unit_input
+            value = unit
+        />
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Editing(bool);
 
 #[component]
-fn Ingredient(ingredient: ReadSignal<Ingredient>) -> impl IntoView {
+fn Ingredient(
+    ingredient: ReadSignal<Ingredient>,
+    factor: Signal<f64>,
+    substitutable: bool,
+) -> impl IntoView {
     let name = create_memo(move |_| ingredient.get().name);
-    let quantity = create_memo(move |_| ingredient.get().quantity);
+    let preparation = create_memo(move |_| {
+        ingredient
+            .get()
+            .preparation
+            .map(|preparation| format!(", {preparation}"))
+    });
+    let quantity = create_memo(move |_| {
+        let quantity = ingredient.get().quantity;
+        match factor.get() {
+            factor if factor == 1.0 => quantity,
+            factor => quantity.scale(factor),
+        }
+    });
+
+    let component = create_memo(move |_| {
+        ingredient.get().component.map(|component| Component {
+            portion: component.portion.scale(factor.get()),
+            ..component
+        })
+    });
+
+    let scaled = Signal::derive(move || Ingredient {
+        quantity: quantity.get(),
+        ..ingredient.get()
+    });
+    let name_view = move || {
+        match component.get() {
+        Some(component) => view! {
+            <a class = "link link-hover" href = format!("/?recipe={}", component.recipe_id)>{name.get()}</a>
+            <span class = "text-xs opacity-70">{format!(" ({})", component.portion)}</span>
+        }
+        .into_view(),
+        None => name.get().into_view(),
+    }
+    };
 
     view! {
         <li>
-            <Quantity quantity = quantity/>" "{ name }
+            <Quantity quantity = quantity/>" "{ name_view }{ preparation }
+            {substitutable.then(|| view! { <Substitutes ingredient = scaled /> })}
         </li>
     }
 }
+
+/// Id of the list of catalogue names that ingredient names autocomplete from.
+const CATALOGUE_LIST_ID: &str = "ingredient-catalogue";
+
+type Catalogue = Resource<(), Vec<CatalogueEntryWithId>>;
+
+/// The id of the catalogue entry going by the name, if any.
+fn catalogue_id(name: &str, catalogue: Catalogue) -> Option<i64> {
+    catalogue.with_untracked(|catalogue| {
+        catalogue
+            .as_deref()
+            .and_then(|catalogue| lookup(name, catalogue))
+            .map(|entry| entry.id)
+    })
+}
+
+const PORTION_SERVINGS: &str = "servings";
+const PORTION_FRACTION: &str = "of recipe";
+
+/// Makes the ingredient another of our recipes, by its id, along with how
+/// much of that recipe goes in.
+#[component]
+fn ComponentForm(
+    component: Option<Component>,
+    set_ingredient: WriteSignal<Ingredient>,
+) -> impl IntoView {
+    let (portion, set_portion) =
+        create_signal(component.map_or(Portion::Fraction(1.0), |component| component.portion));
+    let update_portion = move |new_portion: Portion| {
+        set_portion.set(new_portion);
+        set_ingredient.update(|ingr| {
+            if let Some(component) = ingr.component.as_mut() {
+                component.portion = new_portion;
+            }
+        });
+    };
+    let recipe_input = move |ev: Event| {
+        let recipe_id = event_target_value(&ev).parse::<i64>().ok();
+        set_ingredient.update(|ingr| {
+            ingr.component = recipe_id.map(|recipe_id| Component {
+                recipe_id,
+                portion: portion.get_untracked(),
+            });
+        });
+    };
+    let amount_input = move |ev: Event| {
+        let Ok(amount) = event_target_value(&ev).parse::<f64>() else {
+            return;
+        };
+        update_portion(match portion.get_untracked() {
+            Portion::Servings(_) => Portion::Servings(amount),
+            Portion::Fraction(_) => Portion::Fraction(amount),
+        });
+    };
+    let kind_input = move |ev: Event| {
+        let amount = portion.get_untracked().amount();
+        update_portion(match event_target_value(&ev).as_str() {
+            PORTION_SERVINGS => Portion::Servings(amount),
+            _ => Portion::Fraction(amount),
+        });
+    };
+    let by_servings = matches!(portion.get_untracked(), Portion::Servings(_));
+
+    view! {
+        <input
+            type = "number"
+            min = "1"
+            class = "input input-xs input-bordered input-primary py-0 px-1 bg-base-300 w-20 join-item"
+            placeholder = "recipe #"
+            title = "Id of the recipe this ingredient is made by"
+            on:input = recipe_input
+            value = component.map(|component| component.recipe_id)
+        />
+        <input
+            type = "text"
+            class = "input input-xs input-bordered input-primary py-0 px-1 bg-base-300 w-12 join-item"
+            on:input = amount_input
+            value = portion.get_untracked().amount()
+        />
+        <select
+            class = "select select-bordered select-primary py-0 px-1 select-xs bg-base-300 join-item"
+            on:input = kind_input
+        >
+            <option value = PORTION_FRACTION selected = !by_servings>{PORTION_FRACTION}</option>
+            <option value = PORTION_SERVINGS selected = by_servings>{PORTION_SERVINGS}</option>
+        </select>
+    }
+}
+
 #[component]
 fn IngredientForm(
     #[prop()] ingredient: Ingredient,
     set_ingredient: WriteSignal<Ingredient>,
+    catalogue: Catalogue,
 ) -> impl IntoView {
-    let Ingredient { name, quantity } = ingredient;
-    let (_, set_quantity) = create_signal(quantity);
+    let Ingredient {
+        name,
+        quantity,
+        preparation,
+        component,
+        ..
+    } = ingredient;
+    let (_, set_quantity) = create_signal(quantity.clone());
     let text_input = move |ev: Event| {
         let name = event_target_value(&ev);
-        set_ingredient.update(|ingr| ingr.name = name);
+        let catalogue_id = catalogue_id(&name, catalogue);
+        set_ingredient.update(|ingr| {
+            ingr.name = name;
+            ingr.catalogue_id = catalogue_id;
+        });
+    };
+    let preparation_input = move |ev: Event| {
+        let preparation = event_target_value(&ev);
+        set_ingredient
+            .update(|ingr| ingr.preparation = (!preparation.is_empty()).then_some(preparation));
     };
     let quantity_adjust = move |quantity| {
         set_ingredient.update(|ingr| {
@@ -161,14 +355,29 @@ fn IngredientForm(
     };
 
     view! {
-        <QuantityForm initial_quantity = quantity set_quantity_val = quantity_adjust set_quantity />
-        <input class = "bg-base-300 input input-bordered input-primary input-xs w-fit py-0 px-1 join-item" on:input = text_input value = {name}/>
+        {match quantity {
+            Quantity::Other { amount, unit } => view! {
+                <OtherQuantityForm amount unit set_ingredient />
+            }.into_view(),
+            quantity => view! {
+                <QuantityForm initial_quantity = quantity set_quantity_val = quantity_adjust set_quantity />
+            }.into_view(),
+        }}
+        <input class = "bg-base-300 input input-bordered input-primary input-xs w-fit py-0 px-1 join-item" list = CATALOGUE_LIST_ID on:input = text_input value = {name}/>
+        <input class = "bg-base-300 input input-bordered input-primary input-xs w-1/4 py-0 px-1 join-item" placeholder = "preparation" on:input = preparation_input value = {preparation}/>
+        <ComponentForm component set_ingredient />
     }
 }
 
 pub type IndexedIngredientState = (Uuid, (ReadSignal<Ingredient>, WriteSignal<Ingredient>));
 #[component]
-pub fn Ingredients(ingredients: ReadSignal<Vec<IndexedIngredientState>>) -> impl IntoView {
+pub fn Ingredients(
+    ingredients: ReadSignal<Vec<IndexedIngredientState>>,
+    factor: Signal<f64>,
+    /// Whether each ingredient offers substitutes.
+    #[prop(optional)]
+    substitutable: bool,
+) -> impl IntoView {
     view! {
 
         <ul>
@@ -177,7 +386,7 @@ pub fn Ingredients(ingredients: ReadSignal<Vec<IndexedIngredientState>>) -> impl
                 key = |(idx, (_, _))| *idx
                 children = move | (_, (get_ingredient, _))| {
                     view! {
-                        <Ingredient ingredient = get_ingredient />
+                        <Ingredient ingredient = get_ingredient factor substitutable />
                     }
                 }
             />
@@ -190,8 +399,72 @@ pub fn IngredientsForm(
     ingredients_data: ReadSignal<Vec<IndexedIngredientState>>,
     ingredients: WriteSignal<Vec<IndexedIngredientState>>,
 ) -> impl IntoView {
+    let catalogue = create_resource(
+        || (),
+        |_| async move {
+            get_catalogue().await.unwrap_or_else(|err| {
+                warn!("Failed to get ingredient catalogue: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let (pasting, set_pasting) = create_signal(false);
+    let (pasted, set_pasted) = create_signal(String::new());
+    let add_pasted = move |_: ev::MouseEvent| {
+        let text = pasted.get_untracked();
+        let mut unparsed = Vec::new();
+        let mut parsed = Vec::new();
+        for (line, result) in parse_ingredients(&text) {
+            match result {
+                Ok(ingredient) => parsed.push(Ingredient {
+                    catalogue_id: catalogue_id(&ingredient.name, catalogue),
+                    ..ingredient
+                }),
+                Err(err) => {
+                    log!("Failed to parse ingredient line: {err}");
+                    unparsed.push(line);
+                }
+            }
+        }
+        ingredients.update(|ingredients| {
+            ingredients.extend(
+                parsed
+                    .into_iter()
+                    .map(|ingredient| (uuid::Uuid::new_v4(), create_signal(ingredient))),
+            )
+        });
+        // Lines that couldn't be parsed stay in the box so they can be fixed up
+        set_pasting.set(!unparsed.is_empty());
+        set_pasted.set(unparsed.join("\n"));
+    };
+    let paste_view = move || {
+        pasting.get().then(|| {
+            view! {
+                <textarea
+                    class = "textarea textarea-primary textarea-bordered bg-base-300 w-full h-32"
+                    placeholder = "One ingredient per line, e.g. 2 1/2 cups plain flour, sifted"
+                    prop:value = pasted
+                    on:input = move |ev| set_pasted.set(event_target_value(&ev))
+                />
+                <div class = "grid grid-cols-9 place-content-center">
+                    <div class = "col-start-4 col-span-3">
+                        <button class = "btn btn-primary btn-xs btn-wide w-full" on:click = add_pasted>
+                            "add ingredients"
+                        </button>
+                    </div>
+                </div>
+            }
+        })
+    };
     view! {
         <div class = "grid grid-cols-1 gap-2">
+            <datalist id = CATALOGUE_LIST_ID>
+                {move || catalogue.get().unwrap_or_default().into_iter().flat_map(|entry| {
+                    entry.data.names().cloned().collect::<Vec<_>>()
+                })
+                .map(|name| view! { <option value = name /> })
+                .collect_view()}
+            </datalist>
             <For
                 each = move || ingredients_data.get()
                 key = |data| data.0
@@ -199,7 +472,7 @@ pub fn IngredientsForm(
                     log!("Ingredient {idx}");
                     view! {
                     <div class = "join w-full mx-auto" >
-                        <IngredientForm ingredient = get_ingredient.get_untracked() set_ingredient />
+                        <IngredientForm ingredient = get_ingredient.get_untracked() set_ingredient catalogue />
                         <button
                             class = "btn btn-circle btn-primary btn-xs join-item"
                             on:click = move |_| {
@@ -223,7 +496,10 @@ pub fn IngredientsForm(
                             ingredients.update(move |ingredients| {
                                 let signals = create_signal( Ingredient {
                                     name: "".into(),
-                                    quantity: Quantity::Count(0),
+                                    quantity: Quantity::Count(0.0),
+                                    preparation: None,
+                                    catalogue_id: None,
+                                    component: None,
                                 });
                                 {
                                     ingredients.push((uuid::Uuid::new_v4(), signals))
@@ -234,16 +510,175 @@ pub fn IngredientsForm(
                        <svg xmlns="http://www.w3.org/2000/svg" height = "100%" className="h-6 w-6" fill="none" viewBox="0 0 24 24" stroke="currentColor"><path strokeLinecap="round" strokeLinejoin="round" strokeWidth="2" d="M12 18 L12 6 M 18 12 L 6 12" /></svg>
                     </button>
                 </div>
+                <div class = "col-span-3 text-right">
+                    <button
+                        class = "btn btn-ghost btn-xs"
+                        on:click = move |_| set_pasting.update(|pasting| *pasting = !*pasting)
+                    >
+                        "paste ingredient list"
+                    </button>
+                </div>
             </div>
+            {paste_view}
+        </div>
+    }
+}
+
+#[component]
+fn Tags(tags: ReadSignal<Vec<String>>) -> impl IntoView {
+    view! {
+        <div class = "flex flex-wrap gap-1">
+            <For
+                each = move || tags.get()
+                key = |tag| tag.clone()
+                children = move |tag| view! {
+                    <span class = "badge badge-outline badge-sm">{tag}</span>
+                }
+            />
+        </div>
+    }
+}
+
+/// Allergens a recipe contains and diets it suits, as worked out from its
+/// ingredients.
+#[component]
+fn DietaryBadges(dietary: ReadSignal<DietaryFlags>) -> impl IntoView {
+    view! {
+        <div class = "flex flex-wrap gap-1">
+            {move || dietary.with(|dietary| {
+                dietary
+                    .diets
+                    .iter()
+                    .map(|diet| view! {
+                        <span class = "badge badge-success badge-sm">{diet.to_string()}</span>
+                    })
+                    .chain(dietary.allergens.iter().map(|allergen| view! {
+                        <span class = "badge badge-warning badge-sm" title = "contains">
+                            {allergen.to_string()}
+                        </span>
+                    }))
+                    .collect_view()
+            })}
+        </div>
+    }
+}
+
+/// Edits tags as chips. Typing a comma or pressing enter adds what has been
+/// typed so far, with suggestions from the datalist with id `list` if given.
+#[component]
+fn TagsForm(
+    tags: ReadSignal<Vec<String>>,
+    set_tags: WriteSignal<Vec<String>>,
+    #[prop(default = "add tag")] placeholder: &'static str,
+    #[prop(optional)] list: Option<&'static str>,
+) -> impl IntoView {
+    let (input, set_input) = create_signal(String::new());
+    let add_tags = move |text: &str| {
+        set_tags.update(|tags| {
+            for tag in split_tags(text) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        });
+    };
+    let on_input = move |ev: Event| {
+        let text = event_target_value(&ev);
+        match text.rsplit_once(',') {
+            Some((complete, rest)) => {
+                add_tags(complete);
+                set_input.set(rest.to_string());
+            }
+            None => set_input.set(text),
+        }
+    };
+    let on_keydown = move |ev: ev::KeyboardEvent| {
+        if ev.key().as_str() == "Enter" {
+            ev.prevent_default();
+            add_tags(&input.get_untracked());
+            set_input.set(String::new());
+        }
+    };
+    let on_blur = move |_| {
+        if normalise_tag(&input.get_untracked()).is_some() {
+            add_tags(&input.get_untracked());
+            set_input.set(String::new());
+        }
+    };
+
+    view! {
+        <div class = "flex flex-wrap gap-1 items-center">
+            <For
+                each = move || tags.get()
+                key = |tag| tag.clone()
+                children = move |tag| {
+                    let removed = tag.clone();
+                    view! {
+                        <span class = "badge badge-primary gap-1">
+                            {tag}
+                            <button
+                                class = "btn btn-ghost btn-circle btn-xs min-h-0 h-4 w-4"
+                                on:click = move |_| set_tags.update(|tags| tags.retain(|tag| *tag != removed))
+                            >"×"</button>
+                        </span>
+                    }
+                }
+            />
+            <input
+                type = "text"
+                class = "input input-bordered input-primary input-xs bg-base-300 flex-1 min-w-[6rem]"
+                placeholder = placeholder
+                list = list
+                prop:value = input
+                on:input = on_input
+                on:keydown = on_keydown
+                on:blur = on_blur
+            />
         </div>
     }
 }
 
+/// Edits the equipment as chips, suggesting names from the catalogue.
+#[component]
+fn EquipmentForm(
+    equipment: ReadSignal<Vec<String>>,
+    set_equipment: WriteSignal<Vec<String>>,
+) -> impl IntoView {
+    let catalogue = create_resource(
+        || (),
+        |_| async move {
+            get_equipment().await.unwrap_or_else(|err| {
+                warn!("Failed to get equipment catalogue: {err}");
+                Vec::new()
+            })
+        },
+    );
+    view! {
+        <datalist id = "equipment-catalogue">
+            {move || catalogue.get().unwrap_or_default().into_iter().map(|entry| view! {
+                <option value = entry.data.name />
+            })
+            .collect_view()}
+        </datalist>
+        <TagsForm
+            tags = equipment
+            set_tags = set_equipment
+            placeholder = "add equipment"
+            list = "equipment-catalogue"
+        />
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct RecipeWriteState {
     title: WriteSignal<String>,
     ingredients: WriteSignal<Vec<IndexedIngredientState>>,
     description: WriteSignal<String>,
+    servings: WriteSignal<Option<u32>>,
+    tags: WriteSignal<Vec<String>>,
+    dietary: WriteSignal<DietaryFlags>,
+    timing: WriteSignal<Timing>,
+    equipment: WriteSignal<Vec<String>>,
 }
 impl RecipeWriteState {
     pub fn set(
@@ -253,11 +688,21 @@ impl RecipeWriteState {
             name,
             description,
             ingredients,
+            servings,
+            tags,
+            dietary,
+            timing,
+            equipment,
             ..
         }: Recipe,
     ) {
         self.title.set(name);
         self.description.set(description);
+        self.servings.set(servings);
+        self.tags.set(tags);
+        self.dietary.set(dietary);
+        self.timing.set(timing);
+        self.equipment.set(equipment);
         let ingredients = ingredients
             .into_iter()
             .map(|ingredient| (uuid::Uuid::new_v4(), create_signal(ingredient)))
@@ -271,6 +716,11 @@ pub struct RecipeReadState {
     pub title: ReadSignal<String>,
     pub ingredients: ReadSignal<Vec<IndexedIngredientState>>,
     description: ReadSignal<String>,
+    pub servings: ReadSignal<Option<u32>>,
+    pub tags: ReadSignal<Vec<String>>,
+    pub dietary: ReadSignal<DietaryFlags>,
+    pub timing: ReadSignal<Timing>,
+    pub equipment: ReadSignal<Vec<String>>,
 }
 
 impl RecipeReadState {
@@ -280,6 +730,11 @@ impl RecipeReadState {
             title,
             ingredients,
             description,
+            servings,
+            tags,
+            dietary,
+            timing,
+            equipment,
         } = *self;
         log!("title: {title:#?}");
         let title = title.get_untracked();
@@ -291,12 +746,23 @@ impl RecipeReadState {
                 .collect::<Vec<_>>()
         });
         let description = description.get_untracked();
+        let servings = servings.get_untracked();
+        let tags = tags.get_untracked();
+        let dietary = dietary.get_untracked();
+        let timing = timing.get_untracked();
+        let equipment = equipment.get_untracked();
         Recipe {
             name: title,
             ingredients,
             description,
             liked: None,
             embedding: None,
+            servings,
+            tags,
+            dietary,
+            images: Vec::new(),
+            timing,
+            equipment,
         }
     }
 
@@ -306,6 +772,11 @@ impl RecipeReadState {
             title,
             ingredients,
             description,
+            servings,
+            tags,
+            dietary,
+            timing,
+            equipment,
         } = *self;
         log!("title: {title:#?}");
         let title = title.get();
@@ -317,12 +788,23 @@ impl RecipeReadState {
                 .collect::<Vec<_>>()
         });
         let description = description.get();
+        let servings = servings.get();
+        let tags = tags.get();
+        let dietary = dietary.get();
+        let timing = timing.get();
+        let equipment = equipment.get();
         Recipe {
             name: title,
             ingredients,
             description,
             liked: None,
             embedding: None,
+            servings,
+            tags,
+            dietary,
+            images: Vec::new(),
+            timing,
+            equipment,
         }
     }
 }
@@ -336,16 +818,31 @@ impl RecipeState {
 
         let (get_title, set_title) = create_signal(String::new());
         let (get_description, set_description) = create_signal(String::new());
+        let (get_servings, set_servings) = create_signal(None);
+        let (get_tags, set_tags) = create_signal(Vec::new());
+        let (get_dietary, set_dietary) = create_signal(DietaryFlags::default());
+        let (get_timing, set_timing) = create_signal(Timing::default());
+        let (get_equipment, set_equipment) = create_signal(Vec::new());
         let read_state = RecipeReadState {
             title: get_title,
             ingredients: get_ingredients,
             description: get_description,
+            servings: get_servings,
+            tags: get_tags,
+            dietary: get_dietary,
+            timing: get_timing,
+            equipment: get_equipment,
         };
 
         let write_state = RecipeWriteState {
             title: set_title,
             ingredients: set_ingredients,
             description: set_description,
+            servings: set_servings,
+            tags: set_tags,
+            dietary: set_dietary,
+            timing: set_timing,
+            equipment: set_equipment,
         };
 
         (read_state, write_state)
@@ -358,8 +855,39 @@ pub fn Recipe(read_state: RecipeReadState, focus: bool) -> impl IntoView {
         title: get_title,
         ingredients: get_ingredients,
         description: get_description,
-        ..
+        servings: get_servings,
+        tags: get_tags,
+        dietary: get_dietary,
+        timing: get_timing,
+        equipment: get_equipment,
     } = read_state;
+    let (scale_to, set_scale_to) = create_signal(None::<u32>);
+    let servings = move || {
+        scale_to
+            .get()
+            .or(get_servings.get())
+            .unwrap_or(DEFAULT_SERVINGS)
+    };
+    let factor = Signal::derive(move || scale_factor(get_servings.get(), servings()));
+    let servings_control = move || {
+        focus.then(|| {
+            view! {
+                <div class = "join my-2">
+                    <button
+                        class = "btn btn-xs btn-primary join-item"
+                        on:click = move |_| set_scale_to.set(Some(servings().saturating_sub(1).max(1)))
+                    >"-"</button>
+                    <div class = "btn btn-xs no-animation join-item">
+                        {move || format!("{} servings", servings())}
+                    </div>
+                    <button
+                        class = "btn btn-xs btn-primary join-item"
+                        on:click = move |_| set_scale_to.set(Some(servings() + 1))
+                    >"+"</button>
+                </div>
+            }
+        })
+    };
     let prose_class = if focus { "prose-md" } else { "prose-xs" };
     let max_height = if focus { "" } else { "max-h-60" };
     let class = format!(
@@ -375,7 +903,12 @@ pub fn Recipe(read_state: RecipeReadState, focus: bool) -> impl IntoView {
             <div class = {card_body_class}>
                 <div class = {overflow}>
                     <h2 class = "card-title">{move || get_title.get()}</h2>
-                    <Ingredients ingredients = get_ingredients />
+                    <Tags tags = get_tags />
+                    <DietaryBadges dietary = get_dietary />
+                    <TimingSummary timing = get_timing />
+                    <EquipmentBadges equipment = get_equipment />
+                    {servings_control}
+                    <Ingredients ingredients = get_ingredients factor substitutable = focus />
                     <div inner_html =
                         { move || markdown_to_html(&get_description.get()) } >
                     </div>
@@ -388,18 +921,133 @@ pub fn Recipe(read_state: RecipeReadState, focus: bool) -> impl IntoView {
     }
 }
 
+/// The equipment a recipe needs.
+#[component]
+fn EquipmentBadges(equipment: ReadSignal<Vec<String>>) -> impl IntoView {
+    view! {
+        <div class = "flex flex-wrap gap-1">
+            <For
+                each = move || equipment.get()
+                key = |name| name.clone()
+                children = move |name| view! {
+                    <span class = "badge badge-info badge-outline badge-sm">{name}</span>
+                }
+            />
+        </div>
+    }
+}
+
+/// Prep, cook and total times and the oven temperature, in both units.
+#[component]
+pub fn TimingSummary(timing: ReadSignal<Timing>) -> impl IntoView {
+    view! {
+        <div class = "flex flex-wrap gap-1 my-1">
+            {move || timing.get().summary().into_iter().map(|(label, value)| view! {
+                <span class = "badge badge-outline badge-sm">{format!("{label} {value}")}</span>
+            })
+            .collect_view()}
+        </div>
+    }
+}
+
+fn parse_minutes(ev: &Event) -> Option<u32> {
+    event_target_value(ev).trim().parse::<u32>().ok()
+}
+
+#[component]
+fn TimingForm(timing: ReadSignal<Timing>, set_timing: WriteSignal<Timing>) -> impl IntoView {
+    let initial = timing.get_untracked();
+    let minutes_input = move |label: &'static str,
+                              value: Option<u32>,
+                              set: fn(&mut Timing, Option<u32>)| {
+        view! {
+            <label class = "join">
+                <input
+                    type = "number"
+                    min = "0"
+                    class = "input input-bordered input-primary input-sm bg-base-300 w-24 join-item"
+                    placeholder = label
+                    value = value
+                    on:input = move |ev| {
+                        let minutes = parse_minutes(&ev);
+                        set_timing.update(|timing| set(timing, minutes));
+                    }
+                />
+                <span class = "btn btn-sm no-animation join-item">{format!("{label} min")}</span>
+            </label>
+        }
+    };
+    let set_oven_degrees = move |ev: Event| {
+        let degrees = parse_minutes(&ev);
+        set_timing.update(|timing| {
+            let unit = timing.oven.map(|oven| oven.unit).unwrap_or_default();
+            timing.oven = degrees.map(|degrees| Temperature { degrees, unit });
+        });
+    };
+    let set_oven_unit = move |ev: Event| {
+        let Some(unit) = TemperatureUnit::parse(&event_target_value(&ev)) else {
+            return;
+        };
+        set_timing.update(|timing| {
+            if let Some(oven) = timing.oven.as_mut() {
+                oven.unit = unit;
+            }
+        });
+    };
+    let initial_unit = initial.oven.map(|oven| oven.unit).unwrap_or_default();
+    view! {
+        <div class = "flex flex-wrap items-center gap-2">
+            {minutes_input("prep", initial.prep_minutes, |timing, minutes| timing.prep_minutes = minutes)}
+            {minutes_input("cook", initial.cook_minutes, |timing, minutes| timing.cook_minutes = minutes)}
+            {minutes_input("total", initial.total_minutes, |timing, minutes| timing.total_minutes = minutes)}
+            <div class = "join">
+                <input
+                    type = "number"
+                    min = "0"
+                    class = "input input-bordered input-primary input-sm bg-base-300 w-24 join-item"
+                    placeholder = "oven"
+                    value = initial.oven.map(|oven| oven.degrees)
+                    on:input = set_oven_degrees
+                />
+                <select class = "select select-bordered select-primary select-sm bg-base-300 join-item" on:change = set_oven_unit>
+                    {TemperatureUnit::ALL.into_iter().map(|unit| view! {
+                        <option value = unit.as_str() selected = unit == initial_unit>{unit.symbol()}</option>
+                    })
+                    .collect_view()}
+                </select>
+            </div>
+            <span class = "text-error text-sm">
+                {move || timing.get().validate().err().map(|err| err.to_string())}
+            </span>
+        </div>
+    }
+}
+
 #[component]
 pub fn RecipeForm(read_state: RecipeReadState, write_state: RecipeWriteState) -> impl IntoView {
     let RecipeWriteState {
         title: set_title,
         ingredients: set_ingredients,
         description: set_description,
+        servings: set_servings,
+        tags: set_tags,
+        timing: set_timing,
+        equipment: set_equipment,
+        ..
     } = write_state;
     let set_title = move |ev: Event| {
         let title = event_target_value(&ev);
         set_title.set(title);
     };
 
+    let set_servings = move |ev: Event| {
+        let servings = event_target_value(&ev)
+            .parse::<u32>()
+            .ok()
+            .filter(|servings| *servings > 0);
+        set_servings.set(servings);
+    };
+
     let set_description = move |ev: Event| {
         let description = event_target_value(&ev);
         set_description.set(description);
@@ -408,6 +1056,10 @@ pub fn RecipeForm(read_state: RecipeReadState, write_state: RecipeWriteState) ->
         title,
         description: description_data,
         ingredients,
+        servings,
+        tags,
+        timing,
+        equipment,
         ..
     } = read_state;
     view! {
@@ -420,6 +1072,30 @@ pub fn RecipeForm(read_state: RecipeReadState, write_state: RecipeWriteState) ->
                     <div>
                         <input class = "input input-bordered input-primary bg-base-300 w-full" on:input = set_title value={title.get_untracked()} />
                     </div>
+                    <label class="label">
+                        <span class="label-text">Servings</span>
+                    </label>
+                    <div>
+                        <input type = "number" min = "1" class = "input input-bordered input-primary bg-base-300 w-full" on:input = set_servings value={servings.get_untracked()} />
+                    </div>
+                    <label class="label">
+                        <span class="label-text">Times and oven</span>
+                    </label>
+                    <div>
+                        <TimingForm timing set_timing />
+                    </div>
+                    <label class="label">
+                        <span class="label-text">Tags</span>
+                    </label>
+                    <div>
+                        <TagsForm tags set_tags />
+                    </div>
+                    <label class="label">
+                        <span class="label-text">Equipment</span>
+                    </label>
+                    <div>
+                        <EquipmentForm equipment set_equipment />
+                    </div>
                     <label class="label">
                         <span class="label-text">Ingredients</span>
                     </label>
@@ -0,0 +1,110 @@
+use leptos::html::Input;
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::image::ImageVariant;
+
+use crate::api::*;
+
+#[derive(Debug, Clone)]
+enum ImageEdit {
+    Upload(Vec<web_sys::File>),
+    Cover(i64),
+    Delete(i64),
+}
+
+/// The recipe's images, the cover first, with a form to upload more.
+#[component]
+pub fn RecipeImages(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let images = create_resource(
+        move || refresh.get(),
+        move |_| async move {
+            get_recipe_images(recipe_id).await.unwrap_or_else(|err| {
+                warn!("Failed to get recipe images: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let edit = create_action(move |edit: &ImageEdit| {
+        let edit = edit.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                ImageEdit::Upload(files) => upload_recipe_images(recipe_id, &files, api_key)
+                    .await
+                    .map(|_| ()),
+                ImageEdit::Cover(image_id) => {
+                    make_recipe_image_cover(recipe_id, image_id, api_key).await
+                }
+                ImageEdit::Delete(image_id) => {
+                    delete_recipe_image(recipe_id, image_id, api_key).await
+                }
+            };
+            if let Err(err) = result {
+                warn!("Failed to change recipe images: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    let file_input = create_node_ref::<Input>();
+    let upload = move || {
+        let Some(input) = file_input.get_untracked() else {
+            return;
+        };
+        let files = input
+            .files()
+            .map(|files| (0..files.length()).filter_map(|i| files.get(i)).collect())
+            .unwrap_or_else(Vec::new);
+        if !files.is_empty() {
+            edit.dispatch(ImageEdit::Upload(files));
+        }
+        input.set_value("");
+    };
+
+    let image = move |(position, image_id): (usize, i64)| {
+        view! {
+            <div class = "relative">
+                <a href = recipe_image_url(recipe_id, image_id, ImageVariant::Original) target = "_blank">
+                    <img
+                        class = "rounded max-h-96 object-contain"
+                        src = recipe_image_url(recipe_id, image_id, ImageVariant::Large)
+                        loading = "lazy"
+                    />
+                </a>
+                <div class = "absolute top-1 right-1 flex gap-1">
+                    {(position > 0).then(|| view! {
+                        <button class = "btn btn-xs" on:click = move |_| edit.dispatch(ImageEdit::Cover(image_id))>
+                            "cover"
+                        </button>
+                    })}
+                    <button class = "btn btn-circle btn-xs" on:click = move |_| edit.dispatch(ImageEdit::Delete(image_id))>
+                        "×"
+                    </button>
+                </div>
+            </div>
+        }
+    };
+
+    view! {
+        <div class = "flex flex-col gap-2">
+            <div class = "flex flex-wrap gap-2">
+                {move || images.get().unwrap_or_default().into_iter().enumerate().map(image).collect_view()}
+            </div>
+            <div class = "flex items-center gap-1">
+                <input
+                    type = "file"
+                    accept = "image/png,image/jpeg,image/gif,image/webp"
+                    multiple = true
+                    class = "file-input file-input-bordered file-input-primary file-input-xs bg-base-300"
+                    node_ref = file_input
+                />
+                <button class = "btn btn-primary btn-xs" on:click = move |_| upload()>"upload"</button>
+                {move || (images.loading().get() || edit.pending().get()).then(|| view! {
+                    <span class = "loading loading-infinity loading-secondary loading-xs" />
+                })}
+            </div>
+        </div>
+    }
+}
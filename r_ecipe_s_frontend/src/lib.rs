@@ -1,5 +1,14 @@
 pub mod form_component_ls;
 
 pub mod api;
+pub mod collections_ls;
+pub mod cook_log_ls;
+pub mod cost_ls;
+pub mod image_ls;
+pub mod meal_plan_ls;
+pub mod nutrition_ls;
+pub mod pantry_ls;
 pub mod recipes_ls;
+pub mod shopping_ls;
+pub mod substitution_ls;
 pub mod util;
@@ -0,0 +1,286 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::plan::{MealSlot, PlanRange, PlannedMeal, PlannedMealWithId, PlannedRecipe};
+use r_ecipe_s_model::shopping::ShoppingListRequest;
+use r_ecipe_s_model::time::{macros::format_description, Date, Duration, Month};
+use r_ecipe_s_model::RecipeWithId;
+
+use crate::api::*;
+use crate::shopping_ls::NewShoppingList;
+
+/// Today in the browser's time zone.
+pub fn today() -> Date {
+    let now = js_sys::Date::new_0();
+    Month::try_from(now.get_month() as u8 + 1)
+        .ok()
+        .and_then(|month| {
+            Date::from_calendar_date(now.get_full_year() as i32, month, now.get_date() as u8).ok()
+        })
+        .unwrap_or(Date::MIN)
+}
+
+pub(crate) fn parse_date(value: &str) -> Option<Date> {
+    Date::parse(value, format_description!("[year]-[month]-[day]")).ok()
+}
+
+#[derive(Debug, Clone)]
+enum PlanEdit {
+    Servings(PlannedMealWithId, Option<u32>),
+    Remove(i64),
+}
+
+/// The meals planned for a week, a column for each day.
+#[component]
+pub fn MealPlanner<F: Fn(RecipeWithId) + Copy + 'static>(
+    api_key: Signal<Option<String>>,
+    open_recipe: F,
+) -> impl IntoView {
+    let (week, set_week) = create_signal(PlanRange::week_of(today()));
+    let (refresh, set_refresh) = create_signal(0usize);
+    let planned = create_resource(
+        move || (week.get(), refresh.get()),
+        |(week, _)| async move {
+            get_planned_recipes(week).await.unwrap_or_else(|err| {
+                warn!("Failed to get meal plan: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let edit = create_action(move |edit: &PlanEdit| {
+        let edit = edit.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                PlanEdit::Servings(PlannedMealWithId { id, data }, servings) => {
+                    let meal = PlannedMeal { servings, ..data };
+                    update_planned_meal(id, &meal, api_key).await.map(|_| ())
+                }
+                PlanEdit::Remove(id) => delete_planned_meal(id, api_key).await,
+            };
+            if let Err(err) = result {
+                warn!("Failed to change meal plan: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+    let shift = move |days: i64| {
+        set_week.update(|week| {
+            *week = PlanRange::week_of(week.from + Duration::days(days));
+        })
+    };
+    let heading = move || {
+        let PlanRange { from, to } = week.get();
+        format!(
+            "{} {} – {} {} {}",
+            from.day(),
+            from.month(),
+            to.day(),
+            to.month(),
+            to.year()
+        )
+    };
+
+    let feed_token = create_resource(
+        move || api_key.get(),
+        |api_key| async move {
+            match api_key {
+                Some(api_key) => get_feed_token(Some(&api_key))
+                    .await
+                    .map_err(|err| warn!("Failed to get calendar feed token: {err}"))
+                    .ok(),
+                None => None,
+            }
+        },
+    );
+    let feed_url = move || {
+        let origin = window().location().origin().ok()?;
+        feed_token
+            .get()
+            .flatten()
+            .map(|token| meal_plan_feed_url(&origin, &token))
+    };
+
+    let day_view = move |day: Date| {
+        let meals = move |slot: MealSlot| {
+            planned
+                .get()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(move |entry| entry.meal.data.date == day && entry.meal.data.slot == slot)
+                .collect::<Vec<_>>()
+        };
+        let class = if day == today() {
+            "card bg-base-100 border border-primary shadow-md shadow-base-300"
+        } else {
+            "card bg-base-100 border border-base-content shadow-md shadow-base-300"
+        };
+        view! {
+            <div class = {class}>
+                <div class = "card-body p-2">
+                    <h3 class = "font-bold">{format!("{} {}", day.weekday(), day.day())}</h3>
+                    {MealSlot::ALL.into_iter().map(|slot| view! {
+                        <div class = "text-xs uppercase opacity-60 mt-1">{slot.as_str()}</div>
+                        <ul>
+                            {move || meals(slot).into_iter().map(|PlannedRecipe { meal, recipe }| view! {
+                                <PlannedMealView meal recipe edit open_recipe />
+                            })
+                            .collect_view()}
+                        </ul>
+                    })
+                    .collect_view()}
+                </div>
+            </div>
+        }
+    };
+
+    view! {
+        <div class = "grid grid-cols-1 gap-4">
+            <div class = "flex items-center gap-2">
+                <div class = "join">
+                    <button class = "join-item btn btn-sm" on:click = move |_| shift(-7)>"«"</button>
+                    <button class = "join-item btn btn-sm" on:click = move |_| set_week.set(PlanRange::week_of(today()))>
+                        "this week"
+                    </button>
+                    <button class = "join-item btn btn-sm" on:click = move |_| shift(7)>"»"</button>
+                </div>
+                <h2 class = "text-lg font-bold">{heading}</h2>
+                <NewShoppingList
+                    request = move || ShoppingListRequest {
+                        range: Some(week.get_untracked()),
+                        ..ShoppingListRequest::default()
+                    }
+                    api_key
+                />
+                {move || feed_url().map(|feed_url| view! {
+                    <a
+                        class = "btn btn-xs"
+                        href = feed_url
+                        title = "Subscribe to the meal plan in a calendar app"
+                    >"calendar feed"</a>
+                })}
+                {move || (planned.loading().get() || edit.pending().get()).then(|| view! {
+                    <div class = "loading loading-infinity loading-secondary" />
+                })}
+            </div>
+            <div class = "grid grid-cols-1 md:grid-cols-7 gap-2">
+                {move || week.get().days().map(day_view).collect_view()}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn PlannedMealView<F: Fn(RecipeWithId) + Copy + 'static>(
+    meal: PlannedMealWithId,
+    recipe: Option<RecipeWithId>,
+    edit: Action<PlanEdit, ()>,
+    open_recipe: F,
+) -> impl IntoView {
+    let id = meal.id;
+    let name = meal.data.recipe_name.clone();
+    let servings = meal
+        .data
+        .servings
+        .or(recipe.as_ref().and_then(|recipe| recipe.data.servings));
+    let note = meal.data.note.clone();
+    let set_servings = move |ev| {
+        let servings = event_target_value(&ev)
+            .parse::<u32>()
+            .ok()
+            .filter(|servings| *servings > 0);
+        edit.dispatch(PlanEdit::Servings(meal.clone(), servings));
+    };
+    let title = match recipe {
+        Some(recipe) => view! {
+            <a class = "link link-hover" on:click = move |_| open_recipe(recipe.clone())>{name}</a>
+        }
+        .into_view(),
+        None => view! {
+            <span class = "italic opacity-60" title = "This recipe has been deleted">{name}</span>
+        }
+        .into_view(),
+    };
+
+    view! {
+        <li class = "flex items-center gap-1 text-sm">
+            <span class = "flex-1">
+                {title}
+                {note.map(|note| view! { <span class = "block text-xs opacity-60">{note}</span> })}
+            </span>
+            <input
+                type = "number"
+                min = "1"
+                class = "input input-bordered input-xs bg-base-300 w-12 px-1"
+                title = "servings"
+                value = {servings}
+                on:change = set_servings
+            />
+            <button class = "btn btn-circle btn-ghost btn-xs" on:click = move |_| edit.dispatch(PlanEdit::Remove(id))>
+                "×"
+            </button>
+        </li>
+    }
+}
+
+/// Plans a recipe for a meal on a chosen day.
+#[component]
+pub fn PlanMeal(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let (date, set_date) = create_signal(today());
+    let (slot, set_slot) = create_signal(MealSlot::Dinner);
+    let plan = create_action(move |meal: &PlannedMeal| {
+        let meal = meal.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            plan_meal(&meal, api_key.as_deref())
+                .await
+                .map_err(|err| format!("{err}"))
+        }
+    });
+
+    view! {
+        <div class = "join">
+            <input
+                type = "date"
+                class = "input input-bordered input-primary input-xs bg-base-300 join-item"
+                value = {date.get_untracked().to_string()}
+                on:change = move |ev| {
+                    if let Some(date) = parse_date(&event_target_value(&ev)) {
+                        set_date.set(date);
+                    }
+                }
+            />
+            <select
+                class = "select select-bordered select-primary select-xs bg-base-300 join-item"
+                on:change = move |ev| {
+                    if let Ok(slot) = event_target_value(&ev).parse() {
+                        set_slot.set(slot);
+                    }
+                }
+            >
+                {MealSlot::ALL.into_iter().map(|option| view! {
+                    <option value = {option.as_str()} selected = {option == slot.get_untracked()}>{option.as_str()}</option>
+                })
+                .collect_view()}
+            </select>
+            <button
+                class = "btn btn-primary btn-xs join-item"
+                on:click = move |_| plan.dispatch(PlannedMeal {
+                    date: date.get_untracked(),
+                    slot: slot.get_untracked(),
+                    recipe_id: Some(recipe_id),
+                    recipe_name: String::new(),
+                    servings: None,
+                    note: None,
+                })
+            >
+                {move || match plan.value().get() {
+                    _ if plan.pending().get() => "planning",
+                    Some(Ok(_)) => "planned",
+                    Some(Err(_)) => "failed",
+                    None => "plan",
+                }}
+            </button>
+        </div>
+    }
+}
@@ -0,0 +1,133 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::nutrition::{
+    IngredientMapping, Nutrients, RecipeNutrition, Unmatched, UnmatchedIngredient,
+};
+
+use crate::api::*;
+
+/// Rows of the nutrition table: label, unit and how to read the value.
+const ROWS: [(&str, &str, fn(&Nutrients) -> f64); 6] = [
+    ("energy", "kcal", |n| n.kcal),
+    ("protein", "g", |n| n.protein),
+    ("fat", "g", |n| n.fat),
+    ("carbohydrate", "g", |n| n.carbs),
+    ("fibre", "g", |n| n.fibre),
+    ("sodium", "mg", |n| n.sodium),
+];
+
+/// The recipe's nutrients per serving and in total, with a way to pick a
+/// food for each ingredient that couldn't be matched.
+#[component]
+pub fn RecipeNutritionPanel(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let nutrition = create_resource(
+        move || refresh.get(),
+        move |_| async move {
+            get_recipe_nutrition(recipe_id)
+                .await
+                .map_err(|err| warn!("Failed to get nutrition: {err}"))
+                .ok()
+        },
+    );
+    let foods = create_resource(
+        || (),
+        |_| async move {
+            get_foods().await.unwrap_or_else(|err| {
+                warn!("Failed to get foods: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let map = create_action(move |mapping: &IngredientMapping| {
+        let mapping = mapping.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            if let Err(err) = map_ingredient(&mapping, api_key.as_deref()).await {
+                warn!("Failed to map ingredient: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    let table = |nutrition: &RecipeNutrition| {
+        let (total, per_serving) = (nutrition.total, nutrition.per_serving);
+        view! {
+            <table class = "table table-xs">
+                <thead>
+                    <tr>
+                        <th></th>
+                        {per_serving.is_some().then(|| view! { <th>"per serving"</th> })}
+                        <th>"total"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {ROWS.into_iter().map(|(label, unit, value)| view! {
+                        <tr>
+                            <td>{label}</td>
+                            {per_serving.map(|per_serving| view! {
+                                <td>{format!("{:.0} {unit}", value(&per_serving))}</td>
+                            })}
+                            <td>{format!("{:.0} {unit}", value(&total))}</td>
+                        </tr>
+                    })
+                    .collect_view()}
+                </tbody>
+            </table>
+        }
+    };
+
+    let unmatched_row = move |UnmatchedIngredient { name, reason }: UnmatchedIngredient| {
+        let reason = match reason {
+            Unmatched::NoFood => "not in the food table",
+            Unmatched::NoWeight => "amount can't be weighed",
+        };
+        let ingredient = name.clone();
+        view! {
+            <li class = "flex items-center gap-2">
+                <span>{name}</span>
+                <span class = "text-xs opacity-60">{reason}</span>
+                <select
+                    class = "select select-bordered select-xs bg-base-300"
+                    on:change = move |ev| {
+                        if let Ok(food_id) = event_target_value(&ev).parse() {
+                            map.dispatch(IngredientMapping {
+                                ingredient: ingredient.clone(),
+                                food_id,
+                            });
+                        }
+                    }
+                >
+                    <option value = "" selected>"map to food"</option>
+                    {move || foods.get().unwrap_or_default().into_iter().map(|food| view! {
+                        <option value = food.id.to_string()>{food.data.name}</option>
+                    })
+                    .collect_view()}
+                </select>
+            </li>
+        }
+    };
+
+    view! {
+        <div class = "collapse collapse-arrow bg-base-200">
+            <input type = "checkbox"/>
+            <div class = "collapse-title font-bold">
+                "Nutrition"
+                {move || (nutrition.loading().get() || map.pending().get()).then(|| view! {
+                    <span class = "loading loading-infinity loading-secondary loading-xs ml-2" />
+                })}
+            </div>
+            <div class = "collapse-content">
+                {move || nutrition.get().flatten().map(|nutrition| view! {
+                    {table(&nutrition)}
+                    {(!nutrition.unmatched.is_empty()).then(|| view! {
+                        <h3 class = "font-bold mt-2">"Not counted"</h3>
+                        <ul class = "text-sm">
+                            {nutrition.unmatched.clone().into_iter().map(unmatched_row).collect_view()}
+                        </ul>
+                    })}
+                })}
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,230 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::ingredient_parser::ingredient_from_line;
+use r_ecipe_s_model::pantry::{CookedRecipe, ExpiringItem, PantryItem, PantryItemWithId};
+use r_ecipe_s_model::time::{Date, Duration};
+use r_ecipe_s_model::RecipeWithId;
+
+use crate::api::*;
+use crate::meal_plan_ls::{parse_date, today};
+
+/// Days ahead the expiry report looks.
+const EXPIRY_DAYS: i64 = 7;
+
+#[derive(Debug, Clone)]
+enum PantryEdit {
+    Add(PantryItem),
+    Expires(PantryItemWithId, Option<Date>),
+    Remove(i64),
+}
+
+/// The pantry's stock, what is about to expire and recipes to use it in.
+#[component]
+pub fn Pantry<F: Fn(RecipeWithId) + Copy + 'static>(
+    api_key: Signal<Option<String>>,
+    open_recipe: F,
+) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let items = create_resource(
+        move || refresh.get(),
+        |_| async move {
+            get_pantry().await.unwrap_or_else(|err| {
+                warn!("Failed to get pantry: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let expiring = create_resource(
+        move || refresh.get(),
+        |_| async move {
+            get_expiring(EXPIRY_DAYS).await.unwrap_or_else(|err| {
+                warn!("Failed to get expiring items: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let edit = create_action(move |edit: &PantryEdit| {
+        let edit = edit.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                PantryEdit::Add(item) => put_pantry_item(&item, api_key).await.map(|_| ()),
+                PantryEdit::Expires(PantryItemWithId { id, data }, expires) => {
+                    let item = PantryItem { expires, ..data };
+                    update_pantry_item(id, &item, api_key).await.map(|_| ())
+                }
+                PantryEdit::Remove(id) => delete_pantry_item(id, api_key).await,
+            };
+            if let Err(err) = result {
+                warn!("Failed to change pantry: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    let (line, set_line) = create_signal(String::new());
+    let (expires, set_expires) = create_signal(None::<Date>);
+    let add = move || {
+        let line = line.get_untracked();
+        if line.trim().is_empty() {
+            return;
+        }
+        let ingredient = ingredient_from_line(&line);
+        edit.dispatch(PantryEdit::Add(PantryItem {
+            name: ingredient.name,
+            quantity: ingredient.quantity,
+            expires: expires.get_untracked(),
+        }));
+        set_line.set(String::new());
+    };
+
+    let row = move |item: PantryItemWithId| {
+        let PantryItemWithId { id, data } = item.clone();
+        let status = match data.expires {
+            Some(expires) if expires < today() => "text-error",
+            Some(expires) if expires <= today() + Duration::days(EXPIRY_DAYS) => "text-warning",
+            _ => "",
+        };
+        view! {
+            <tr>
+                <td>{data.name}</td>
+                <td>{data.quantity.to_string()}</td>
+                <td>
+                    <input
+                        type = "date"
+                        class = format!("input input-bordered input-xs bg-base-300 {status}")
+                        value = {data.expires.map(|expires| expires.to_string())}
+                        on:change = move |ev| {
+                            let expires = parse_date(&event_target_value(&ev));
+                            edit.dispatch(PantryEdit::Expires(item.clone(), expires));
+                        }
+                    />
+                </td>
+                <td>
+                    <button class = "btn btn-circle btn-ghost btn-xs" on:click = move |_| edit.dispatch(PantryEdit::Remove(id))>
+                        "×"
+                    </button>
+                </td>
+            </tr>
+        }
+    };
+
+    view! {
+        <div class = "grid grid-cols-1 md:grid-cols-3 gap-4">
+            <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300 md:col-span-2">
+                <div class = "card-body p-4">
+                    <h2 class = "card-title">
+                        Pantry
+                        {move || (items.loading().get() || edit.pending().get()).then(|| view! {
+                            <div class = "loading loading-infinity loading-secondary" />
+                        })}
+                    </h2>
+                    <div class = "join w-full">
+                        <input
+                            class = "input input-bordered input-primary input-sm bg-base-300 join-item w-full"
+                            placeholder = "500 g flour"
+                            prop:value = line
+                            on:input = move |ev| set_line.set(event_target_value(&ev))
+                            on:keydown = move |ev| if ev.key() == "Enter" { add() }
+                        />
+                        <input
+                            type = "date"
+                            class = "input input-bordered input-primary input-sm bg-base-300 join-item"
+                            title = "expires"
+                            on:change = move |ev| set_expires.set(parse_date(&event_target_value(&ev)))
+                        />
+                        <button class = "btn btn-primary btn-sm join-item" on:click = move |_| add()>"add"</button>
+                    </div>
+                    <table class = "table table-sm">
+                        <thead>
+                            <tr><th>"item"</th><th>"amount"</th><th>"expires"</th><th></th></tr>
+                        </thead>
+                        <tbody>
+                            {move || items.get().unwrap_or_default().into_iter().map(row).collect_view()}
+                        </tbody>
+                    </table>
+                </div>
+            </div>
+            <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300">
+                <div class = "card-body p-4">
+                    <h2 class = "card-title">Use soon</h2>
+                    {move || expiring.get().is_some_and(|expiring| expiring.is_empty()).then(|| view! {
+                        <p class = "italic">{format!("Nothing expires in the next {EXPIRY_DAYS} days.")}</p>
+                    })}
+                    {move || expiring.get().unwrap_or_default().into_iter().map(|ExpiringItem { item, recipes }| view! {
+                        <div>
+                            <h3 class = "font-bold">
+                                {item.data.name}
+                                <span class = "text-xs font-normal opacity-60 ml-2">
+                                    {item.data.expires.map(|expires| expires.to_string())}
+                                </span>
+                            </h3>
+                            <ul class = "list-disc list-inside text-sm">
+                                {recipes.into_iter().map(|recipe| {
+                                    let name = recipe.data.name.clone();
+                                    view! {
+                                        <li>
+                                            <a class = "link link-hover" on:click = move |_| open_recipe(recipe.clone())>{name}</a>
+                                        </li>
+                                    }
+                                })
+                                .collect_view()}
+                            </ul>
+                        </div>
+                    })
+                    .collect_view()}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Takes a recipe's ingredients out of the pantry once it has been cooked.
+#[component]
+pub fn CookedThis(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let cook = create_action(move |cooked: &CookedRecipe| {
+        let cooked = *cooked;
+        let api_key = api_key.get_untracked();
+        async move {
+            cook_from_pantry(&cooked, api_key.as_deref())
+                .await
+                .map_err(|err| format!("{err}"))
+        }
+    });
+    let missing = move || {
+        cook.value()
+            .get()
+            .and_then(Result::ok)
+            .map(|pantry_use| {
+                pantry_use
+                    .missing
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    view! {
+        <button
+            class = "btn btn-primary btn-xs"
+            title = move || match missing() {
+                missing if missing.is_empty() => String::new(),
+                missing => format!("Not in the pantry: {}", missing.join(", ")),
+            }
+            on:click = move |_| cook.dispatch(CookedRecipe {
+                recipe_id,
+                servings: None,
+            })
+        >
+            {move || match cook.value().get() {
+                _ if cook.pending().get() => "updating pantry",
+                Some(Ok(_)) if missing().is_empty() => "pantry updated",
+                Some(Ok(_)) => "pantry updated, some missing",
+                Some(Err(_)) => "failed",
+                None => "cooked this",
+            }}
+        </button>
+    }
+}
@@ -0,0 +1,220 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::shopping::{
+    RecipeServings, ShoppingItemWithId, ShoppingListRequest, ShoppingListWithId,
+};
+
+use crate::api::*;
+use crate::cost_ls::ShoppingListCost;
+
+#[derive(Debug, Clone)]
+enum ShoppingEdit {
+    Check { item_id: i64, checked: bool },
+    Delete,
+}
+
+/// Lists the shopping lists, newest first, beside the items of the chosen
+/// one grouped by aisle.
+#[component]
+pub fn ShoppingLists(api_key: Signal<Option<String>>) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0usize);
+    let lists = create_resource(
+        move || refresh.get(),
+        |_| async move {
+            get_shopping_lists().await.unwrap_or_else(|err| {
+                warn!("Failed to get shopping lists: {err}");
+                Vec::new()
+            })
+        },
+    );
+    let (selected, set_selected) = create_signal(None::<i64>);
+    let chosen = move || {
+        let lists = lists.get()?;
+        match selected.get() {
+            Some(selected) => lists.into_iter().find(|list| list.id == selected),
+            None => lists.into_iter().next(),
+        }
+    };
+
+    view! {
+        <div class = "grid grid-cols-1 md:grid-cols-3 gap-4">
+            <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300">
+                <div class = "card-body p-4">
+                    <h2 class = "card-title">Shopping lists</h2>
+                    {move || lists.get().is_some_and(|lists| lists.is_empty()).then(|| view! {
+                        <p class = "italic">"Make a shopping list from a recipe or the meal plan."</p>
+                    })}
+                    <ul class = "menu p-0">
+                        {move || lists.get().unwrap_or_default().into_iter().map(|ShoppingListWithId { id, data }| {
+                            let active = move || {
+                                let current = chosen().map(|list| list.id);
+                                if current == Some(id) { "active" } else { "" }
+                            };
+                            let left = data.items.iter().filter(|item| !item.data.checked).count();
+                            view! {
+                                <li>
+                                    <a class = active on:click = move |_| set_selected.set(Some(id))>
+                                        {data.name}
+                                        <span class = "badge badge-sm">{left}</span>
+                                    </a>
+                                </li>
+                            }
+                        })
+                        .collect_view()}
+                    </ul>
+                </div>
+            </div>
+            <div class = "md:col-span-2">
+                {move || chosen().map(|list| view! {
+                    <ShoppingListDetail list api_key set_refresh set_selected />
+                })}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn ShoppingListDetail(
+    list: ShoppingListWithId,
+    api_key: Signal<Option<String>>,
+    set_refresh: WriteSignal<usize>,
+    set_selected: WriteSignal<Option<i64>>,
+) -> impl IntoView {
+    let ShoppingListWithId { id, data } = list;
+    let edit = create_action(move |edit: &ShoppingEdit| {
+        let edit = edit.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            let api_key = api_key.as_deref();
+            let result = match edit {
+                ShoppingEdit::Check { item_id, checked } => {
+                    check_shopping_item(id, item_id, checked, api_key).await
+                }
+                ShoppingEdit::Delete => {
+                    let result = delete_shopping_list(id, api_key).await;
+                    if result.is_ok() {
+                        set_selected.set(None);
+                    }
+                    result
+                }
+            };
+            if let Err(err) = result {
+                warn!("Failed to edit shopping list {id}: {err}");
+            }
+            set_refresh.update(|refresh| *refresh += 1);
+        }
+    });
+
+    // Items arrive sorted by aisle, so each aisle is a run of items.
+    let mut aisles: Vec<(String, Vec<ShoppingItemWithId>)> = Vec::new();
+    for item in data.items {
+        match aisles.last_mut() {
+            Some((aisle, items)) if *aisle == item.data.aisle => items.push(item),
+            _ => aisles.push((item.data.aisle.clone(), vec![item])),
+        }
+    }
+    let count = aisles.len();
+    let aisles = aisles
+        .into_iter()
+        .map(|(aisle, items)| {
+            view! {
+                <h3 class = "text-xs uppercase opacity-60 mt-2">{aisle}</h3>
+                <ul>
+                    {items.into_iter().map(|ShoppingItemWithId { id: item_id, data: item }| {
+                        let amount = item.amount();
+                        let checked = item.checked;
+                        view! {
+                            <li>
+                                <label class = "label cursor-pointer justify-start gap-2 py-1">
+                                    <input
+                                        type = "checkbox"
+                                        class = "checkbox checkbox-primary checkbox-sm"
+                                        checked = checked
+                                        on:change = move |ev| edit.dispatch(ShoppingEdit::Check {
+                                            item_id,
+                                            checked: event_target_checked(&ev),
+                                        })
+                                    />
+                                    <span class:line-through = checked class:opacity-60 = checked>
+                                        {item.name}
+                                        {(!amount.is_empty()).then(|| view! {
+                                            <span class = "opacity-60">{format!(" ({amount})")}</span>
+                                        })}
+                                    </span>
+                                </label>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+                </ul>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class = "card bg-base-100 border border-base-content shadow-md shadow-base-300">
+            <div class = "card-body p-4">
+                <h2 class = "card-title">{data.name}</h2>
+                {(count == 0).then(|| view! {
+                    <p class = "italic">"Nothing to buy."</p>
+                })}
+                {aisles}
+                <ShoppingListCost list_id = id/>
+                <div class = "card-actions justify-end">
+                    {move || edit.pending().get().then(|| view! {
+                        <div class = "loading loading-infinity loading-secondary" />
+                    })}
+                    <button class = "btn btn-xs btn-primary" on:click = move |_| edit.dispatch(ShoppingEdit::Delete)>
+                        "delete list"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Makes a shopping list from what `request` returns when clicked.
+#[component]
+pub fn NewShoppingList<F: Fn() -> ShoppingListRequest + 'static>(
+    request: F,
+    api_key: Signal<Option<String>>,
+) -> impl IntoView {
+    let make = create_action(move |request: &ShoppingListRequest| {
+        let request = request.clone();
+        let api_key = api_key.get_untracked();
+        async move {
+            put_shopping_list(&request, api_key.as_deref())
+                .await
+                .map_err(|err| format!("{err}"))
+        }
+    });
+
+    view! {
+        <button class = "btn btn-primary btn-xs" on:click = move |_| make.dispatch(request())>
+            {move || match make.value().get() {
+                _ if make.pending().get() => "making list",
+                Some(Ok(_)) => "list made",
+                Some(Err(_)) => "failed",
+                None => "shopping list",
+            }}
+        </button>
+        {move || make.value().get().and_then(Result::err).map(|err| view! {
+            <span class = "text-error text-xs ml-2">{err}</span>
+        })}
+    }
+}
+
+/// Makes a shopping list for one recipe.
+#[component]
+pub fn ShopForRecipe(recipe_id: i64, api_key: Signal<Option<String>>) -> impl IntoView {
+    let request = move || ShoppingListRequest {
+        recipes: vec![RecipeServings {
+            recipe_id,
+            servings: None,
+        }],
+        ..ShoppingListRequest::default()
+    };
+    view! {
+        <NewShoppingList request api_key/>
+    }
+}
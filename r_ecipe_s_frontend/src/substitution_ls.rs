@@ -0,0 +1,82 @@
+use leptos::logging::warn;
+use leptos::*;
+use r_ecipe_s_model::substitution::SuggestedSubstitute;
+use r_ecipe_s_model::Ingredient;
+
+use crate::api::*;
+
+fn describe(ingredients: &[Ingredient]) -> String {
+    ingredients
+        .iter()
+        .map(|ingredient| ingredient.to_string())
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// A button showing what could stand in for the ingredient, in the amount
+/// it is needed.
+#[component]
+pub fn Substitutes(ingredient: Signal<Ingredient>) -> impl IntoView {
+    let (open, set_open) = create_signal(false);
+    let suggestions = create_resource(
+        move || open.get().then(|| ingredient.get()),
+        |ingredient| async move {
+            match ingredient {
+                Some(ingredient) => suggest_substitutes(&ingredient)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!("Failed to get substitutes: {err}");
+                        Vec::new()
+                    }),
+                None => Vec::new(),
+            }
+        },
+    );
+
+    let suggestion = |suggestion: SuggestedSubstitute| {
+        let substitution = suggestion.substitution.data;
+        let amount = (!suggestion.scaled).then(|| {
+            format!(
+                " (for {})",
+                Ingredient {
+                    name: substitution.ingredient.clone(),
+                    quantity: substitution.per.clone(),
+                    preparation: None,
+                    catalogue_id: None,
+                    component: None,
+                }
+            )
+        });
+        view! {
+            <li>
+                {describe(&suggestion.replacement)}
+                {amount}
+                {substitution.note.map(|note| view! {
+                    <span class = "text-xs opacity-60 ml-2">{note}</span>
+                })}
+            </li>
+        }
+    };
+
+    view! {
+        <button
+            class = "btn btn-ghost btn-xs ml-2"
+            on:click = move |_| set_open.update(|open| *open = !*open)
+        >"swap"</button>
+        {move || open.get().then(|| view! {
+            <ul class = "text-sm ml-4">
+                {move || match suggestions.get() {
+                    None => view! {
+                        <span class = "loading loading-infinity loading-secondary loading-xs" />
+                    }
+                    .into_view(),
+                    Some(suggestions) if suggestions.is_empty() => view! {
+                        <li class = "opacity-60">"no substitutes known"</li>
+                    }
+                    .into_view(),
+                    Some(suggestions) => suggestions.into_iter().map(suggestion).collect_view(),
+                }}
+            </ul>
+        })}
+    }
+}
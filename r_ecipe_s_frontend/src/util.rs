@@ -6,6 +6,15 @@ use pulldown_cmark::{html, Options, Parser};
 pub fn background(image_name: &str) -> String {
     format!("background-image: url(/static/{image_name})")
 }
+/// The recipe a link such as `/?recipe=12` points at, as used by the meal
+/// plan calendar feed.
+pub fn linked_recipe_id() -> Option<i64> {
+    let search = web_sys::window()?.location().search().ok()?;
+    url::form_urlencoded::parse(search.trim_start_matches('?').as_bytes())
+        .find(|(key, _)| key == "recipe")
+        .and_then(|(_, id)| id.parse().ok())
+}
+
 pub fn markdown_to_html(markdown_str: &str) -> String {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
@@ -0,0 +1,102 @@
+use crate::{ImportResult, RecipeWithId};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+pub const ARCHIVE_FORMAT: &str = "r_ecipe_s";
+pub const ARCHIVE_VERSION: u32 = 1;
+pub const ARCHIVE_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// First line of a library archive. Every following line is a
+/// [`RecipeWithId`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchiveMetadata {
+    pub format: String,
+    pub version: u32,
+    /// Seconds since the Unix epoch.
+    pub exported_at: i64,
+    pub recipe_count: i64,
+    pub embeddings: bool,
+}
+
+impl ArchiveMetadata {
+    pub fn new(exported_at: i64, recipe_count: i64, embeddings: bool) -> Self {
+        ArchiveMetadata {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at,
+            recipe_count,
+            embeddings,
+        }
+    }
+}
+
+/// How an archive import treats the recipes already in the library. Merging
+/// updates recipes of the same name and adds the rest, replacing removes every
+/// existing recipe first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    #[default]
+    Merge,
+    Replace,
+}
+
+/// Result of importing an archive. Each result's source is the recipe's id in
+/// the archive and its outcome the id it has in the library, so the results
+/// double as the id remapping.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchiveImportReport {
+    pub metadata: ArchiveMetadata,
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub removed: u64,
+    pub results: Vec<ImportResult>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Empty,
+    Metadata(serde_json::Error),
+    Unsupported { format: String, version: u32 },
+}
+
+impl Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Empty => write!(f, "Archive is empty"),
+            ArchiveError::Metadata(err) => write!(f, "Invalid archive metadata: {err}"),
+            ArchiveError::Unsupported { format, version } => write!(
+                f,
+                "Unsupported archive {format} version {version}, expected {ARCHIVE_FORMAT} up to version {ARCHIVE_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+/// A recipe line of an archive, numbered from 1 counting the metadata line.
+pub type ArchiveLine = (usize, Result<RecipeWithId, serde_json::Error>);
+
+/// Reads an archive's metadata and recipe lines, skipping blank lines. Lines
+/// that aren't a recipe are returned as errors so the rest can still be read.
+pub fn read_archive(archive: &str) -> Result<(ArchiveMetadata, Vec<ArchiveLine>), ArchiveError> {
+    let mut lines = archive
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let (_, metadata) = lines.next().ok_or(ArchiveError::Empty)?;
+    let metadata =
+        serde_json::from_str::<ArchiveMetadata>(metadata).map_err(ArchiveError::Metadata)?;
+    if metadata.format != ARCHIVE_FORMAT || metadata.version > ARCHIVE_VERSION {
+        return Err(ArchiveError::Unsupported {
+            format: metadata.format,
+            version: metadata.version,
+        });
+    }
+    let recipes = lines
+        .map(|(number, line)| (number, serde_json::from_str::<RecipeWithId>(line)))
+        .collect();
+    Ok((metadata, recipes))
+}
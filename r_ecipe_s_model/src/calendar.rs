@@ -0,0 +1,127 @@
+use crate::plan::{MealSlot, PlannedRecipe};
+use time::macros::{format_description, time};
+use time::{OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+pub const CALENDAR_EXTENSION: &str = "ics";
+pub const CALENDAR_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// Longest content line allowed by RFC 5545, in bytes, before it is folded.
+const MAX_LINE_BYTES: usize = 75;
+
+/// Time of day meals in a slot are put at, as the plan has no times.
+fn slot_time(slot: MealSlot) -> Time {
+    match slot {
+        MealSlot::Breakfast => time!(08:00),
+        MealSlot::Lunch => time!(12:30),
+        MealSlot::Snack => time!(16:00),
+        MealSlot::Dinner => time!(19:00),
+    }
+}
+
+/// Escapes text for a TEXT property value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folding it onto continuation lines so that none
+/// is longer than 75 bytes, without splitting a character.
+fn push_line(calendar: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            calendar.push_str("\r\n ");
+            width = 1;
+        }
+        calendar.push(c);
+        width += c.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+fn local_time(date_time: PrimitiveDateTime) -> String {
+    date_time
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]"
+        ))
+        .unwrap_or_default()
+}
+
+/// Writes the planned meals as an iCalendar feed with one event per meal.
+/// Events start at the usual time for their slot in the calendar's own time
+/// zone, link to the recipe through `recipe_url` and list the ingredients,
+/// scaled to the planned servings, in their description.
+pub fn meal_plan_to_ics(
+    planned: &[PlannedRecipe],
+    recipe_url: impl Fn(i64) -> String,
+    stamp: OffsetDateTime,
+) -> String {
+    let stamp = stamp.to_offset(UtcOffset::UTC);
+    let stamp = local_time(PrimitiveDateTime::new(stamp.date(), stamp.time())) + "Z";
+    let mut calendar = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//r_ecipe_s//Meal plan//EN",
+        "CALSCALE:GREGORIAN",
+        "X-WR-CALNAME:Meal plan",
+    ] {
+        push_line(&mut calendar, line);
+    }
+    for PlannedRecipe { meal, recipe } in planned {
+        let start = PrimitiveDateTime::new(meal.data.date, slot_time(meal.data.slot));
+        let mut description = Vec::new();
+        if let Some(note) = &meal.data.note {
+            description.push(note.clone());
+        }
+        if let Some(recipe) = recipe {
+            let recipe = match meal.data.servings {
+                Some(servings) => recipe.data.scaled(servings),
+                None => recipe.data.clone(),
+            };
+            if let Some(servings) = recipe.servings {
+                description.push(format!("Serves {servings}"));
+            }
+            if !recipe.ingredients.is_empty() {
+                let ingredients = recipe
+                    .ingredients
+                    .iter()
+                    .map(|ingredient| format!("- {ingredient}"))
+                    .collect::<Vec<_>>();
+                description.push(ingredients.join("\n"));
+            }
+        }
+        let url = meal.data.recipe_id.map(&recipe_url);
+        if let Some(url) = &url {
+            description.push(url.clone());
+        }
+
+        push_line(&mut calendar, "BEGIN:VEVENT");
+        push_line(&mut calendar, &format!("UID:meal-{}@r_ecipe_s", meal.id));
+        push_line(&mut calendar, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut calendar, &format!("DTSTART:{}", local_time(start)));
+        push_line(&mut calendar, "DURATION:PT1H");
+        push_line(
+            &mut calendar,
+            &format!(
+                "SUMMARY:{}",
+                escape(&format!("{} ({})", meal.data.recipe_name, meal.data.slot))
+            ),
+        );
+        if !description.is_empty() {
+            push_line(
+                &mut calendar,
+                &format!("DESCRIPTION:{}", escape(&description.join("\n\n"))),
+            );
+        }
+        if let Some(url) = &url {
+            push_line(&mut calendar, &format!("URL:{url}"));
+        }
+        push_line(&mut calendar, "END:VEVENT");
+    }
+    push_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
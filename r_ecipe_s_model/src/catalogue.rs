@@ -0,0 +1,201 @@
+use crate::nutrition::food_key;
+use crate::{Ingredient, RecipeWithId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An ingredient under its canonical name, with the other names recipes
+/// call it by, so that `scallions`, `spring onions` and `green onions` are
+/// one thing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CatalogueEntry {
+    pub name: String,
+    /// The plural of the name, where adding an `s` doesn't make it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plural: Option<String>,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CatalogueEntryWithId {
+    pub id: i64,
+    pub data: CatalogueEntry,
+}
+
+impl CatalogueEntry {
+    /// Every name the entry goes by.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.name)
+            .chain(&self.plural)
+            .chain(&self.synonyms)
+    }
+
+    fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.names().map(|name| food_key(name))
+    }
+
+    /// Whether the entry goes by the name, in the singular or plural.
+    pub fn is_called(&self, name: &str) -> bool {
+        let key = food_key(name);
+        !key.is_empty() && self.keys().any(|entry_key| entry_key == key)
+    }
+}
+
+/// The entry going by the name.
+pub fn lookup<'a>(
+    name: &str,
+    catalogue: &'a [CatalogueEntryWithId],
+) -> Option<&'a CatalogueEntryWithId> {
+    catalogue.iter().find(|entry| entry.data.is_called(name))
+}
+
+/// The entry the ingredient is linked to, or else the one going by its name.
+pub fn entry_of<'a>(
+    ingredient: &Ingredient,
+    catalogue: &'a [CatalogueEntryWithId],
+) -> Option<&'a CatalogueEntryWithId> {
+    ingredient
+        .catalogue_id
+        .and_then(|id| catalogue.iter().find(|entry| entry.id == id))
+        .or_else(|| lookup(&ingredient.name, catalogue))
+}
+
+/// The ingredients under their canonical names and linked to their entries,
+/// so that shopping lists add up `scallions` and `spring onions`.
+pub fn canonicalise(
+    ingredients: impl IntoIterator<Item = Ingredient>,
+    catalogue: &[CatalogueEntryWithId],
+) -> Vec<Ingredient> {
+    ingredients
+        .into_iter()
+        .map(|ingredient| match entry_of(&ingredient, catalogue) {
+            Some(entry) => Ingredient {
+                name: entry.data.name.clone(),
+                catalogue_id: Some(entry.id),
+                ..ingredient
+            },
+            None => ingredient,
+        })
+        .collect()
+}
+
+/// The ingredients renamed to whichever of `names` is the same entry in the
+/// catalogue, so that a recipe's `green onions` are found in a pantry
+/// holding `scallions`.
+pub fn rename_like(
+    ingredients: &[Ingredient],
+    names: &[&str],
+    catalogue: &[CatalogueEntryWithId],
+) -> Vec<Ingredient> {
+    ingredients
+        .iter()
+        .map(|ingredient| {
+            let same = entry_of(ingredient, catalogue)
+                .and_then(|entry| names.iter().find(|name| entry.data.is_called(name)));
+            match same {
+                Some(name) => Ingredient {
+                    name: name.to_string(),
+                    ..ingredient.clone()
+                },
+                None => ingredient.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Each name in the catalogue with the other names of its entry, for the
+/// search index to treat as one word.
+pub fn synonyms(catalogue: &[CatalogueEntryWithId]) -> HashMap<String, Vec<String>> {
+    let mut synonyms = HashMap::new();
+    for entry in catalogue {
+        let names = entry
+            .data
+            .names()
+            .map(|name| name.to_lowercase())
+            .collect::<Vec<_>>();
+        for name in &names {
+            let others = names.iter().filter(|other| *other != name).cloned();
+            synonyms
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .extend(others);
+        }
+    }
+    synonyms
+}
+
+/// A catalogue entry proposed for an ingredient of a recipe that isn't
+/// linked to one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CatalogueMatch {
+    pub recipe_id: i64,
+    pub recipe_name: String,
+    /// Position of the ingredient in the recipe.
+    pub index: usize,
+    pub ingredient: String,
+    pub catalogue_id: i64,
+    pub catalogue_name: String,
+    /// Whether the ingredient goes by one of the entry's names, rather than
+    /// being a kind of it, as `red onion` is of `onion`.
+    pub exact: bool,
+}
+
+/// The entry going by the name, or else the most specific one it is a kind
+/// of.
+fn propose<'a>(
+    name: &str,
+    catalogue: &'a [CatalogueEntryWithId],
+) -> Option<(&'a CatalogueEntryWithId, bool)> {
+    if let Some(entry) = lookup(name, catalogue) {
+        return Some((entry, true));
+    }
+    let key = food_key(name);
+    catalogue
+        .iter()
+        .flat_map(|entry| entry.data.keys().map(move |entry_key| (entry_key, entry)))
+        .filter(|(entry_key, _)| !entry_key.is_empty() && key.ends_with(&format!(" {entry_key}")))
+        .max_by_key(|(entry_key, _)| entry_key.len())
+        .map(|(_, entry)| (entry, false))
+}
+
+/// Proposes catalogue entries for the recipes' unlinked ingredients.
+pub fn propose_matches(
+    recipes: &[RecipeWithId],
+    catalogue: &[CatalogueEntryWithId],
+) -> Vec<CatalogueMatch> {
+    recipes
+        .iter()
+        .flat_map(|recipe| {
+            recipe
+                .data
+                .ingredients
+                .iter()
+                .enumerate()
+                .filter(|(_, ingredient)| ingredient.catalogue_id.is_none())
+                .filter_map(move |(index, ingredient)| {
+                    let (entry, exact) = propose(&ingredient.name, catalogue)?;
+                    Some(CatalogueMatch {
+                        recipe_id: recipe.id,
+                        recipe_name: recipe.data.name.clone(),
+                        index,
+                        ingredient: ingredient.name.clone(),
+                        catalogue_id: entry.id,
+                        catalogue_name: entry.data.name.clone(),
+                        exact,
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Links the ingredient at the match's position to its entry, unless the
+/// ingredient has been renamed since. Returns whether it was linked.
+pub fn link_ingredient(ingredients: &mut [Ingredient], catalogue_match: &CatalogueMatch) -> bool {
+    match ingredients.get_mut(catalogue_match.index) {
+        Some(ingredient) if ingredient.name == catalogue_match.ingredient => {
+            ingredient.catalogue_id = Some(catalogue_match.catalogue_id);
+            true
+        }
+        _ => false,
+    }
+}
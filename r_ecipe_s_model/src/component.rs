@@ -0,0 +1,173 @@
+use crate::scaling::DEFAULT_SERVINGS;
+use crate::{format_amount, Ingredient, Recipe};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Another recipe used as an ingredient, such as pizza dough or a stock.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Component {
+    pub recipe_id: i64,
+    pub portion: Portion,
+}
+
+/// How much of a component recipe is used.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Portion {
+    /// Servings of what the recipe yields.
+    Servings(f64),
+    /// A share of the whole recipe, 1 being all of it.
+    Fraction(f64),
+}
+
+impl Portion {
+    /// The factor by which the ingredients of a recipe yielding `servings`
+    /// are multiplied to make this much of it.
+    pub fn factor(&self, servings: Option<u32>) -> f64 {
+        match self {
+            Portion::Servings(portion) => {
+                portion / f64::from(servings.unwrap_or(DEFAULT_SERVINGS).max(1))
+            }
+            Portion::Fraction(fraction) => *fraction,
+        }
+    }
+
+    pub fn scale(self, factor: f64) -> Portion {
+        match self {
+            Portion::Servings(portion) => Portion::Servings(portion * factor),
+            Portion::Fraction(fraction) => Portion::Fraction(fraction * factor),
+        }
+    }
+
+    pub fn amount(&self) -> f64 {
+        match self {
+            Portion::Servings(amount) | Portion::Fraction(amount) => *amount,
+        }
+    }
+
+    /// Whether this is some of the recipe, rather than none, less than none
+    /// or not a number.
+    pub fn is_valid(&self) -> bool {
+        let amount = self.amount();
+        amount.is_finite() && amount > 0.0
+    }
+}
+
+/// Formats as e.g. `2 servings` or `0.5 of the recipe`.
+impl Display for Portion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Portion::Servings(servings) if *servings == 1.0 => write!(f, "1 serving"),
+            Portion::Servings(servings) => write!(f, "{} servings", format_amount(*servings)),
+            Portion::Fraction(fraction) if *fraction == 1.0 => write!(f, "the whole recipe"),
+            Portion::Fraction(fraction) => write!(f, "{} of the recipe", format_amount(*fraction)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentError {
+    Missing(i64),
+    /// The id of the recipe used with a portion that isn't positive.
+    InvalidPortion(i64),
+    /// The recipes, in the order they use each other, ending with the one
+    /// that was already being made.
+    Cycle(Vec<i64>),
+}
+
+impl Display for ComponentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComponentError::Missing(id) => write!(f, "Component recipe {id} doesn't exist"),
+            ComponentError::InvalidPortion(id) => {
+                write!(f, "Component recipe {id} needs a portion greater than zero")
+            }
+            ComponentError::Cycle(path) => {
+                let path = path
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" → ");
+                write!(f, "Recipes use each other as components: {path}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComponentError {}
+
+/// Ids of the recipes used directly as components.
+pub fn component_ids(ingredients: &[Ingredient]) -> Vec<i64> {
+    let mut ids = ingredients
+        .iter()
+        .filter_map(|ingredient| Some(ingredient.component?.recipe_id))
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+fn expand(
+    ingredients: &[Ingredient],
+    factor: f64,
+    recipes: &HashMap<i64, Recipe>,
+    path: &mut Vec<i64>,
+    expanded: &mut Vec<Ingredient>,
+) -> Result<(), ComponentError> {
+    for ingredient in ingredients {
+        let Some(component) = ingredient.component else {
+            expanded.push(ingredient.scaled(factor));
+            continue;
+        };
+        if path.contains(&component.recipe_id) {
+            path.push(component.recipe_id);
+            return Err(ComponentError::Cycle(path.clone()));
+        }
+        let recipe = recipes
+            .get(&component.recipe_id)
+            .ok_or(ComponentError::Missing(component.recipe_id))?;
+        path.push(component.recipe_id);
+        let component_factor = factor * component.portion.factor(recipe.servings);
+        expand(
+            &recipe.ingredients,
+            component_factor,
+            recipes,
+            path,
+            expanded,
+        )?;
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Replaces ingredients that are other recipes with those recipes'
+/// ingredients, scaled to the portion used, and so on through components of
+/// components. `recipes` holds every recipe reachable this way by id.
+pub fn expand_components(
+    ingredients: &[Ingredient],
+    recipes: &HashMap<i64, Recipe>,
+) -> Result<Vec<Ingredient>, ComponentError> {
+    let mut expanded = Vec::with_capacity(ingredients.len());
+    expand(ingredients, 1.0, recipes, &mut Vec::new(), &mut expanded)?;
+    Ok(expanded)
+}
+
+/// Checks that the components of the recipe with `id`, if it has been saved
+/// before, are used in positive portions, exist and never lead back to it or
+/// to each other.
+pub fn check_components(
+    id: Option<i64>,
+    ingredients: &[Ingredient],
+    recipes: &HashMap<i64, Recipe>,
+) -> Result<(), ComponentError> {
+    if let Some(component) = ingredients
+        .iter()
+        .filter_map(|ingredient| ingredient.component)
+        .find(|component| !component.portion.is_valid())
+    {
+        return Err(ComponentError::InvalidPortion(component.recipe_id));
+    }
+    let mut path = id.into_iter().collect();
+    expand(ingredients, 1.0, recipes, &mut path, &mut Vec::new())
+}
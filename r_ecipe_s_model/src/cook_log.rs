@@ -0,0 +1,111 @@
+use crate::plan::iso_date;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use time::Date;
+
+/// Ratings go from one to this many stars.
+pub const MAX_RATING: u8 = 5;
+
+/// A time a recipe was cooked.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CookLogEntry {
+    pub recipe_id: i64,
+    #[serde(with = "iso_date")]
+    pub cooked: Date,
+    /// Who cooked it.
+    #[serde(default)]
+    pub cook: Option<String>,
+    /// From 1 to [`MAX_RATING`].
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CookLogEntryWithId {
+    pub id: i64,
+    pub data: CookLogEntry,
+    /// Whether a photo of the result was uploaded.
+    #[serde(default)]
+    pub photo: bool,
+}
+
+pub fn is_rating(rating: u8) -> bool {
+    (1..=MAX_RATING).contains(&rating)
+}
+
+/// How often and how recently a recipe was cooked, and how well it went.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CookingStats {
+    pub recipe_id: i64,
+    pub times_cooked: i64,
+    #[serde(default, with = "iso_date::option")]
+    pub last_cooked: Option<Date>,
+    /// Of the times it was rated.
+    #[serde(default)]
+    pub average_rating: Option<f64>,
+}
+
+/// How the recipe list is sorted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeOrder {
+    /// Most recently changed first.
+    #[default]
+    Updated,
+    Name,
+    /// Most recently cooked first.
+    LastCooked,
+    /// Never cooked, then cooked longest ago, first.
+    LeastRecentlyCooked,
+    MostCooked,
+    /// Highest average rating first.
+    Rating,
+}
+
+impl RecipeOrder {
+    pub const ALL: [RecipeOrder; 6] = [
+        RecipeOrder::Updated,
+        RecipeOrder::Name,
+        RecipeOrder::LastCooked,
+        RecipeOrder::LeastRecentlyCooked,
+        RecipeOrder::MostCooked,
+        RecipeOrder::Rating,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecipeOrder::Updated => "updated",
+            RecipeOrder::Name => "name",
+            RecipeOrder::LastCooked => "last_cooked",
+            RecipeOrder::LeastRecentlyCooked => "least_recently_cooked",
+            RecipeOrder::MostCooked => "most_cooked",
+            RecipeOrder::Rating => "rating",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<RecipeOrder> {
+        let name = name.trim();
+        RecipeOrder::ALL
+            .into_iter()
+            .find(|order| order.as_str().eq_ignore_ascii_case(name))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecipeOrder::Updated => "recently changed",
+            RecipeOrder::Name => "name",
+            RecipeOrder::LastCooked => "recently cooked",
+            RecipeOrder::LeastRecentlyCooked => "not cooked lately",
+            RecipeOrder::MostCooked => "most cooked",
+            RecipeOrder::Rating => "best rated",
+        }
+    }
+}
+
+impl Display for RecipeOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
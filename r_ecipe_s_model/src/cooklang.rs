@@ -0,0 +1,430 @@
+use crate::diet::DietaryFlags;
+use crate::ingredient_parser::{parse_number, unit};
+use crate::method::list_item;
+use crate::tags::{normalise_tags, split_tags};
+use crate::timing::Timing;
+use crate::{Ingredient, Quantity, Recipe};
+use std::fmt::Display;
+
+pub const COOKLANG_EXTENSION: &str = "cook";
+const TITLE_KEYS: [&str; 2] = ["title", "name"];
+const SERVINGS_KEYS: [&str; 2] = ["servings", "serves"];
+const TAGS_KEY: &str = "tags";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CooklangError {
+    MissingName,
+}
+
+impl Display for CooklangError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CooklangError::MissingName => {
+                write!(f, "Cooklang recipe has no title and no file name")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CooklangError {}
+
+/// Removes `-- line` and `[- block -]` comments.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(open) = rest.find("[-") {
+        stripped.push_str(&rest[..open]);
+        rest = match rest[open..].find("-]") {
+            Some(close) => &rest[open + close + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+        .lines()
+        .map(|line| match line.find("--") {
+            Some(comment) => &line[..comment],
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses a whole number amount, leaving ranges such as `2-3` to be kept as
+/// text.
+fn number(amount: &str) -> Option<f64> {
+    if amount.contains(['-', '–']) {
+        return None;
+    }
+    parse_number(amount)
+}
+
+/// Maps a Cooklang amount and unit onto a [`Quantity`]. Anything that doesn't
+/// map exactly, including units the ingredient parser converts such as ounces,
+/// is kept as text so it survives a round trip.
+fn quantity(amount: &str, unit_name: &str) -> Quantity {
+    let (amount, unit_name) = (amount.trim(), unit_name.trim());
+    if amount.is_empty() && unit_name.is_empty() {
+        return Quantity::Count(0.0);
+    }
+    if let Some(number) = number(amount) {
+        if unit_name.is_empty() {
+            return Quantity::Count(number);
+        }
+        if let Some(quantity) = unit(unit_name)
+            .map(|unit| unit(number))
+            .filter(|quantity| quantity.value() == number)
+        {
+            return quantity;
+        }
+    }
+    Quantity::Other {
+        amount: amount.to_string(),
+        unit: unit_name.to_string(),
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+struct Marker<'a> {
+    name: &'a str,
+    amount: Option<(&'a str, &'a str)>,
+    preparation: Option<&'a str>,
+    len: usize,
+}
+
+/// Reads the ingredient, cookware or timer following a `@`, `#` or `~`. Names
+/// spanning several words must end in braces, single words may leave them out.
+fn marker(text: &str) -> Marker<'_> {
+    let braced = text.find('{').filter(|brace| {
+        !text[..*brace].contains(['@', '#', '~', '\n']) && text[*brace..].contains('}')
+    });
+    let Some(brace) = braced else {
+        let len = text.find(|c| !is_name_char(c)).unwrap_or(text.len());
+        return Marker {
+            name: &text[..len],
+            amount: None,
+            preparation: None,
+            len,
+        };
+    };
+    let close = brace + text[brace..].find('}').unwrap_or(0);
+    let contents = &text[brace + 1..close];
+    let amount = contents.split_once('%').unwrap_or((contents, ""));
+    let mut len = close + 1;
+    let mut preparation = None;
+    if text[len..].starts_with('(') {
+        if let Some(end) = text[len..].find(')') {
+            preparation = Some(text[len + 1..len + end].trim()).filter(|p| !p.is_empty());
+            len += end + 1;
+        }
+    }
+    Marker {
+        name: text[..brace].trim(),
+        amount: Some(amount),
+        preparation,
+        len,
+    }
+}
+
+/// Replaces the markers in a step with plain text, collecting its ingredients
+/// and cookware.
+fn read_step(step: &str, ingredients: &mut Vec<Ingredient>, cookware: &mut Vec<String>) -> String {
+    let mut text = String::with_capacity(step.len());
+    let mut rest = step;
+    while let Some(position) = rest.find(['@', '#', '~']) {
+        text.push_str(&rest[..position]);
+        let symbol = &rest[position..position + 1];
+        let marker = marker(&rest[position + 1..]);
+        if marker.len == 0 {
+            text.push_str(symbol);
+            rest = &rest[position + 1..];
+            continue;
+        }
+        rest = &rest[position + 1 + marker.len..];
+        match (symbol, marker.amount) {
+            ("@", amount) => {
+                text.push_str(marker.name);
+                let quantity = amount.map_or(Quantity::Count(0.0), |(amount, unit)| {
+                    quantity(amount, unit)
+                });
+                let repeated = quantity == Quantity::Count(0.0)
+                    && marker.preparation.is_none()
+                    && ingredients.iter().any(|i| i.name == marker.name);
+                if !repeated {
+                    ingredients.push(Ingredient {
+                        name: marker.name.to_string(),
+                        quantity,
+                        preparation: marker.preparation.map(String::from),
+                        catalogue_id: None,
+                        component: None,
+                    });
+                }
+            }
+            ("#", _) => {
+                text.push_str(marker.name);
+                cookware.push(marker.name.to_string());
+            }
+            ("~", Some((amount, unit))) => {
+                text.push_str(format!("{} {}", amount.trim(), unit.trim()).trim());
+            }
+            (_, _) => text.push_str(marker.name),
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+fn servings(value: &str) -> Option<u32> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|digits| !digits.is_empty())
+        .and_then(|digits| digits.parse::<u32>().ok())
+        .filter(|servings| *servings > 0)
+}
+
+/// Parses a Cooklang recipe. The `>> title` metadata names the recipe, falling
+/// back to `file_name` without its extension, and `>> tags` are read as a comma
+/// separated list. Steps become a numbered markdown method with ingredients,
+/// cookware and timers written out as plain text, and the cookware becomes the
+/// recipe's equipment. Notes become the summary and any other metadata is kept
+/// as `>> key: value` lines at the top of the description, from where
+/// [`to_cooklang`] restores it.
+pub fn parse_cooklang(source: &str, file_name: Option<&str>) -> Result<Recipe, CooklangError> {
+    let source = strip_comments(source);
+    let mut name = None;
+    let mut servings_value = None;
+    let mut tags = Vec::new();
+    let mut metadata = Vec::new();
+    let mut notes = Vec::new();
+    let mut method = Vec::new();
+    let mut ingredients = Vec::new();
+    let mut cookware = Vec::new();
+    let mut step = Vec::new();
+
+    let mut finish_step = |step: &mut Vec<&str>, method: &mut Vec<String>| {
+        if !step.is_empty() {
+            let text = read_step(&step.join(" "), &mut ingredients, &mut cookware);
+            method.push(format!("1. {}", text.trim()));
+            step.clear();
+        }
+    };
+    for line in source.lines().map(str::trim) {
+        if let Some(entry) = line.strip_prefix(">>") {
+            let (key, value) = entry.split_once(':').unwrap_or((entry, ""));
+            let (key, value) = (key.trim(), value.trim());
+            if TITLE_KEYS.contains(&key.to_lowercase().as_str()) && name.is_none() {
+                name = Some(value.to_string());
+            } else if SERVINGS_KEYS.contains(&key.to_lowercase().as_str())
+                && servings_value.is_none()
+                && servings(value).is_some()
+            {
+                servings_value = servings(value);
+            } else if key.eq_ignore_ascii_case(TAGS_KEY) {
+                tags.extend(split_tags(value));
+            } else {
+                metadata.push(format!(">> {key}: {value}"));
+            }
+        } else if let Some(note) = line.strip_prefix('>') {
+            notes.push(note.trim().to_string());
+        } else if line.starts_with('=') {
+            finish_step(&mut step, &mut method);
+            let section = line.trim_matches('=').trim();
+            if !section.is_empty() {
+                method.push(format!("### {section}"));
+            }
+        } else if line.is_empty() {
+            finish_step(&mut step, &mut method);
+        } else {
+            step.push(line);
+        }
+    }
+    finish_step(&mut step, &mut method);
+
+    let name = name
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            let file_name = file_name?.rsplit('/').next()?;
+            let stem = file_name
+                .strip_suffix(&format!(".{COOKLANG_EXTENSION}"))
+                .unwrap_or(file_name);
+            Some(stem.trim().to_string()).filter(|stem| !stem.is_empty())
+        })
+        .ok_or(CooklangError::MissingName)?;
+    let description = [metadata.join("\n"), notes.join("\n"), method.join("\n")]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(Recipe {
+        name,
+        ingredients,
+        description,
+        liked: None,
+        embedding: None,
+        servings: servings_value,
+        tags: normalise_tags(tags),
+        dietary: DietaryFlags::default(),
+        images: Vec::new(),
+        timing: Timing::default(),
+        equipment: normalise_tags(cookware),
+    })
+}
+
+/// The Cooklang amount and unit for a quantity, without the trailing full stop
+/// of its display abbreviation so that [`parse_cooklang`] maps it back. The
+/// amount is written in full rather than rounded for display, so that it
+/// survives a round trip.
+fn cooklang_amount(quantity: &Quantity) -> String {
+    match quantity {
+        Quantity::Count(count) if *count == 0.0 => String::new(),
+        Quantity::Count(count) => count.to_string(),
+        Quantity::Other { amount, unit } if unit.is_empty() => amount.clone(),
+        Quantity::Other { amount, unit } => format!("{amount}%{unit}"),
+        quantity => format!(
+            "{}%{}",
+            quantity.value(),
+            quantity.abbreviation().trim_end_matches('.')
+        ),
+    }
+}
+
+fn ingredient_marker(ingredient: &Ingredient) -> String {
+    let mut marker = format!(
+        "@{}{{{}}}",
+        ingredient.name,
+        cooklang_amount(&ingredient.quantity)
+    );
+    if let Some(preparation) = &ingredient.preparation {
+        marker.push_str(&format!("({preparation})"));
+    }
+    marker
+}
+
+fn cookware_marker(name: &str) -> String {
+    format!("#{name}{{}}")
+}
+
+enum Segment {
+    Text(String),
+    Marker(String),
+}
+
+/// Finds `name` in `text` as a whole word, ignoring case.
+fn find_word(text: &str, name: &str) -> Option<usize> {
+    let lowercase_text = text.to_lowercase();
+    let lowercase_name = name.to_lowercase();
+    if lowercase_name.is_empty() || lowercase_text.len() != text.len() {
+        return None;
+    }
+    lowercase_text
+        .match_indices(&lowercase_name)
+        .map(|(position, _)| position)
+        .find(|position| {
+            let before = text[..*position].chars().next_back();
+            let after = text[position + name.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+}
+
+/// Replaces the first mention of `name` in the steps with `marker`.
+fn place_marker(steps: &mut [Vec<Segment>], name: &str, marker: String) -> bool {
+    for step in steps.iter_mut() {
+        for index in 0..step.len() {
+            let Segment::Text(text) = &step[index] else {
+                continue;
+            };
+            let Some(position) = find_word(text, name) else {
+                continue;
+            };
+            let before = text[..position].to_string();
+            let after = text[position + name.len()..].to_string();
+            step.splice(
+                index..=index,
+                [
+                    Segment::Text(before),
+                    Segment::Marker(marker),
+                    Segment::Text(after),
+                ],
+            );
+            return true;
+        }
+    }
+    false
+}
+
+/// Writes a recipe as Cooklang. Each ingredient and piece of equipment is
+/// marked where the method first mentions it; those the method doesn't mention
+/// are listed in a step of their own at the start. Method headers become
+/// sections and the summary becomes notes.
+pub fn to_cooklang(recipe: &Recipe) -> String {
+    let mut header = vec![format!(">> title: {}", recipe.name)];
+    if let Some(servings) = recipe.servings {
+        header.push(format!(">> servings: {servings}"));
+    }
+    if !recipe.tags.is_empty() {
+        header.push(format!(">> {TAGS_KEY}: {}", recipe.tags.join(", ")));
+    }
+    let lines = recipe
+        .description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let has_list = lines.iter().any(|line| list_item(line).is_some());
+    let mut notes = Vec::new();
+    let mut sections = Vec::new();
+    let mut steps = Vec::new();
+    for line in lines {
+        if line.starts_with(">>") {
+            header.push(line.to_string());
+        } else if line.starts_with('#') {
+            sections.push((steps.len(), line.trim_start_matches('#').trim()));
+        } else if let Some(step) = list_item(line).or((!has_list).then_some(line)) {
+            steps.push(vec![Segment::Text(step.to_string())]);
+        } else {
+            notes.push(format!("> {}", line.trim_start_matches('>').trim()));
+        }
+    }
+
+    let unplaced = recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| (ingredient.name.as_str(), ingredient_marker(ingredient)))
+        .chain(
+            recipe
+                .equipment
+                .iter()
+                .map(|name| (name.as_str(), cookware_marker(name))),
+        )
+        .filter_map(|(name, marker)| {
+            (!place_marker(&mut steps, name, marker.clone())).then_some(marker)
+        })
+        .collect::<Vec<_>>();
+
+    let mut blocks = vec![header.join("\n")];
+    if !notes.is_empty() {
+        blocks.push(notes.join("\n"));
+    }
+    if !unplaced.is_empty() {
+        blocks.push(unplaced.join(", "));
+    }
+    for (index, step) in steps.into_iter().enumerate() {
+        for (_, section) in sections.iter().filter(|(start, _)| *start == index) {
+            blocks.push(format!("= {section}"));
+        }
+        let step = step
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) | Segment::Marker(text) => text,
+            })
+            .collect::<String>();
+        blocks.push(step);
+    }
+    let mut cooklang = blocks.join("\n\n");
+    cooklang.push('\n');
+    cooklang
+}
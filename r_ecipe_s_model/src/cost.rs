@@ -0,0 +1,169 @@
+use crate::catalogue::{entry_of, CatalogueEntryWithId};
+use crate::nutrition::{match_food, FoodWithId};
+use crate::plan::iso_date;
+use crate::shopping::ShoppingList;
+use crate::{Ingredient, Quantity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::Date;
+
+/// What an amount of an ingredient in the catalogue cost to buy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Price {
+    pub catalogue_id: i64,
+    /// The amount bought, e.g. a 1.5 kg bag.
+    pub per: Quantity,
+    pub price: f64,
+    /// An ISO 4217 code, such as `GBP`.
+    pub currency: String,
+    #[serde(with = "iso_date")]
+    pub date: Date,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceWithId {
+    pub id: i64,
+    pub data: Price,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Cost {
+    pub amount: f64,
+    pub currency: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unpriced {
+    /// The ingredient isn't in the catalogue, or has no price.
+    NoPrice,
+    /// The amount can't be converted to the unit the price is for.
+    NoConversion,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnpricedIngredient {
+    pub name: String,
+    pub reason: Unpriced,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PricedIngredient {
+    pub name: String,
+    pub cost: Cost,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CostEstimate {
+    /// For everything priced, one total for each currency the prices are in.
+    pub total: Vec<Cost>,
+    /// For one serving, when the recipe says how many it serves.
+    pub per_serving: Option<Vec<Cost>>,
+    pub priced: Vec<PricedIngredient>,
+    /// Ingredients left out of the totals.
+    pub unpriced: Vec<UnpricedIngredient>,
+}
+
+/// Everything needed to price ingredients: the catalogue to find them in,
+/// their prices, and the nutrition table's weights to convert between
+/// volumes, weights and counts.
+#[derive(Debug, Clone, Default)]
+pub struct PriceBook {
+    pub catalogue: Vec<CatalogueEntryWithId>,
+    pub prices: Vec<PriceWithId>,
+    pub foods: Vec<FoodWithId>,
+    pub mappings: HashMap<String, i64>,
+}
+
+impl PriceBook {
+    /// The most recent price of the catalogue entry.
+    pub fn latest_price(&self, catalogue_id: i64) -> Option<&PriceWithId> {
+        self.prices
+            .iter()
+            .filter(|price| price.data.catalogue_id == catalogue_id)
+            .max_by_key(|price| (price.data.date, price.id))
+    }
+
+    /// How many of the price's amounts `quantity` is: directly when both are
+    /// measured the same way, or else by weighing both as the food.
+    fn units_of(&self, ingredient: &Ingredient, per: &Quantity) -> Option<f64> {
+        if let Some(ratio) = ingredient.quantity.ratio(per) {
+            return Some(ratio);
+        }
+        let food = match_food(&ingredient.name, &self.foods, &self.mappings)?;
+        let grams = food.data.grams(&ingredient.quantity)?;
+        let per_grams = food.data.grams(per)?;
+        (per_grams > 0.0).then(|| grams / per_grams)
+    }
+
+    fn cost(&self, ingredient: &Ingredient) -> Result<Cost, Unpriced> {
+        let price = entry_of(ingredient, &self.catalogue)
+            .and_then(|entry| self.latest_price(entry.id))
+            .ok_or(Unpriced::NoPrice)?;
+        let units = self
+            .units_of(ingredient, &price.data.per)
+            .ok_or(Unpriced::NoConversion)?;
+        Ok(Cost {
+            amount: units * price.data.price,
+            currency: price.data.currency.clone(),
+        })
+    }
+
+    /// Adds up what the ingredients cost at their latest prices. Ingredients
+    /// without an amount, such as salt to taste, are left out.
+    pub fn estimate(&self, ingredients: &[Ingredient], servings: Option<u32>) -> CostEstimate {
+        let mut estimate = CostEstimate::default();
+        for ingredient in ingredients {
+            if ingredient.quantity.value() <= 0.0 {
+                continue;
+            }
+            let name = ingredient.name.trim().to_string();
+            match self.cost(ingredient) {
+                Ok(cost) => {
+                    add_cost(&mut estimate.total, &cost);
+                    estimate.priced.push(PricedIngredient { name, cost });
+                }
+                Err(reason) => estimate.unpriced.push(UnpricedIngredient { name, reason }),
+            }
+        }
+        estimate.per_serving = servings.filter(|servings| *servings > 0).map(|servings| {
+            estimate
+                .total
+                .iter()
+                .map(|cost| Cost {
+                    amount: cost.amount / f64::from(servings),
+                    currency: cost.currency.clone(),
+                })
+                .collect()
+        });
+        estimate
+    }
+}
+
+fn add_cost(totals: &mut Vec<Cost>, cost: &Cost) {
+    match totals
+        .iter_mut()
+        .find(|total| total.currency == cost.currency)
+    {
+        Some(total) => total.amount += cost.amount,
+        None => totals.push(cost.clone()),
+    }
+}
+
+impl ShoppingList {
+    /// The amounts to buy as ingredients, one for each of an item's
+    /// quantities.
+    pub fn ingredients(&self) -> Vec<Ingredient> {
+        self.items
+            .iter()
+            .flat_map(|item| {
+                item.data.quantities.iter().map(|quantity| Ingredient {
+                    name: item.data.name.clone(),
+                    quantity: quantity.clone(),
+                    preparation: None,
+                    catalogue_id: None,
+                    component: None,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,436 @@
+use crate::nutrition::{known_food, singular, FoodWithId};
+use crate::Ingredient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Allergen {
+    Gluten,
+    Nuts,
+    Dairy,
+    Egg,
+    Shellfish,
+    Soy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Diet {
+    Vegetarian,
+    Vegan,
+}
+
+impl Allergen {
+    pub const ALL: [Allergen; 6] = [
+        Allergen::Gluten,
+        Allergen::Nuts,
+        Allergen::Dairy,
+        Allergen::Egg,
+        Allergen::Shellfish,
+        Allergen::Soy,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Allergen::Gluten => "gluten",
+            Allergen::Nuts => "nuts",
+            Allergen::Dairy => "dairy",
+            Allergen::Egg => "egg",
+            Allergen::Shellfish => "shellfish",
+            Allergen::Soy => "soy",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Allergen> {
+        let name = name.trim();
+        Allergen::ALL
+            .into_iter()
+            .find(|allergen| allergen.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Keywords in an ingredient's name that mean it contains the allergen,
+    /// and phrases that mean it doesn't after all, as `coconut milk` isn't
+    /// dairy.
+    fn keywords(&self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            Allergen::Gluten => (
+                &[
+                    "flour",
+                    "wheat",
+                    "bread",
+                    "breadcrumbs",
+                    "panko",
+                    "pasta",
+                    "spaghetti",
+                    "penne",
+                    "macaroni",
+                    "fusilli",
+                    "linguine",
+                    "tagliatelle",
+                    "lasagne",
+                    "lasagna",
+                    "orzo",
+                    "gnocchi",
+                    "noodles",
+                    "udon",
+                    "couscous",
+                    "bulgur",
+                    "barley",
+                    "rye",
+                    "spelt",
+                    "farro",
+                    "semolina",
+                    "seitan",
+                    "tortilla",
+                    "pitta",
+                    "pita",
+                    "naan",
+                    "bagel",
+                    "croissant",
+                    "crackers",
+                    "biscuits",
+                    "cookies",
+                    "cake",
+                    "pastry",
+                    "filo",
+                    "phyllo",
+                    "soy sauce",
+                    "beer",
+                    "malt",
+                ],
+                &[
+                    "gluten free",
+                    "rice flour",
+                    "rice noodles",
+                    "almond flour",
+                    "coconut flour",
+                    "chickpea flour",
+                    "gram flour",
+                    "buckwheat flour",
+                    "tapioca flour",
+                    "potato flour",
+                    "corn tortilla",
+                ],
+            ),
+            Allergen::Nuts => (
+                &[
+                    "nuts",
+                    "almonds",
+                    "walnuts",
+                    "pecans",
+                    "cashews",
+                    "hazelnuts",
+                    "pistachios",
+                    "peanuts",
+                    "macadamias",
+                    "marzipan",
+                    "praline",
+                    "satay",
+                ],
+                &["nut free"],
+            ),
+            Allergen::Dairy => (
+                &[
+                    "milk",
+                    "buttermilk",
+                    "butter",
+                    "cream",
+                    "cheese",
+                    "cheddar",
+                    "parmesan",
+                    "mozzarella",
+                    "feta",
+                    "ricotta",
+                    "mascarpone",
+                    "halloumi",
+                    "brie",
+                    "gouda",
+                    "gruyere",
+                    "paneer",
+                    "yogurt",
+                    "yoghurt",
+                    "ghee",
+                    "creme fraiche",
+                    "custard",
+                    "whey",
+                    "kefir",
+                    "quark",
+                ],
+                &[
+                    "dairy free",
+                    "non dairy",
+                    "vegan",
+                    "plant based",
+                    "coconut milk",
+                    "coconut cream",
+                    "almond milk",
+                    "oat milk",
+                    "soy milk",
+                    "soya milk",
+                    "rice milk",
+                    "cashew milk",
+                    "peanut butter",
+                    "almond butter",
+                    "cashew butter",
+                    "nut butter",
+                    "cocoa butter",
+                    "apple butter",
+                    "cream of tartar",
+                ],
+            ),
+            Allergen::Egg => (
+                &["eggs", "mayonnaise", "mayo", "meringue", "aioli"],
+                &["egg free", "vegan"],
+            ),
+            Allergen::Shellfish => (
+                &[
+                    "prawns",
+                    "shrimp",
+                    "crab",
+                    "lobster",
+                    "crayfish",
+                    "langoustines",
+                    "scampi",
+                    "mussels",
+                    "clams",
+                    "oysters",
+                    "scallops",
+                    "cockles",
+                ],
+                &["oyster mushrooms"],
+            ),
+            Allergen::Soy => (
+                &[
+                    "soy", "soya", "soybeans", "tofu", "edamame", "miso", "tempeh", "tamari",
+                ],
+                &[],
+            ),
+        }
+    }
+}
+
+impl Diet {
+    pub const ALL: [Diet; 2] = [Diet::Vegetarian, Diet::Vegan];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Diet::Vegetarian => "vegetarian",
+            Diet::Vegan => "vegan",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Diet> {
+        let name = name.trim();
+        Diet::ALL
+            .into_iter()
+            .find(|diet| diet.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Display for Allergen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Display for Diet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Meat and fish, which no vegetarian recipe calls for.
+const MEAT: &[&str] = &[
+    "meat",
+    "chicken",
+    "beef",
+    "pork",
+    "bacon",
+    "ham",
+    "lamb",
+    "mutton",
+    "veal",
+    "venison",
+    "rabbit",
+    "turkey",
+    "duck",
+    "goose",
+    "sausages",
+    "chorizo",
+    "salami",
+    "pepperoni",
+    "prosciutto",
+    "pancetta",
+    "mince",
+    "steak",
+    "meatballs",
+    "gelatin",
+    "gelatine",
+    "lard",
+    "suet",
+    "fish",
+    "anchovies",
+    "salmon",
+    "tuna",
+    "cod",
+    "haddock",
+    "mackerel",
+    "sardines",
+    "trout",
+    "halibut",
+    "tilapia",
+    "squid",
+    "octopus",
+    "caviar",
+    "worcestershire",
+];
+const MEAT_EXCEPTIONS: &[&str] = &[
+    "vegetarian",
+    "vegan",
+    "meat free",
+    "plant based",
+    "duck eggs",
+    "fish free",
+];
+/// Animal products other than meat, which no vegan recipe calls for.
+const ANIMAL_PRODUCTS: &[&str] = &["honey", "beeswax"];
+
+/// Lowercase words of a name, in the singular.
+fn words(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(singular)
+        .collect()
+}
+
+/// Whether any of the phrases appears in the name as whole words.
+fn mentions(name: &[String], phrases: &[&str]) -> bool {
+    phrases.iter().any(|phrase| {
+        let phrase = words(phrase);
+        !phrase.is_empty() && name.windows(phrase.len()).any(|window| window == phrase)
+    })
+}
+
+/// Whether the name mentions a keyword, unless it mentions an exception.
+fn contains(name: &[String], keywords: &[&str], exceptions: &[&str]) -> bool {
+    mentions(name, keywords) && !mentions(name, exceptions)
+}
+
+/// What a recipe's ingredients say about who can eat it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DietaryFlags {
+    pub allergens: Vec<Allergen>,
+    pub diets: Vec<Diet>,
+}
+
+impl DietaryFlags {
+    pub fn allergen_names(&self) -> Vec<String> {
+        self.allergens
+            .iter()
+            .map(|allergen| allergen.to_string())
+            .collect()
+    }
+
+    pub fn diet_names(&self) -> Vec<String> {
+        self.diets.iter().map(|diet| diet.to_string()).collect()
+    }
+
+    /// Reads flags as stored, skipping names it doesn't know.
+    pub fn from_names<S: AsRef<str>>(allergens: &[S], diets: &[S]) -> DietaryFlags {
+        DietaryFlags {
+            allergens: allergens
+                .iter()
+                .filter_map(|name| Allergen::parse(name.as_ref()))
+                .collect(),
+            diets: diets
+                .iter()
+                .filter_map(|name| Diet::parse(name.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+/// Flags a recipe for the allergens its ingredients contain and the diets it
+/// suits. Each ingredient is judged by its name, and by the name of the food
+/// it is mapped to or named after in the nutrition table, so that mapping
+/// `queso` to `feta` flags it as dairy.
+pub fn dietary_flags(
+    ingredients: &[Ingredient],
+    foods: &[FoodWithId],
+    mappings: &HashMap<String, i64>,
+) -> DietaryFlags {
+    let names = ingredients
+        .iter()
+        .flat_map(|ingredient| {
+            let food = known_food(&ingredient.name, foods, mappings);
+            std::iter::once(words(&ingredient.name)).chain(food.map(|food| words(&food.data.name)))
+        })
+        .collect::<Vec<_>>();
+    let allergens = Allergen::ALL
+        .into_iter()
+        .filter(|allergen| {
+            let (keywords, exceptions) = allergen.keywords();
+            names
+                .iter()
+                .any(|name| contains(name, keywords, exceptions))
+        })
+        .collect::<Vec<_>>();
+    let vegetarian = !names.iter().any(|name| {
+        contains(name, MEAT, MEAT_EXCEPTIONS)
+            || contains(
+                name,
+                Allergen::Shellfish.keywords().0,
+                Allergen::Shellfish.keywords().1,
+            )
+    });
+    let vegan = vegetarian
+        && !allergens.contains(&Allergen::Dairy)
+        && !allergens.contains(&Allergen::Egg)
+        && !names
+            .iter()
+            .any(|name| contains(name, ANIMAL_PRODUCTS, &["vegan"]));
+    let diets = [(vegetarian, Diet::Vegetarian), (vegan, Diet::Vegan)]
+        .into_iter()
+        .filter_map(|(suits, diet)| suits.then_some(diet))
+        .collect();
+    DietaryFlags { allergens, diets }
+}
+
+/// Narrows recipes to those suiting a diet and free of some allergens.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DietaryFilter {
+    #[serde(default)]
+    pub diet: Option<Diet>,
+    #[serde(default)]
+    pub free_of: Vec<Allergen>,
+}
+
+impl DietaryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.diet.is_none() && self.free_of.is_empty()
+    }
+
+    /// Reads a filter from query parameters, `free_of` being a comma
+    /// separated list of allergens.
+    pub fn from_query(diet: Option<&str>, free_of: Option<&str>) -> DietaryFilter {
+        DietaryFilter {
+            diet: diet.and_then(Diet::parse),
+            free_of: free_of
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(Allergen::parse)
+                .collect(),
+        }
+    }
+
+    pub fn free_of_query(&self) -> String {
+        self.free_of
+            .iter()
+            .map(Allergen::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
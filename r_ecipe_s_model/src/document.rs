@@ -0,0 +1,25 @@
+use crate::Recipe;
+
+pub const MARKDOWN_EXTENSION: &str = "md";
+pub const MARKDOWN_CONTENT_TYPE: &str = "text/markdown; charset=utf-8";
+
+/// Writes a recipe as a standalone markdown document: the name as title, the
+/// yield, the ingredient list and the description as the method.
+pub fn recipe_to_markdown(recipe: &Recipe) -> String {
+    let mut document = format!("# {}\n\n", recipe.name.trim());
+    if let Some(servings) = recipe.servings {
+        document.push_str(&format!("*Serves {servings}*\n\n"));
+    }
+    if !recipe.ingredients.is_empty() {
+        document.push_str("## Ingredients\n\n");
+        for ingredient in &recipe.ingredients {
+            document.push_str(&format!("- {ingredient}\n"));
+        }
+        document.push('\n');
+    }
+    let method = recipe.description.trim();
+    if !method.is_empty() {
+        document.push_str(&format!("## Method\n\n{method}\n"));
+    }
+    document
+}
@@ -0,0 +1,68 @@
+use crate::tags::{normalise_tag, split_tags};
+use crate::timing::Timing;
+use serde::{Deserialize, Serialize};
+
+/// Needed by every recipe giving an oven temperature.
+pub const OVEN: &str = "oven";
+
+/// A piece of kitchen equipment under its catalogue name, with the other
+/// names recipes call it by, such as `casserole dish` for a Dutch oven.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Equipment {
+    pub name: String,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct EquipmentWithId {
+    pub id: i64,
+    pub data: Equipment,
+    /// Number of recipes needing it.
+    #[serde(default)]
+    pub recipe_count: i64,
+}
+
+impl Equipment {
+    /// Whether the equipment goes by the name, once normalised as tags are.
+    pub fn is_called(&self, name: &str) -> bool {
+        let Some(name) = normalise_tag(name) else {
+            return false;
+        };
+        std::iter::once(&self.name)
+            .chain(&self.synonyms)
+            .any(|known| normalise_tag(known).as_ref() == Some(&name))
+    }
+}
+
+/// The equipment a recipe needs under catalogue names where it has one,
+/// normalised as tags are and without duplicates. The oven is added when the
+/// recipe gives an oven temperature.
+pub fn normalise_equipment<S: AsRef<str>>(
+    names: impl IntoIterator<Item = S>,
+    timing: &Timing,
+    catalogue: &[EquipmentWithId],
+) -> Vec<String> {
+    let oven = timing.oven.map(|_| OVEN);
+    let mut normalised: Vec<String> = Vec::new();
+    for name in names
+        .into_iter()
+        .filter_map(|name| normalise_tag(name.as_ref()))
+        .chain(oven.map(String::from))
+    {
+        let name = catalogue
+            .iter()
+            .find(|entry| entry.data.is_called(&name))
+            .and_then(|entry| normalise_tag(&entry.data.name))
+            .unwrap_or(name);
+        if !normalised.contains(&name) {
+            normalised.push(name);
+        }
+    }
+    normalised
+}
+
+/// Reads a comma separated list of equipment to do without, e.g. `oven`.
+pub fn split_equipment(list: &str) -> Vec<String> {
+    split_tags(list)
+}
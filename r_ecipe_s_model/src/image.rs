@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+/// The forms each uploaded image is kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageVariant {
+    /// As it was uploaded.
+    Original,
+    /// A small WebP, for the recipe grid.
+    Thumbnail,
+    /// A WebP no bigger than a screen, for viewing a recipe.
+    Large,
+}
+
+impl ImageVariant {
+    pub const ALL: [ImageVariant; 3] = [
+        ImageVariant::Original,
+        ImageVariant::Thumbnail,
+        ImageVariant::Large,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageVariant::Original => "original",
+            ImageVariant::Thumbnail => "thumbnail",
+            ImageVariant::Large => "large",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ImageVariant> {
+        let name = name.trim();
+        ImageVariant::ALL
+            .into_iter()
+            .find(|variant| variant.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Display for ImageVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{Ingredient, Quantity};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    MissingName(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Ingredient line is empty"),
+            ParseError::MissingName(line) => {
+                write!(f, "No ingredient name found in line: {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const GRAM_PER_OZ: f64 = 28.3495;
+const GRAM_PER_LB: f64 = 453.592;
+
+fn ounces(oz: f64) -> Quantity {
+    Quantity::Gram(oz * GRAM_PER_OZ)
+}
+
+fn pounds(lb: f64) -> Quantity {
+    Quantity::Gram(lb * GRAM_PER_LB)
+}
+
+pub(crate) type Unit = fn(f64) -> Quantity;
+
+/// Unit words recognised after the amount, matched case-insensitively with any
+/// trailing full stop removed. Imperial weights are converted to grams.
+const UNITS: &[(&str, Unit)] = &[
+    ("teaspoon", Quantity::Tsp),
+    ("teaspoons", Quantity::Tsp),
+    ("tsp", Quantity::Tsp),
+    ("tsps", Quantity::Tsp),
+    ("tablespoon", Quantity::Tbsp),
+    ("tablespoons", Quantity::Tbsp),
+    ("tbsp", Quantity::Tbsp),
+    ("tbsps", Quantity::Tbsp),
+    ("tbs", Quantity::Tbsp),
+    ("cup", Quantity::Cup),
+    ("cups", Quantity::Cup),
+    ("c", Quantity::Cup),
+    ("g", Quantity::Gram),
+    ("gr", Quantity::Gram),
+    ("gram", Quantity::Gram),
+    ("grams", Quantity::Gram),
+    ("gramme", Quantity::Gram),
+    ("grammes", Quantity::Gram),
+    ("kg", Quantity::Kg),
+    ("kgs", Quantity::Kg),
+    ("kilo", Quantity::Kg),
+    ("kilos", Quantity::Kg),
+    ("kilogram", Quantity::Kg),
+    ("kilograms", Quantity::Kg),
+    ("ml", Quantity::Ml),
+    ("millilitre", Quantity::Ml),
+    ("millilitres", Quantity::Ml),
+    ("milliliter", Quantity::Ml),
+    ("milliliters", Quantity::Ml),
+    ("l", Quantity::L),
+    ("litre", Quantity::L),
+    ("litres", Quantity::L),
+    ("liter", Quantity::L),
+    ("liters", Quantity::L),
+    ("oz", ounces),
+    ("ounce", ounces),
+    ("ounces", ounces),
+    ("lb", pounds),
+    ("lbs", pounds),
+    ("pound", pounds),
+    ("pounds", pounds),
+];
+
+const BULLETS: &[char] = &['-', '*', '•', '·'];
+
+fn unicode_fraction(c: char) -> Option<f64> {
+    let value = match c {
+        '½' => 1.0 / 2.0,
+        '⅓' => 1.0 / 3.0,
+        '⅔' => 2.0 / 3.0,
+        '¼' => 1.0 / 4.0,
+        '¾' => 3.0 / 4.0,
+        '⅕' => 1.0 / 5.0,
+        '⅖' => 2.0 / 5.0,
+        '⅗' => 3.0 / 5.0,
+        '⅘' => 4.0 / 5.0,
+        '⅙' => 1.0 / 6.0,
+        '⅚' => 5.0 / 6.0,
+        '⅛' => 1.0 / 8.0,
+        '⅜' => 3.0 / 8.0,
+        '⅝' => 5.0 / 8.0,
+        '⅞' => 7.0 / 8.0,
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | ',' | '/' | '-' | '–') || unicode_fraction(c).is_some()
+}
+
+/// Parses a single amount token: `3`, `2.5`, `2,5`, `1/2`, `½`, `1½` or a
+/// range such as `2-3`, of which the lower bound is taken.
+pub(crate) fn parse_number(token: &str) -> Option<f64> {
+    if let Some((low, high)) = token.split_once(['-', '–']) {
+        if !low.is_empty() && !high.is_empty() {
+            parse_number(high)?;
+            return parse_number(low);
+        }
+    }
+    let mut chars = token.chars();
+    let last = chars.next_back()?;
+    if let Some(fraction) = unicode_fraction(last) {
+        let whole = chars.as_str();
+        if whole.is_empty() {
+            return Some(fraction);
+        }
+        return whole
+            .parse::<u32>()
+            .ok()
+            .map(|whole| f64::from(whole) + fraction);
+    }
+    if let Some((numerator, denominator)) = token.split_once('/') {
+        let numerator = numerator.parse::<u32>().ok()?;
+        let denominator = denominator.parse::<u32>().ok()?;
+        return (denominator != 0).then(|| f64::from(numerator) / f64::from(denominator));
+    }
+    if !token.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    token.replace(',', ".").parse::<f64>().ok()
+}
+
+fn is_fraction(token: &str) -> bool {
+    token.contains('/') || token.chars().all(|c| unicode_fraction(c).is_some())
+}
+
+pub(crate) fn unit(token: &str) -> Option<Unit> {
+    let token = token.trim_end_matches('.').to_lowercase();
+    UNITS
+        .iter()
+        .find(|(name, _)| *name == token)
+        .map(|(_, unit)| *unit)
+}
+
+/// Splits tokens such as `200g` or `1½cups` into an amount and a unit.
+fn split_attached_unit(token: &str) -> Option<(&str, &str)> {
+    let split = token.find(|c: char| !is_number_char(c))?;
+    let (amount, unit) = token.split_at(split);
+    (!amount.is_empty() && unit.chars().all(char::is_alphabetic)).then_some((amount, unit))
+}
+
+/// Removes parenthesised asides such as `(about 200g)` from `text`, returning
+/// the remaining text and the asides.
+fn take_parentheticals(text: &str) -> (String, Vec<String>) {
+    let mut remaining = String::with_capacity(text.len());
+    let mut asides = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('(') {
+        let Some(close) = rest[open..].find(')') else {
+            break;
+        };
+        remaining.push_str(&rest[..open]);
+        let aside = rest[open + 1..open + close].trim();
+        if !aside.is_empty() {
+            asides.push(aside.to_string());
+        }
+        rest = &rest[open + close + 1..];
+    }
+    remaining.push_str(rest);
+    (remaining, asides)
+}
+
+/// Puts a space before every comma that isn't a decimal separator, so that
+/// `3 tbsp, melted` doesn't read `tbsp,` as the ingredient name.
+fn separate_commas(text: &str) -> String {
+    let mut separated = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ',' && !chars.peek().is_some_and(char::is_ascii_digit) {
+            separated.push(' ');
+        }
+        separated.push(c);
+    }
+    separated
+}
+
+/// Parses a free-text ingredient line such as `2 1/2 cups plain flour, sifted`
+/// into an [`Ingredient`]. Anything after the first comma, along with any
+/// parenthesised aside, becomes the preparation note. Lines without an amount
+/// get a count of zero, as a freshly added ingredient in the form does.
+pub fn parse_ingredient(line: &str) -> Result<Ingredient, ParseError> {
+    let line = line.trim().trim_start_matches(BULLETS).trim();
+    if line.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let (line_without_asides, asides) = take_parentheticals(line);
+    let line_without_asides = separate_commas(&line_without_asides);
+    let mut tokens = line_without_asides.split_whitespace().peekable();
+
+    let mut amount = None;
+    let mut attached_unit = None;
+    if let Some(first) = tokens.peek().copied() {
+        if let Some(number) = parse_number(first) {
+            amount = Some(number);
+            tokens.next();
+        } else if let Some((number, unit_str)) = split_attached_unit(first) {
+            if let (Some(number), Some(unit)) = (parse_number(number), unit(unit_str)) {
+                amount = Some(number);
+                attached_unit = Some(unit);
+                tokens.next();
+            }
+        } else if matches!(first.to_lowercase().as_str(), "a" | "an") {
+            let mut lookahead = tokens.clone();
+            lookahead.next();
+            if lookahead.next().and_then(unit).is_some() {
+                amount = Some(1.0);
+                tokens.next();
+            }
+        }
+    }
+    if let Some(whole) = amount.filter(|_| attached_unit.is_none()) {
+        if let Some(fraction) = tokens
+            .peek()
+            .filter(|token| is_fraction(token))
+            .and_then(|token| parse_number(token))
+        {
+            amount = Some(whole + fraction);
+            tokens.next();
+        }
+    }
+
+    let quantity = match amount {
+        Some(amount) => {
+            let unit = attached_unit.or_else(|| {
+                let unit = tokens.peek().and_then(|token| unit(token))?;
+                tokens.next();
+                Some(unit)
+            });
+            match unit {
+                Some(unit) => {
+                    tokens.next_if(|token| token.eq_ignore_ascii_case("of"));
+                    unit(amount)
+                }
+                None => Quantity::Count(amount),
+            }
+        }
+        None => Quantity::Count(0.0),
+    };
+
+    let rest = tokens.collect::<Vec<_>>().join(" ");
+    let (name, note) = match rest.split_once(',') {
+        Some((name, note)) => (name.trim(), Some(note.trim())),
+        None => (rest.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(ParseError::MissingName(line.to_string()));
+    }
+    let preparation = note
+        .filter(|note| !note.is_empty())
+        .map(String::from)
+        .into_iter()
+        .chain(asides)
+        .collect::<Vec<_>>();
+
+    Ok(Ingredient {
+        name: name.to_string(),
+        quantity,
+        preparation: (!preparation.is_empty()).then(|| preparation.join(", ")),
+        catalogue_id: None,
+        component: None,
+    })
+}
+
+/// Parses an ingredient line imported from elsewhere, keeping the whole line
+/// as the name of an unspecified count when it can't be parsed.
+pub fn ingredient_from_line(line: &str) -> Ingredient {
+    parse_ingredient(line).unwrap_or_else(|_| Ingredient {
+        name: line.trim().to_string(),
+        quantity: Quantity::Count(0.0),
+        preparation: None,
+        catalogue_id: None,
+        component: None,
+    })
+}
+
+/// Parses a pasted ingredient list, one ingredient per line, skipping blank
+/// lines. Each result is paired with the line it came from.
+pub fn parse_ingredients(text: &str) -> Vec<(&str, Result<Ingredient, ParseError>)> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| (line, parse_ingredient(line)))
+        .collect()
+}
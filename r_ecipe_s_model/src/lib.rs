@@ -1,6 +1,39 @@
+use component::Component;
+use diet::DietaryFlags;
 use meilisearch_sdk::document::Document;
 use serde::{Deserialize, Serialize};
 pub use serde_json;
+use std::fmt::Display;
+pub use time;
+use timing::Timing;
+
+pub mod archive;
+pub mod calendar;
+pub mod catalogue;
+pub mod component;
+pub mod cook_log;
+pub mod cooklang;
+pub mod cost;
+pub mod diet;
+pub mod document;
+pub mod equipment;
+pub mod image;
+pub mod ingredient_parser;
+pub mod mealie;
+pub mod method;
+pub mod nutrition;
+pub mod pantry;
+pub mod paprika;
+pub mod plan;
+pub mod record;
+pub mod scaling;
+pub mod schema_org;
+pub mod shopping;
+pub mod substitution;
+pub mod tags;
+pub mod timing;
+pub mod user;
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Recipe {
     pub name: String,
@@ -8,6 +41,21 @@ pub struct Recipe {
     pub description: String,
     pub liked: Option<bool>,
     pub embedding: Option<Vec<f32>>,
+    pub servings: Option<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Worked out from the ingredients when the recipe is saved.
+    #[serde(default)]
+    pub dietary: DietaryFlags,
+    /// Ids of the uploaded images, the cover first. They're added and removed
+    /// through their own endpoints, not by saving the recipe.
+    #[serde(default)]
+    pub images: Vec<i64>,
+    #[serde(default)]
+    pub timing: Timing,
+    /// Names in the equipment catalogue where they're in it.
+    #[serde(default)]
+    pub equipment: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
@@ -22,23 +70,46 @@ pub struct RecipesResponse {
     pub total_pages: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Ingredient {
     pub name: String,
     pub quantity: Quantity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preparation: Option<String>,
+    /// The entry in the ingredient catalogue this is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalogue_id: Option<i64>,
+    /// The recipe this ingredient is made by, if it's one of ours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<Component>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Eq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Quantity {
-    Count(usize),
-    Tsp(usize),
-    Gram(usize),
-    Ml(usize),
+    Count(f64),
+    Tsp(f64),
+    Tbsp(f64),
+    Cup(f64),
+    Gram(f64),
+    Kg(f64),
+    Ml(f64),
+    L(f64),
+    /// An amount and unit that couldn't be mapped onto the others, kept as
+    /// text, e.g. `2` `cloves` or `a pinch` with no unit.
+    Other {
+        amount: String,
+        unit: String,
+    },
 }
 pub const COUNT: &str = "count";
 pub const TSP: &str = "tsp";
+pub const TBSP: &str = "tbsp";
+pub const CUP: &str = "cup";
 pub const GRAM: &str = "gram";
+pub const KG: &str = "kg";
 pub const ML: &str = "ml";
+pub const L: &str = "l";
+pub const OTHER: &str = "other";
 
 fn matches_gram(quantity: &Quantity) -> bool {
     matches!(quantity, Quantity::Gram(_))
@@ -52,15 +123,35 @@ fn matches_tsp(quantity: &Quantity) -> bool {
     matches!(quantity, Quantity::Tsp(_))
 }
 
+fn matches_tbsp(quantity: &Quantity) -> bool {
+    matches!(quantity, Quantity::Tbsp(_))
+}
+
+fn matches_cup(quantity: &Quantity) -> bool {
+    matches!(quantity, Quantity::Cup(_))
+}
+
+fn matches_kg(quantity: &Quantity) -> bool {
+    matches!(quantity, Quantity::Kg(_))
+}
+
 fn matches_ml(quantity: &Quantity) -> bool {
     matches!(quantity, Quantity::Ml(_))
 }
 
-pub const MATCHERS: [(&str, for<'a> fn(&'a Quantity) -> bool); 4] = [
+fn matches_l(quantity: &Quantity) -> bool {
+    matches!(quantity, Quantity::L(_))
+}
+
+pub const MATCHERS: [(&str, for<'a> fn(&'a Quantity) -> bool); 8] = [
     (COUNT, matches_count),
     (TSP, matches_tsp),
+    (TBSP, matches_tbsp),
+    (CUP, matches_cup),
     (GRAM, matches_gram),
+    (KG, matches_kg),
     (ML, matches_ml),
+    (L, matches_l),
 ];
 
 impl Quantity {
@@ -68,24 +159,85 @@ impl Quantity {
         match self {
             Quantity::Count(_) => COUNT,
             Quantity::Tsp(_) => TSP,
+            Quantity::Tbsp(_) => TBSP,
+            Quantity::Cup(_) => CUP,
             Quantity::Gram(_) => GRAM,
+            Quantity::Kg(_) => KG,
             Quantity::Ml(_) => ML,
+            Quantity::L(_) => L,
+            Quantity::Other { .. } => OTHER,
+        }
+    }
+
+    /// The numeric amount. Free-text amounts that aren't a number count as 0.
+    pub fn value(&self) -> f64 {
+        match self {
+            Quantity::Count(count) => *count,
+            Quantity::Tsp(tsp) => *tsp,
+            Quantity::Tbsp(tbsp) => *tbsp,
+            Quantity::Cup(cup) => *cup,
+            Quantity::Gram(gram) => *gram,
+            Quantity::Kg(kg) => *kg,
+            Quantity::Ml(ml) => *ml,
+            Quantity::L(l) => *l,
+            Quantity::Other { amount, .. } => amount.trim().parse().unwrap_or(0.0),
+        }
+    }
+
+    /// Short unit suffix used when displaying a quantity. Counts have none.
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Quantity::Count(_) => "",
+            Quantity::Tsp(_) => "tsp.",
+            Quantity::Tbsp(_) => "tbsp.",
+            Quantity::Cup(_) => "cup",
+            Quantity::Gram(_) => "g",
+            Quantity::Kg(_) => "kg",
+            Quantity::Ml(_) => "ml",
+            Quantity::L(_) => "l",
+            Quantity::Other { .. } => "",
+        }
+    }
+}
+
+/// Formats an amount with at most two decimals and no trailing zeros,
+/// so that scaled quantities such as `1.4999999` display as `1.5`.
+pub fn format_amount(amount: f64) -> String {
+    let rounded = (amount * 100.0).round() / 100.0;
+    format!("{rounded}")
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Quantity::Other { amount, unit } = self {
+            return write!(f, "{}", format!("{amount} {unit}").trim());
+        }
+        let amount = format_amount(self.value());
+        match self.abbreviation() {
+            "" => write!(f, "{amount}"),
+            unit => write!(f, "{amount} {unit}"),
         }
     }
+}
 
-    pub fn value(&self) -> usize {
-        match *self {
-            Quantity::Count(count) => count,
-            Quantity::Tsp(tsp) => tsp,
-            Quantity::Gram(gram) => gram,
-            Quantity::Ml(ml) => ml,
+/// Formats the ingredient as a single line, e.g. `2.5 cup plain flour, sifted`,
+/// that the ingredient parser reads back. Unspecified counts are left out.
+impl Display for Ingredient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.quantity != Quantity::Count(0.0) {
+            write!(f, "{} ", self.quantity)?;
         }
+        write!(f, "{}", self.name)?;
+        if let Some(preparation) = &self.preparation {
+            write!(f, ", {preparation}")?;
+        }
+        Ok(())
     }
 }
 
 impl Default for Quantity {
     fn default() -> Self {
-        Quantity::Count(0)
+        Quantity::Count(0.0)
     }
 }
 
@@ -97,9 +249,83 @@ impl Document for RecipeWithId {
     }
 }
 
+/// Outcome of importing a batch of recipes, one result per source file or
+/// record, in the order they were read.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ImportReport {
+    pub results: Vec<ImportResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ImportResult {
+    pub source: String,
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ImportOutcome {
+    Imported { id: i64, name: String },
+    Updated { id: i64, name: String },
+    Failed { error: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Tag {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct TagWithId {
+    pub id: i64,
+    pub data: Tag,
+    /// Number of recipes with the tag.
+    #[serde(default)]
+    pub recipe_count: i64,
+}
+
+/// A named, ordered group of recipes, such as the dishes for one occasion.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Collection {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Recipes in the collection, in order.
+    #[serde(default)]
+    pub entries: Vec<CollectionEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollectionEntry {
+    pub recipe_id: i64,
+    /// Note on the recipe for this collection, e.g. "double for 12 guests".
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollectionWithId {
+    pub id: i64,
+    pub data: Collection,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchQuery {
     pub query: String,
+    /// Only return recipes with this tag.
+    pub tag: Option<String>,
+    /// Only return recipes suiting this diet.
+    #[serde(default)]
+    pub diet: Option<String>,
+    /// Only return recipes free of these comma separated allergens.
+    #[serde(default)]
+    pub free_of: Option<String>,
+    /// Only return recipes taking at most this many minutes in total.
+    #[serde(default)]
+    pub max_minutes: Option<u32>,
+    /// Only return recipes needing none of this comma separated equipment,
+    /// e.g. `oven`.
+    #[serde(default)]
+    pub without_equipment: Option<String>,
     // todo: limit + offset
 }
 
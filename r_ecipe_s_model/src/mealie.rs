@@ -0,0 +1,187 @@
+use crate::diet::DietaryFlags;
+use crate::ingredient_parser::ingredient_from_line;
+use crate::record::{description, field_text, numbered_steps, unmapped_notes, RecordError};
+use crate::schema_org::servings;
+use crate::tags::normalise_tags;
+use crate::timing::Timing;
+use crate::{Ingredient, Recipe};
+use serde_json::Value;
+
+const MAPPED: &[&str] = &[
+    "name",
+    "description",
+    "recipeYield",
+    "recipeServings",
+    "recipeIngredient",
+    "recipeInstructions",
+    "notes",
+    "tags",
+    "recipeCategory",
+    "tools",
+];
+
+/// Identifiers, timestamps and settings that mean nothing outside Mealie.
+const IGNORED: &[&str] = &[
+    "id",
+    "slug",
+    "image",
+    "userId",
+    "groupId",
+    "householdId",
+    "dateAdded",
+    "dateUpdated",
+    "createdAt",
+    "updatedAt",
+    "update_at",
+    "settings",
+    "assets",
+    "comments",
+    "extras",
+    "recipeYieldQuantity",
+];
+
+/// The recipe records in a Mealie JSON export, which may hold a single recipe,
+/// a list of them or a `recipes` list.
+pub fn mealie_records(export: &Value) -> Vec<&Value> {
+    match export {
+        Value::Array(records) => records.iter().collect(),
+        Value::Object(object) => match object.get("recipes") {
+            Some(Value::Array(records)) => records.iter().collect(),
+            _ => vec![export],
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn name_of(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("name").and_then(field_text),
+        value => field_text(value),
+    }
+}
+
+/// Reads a Mealie ingredient, preferring the line as originally written over
+/// Mealie's own parse of it.
+fn ingredient(value: &Value) -> Option<Ingredient> {
+    let Value::Object(object) = value else {
+        return field_text(value).map(|line| ingredient_from_line(&line));
+    };
+    if let Some(line) = ["originalText", "display"]
+        .iter()
+        .find_map(|key| object.get(*key).and_then(field_text))
+    {
+        return Some(ingredient_from_line(&line));
+    }
+    let amount = object
+        .get("quantity")
+        .and_then(Value::as_f64)
+        .filter(|quantity| *quantity > 0.0)
+        .map(|quantity| quantity.to_string());
+    let line = [
+        amount,
+        object.get("unit").and_then(name_of),
+        object.get("food").and_then(name_of),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+    let line = match object.get("note").and_then(field_text) {
+        Some(note) if line.is_empty() => note,
+        Some(note) => format!("{line}, {note}"),
+        None => line,
+    };
+    (!line.is_empty()).then(|| ingredient_from_line(&line))
+}
+
+/// Mealie instructions are a list of steps, each with text and an optional
+/// title that starts a new section.
+fn method(instructions: &Value) -> Vec<String> {
+    let Value::Array(steps) = instructions else {
+        return instructions
+            .as_str()
+            .map(numbered_steps)
+            .unwrap_or_default();
+    };
+    let mut method = Vec::new();
+    for step in steps {
+        if let Some(title) = step.get("title").and_then(field_text) {
+            method.push(format!("### {title}"));
+        }
+        let text = match step {
+            Value::Object(object) => object.get("text").and_then(field_text),
+            step => field_text(step),
+        };
+        method.extend(text.as_deref().map(numbered_steps).unwrap_or_default());
+    }
+    method
+}
+
+/// Converts a recipe from a Mealie JSON export into a [`Recipe`]. Ingredients
+/// go through the ingredient parser, tags and categories become tags and tools
+/// become equipment. Mealie's notes, and any field without a counterpart such
+/// as times or the original URL, are listed under notes.
+pub fn recipe_from_mealie(record: &Value) -> Result<Recipe, RecordError> {
+    let record = record.as_object().ok_or(RecordError::NotAnObject)?;
+    let name = record
+        .get("name")
+        .and_then(field_text)
+        .ok_or(RecordError::MissingName)?;
+    let ingredients = match record.get("recipeIngredient") {
+        Some(Value::Array(ingredients)) => ingredients.iter().filter_map(ingredient).collect(),
+        _ => Vec::new(),
+    };
+    let method = record
+        .get("recipeInstructions")
+        .map(method)
+        .unwrap_or_default();
+    let mut notes = match record.get("notes") {
+        Some(Value::Array(notes)) => notes
+            .iter()
+            .filter_map(|note| {
+                let text = note.get("text").and_then(field_text)?;
+                Some(match note.get("title").and_then(field_text) {
+                    Some(title) => format!("**{title}**: {text}"),
+                    None => text,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    notes.extend(unmapped_notes(record, MAPPED, IGNORED));
+    let servings = record
+        .get("recipeServings")
+        .and_then(servings)
+        .or_else(|| record.get("recipeYield").and_then(servings));
+
+    Ok(Recipe {
+        name,
+        ingredients,
+        description: description(
+            record.get("description").and_then(field_text),
+            &method,
+            &notes,
+        ),
+        liked: None,
+        embedding: None,
+        servings,
+        tags: normalise_tags(
+            ["tags", "recipeCategory"]
+                .iter()
+                .filter_map(|key| record.get(*key)?.as_array())
+                .flatten()
+                .filter_map(name_of),
+        ),
+        dietary: DietaryFlags::default(),
+        images: Vec::new(),
+        timing: Timing::default(),
+        equipment: normalise_tags(
+            record
+                .get("tools")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(name_of),
+        ),
+    })
+}
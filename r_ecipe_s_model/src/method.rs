@@ -0,0 +1,39 @@
+/// Returns the text of a markdown list item such as `- Stir` or `2. Stir`.
+pub fn list_item(line: &str) -> Option<&str> {
+    if let Some(item) = line.strip_prefix(['-', '*', '+']) {
+        return item.starts_with(' ').then(|| item.trim());
+    }
+    let without_number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if without_number.len() == line.len() {
+        return None;
+    }
+    without_number
+        .strip_prefix(['.', ')'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Splits a markdown method into a summary and its steps. When the method
+/// contains a list, its items are the steps and the remaining paragraphs the
+/// summary. Otherwise every non-empty line is a step. Headers are dropped.
+pub fn split_method(description: &str) -> (Option<String>, Vec<&str>) {
+    let lines = description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    let has_list = lines.iter().any(|line| list_item(line).is_some());
+    let (steps, summary) = if has_list {
+        lines
+            .into_iter()
+            .partition::<Vec<_>, _>(|line| list_item(line).is_some())
+    } else {
+        (lines, Vec::new())
+    };
+    let steps = steps
+        .into_iter()
+        .map(|step| list_item(step).unwrap_or(step))
+        .collect();
+    let summary = (!summary.is_empty()).then(|| summary.join("\n"));
+    (summary, steps)
+}
@@ -0,0 +1,252 @@
+use crate::shopping::ingredient_key;
+use crate::{Quantity, Recipe};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Add, AddAssign};
+
+const ML_PER_TSP: f64 = 4.929;
+const ML_PER_TBSP: f64 = 14.787;
+const ML_PER_CUP: f64 = 236.588;
+/// Grams per millilitre assumed for foods without a known density.
+const DEFAULT_DENSITY: f64 = 1.0;
+/// Free-text units that count whole pieces, as in `2 cloves garlic`.
+const PIECE_UNITS: [&str; 8] = [
+    "clove", "cloves", "piece", "pieces", "slice", "slices", "whole", "",
+];
+
+/// Nutrients in an amount of food. Energy is in kcal, sodium in mg and the
+/// rest in grams.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Nutrients {
+    pub kcal: f64,
+    pub protein: f64,
+    pub fat: f64,
+    pub carbs: f64,
+    pub fibre: f64,
+    pub sodium: f64,
+}
+
+impl Nutrients {
+    pub fn scaled(self, factor: f64) -> Nutrients {
+        Nutrients {
+            kcal: self.kcal * factor,
+            protein: self.protein * factor,
+            fat: self.fat * factor,
+            carbs: self.carbs * factor,
+            fibre: self.fibre * factor,
+            sodium: self.sodium * factor,
+        }
+    }
+}
+
+impl Add for Nutrients {
+    type Output = Nutrients;
+
+    fn add(self, other: Nutrients) -> Nutrients {
+        Nutrients {
+            kcal: self.kcal + other.kcal,
+            protein: self.protein + other.protein,
+            fat: self.fat + other.fat,
+            carbs: self.carbs + other.carbs,
+            fibre: self.fibre + other.fibre,
+            sodium: self.sodium + other.sodium,
+        }
+    }
+}
+
+impl AddAssign for Nutrients {
+    fn add_assign(&mut self, other: Nutrients) {
+        *self = *self + other;
+    }
+}
+
+/// An entry of the nutrition table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Food {
+    pub name: String,
+    /// Other names the food goes by in recipes.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub per_100g: Nutrients,
+    /// Grams per millilitre, for foods measured by volume.
+    #[serde(default)]
+    pub density: Option<f64>,
+    /// Grams in one piece, for foods counted.
+    #[serde(default)]
+    pub unit_weight: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FoodWithId {
+    pub id: i64,
+    pub data: Food,
+}
+
+/// A food chosen by hand for an ingredient name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct IngredientMapping {
+    pub ingredient: String,
+    pub food_id: i64,
+}
+
+impl Food {
+    /// The weight of `quantity` of the food, when it can be worked out.
+    pub fn grams(&self, quantity: &Quantity) -> Option<f64> {
+        let density = self.density.unwrap_or(DEFAULT_DENSITY);
+        match quantity {
+            Quantity::Gram(gram) => Some(*gram),
+            Quantity::Kg(kg) => Some(kg * 1000.0),
+            Quantity::Ml(ml) => Some(ml * density),
+            Quantity::L(l) => Some(l * 1000.0 * density),
+            Quantity::Tsp(tsp) => Some(tsp * ML_PER_TSP * density),
+            Quantity::Tbsp(tbsp) => Some(tbsp * ML_PER_TBSP * density),
+            Quantity::Cup(cup) => Some(cup * ML_PER_CUP * density),
+            Quantity::Count(count) => Some(count * self.unit_weight?),
+            Quantity::Other { unit, .. } => {
+                let unit = unit.trim().to_lowercase();
+                PIECE_UNITS
+                    .contains(&unit.as_str())
+                    .then_some(quantity.value() * self.unit_weight?)
+            }
+        }
+    }
+}
+
+/// The singular of a word in an ingredient's name, near enough to match
+/// `tomatoes` with `tomato` and `berries` with `berry`.
+pub(crate) fn singular(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = word.strip_suffix("oes") {
+        format!("{stem}o")
+    } else if word.ends_with("ss") || word.len() <= 3 {
+        word.to_string()
+    } else {
+        word.strip_suffix('s').unwrap_or(word).to_string()
+    }
+}
+
+/// Matches recipes' ingredient names to foods: `Tomatoes` and `tomato` are
+/// one food, so are `plain flour` and `flour`.
+pub(crate) fn food_key(name: &str) -> String {
+    let key = ingredient_key(name);
+    match key.rsplit_once(' ') {
+        Some((rest, last)) => format!("{rest} {}", singular(last)),
+        None => singular(&key),
+    }
+}
+
+fn food_names(food: &FoodWithId) -> impl Iterator<Item = String> + '_ {
+    std::iter::once(&food.data.name)
+        .chain(&food.data.aliases)
+        .map(|name| food_key(name))
+}
+
+/// The food mapped to an ingredient by hand, or else the food going by the
+/// ingredient's name.
+pub(crate) fn known_food<'a>(
+    name: &str,
+    foods: &'a [FoodWithId],
+    mappings: &HashMap<String, i64>,
+) -> Option<&'a FoodWithId> {
+    if let Some(food_id) = mappings.get(&ingredient_key(name)) {
+        return foods.iter().find(|food| food.id == *food_id);
+    }
+    let key = food_key(name);
+    foods
+        .iter()
+        .find(|food| food_names(food).any(|food_name| food_name == key))
+}
+
+/// Finds the food for an ingredient: the one mapped to it by hand, or the
+/// food whose name or alias it is, or else the most specific one it is a
+/// kind of, as `extra virgin olive oil` is `olive oil`.
+pub fn match_food<'a>(
+    name: &str,
+    foods: &'a [FoodWithId],
+    mappings: &HashMap<String, i64>,
+) -> Option<&'a FoodWithId> {
+    if let Some(food) = known_food(name, foods, mappings) {
+        return Some(food);
+    }
+    let key = food_key(name);
+    foods
+        .iter()
+        .flat_map(|food| food_names(food).map(move |food_name| (food_name, food)))
+        .filter(|(food_name, _)| key.ends_with(&format!(" {food_name}")))
+        .max_by_key(|(food_name, _)| food_name.len())
+        .map(|(_, food)| food)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unmatched {
+    /// No food in the table goes by the ingredient's name.
+    NoFood,
+    /// The food is known but its amount can't be turned into grams.
+    NoWeight,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnmatchedIngredient {
+    pub name: String,
+    pub reason: Unmatched,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MatchedIngredient {
+    pub name: String,
+    pub food: String,
+    pub grams: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RecipeNutrition {
+    /// For the whole recipe.
+    pub total: Nutrients,
+    /// For one serving, when the recipe says how many it serves.
+    pub per_serving: Option<Nutrients>,
+    pub matched: Vec<MatchedIngredient>,
+    /// Ingredients left out of the totals.
+    pub unmatched: Vec<UnmatchedIngredient>,
+}
+
+/// Adds up the nutrients of the recipe's ingredients. Ingredients without
+/// an amount, such as salt to taste, are left out.
+pub fn recipe_nutrition(
+    recipe: &Recipe,
+    foods: &[FoodWithId],
+    mappings: &HashMap<String, i64>,
+) -> RecipeNutrition {
+    let mut nutrition = RecipeNutrition::default();
+    for ingredient in &recipe.ingredients {
+        if ingredient.quantity.value() <= 0.0 {
+            continue;
+        }
+        let name = ingredient.name.trim().to_string();
+        let Some(food) = match_food(&name, foods, mappings) else {
+            nutrition.unmatched.push(UnmatchedIngredient {
+                name,
+                reason: Unmatched::NoFood,
+            });
+            continue;
+        };
+        let Some(grams) = food.data.grams(&ingredient.quantity) else {
+            nutrition.unmatched.push(UnmatchedIngredient {
+                name,
+                reason: Unmatched::NoWeight,
+            });
+            continue;
+        };
+        nutrition.total += food.data.per_100g.scaled(grams / 100.0);
+        nutrition.matched.push(MatchedIngredient {
+            name,
+            food: food.data.name.clone(),
+            grams,
+        });
+    }
+    nutrition.per_serving = recipe
+        .servings
+        .filter(|servings| *servings > 0)
+        .map(|servings| nutrition.total.scaled(1.0 / f64::from(servings)));
+    nutrition
+}
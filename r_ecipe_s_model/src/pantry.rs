@@ -0,0 +1,112 @@
+use crate::plan::iso_date;
+use crate::shopping::ingredient_key;
+use crate::{Ingredient, Quantity, RecipeWithId};
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+/// Something kept in the pantry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PantryItem {
+    pub name: String,
+    pub quantity: Quantity,
+    #[serde(default, with = "iso_date::option")]
+    pub expires: Option<Date>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PantryItemWithId {
+    pub id: i64,
+    pub data: PantryItem,
+}
+
+/// A recipe that was cooked, scaled to `servings` when given.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CookedRecipe {
+    pub recipe_id: i64,
+    #[serde(default)]
+    pub servings: Option<u32>,
+}
+
+/// How cooking a recipe changed the pantry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PantryUse {
+    /// Items with some left, at their new quantity.
+    pub updated: Vec<PantryItemWithId>,
+    /// Items that ran out.
+    pub used_up: Vec<PantryItemWithId>,
+    /// Ingredients, or what was left of them, that the pantry didn't have.
+    pub missing: Vec<Ingredient>,
+}
+
+/// An item about to expire, with recipes that would use it up.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ExpiringItem {
+    pub item: PantryItemWithId,
+    pub recipes: Vec<RecipeWithId>,
+}
+
+fn is_empty(quantity: &Quantity) -> bool {
+    quantity.value() <= 0.0
+}
+
+/// Takes the ingredients out of the pantry `stock`, using up the items that
+/// expire first. Only items of the same name in units that add up with the
+/// ingredient's are used. Ingredients without an amount are left alone.
+pub fn use_ingredients(stock: &[PantryItemWithId], ingredients: &[Ingredient]) -> PantryUse {
+    let mut stock = stock.to_vec();
+    stock.sort_by_key(|item| (item.data.expires.is_none(), item.data.expires, item.id));
+    let mut changed = Vec::new();
+    let mut missing = Vec::new();
+    for ingredient in ingredients {
+        if is_empty(&ingredient.quantity) {
+            continue;
+        }
+        let key = ingredient_key(&ingredient.name);
+        let mut needed = ingredient.quantity.clone();
+        for (index, item) in stock.iter_mut().enumerate() {
+            if is_empty(&needed) {
+                break;
+            }
+            if ingredient_key(&item.data.name) != key || is_empty(&item.data.quantity) {
+                continue;
+            }
+            let (Some(left), Some(short)) = (
+                item.data.quantity.subtract(&needed),
+                needed.subtract(&item.data.quantity),
+            ) else {
+                continue;
+            };
+            item.data.quantity = left;
+            needed = short;
+            if !changed.contains(&index) {
+                changed.push(index);
+            }
+        }
+        if !is_empty(&needed) {
+            missing.push(Ingredient {
+                quantity: needed,
+                ..ingredient.clone()
+            });
+        }
+    }
+    let (used_up, updated) = changed
+        .into_iter()
+        .map(|index| stock[index].clone())
+        .partition(|item| is_empty(&item.data.quantity));
+    PantryUse {
+        updated,
+        used_up,
+        missing,
+    }
+}
+
+/// Whether the ingredients call for the item, or a kind of it, as `red
+/// onion` is a kind of `onion`.
+pub fn uses_item(ingredients: &[Ingredient], item: &PantryItem) -> bool {
+    let key = ingredient_key(&item.name);
+    let kind = format!(" {key}");
+    ingredients.iter().any(|ingredient| {
+        let name = ingredient_key(&ingredient.name);
+        name == key || name.ends_with(&kind)
+    })
+}
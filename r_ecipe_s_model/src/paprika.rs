@@ -0,0 +1,102 @@
+use crate::diet::DietaryFlags;
+use crate::ingredient_parser::ingredient_from_line;
+use crate::record::{description, field_text, numbered_steps, unmapped_notes, RecordError};
+use crate::schema_org::servings;
+use crate::tags::normalise_tags;
+use crate::timing::Timing;
+use crate::Recipe;
+use serde_json::Value;
+
+/// Extension of a Paprika export, a zip archive of `.paprikarecipe` files.
+pub const PAPRIKA_ARCHIVE_EXTENSION: &str = "paprikarecipes";
+/// Extension of a single gzipped Paprika recipe.
+pub const PAPRIKA_RECIPE_EXTENSION: &str = "paprikarecipe";
+
+const MAPPED: &[&str] = &[
+    "name",
+    "ingredients",
+    "directions",
+    "description",
+    "servings",
+    "notes",
+    "on_favorites",
+    "categories",
+];
+
+/// Bookkeeping and photo fields that mean nothing outside Paprika.
+const IGNORED: &[&str] = &[
+    "uid",
+    "hash",
+    "created",
+    "photo",
+    "photo_hash",
+    "photo_large",
+    "photo_data",
+    "photos",
+    "image_url",
+    "scale",
+    "in_trash",
+    "is_pinned",
+    "on_grocery_list",
+];
+
+/// Converts a Paprika recipe, once un-gzipped, into a [`Recipe`]. Ingredient
+/// lines go through the ingredient parser, each line of the directions becomes
+/// a step and categories become tags. Paprika's notes, and any field without a
+/// counterpart such as the source or times, are listed under notes.
+pub fn recipe_from_paprika(record: &Value) -> Result<Recipe, RecordError> {
+    let record = record.as_object().ok_or(RecordError::NotAnObject)?;
+    let name = record
+        .get("name")
+        .and_then(field_text)
+        .ok_or(RecordError::MissingName)?;
+    let ingredients = record
+        .get("ingredients")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(ingredient_from_line)
+        .collect();
+    let method = record
+        .get("directions")
+        .and_then(Value::as_str)
+        .map(numbered_steps)
+        .unwrap_or_default();
+    let mut notes = record
+        .get("notes")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    notes.extend(unmapped_notes(record, MAPPED, IGNORED));
+
+    Ok(Recipe {
+        name,
+        ingredients,
+        description: description(
+            record.get("description").and_then(field_text),
+            &method,
+            &notes,
+        ),
+        liked: record
+            .get("on_favorites")
+            .and_then(Value::as_bool)
+            .filter(|favourite| *favourite),
+        embedding: None,
+        servings: record.get("servings").and_then(servings),
+        tags: match record.get("categories") {
+            Some(Value::Array(categories)) => {
+                normalise_tags(categories.iter().filter_map(field_text))
+            }
+            _ => Vec::new(),
+        },
+        dietary: DietaryFlags::default(),
+        images: Vec::new(),
+        timing: Timing::default(),
+        equipment: Vec::new(),
+    })
+}
@@ -0,0 +1,116 @@
+use crate::RecipeWithId;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+use time::{Date, Duration};
+
+time::serde::format_description!(pub(crate) iso_date, Date, "[year]-[month]-[day]");
+
+/// Longest range of days the meal plan can be read for at once.
+pub const MAX_PLAN_DAYS: i64 = 366;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum MealSlot {
+    Breakfast,
+    Lunch,
+    Dinner,
+    Snack,
+}
+
+impl MealSlot {
+    /// Every slot, in the order meals are eaten through the day.
+    pub const ALL: [MealSlot; 4] = [
+        MealSlot::Breakfast,
+        MealSlot::Lunch,
+        MealSlot::Dinner,
+        MealSlot::Snack,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MealSlot::Breakfast => "breakfast",
+            MealSlot::Lunch => "lunch",
+            MealSlot::Dinner => "dinner",
+            MealSlot::Snack => "snack",
+        }
+    }
+}
+
+impl Display for MealSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MealSlot {
+    type Err = String;
+
+    fn from_str(slot: &str) -> Result<Self, Self::Err> {
+        MealSlot::ALL
+            .into_iter()
+            .find(|known| known.as_str() == slot)
+            .ok_or_else(|| format!("Unknown meal slot: {slot}"))
+    }
+}
+
+/// A recipe planned for a meal on a day.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlannedMeal {
+    #[serde(with = "iso_date")]
+    pub date: Date,
+    pub slot: MealSlot,
+    /// `None` once the recipe has been deleted.
+    pub recipe_id: Option<i64>,
+    /// Name of the recipe, kept so the meal still reads sensibly after the
+    /// recipe is deleted.
+    #[serde(default)]
+    pub recipe_name: String,
+    /// Servings to cook when different from the recipe's own.
+    #[serde(default)]
+    pub servings: Option<u32>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlannedMealWithId {
+    pub id: i64,
+    pub data: PlannedMeal,
+}
+
+/// A planned meal with its recipe, when the recipe still exists.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlannedRecipe {
+    pub meal: PlannedMealWithId,
+    pub recipe: Option<RecipeWithId>,
+}
+
+/// Days from `from` to `to`, both included.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlanRange {
+    #[serde(with = "iso_date")]
+    pub from: Date,
+    #[serde(with = "iso_date")]
+    pub to: Date,
+}
+
+impl PlanRange {
+    /// The week, Monday to Sunday, that `date` falls in.
+    pub fn week_of(date: Date) -> Self {
+        let from = date - Duration::days(i64::from(date.weekday().number_days_from_monday()));
+        PlanRange {
+            from,
+            to: from + Duration::days(6),
+        }
+    }
+
+    pub fn days(self) -> impl Iterator<Item = Date> {
+        let PlanRange { from, to } = self;
+        std::iter::successors(Some(from), |day| day.next_day()).take_while(move |day| *day <= to)
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.from <= self.to && (self.to - self.from).whole_days() < MAX_PLAN_DAYS
+    }
+}
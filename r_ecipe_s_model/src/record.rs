@@ -0,0 +1,121 @@
+use crate::method::list_item;
+use serde_json::{Map, Value};
+use std::fmt::Display;
+
+/// Why a recipe record exported by another app couldn't be imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    NotAnObject,
+    MissingName,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::NotAnObject => write!(f, "Recipe record is not a JSON object"),
+            RecordError::MissingName => write!(f, "Recipe record has no name"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// The trimmed text of a string or number field, if it isn't empty.
+pub(crate) fn field_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Renders a field of any shape as one line of text. Objects in lists are
+/// represented by their name or text, other objects by their fields. Empty
+/// values, `false` and zero, which apps use for "not set", render as nothing.
+fn render(value: &Value) -> Option<String> {
+    let rendered = match value {
+        Value::Null | Value::Bool(false) => None,
+        Value::Bool(true) => Some("yes".to_string()),
+        Value::Number(number) if number.as_f64() == Some(0.0) => None,
+        Value::Number(_) | Value::String(_) => field_text(value),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::Object(object) => ["name", "title", "text"]
+                        .iter()
+                        .find_map(|key| object.get(*key).and_then(field_text)),
+                    item => render(item),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Value::Object(object) => Some(
+            object
+                .iter()
+                .filter_map(|(key, value)| Some(format!("{}: {}", label(key), render(value)?)))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    };
+    rendered.filter(|text| !text.is_empty())
+}
+
+/// Turns a field name such as `source_url` or `prepTime` into a label such as
+/// `Source url` or `Prep time`.
+fn label(key: &str) -> String {
+    let mut label = String::with_capacity(key.len() + 4);
+    let mut previous_lowercase = false;
+    for c in key.chars() {
+        if c == '_' || c == '-' {
+            label.push(' ');
+        } else if c.is_uppercase() && previous_lowercase {
+            label.push(' ');
+            label.extend(c.to_lowercase());
+        } else if label.is_empty() {
+            label.extend(c.to_uppercase());
+        } else {
+            label.extend(c.to_lowercase());
+        }
+        previous_lowercase = c.is_lowercase();
+    }
+    label
+}
+
+/// Lists every field of `record` that isn't `mapped` or `ignored` as a
+/// markdown list item such as `- Source url: https://…`, so that nothing the
+/// other app recorded is lost.
+pub(crate) fn unmapped_notes(
+    record: &Map<String, Value>,
+    mapped: &[&str],
+    ignored: &[&str],
+) -> Vec<String> {
+    record
+        .iter()
+        .filter(|(key, _)| !mapped.contains(&key.as_str()) && !ignored.contains(&key.as_str()))
+        .filter_map(|(key, value)| Some(format!("- {}: {}", label(key), render(value)?)))
+        .collect()
+}
+
+/// Numbers each non-empty line of a free-text method as a markdown list item.
+pub(crate) fn numbered_steps(method: &str) -> Vec<String> {
+    method
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("1. {}", list_item(line).unwrap_or(line)))
+        .collect()
+}
+
+/// Puts together a recipe description from a summary, the method and notes,
+/// the notes going under their own header at the end.
+pub(crate) fn description(summary: Option<String>, method: &[String], notes: &[String]) -> String {
+    let mut parts = summary.into_iter().collect::<Vec<_>>();
+    if !method.is_empty() {
+        parts.push(method.join("\n"));
+    }
+    if !notes.is_empty() {
+        parts.push(format!("### Notes\n{}", notes.join("\n")));
+    }
+    parts.join("\n\n")
+}
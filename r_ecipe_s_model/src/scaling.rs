@@ -0,0 +1,200 @@
+use crate::component::Component;
+use crate::{Ingredient, Quantity, Recipe};
+
+/// Number of servings assumed for recipes that don't record a yield, so that
+/// scaling them multiplies the recipe as a whole batch.
+pub const DEFAULT_SERVINGS: u32 = 1;
+
+const TSP_PER_TBSP: f64 = 3.0;
+const TSP_PER_CUP: f64 = 48.0;
+const GRAM_PER_KG: f64 = 1000.0;
+const ML_PER_L: f64 = 1000.0;
+
+impl Quantity {
+    fn map(self, f: impl Fn(f64) -> f64) -> Quantity {
+        match self {
+            Quantity::Count(count) => Quantity::Count(f(count)),
+            Quantity::Tsp(tsp) => Quantity::Tsp(f(tsp)),
+            Quantity::Tbsp(tbsp) => Quantity::Tbsp(f(tbsp)),
+            Quantity::Cup(cup) => Quantity::Cup(f(cup)),
+            Quantity::Gram(gram) => Quantity::Gram(f(gram)),
+            Quantity::Kg(kg) => Quantity::Kg(f(kg)),
+            Quantity::Ml(ml) => Quantity::Ml(f(ml)),
+            Quantity::L(l) => Quantity::L(f(l)),
+            Quantity::Other { amount, unit } => match amount.trim().parse::<f64>() {
+                Ok(number) => Quantity::Other {
+                    amount: crate::format_amount(f(number)),
+                    unit,
+                },
+                Err(_) => Quantity::Other { amount, unit },
+            },
+        }
+    }
+
+    /// Multiplies the amount by `factor` and promotes the result to the most
+    /// readable unit, e.g. `Tsp(16.)` scaled by 3 becomes `Cup(1.)`. Free-text
+    /// amounts are only scaled when they are a plain number.
+    pub fn scale(self, factor: f64) -> Quantity {
+        self.map(|amount| amount * factor).promote()
+    }
+
+    /// Re-expresses the quantity in the largest unit of the same measuring
+    /// system in which the amount is at least one. Small amounts are demoted
+    /// the same way, so half a tablespoon becomes 1.5 tsp.
+    pub fn promote(self) -> Quantity {
+        match self {
+            Quantity::Count(_) | Quantity::Other { .. } => self,
+            Quantity::Tsp(_) | Quantity::Tbsp(_) | Quantity::Cup(_) => {
+                let tsp = match self {
+                    Quantity::Tbsp(tbsp) => tbsp * TSP_PER_TBSP,
+                    Quantity::Cup(cup) => cup * TSP_PER_CUP,
+                    other => other.value(),
+                };
+                if tsp >= TSP_PER_CUP {
+                    Quantity::Cup(tsp / TSP_PER_CUP)
+                } else if tsp >= TSP_PER_TBSP {
+                    Quantity::Tbsp(tsp / TSP_PER_TBSP)
+                } else {
+                    Quantity::Tsp(tsp)
+                }
+            }
+            Quantity::Gram(_) | Quantity::Kg(_) => {
+                let gram = match self {
+                    Quantity::Kg(kg) => kg * GRAM_PER_KG,
+                    other => other.value(),
+                };
+                if gram >= GRAM_PER_KG {
+                    Quantity::Kg(gram / GRAM_PER_KG)
+                } else {
+                    Quantity::Gram(gram)
+                }
+            }
+            Quantity::Ml(_) | Quantity::L(_) => {
+                let ml = match self {
+                    Quantity::L(l) => l * ML_PER_L,
+                    other => other.value(),
+                };
+                if ml >= ML_PER_L {
+                    Quantity::L(ml / ML_PER_L)
+                } else {
+                    Quantity::Ml(ml)
+                }
+            }
+        }
+    }
+}
+
+impl Quantity {
+    /// The quantity in the smallest unit of its measuring system, e.g. a cup
+    /// as 48 tsp.
+    fn in_base_unit(&self) -> Quantity {
+        match self {
+            Quantity::Tbsp(tbsp) => Quantity::Tsp(tbsp * TSP_PER_TBSP),
+            Quantity::Cup(cup) => Quantity::Tsp(cup * TSP_PER_CUP),
+            Quantity::Kg(kg) => Quantity::Gram(kg * GRAM_PER_KG),
+            Quantity::L(l) => Quantity::Ml(l * ML_PER_L),
+            other => other.clone(),
+        }
+    }
+
+    /// Adds two quantities measured in the same system, e.g. a cup and two
+    /// tbsp, promoting the sum to the most readable unit. Free-text amounts
+    /// only add up when both are plain numbers of the same unit. Returns
+    /// `None` when the quantities can't be added.
+    pub fn add(&self, other: &Quantity) -> Option<Quantity> {
+        match (self.in_base_unit(), other.in_base_unit()) {
+            (
+                Quantity::Other { amount, unit },
+                Quantity::Other {
+                    amount: other_amount,
+                    unit: other_unit,
+                },
+            ) => {
+                let amount = amount.trim().parse::<f64>().ok()?;
+                let other_amount = other_amount.trim().parse::<f64>().ok()?;
+                (unit.trim().eq_ignore_ascii_case(other_unit.trim())).then(|| Quantity::Other {
+                    amount: crate::format_amount(amount + other_amount),
+                    unit,
+                })
+            }
+            (base, other_base) if base.label() == other_base.label() => {
+                let other_value = other_base.value();
+                Some(base.map(|value| value + other_value).promote())
+            }
+            _ => None,
+        }
+    }
+
+    /// Takes `other` away, stopping at zero. Returns `None` when the
+    /// quantities can't be added.
+    pub fn subtract(&self, other: &Quantity) -> Option<Quantity> {
+        let left = self.add(&other.clone().map(|amount| -amount))?;
+        Some(left.map(|amount| amount.max(0.0)).promote())
+    }
+
+    /// How many times `other` goes into the quantity, e.g. 4 for a cup and a
+    /// quarter cup. Returns `None` when the quantities can't be added or
+    /// `other` is nothing.
+    pub fn ratio(&self, other: &Quantity) -> Option<f64> {
+        let (value, other_value) = match (self.in_base_unit(), other.in_base_unit()) {
+            (
+                Quantity::Other { amount, unit },
+                Quantity::Other {
+                    amount: other_amount,
+                    unit: other_unit,
+                },
+            ) if unit.trim().eq_ignore_ascii_case(other_unit.trim()) => (
+                amount.trim().parse::<f64>().ok()?,
+                other_amount.trim().parse::<f64>().ok()?,
+            ),
+            (Quantity::Other { .. }, _) | (_, Quantity::Other { .. }) => return None,
+            (base, other_base) if base.label() == other_base.label() => {
+                (base.value(), other_base.value())
+            }
+            _ => return None,
+        };
+        (other_value > 0.0).then(|| value / other_value)
+    }
+}
+
+impl Ingredient {
+    pub fn scaled(&self, factor: f64) -> Ingredient {
+        Ingredient {
+            quantity: self.quantity.clone().scale(factor),
+            component: self.component.map(|component| Component {
+                portion: component.portion.scale(factor),
+                ..component
+            }),
+            ..self.clone()
+        }
+    }
+}
+
+pub fn scale_ingredients(ingredients: &[Ingredient], factor: f64) -> Vec<Ingredient> {
+    ingredients
+        .iter()
+        .map(|ingredient| ingredient.scaled(factor))
+        .collect()
+}
+
+/// The factor by which ingredient quantities of a recipe yielding
+/// `recipe_servings` must be multiplied for it to yield `servings`.
+pub fn scale_factor(recipe_servings: Option<u32>, servings: u32) -> f64 {
+    let base = recipe_servings.unwrap_or(DEFAULT_SERVINGS).max(1);
+    f64::from(servings) / f64::from(base)
+}
+
+impl Recipe {
+    pub fn scale_factor(&self, servings: u32) -> f64 {
+        scale_factor(self.servings, servings)
+    }
+
+    pub fn scaled(&self, servings: u32) -> Recipe {
+        let factor = self.scale_factor(servings);
+        Recipe {
+            ingredients: scale_ingredients(&self.ingredients, factor),
+            servings: Some(servings),
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use crate::diet::DietaryFlags;
+use crate::ingredient_parser::ingredient_from_line;
+use crate::method::split_method;
+use crate::tags::{normalise_tags, split_tags};
+use crate::timing::{find_oven_temperature, Timing};
+use crate::Recipe;
+use serde_json::{json, Map, Value};
+use std::fmt::Display;
+
+pub const SCHEMA_ORG_CONTEXT: &str = "https://schema.org";
+pub const JSON_LD_CONTENT_TYPE: &str = "application/ld+json";
+const RECIPE_TYPE: &str = "Recipe";
+
+#[derive(Debug)]
+pub enum JsonLdError {
+    Json(serde_json::Error),
+    NoRecipe,
+}
+
+impl Display for JsonLdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonLdError::Json(err) => write!(f, "Invalid JSON-LD: {err}"),
+            JsonLdError::NoRecipe => write!(f, "No schema.org Recipe found in document"),
+        }
+    }
+}
+
+impl std::error::Error for JsonLdError {}
+
+impl From<serde_json::Error> for JsonLdError {
+    fn from(err: serde_json::Error) -> Self {
+        JsonLdError::Json(err)
+    }
+}
+
+/// Parses an ISO 8601 duration such as `PT1H30M` into whole minutes, rounding
+/// any seconds up.
+pub fn parse_duration(duration: &str) -> Option<u32> {
+    let rest = duration.trim().strip_prefix(['P', 'p'])?;
+    let mut seconds = 0u64;
+    let mut in_time = false;
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c.to_ascii_uppercase() {
+            'T' => in_time = true,
+            c if c.is_ascii_digit() || c == '.' => number.push(c),
+            unit => {
+                let value = number.parse::<f64>().ok()?;
+                number.clear();
+                let unit_seconds = match (unit, in_time) {
+                    ('D', false) => 86_400.0,
+                    ('W', false) => 604_800.0,
+                    ('H', true) => 3_600.0,
+                    ('M', true) => 60.0,
+                    ('S', true) => 1.0,
+                    _ => return None,
+                };
+                seconds = seconds.checked_add((value * unit_seconds).round() as u64)?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    u32::try_from(seconds.div_ceil(60)).ok()
+}
+
+/// Formats minutes as an ISO 8601 duration, e.g. 90 becomes `PT1H30M`.
+pub fn format_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("PT{minutes}M"),
+        (hours, 0) => format!("PT{hours}H"),
+        (hours, minutes) => format!("PT{hours}H{minutes}M"),
+    }
+}
+
+/// Formats minutes for people, e.g. 90 becomes `1 h 30 min`.
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+fn is_recipe(node: &Value) -> bool {
+    match node.get("@type") {
+        Some(Value::String(node_type)) => node_type == RECIPE_TYPE,
+        Some(Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(RECIPE_TYPE)),
+        _ => false,
+    }
+}
+
+/// Finds the first schema.org Recipe node in a JSON-LD value, looking through
+/// top level arrays and `@graph` collections.
+pub fn find_recipe_node(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(nodes) => nodes.iter().find_map(find_recipe_node),
+        Value::Object(_) if is_recipe(value) => Some(value),
+        Value::Object(object) => object.get("@graph").and_then(find_recipe_node),
+        _ => None,
+    }
+}
+
+/// Returns the contents of every `<script type="application/ld+json">` tag in
+/// an HTML document.
+pub fn extract_json_ld_scripts(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets valid for slicing the original
+    let lowercase = html.to_ascii_lowercase();
+    let mut scripts = Vec::new();
+    let mut position = 0;
+    while let Some(start) = lowercase[position..].find("<script") {
+        let tag_start = position + start;
+        let Some(tag_len) = lowercase[tag_start..].find('>') else {
+            break;
+        };
+        let content_start = tag_start + tag_len + 1;
+        let Some(content_len) = lowercase[content_start..].find("</script") else {
+            break;
+        };
+        let content_end = content_start + content_len;
+        if lowercase[tag_start..content_start].contains(JSON_LD_CONTENT_TYPE) {
+            scripts.push(html[content_start..content_end].trim());
+        }
+        position = content_end;
+    }
+    scripts
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(values) => values.iter().find_map(text),
+        Value::Object(object) => object.get("text").or(object.get("name")).and_then(text),
+        _ => None,
+    }
+}
+
+fn instructions_markdown(value: &Value, markdown: &mut Vec<String>) {
+    match value {
+        Value::Array(steps) => steps
+            .iter()
+            .for_each(|step| instructions_markdown(step, markdown)),
+        Value::Object(object) if object.contains_key("itemListElement") => {
+            if let Some(name) = object.get("name").and_then(text) {
+                markdown.push(format!("### {name}"));
+            }
+            if let Some(steps) = object.get("itemListElement") {
+                instructions_markdown(steps, markdown);
+            }
+        }
+        Value::String(steps) => steps
+            .lines()
+            .map(str::trim)
+            .filter(|step| !step.is_empty())
+            .for_each(|step| markdown.push(format!("1. {step}"))),
+        step => {
+            if let Some(step) = text(step) {
+                markdown.push(format!("1. {step}"));
+            }
+        }
+    }
+}
+
+/// Takes the first whole number out of a `recipeYield`, which may be a
+/// number, a string such as `"4 servings"` or a list of either.
+pub(crate) fn servings(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(number) => number.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Value::Array(values) => values.iter().find_map(servings),
+        Value::String(text) => text
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|digits| !digits.is_empty())
+            .and_then(|digits| digits.parse::<u32>().ok()),
+        _ => None,
+    }
+    .filter(|servings| *servings > 0)
+}
+
+fn minutes(node: &Value, key: &str) -> Option<u32> {
+    node.get(key)?.as_str().and_then(parse_duration)
+}
+
+/// Converts a schema.org Recipe node into a [`Recipe`]. Ingredient lines go
+/// through the ingredient parser, keeping the raw line as the name when it
+/// can't be parsed. The schema.org description and instructions are combined
+/// into the markdown description, keywords and categories become tags and
+/// tools become equipment. The oven temperature is the first one mentioned in
+/// the instructions.
+pub fn recipe_from_json_ld(node: &Value) -> Result<Recipe, JsonLdError> {
+    let node = find_recipe_node(node).ok_or(JsonLdError::NoRecipe)?;
+    let name = node.get("name").and_then(text).unwrap_or_default();
+    let ingredients = node
+        .get("recipeIngredient")
+        .or(node.get("ingredients"))
+        .map(|lines| match lines {
+            Value::Array(lines) => lines.iter().filter_map(text).collect::<Vec<_>>(),
+            line => text(line).into_iter().collect(),
+        })
+        .unwrap_or_default()
+        .iter()
+        .map(|line| ingredient_from_line(line))
+        .collect();
+
+    let mut description = Vec::new();
+    if let Some(summary) = node.get("description").and_then(text) {
+        description.push(summary);
+    }
+    let mut steps = Vec::new();
+    if let Some(instructions) = node.get("recipeInstructions") {
+        instructions_markdown(instructions, &mut steps);
+    }
+    let timing = Timing {
+        prep_minutes: minutes(node, "prepTime"),
+        cook_minutes: minutes(node, "cookTime"),
+        total_minutes: minutes(node, "totalTime"),
+        oven: steps.iter().find_map(|step| find_oven_temperature(step)),
+    };
+    if !steps.is_empty() {
+        description.push(steps.join("\n"));
+    }
+
+    let tags = ["keywords", "recipeCategory"]
+        .iter()
+        .filter_map(|key| node.get(*key))
+        .flat_map(|value| match value {
+            Value::Array(values) => values.iter().filter_map(text).collect::<Vec<_>>(),
+            value => text(value).into_iter().collect(),
+        })
+        .flat_map(|list| split_tags(&list))
+        .collect::<Vec<_>>();
+    let equipment = match node.get("tool") {
+        Some(Value::Array(tools)) => tools.iter().filter_map(text).collect(),
+        Some(tool) => text(tool).into_iter().collect(),
+        None => Vec::new(),
+    };
+
+    Ok(Recipe {
+        name,
+        ingredients,
+        description: description.join("\n\n"),
+        liked: None,
+        embedding: None,
+        servings: node.get("recipeYield").and_then(servings),
+        tags: normalise_tags(tags),
+        dietary: DietaryFlags::default(),
+        images: Vec::new(),
+        timing,
+        equipment: normalise_tags(equipment),
+    })
+}
+
+/// Finds a schema.org Recipe in either a JSON-LD document or an HTML page
+/// embedding one.
+pub fn recipe_from_document(document: &str) -> Result<Recipe, JsonLdError> {
+    let trimmed = document.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        let value = serde_json::from_str::<Value>(trimmed)?;
+        return recipe_from_json_ld(&value);
+    }
+    extract_json_ld_scripts(document)
+        .into_iter()
+        .filter_map(|script| serde_json::from_str::<Value>(script).ok())
+        .find_map(|value| recipe_from_json_ld(&value).ok())
+        .ok_or(JsonLdError::NoRecipe)
+}
+
+pub fn recipe_to_json_ld(recipe: &Recipe) -> Value {
+    let mut node = Map::new();
+    node.insert("@context".into(), SCHEMA_ORG_CONTEXT.into());
+    node.insert("@type".into(), RECIPE_TYPE.into());
+    node.insert("name".into(), recipe.name.clone().into());
+    node.insert(
+        "recipeIngredient".into(),
+        recipe
+            .ingredients
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into(),
+    );
+    let (summary, steps) = split_method(&recipe.description);
+    let steps = steps
+        .into_iter()
+        .map(|step| json!({ "@type": "HowToStep", "text": step }))
+        .collect::<Vec<_>>();
+    if let Some(summary) = summary {
+        node.insert("description".into(), summary.into());
+    }
+    node.insert("recipeInstructions".into(), steps.into());
+    if let Some(servings) = recipe.servings {
+        node.insert("recipeYield".into(), format!("{servings} servings").into());
+    }
+    if !recipe.tags.is_empty() {
+        node.insert("keywords".into(), recipe.tags.join(", ").into());
+    }
+    if !recipe.equipment.is_empty() {
+        let tools = recipe
+            .equipment
+            .iter()
+            .map(|name| json!({ "@type": "HowToTool", "name": name }))
+            .collect::<Vec<_>>();
+        node.insert("tool".into(), tools.into());
+    }
+    let times = [
+        ("prepTime", recipe.timing.prep_minutes),
+        ("cookTime", recipe.timing.cook_minutes),
+        ("totalTime", recipe.timing.total()),
+    ];
+    for (key, minutes) in times {
+        if let Some(minutes) = minutes {
+            node.insert(key.into(), format_duration(minutes).into());
+        }
+    }
+    Value::Object(node)
+}
@@ -0,0 +1,296 @@
+use crate::plan::PlanRange;
+use crate::{Ingredient, Quantity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Aisle for ingredients that no keyword matches.
+pub const OTHER_AISLE: &str = "other";
+
+/// Aisles in the order a shop is usually walked through, with the words in
+/// an ingredient's name that place it there.
+const AISLES: [(&str, &[&str]); 9] = [
+    (
+        "produce",
+        &[
+            "apple",
+            "avocado",
+            "banana",
+            "basil",
+            "bean sprout",
+            "berries",
+            "berry",
+            "broccoli",
+            "cabbage",
+            "carrot",
+            "cauliflower",
+            "celery",
+            "chili",
+            "chilli",
+            "coriander",
+            "courgette",
+            "cucumber",
+            "garlic",
+            "ginger",
+            "herb",
+            "kale",
+            "leek",
+            "lemon",
+            "lettuce",
+            "lime",
+            "mint",
+            "mushroom",
+            "onion",
+            "orange",
+            "parsley",
+            "pepper",
+            "potato",
+            "rosemary",
+            "salad",
+            "scallion",
+            "shallot",
+            "spinach",
+            "thyme",
+            "tomato",
+            "zucchini",
+        ],
+    ),
+    (
+        "bakery",
+        &[
+            "bagel", "baguette", "bread", "bun", "roll", "tortilla", "wrap",
+        ],
+    ),
+    (
+        "meat & fish",
+        &[
+            "bacon", "beef", "chicken", "chorizo", "cod", "fish", "ham", "lamb", "mince", "pork",
+            "prawn", "salmon", "sausage", "shrimp", "tuna", "turkey",
+        ],
+    ),
+    (
+        "dairy & eggs",
+        &[
+            "butter",
+            "cheese",
+            "cream",
+            "egg",
+            "feta",
+            "milk",
+            "mozzarella",
+            "parmesan",
+            "ricotta",
+            "yoghurt",
+            "yogurt",
+        ],
+    ),
+    ("frozen", &["ice cream", "pea"]),
+    (
+        "spices",
+        &[
+            "black pepper",
+            "cinnamon",
+            "cumin",
+            "curry",
+            "nutmeg",
+            "oregano",
+            "paprika",
+            "salt",
+            "spice",
+            "turmeric",
+            "vanilla",
+        ],
+    ),
+    (
+        "pantry",
+        &[
+            "baking",
+            "bean",
+            "chickpea",
+            "chocolate",
+            "cocoa",
+            "couscous",
+            "flour",
+            "honey",
+            "lentil",
+            "mustard",
+            "noodle",
+            "nut",
+            "oat",
+            "oil",
+            "pasta",
+            "peanut butter",
+            "rice",
+            "sauce",
+            "stock",
+            "sugar",
+            "syrup",
+            "vinegar",
+            "yeast",
+        ],
+    ),
+    (
+        "drinks",
+        &["beer", "coffee", "juice", "tea", "water", "wine"],
+    ),
+    (OTHER_AISLE, &[]),
+];
+
+/// Every aisle, in shop order.
+pub fn aisles() -> impl Iterator<Item = &'static str> {
+    AISLES.iter().map(|(aisle, _)| *aisle)
+}
+
+fn aisle_position(aisle: &str) -> usize {
+    aisles()
+        .position(|known| known == aisle)
+        .unwrap_or(AISLES.len())
+}
+
+/// Where the last mention of `keyword` as whole words in `name` ends,
+/// allowing for plurals.
+fn mention_end(name: &str, keyword: &str) -> Option<usize> {
+    name.match_indices(keyword)
+        .filter(|(start, _)| {
+            let before = name[..*start].chars().next_back();
+            let after = name[start + keyword.len()..]
+                .trim_start_matches("es")
+                .trim_start_matches('s')
+                .chars()
+                .next();
+            !before.is_some_and(char::is_alphabetic) && !after.is_some_and(char::is_alphabetic)
+        })
+        .map(|(start, _)| start + keyword.len())
+        .last()
+}
+
+/// Guesses the aisle of an ingredient from its name. The word mentioned
+/// last decides, as in `chicken stock`, and longer keywords win ties, as in
+/// `black pepper`. Anything frozen goes to the freezer.
+pub fn aisle_of(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if mention_end(&name, "frozen").is_some() {
+        return "frozen";
+    }
+    AISLES
+        .iter()
+        .flat_map(|(aisle, keywords)| {
+            let name = &name;
+            keywords.iter().filter_map(move |keyword| {
+                mention_end(name, keyword).map(|end| ((end, keyword.len()), *aisle))
+            })
+        })
+        .max_by_key(|(rank, _)| *rank)
+        .map_or(OTHER_AISLE, |(_, aisle)| aisle)
+}
+
+/// Key under which ingredients are matched, so that `Onions ` and `onions`
+/// end up on one line.
+pub fn ingredient_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// A line on a shopping list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShoppingItem {
+    pub name: String,
+    /// Amounts to buy, one for each unit that couldn't be added to the
+    /// others. Empty when no recipe said how much is needed.
+    #[serde(default)]
+    pub quantities: Vec<Quantity>,
+    pub aisle: String,
+    #[serde(default)]
+    pub checked: bool,
+}
+
+impl ShoppingItem {
+    fn add(&mut self, quantity: Quantity) {
+        if quantity == Quantity::Count(0.0) {
+            return;
+        }
+        for existing in &mut self.quantities {
+            if let Some(sum) = existing.add(&quantity) {
+                *existing = sum;
+                return;
+            }
+        }
+        self.quantities.push(quantity);
+    }
+
+    /// The amounts joined for display, e.g. `2 cup + 3`.
+    pub fn amount(&self) -> String {
+        self.quantities
+            .iter()
+            .map(Quantity::to_string)
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShoppingItemWithId {
+    pub id: i64,
+    pub data: ShoppingItem,
+}
+
+/// Merges the ingredients into shopping list items, adding up the amounts
+/// of ingredients with the same name where their units allow. Items come
+/// grouped by aisle in shop order, then by name.
+pub fn shopping_items(ingredients: impl IntoIterator<Item = Ingredient>) -> Vec<ShoppingItem> {
+    let mut items: Vec<ShoppingItem> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for ingredient in ingredients {
+        let name = ingredient.name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let index = *by_key.entry(ingredient_key(name)).or_insert_with(|| {
+            items.push(ShoppingItem {
+                name: name.to_string(),
+                aisle: aisle_of(name).to_string(),
+                ..ShoppingItem::default()
+            });
+            items.len() - 1
+        });
+        items[index].add(ingredient.quantity);
+    }
+    items.sort_by_cached_key(|item| (aisle_position(&item.aisle), ingredient_key(&item.name)));
+    items
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShoppingList {
+    pub name: String,
+    /// Items grouped by aisle in shop order.
+    #[serde(default)]
+    pub items: Vec<ShoppingItemWithId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShoppingListWithId {
+    pub id: i64,
+    pub data: ShoppingList,
+}
+
+/// A recipe to shop for, scaled to `servings` when given.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecipeServings {
+    pub recipe_id: i64,
+    #[serde(default)]
+    pub servings: Option<u32>,
+}
+
+/// What to build a shopping list from: the given recipes, the meals planned
+/// in `range`, or both.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShoppingListRequest {
+    /// Named after the recipes or dates when left out.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub recipes: Vec<RecipeServings>,
+    #[serde(default)]
+    pub range: Option<PlanRange>,
+}
@@ -0,0 +1,75 @@
+use crate::nutrition::food_key;
+use crate::scaling::scale_ingredients;
+use crate::{Ingredient, Quantity};
+use serde::{Deserialize, Serialize};
+
+/// What can stand in for an amount of an ingredient, e.g. for one egg, a
+/// tbsp of ground flax and 3 tbsp of water.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Substitution {
+    pub ingredient: String,
+    /// The amount of the ingredient that `replacement` stands in for.
+    pub per: Quantity,
+    pub replacement: Vec<Ingredient>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SubstitutionWithId {
+    pub id: i64,
+    pub data: Substitution,
+}
+
+/// A substitution suggested for one of a recipe's ingredients.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SuggestedSubstitute {
+    pub substitution: SubstitutionWithId,
+    /// The replacement for the ingredient's amount, or for the
+    /// substitution's `per` when the two amounts can't be compared.
+    pub replacement: Vec<Ingredient>,
+    pub scaled: bool,
+}
+
+impl Substitution {
+    /// Whether the substitution is for the ingredient, or for something it
+    /// is a kind of, as `large eggs` are `eggs`.
+    pub fn applies_to(&self, name: &str) -> bool {
+        let (key, name) = (food_key(&self.ingredient), food_key(name));
+        !key.is_empty() && (name == key || name.ends_with(&format!(" {key}")))
+    }
+
+    /// The replacement for `quantity` of the ingredient, or `None` when it
+    /// isn't measured like `per`.
+    pub fn replacement_for(&self, quantity: &Quantity) -> Option<Vec<Ingredient>> {
+        let factor = quantity.ratio(&self.per)?;
+        Some(scale_ingredients(&self.replacement, factor))
+    }
+}
+
+/// Substitutions for the ingredient, the most specific first, with their
+/// replacements worked out for its amount where possible.
+pub fn suggest_substitutes(
+    ingredient: &Ingredient,
+    substitutions: &[SubstitutionWithId],
+) -> Vec<SuggestedSubstitute> {
+    let mut suggestions = substitutions
+        .iter()
+        .filter(|substitution| substitution.data.applies_to(&ingredient.name))
+        .map(|substitution| {
+            let scaled = substitution.data.replacement_for(&ingredient.quantity);
+            SuggestedSubstitute {
+                substitution: substitution.clone(),
+                scaled: scaled.is_some(),
+                replacement: scaled.unwrap_or_else(|| substitution.data.replacement.clone()),
+            }
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort_by_key(|suggestion| {
+        (
+            std::cmp::Reverse(food_key(&suggestion.substitution.data.ingredient).len()),
+            !suggestion.scaled,
+        )
+    });
+    suggestions
+}
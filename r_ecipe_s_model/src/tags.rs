@@ -0,0 +1,29 @@
+/// Normalises a tag as typed, so that ` Weeknight ` and `weeknight` are the
+/// same tag: trimmed, lowercase and with runs of whitespace collapsed.
+pub fn normalise_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Normalises tags, dropping empty ones and duplicates but keeping the order
+/// they were given in.
+pub fn normalise_tags<S: AsRef<str>>(tags: impl IntoIterator<Item = S>) -> Vec<String> {
+    let mut normalised: Vec<String> = Vec::new();
+    for tag in tags {
+        if let Some(tag) = normalise_tag(tag.as_ref()) {
+            if !normalised.contains(&tag) {
+                normalised.push(tag);
+            }
+        }
+    }
+    normalised
+}
+
+/// Reads a comma separated list of tags, such as schema.org `keywords`.
+pub fn split_tags(list: &str) -> Vec<String> {
+    normalise_tags(list.split(','))
+}
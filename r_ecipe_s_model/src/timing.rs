@@ -0,0 +1,262 @@
+use crate::schema_org::format_minutes;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Longest time a recipe may take, a month, to leave room for ferments and
+/// cures.
+pub const MAX_MINUTES: u32 = 31 * 24 * 60;
+/// Oven temperatures outside this range, in °C, are taken to be mistakes.
+pub const OVEN_RANGE_CELSIUS: (f64, f64) = (30.0, 320.0);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 2] = [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<TemperatureUnit> {
+        let name = name.trim();
+        TemperatureUnit::ALL
+            .into_iter()
+            .find(|unit| unit.as_str().eq_ignore_ascii_case(name) || unit.symbol() == name)
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+impl Display for TemperatureUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An oven temperature in the unit the recipe gives it in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Temperature {
+    pub degrees: u32,
+    pub unit: TemperatureUnit,
+}
+
+impl Temperature {
+    pub fn celsius(&self) -> f64 {
+        match self.unit {
+            TemperatureUnit::Celsius => f64::from(self.degrees),
+            TemperatureUnit::Fahrenheit => (f64::from(self.degrees) - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    /// The temperature converted to the unit, to the nearest degree.
+    pub fn in_unit(&self, unit: TemperatureUnit) -> Temperature {
+        let degrees = match unit {
+            TemperatureUnit::Celsius => self.celsius(),
+            TemperatureUnit::Fahrenheit => self.celsius() * 9.0 / 5.0 + 32.0,
+        };
+        Temperature {
+            degrees: degrees.round().max(0.0) as u32,
+            unit,
+        }
+    }
+
+    pub fn is_oven_temperature(&self) -> bool {
+        let (min, max) = OVEN_RANGE_CELSIUS;
+        (min..=max).contains(&self.celsius())
+    }
+}
+
+/// Formats as e.g. `180 °C`.
+impl Display for Temperature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.degrees, self.unit.symbol())
+    }
+}
+
+/// How long a recipe takes and how hot the oven should be.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Timing {
+    #[serde(default)]
+    pub prep_minutes: Option<u32>,
+    #[serde(default)]
+    pub cook_minutes: Option<u32>,
+    /// Worked out from the prep and cook times when not given, as the
+    /// recipe is saved.
+    #[serde(default)]
+    pub total_minutes: Option<u32>,
+    #[serde(default)]
+    pub oven: Option<Temperature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimingError {
+    TooLong(u32),
+    /// The total time is shorter than the prep and cook times together.
+    TotalTooShort {
+        total: u32,
+        parts: u32,
+    },
+    OvenTemperature(Temperature),
+}
+
+impl Display for TimingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimingError::TooLong(minutes) => write!(
+                f,
+                "{} is longer than the most a recipe may take, {}",
+                format_minutes(*minutes),
+                format_minutes(MAX_MINUTES)
+            ),
+            TimingError::TotalTooShort { total, parts } => write!(
+                f,
+                "Total time of {} is shorter than the prep and cook times, {}",
+                format_minutes(*total),
+                format_minutes(*parts)
+            ),
+            TimingError::OvenTemperature(temperature) => {
+                let (min, max) = OVEN_RANGE_CELSIUS;
+                write!(
+                    f,
+                    "Oven temperature of {temperature} isn't between {min} °C and {max} °C"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimingError {}
+
+impl Timing {
+    /// The total time, or else the prep and cook times added up.
+    pub fn total(&self) -> Option<u32> {
+        self.total_minutes.or_else(|| self.parts())
+    }
+
+    fn parts(&self) -> Option<u32> {
+        match (self.prep_minutes, self.cook_minutes) {
+            (None, None) => None,
+            (prep, cook) => Some(prep.unwrap_or(0) + cook.unwrap_or(0)),
+        }
+    }
+
+    /// The timing with its total filled in from the prep and cook times.
+    pub fn with_total(self) -> Timing {
+        Timing {
+            total_minutes: self.total(),
+            ..self
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), TimingError> {
+        for minutes in [self.prep_minutes, self.cook_minutes, self.total_minutes]
+            .into_iter()
+            .flatten()
+        {
+            if minutes > MAX_MINUTES {
+                return Err(TimingError::TooLong(minutes));
+            }
+        }
+        if let (Some(total), Some(parts)) = (self.total_minutes, self.parts()) {
+            if total < parts {
+                return Err(TimingError::TotalTooShort { total, parts });
+            }
+        }
+        match self.oven {
+            Some(oven) if !oven.is_oven_temperature() => Err(TimingError::OvenTemperature(oven)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Timing::default()
+    }
+
+    /// Labelled times and temperature for display, e.g. `("Prep", "15 min")`
+    /// or `("Oven", "180 °C / 356 °F")`, leaving out those not given.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let times = [
+            ("Prep", self.prep_minutes),
+            ("Cook", self.cook_minutes),
+            ("Total", self.total()),
+        ]
+        .into_iter()
+        .filter_map(|(label, minutes)| Some((label, format_minutes(minutes?))));
+        let oven = self.oven.map(|oven| {
+            let other = match oven.unit {
+                TemperatureUnit::Celsius => TemperatureUnit::Fahrenheit,
+                TemperatureUnit::Fahrenheit => TemperatureUnit::Celsius,
+            };
+            ("Oven", format!("{oven} / {}", oven.in_unit(other)))
+        });
+        times.chain(oven).collect()
+    }
+}
+
+/// Finds the first oven temperature written in text, such as `180°C`,
+/// `350 °F` or `200 degrees C`.
+pub fn find_oven_temperature(text: &str) -> Option<Temperature> {
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let from_digits = &rest[start..];
+        let digits_len = from_digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(from_digits.len());
+        let after = &from_digits[digits_len..];
+        rest = after;
+        let preceded_by_digit_or_letter = text[..text.len() - from_digits.len()]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '.');
+        if preceded_by_digit_or_letter {
+            continue;
+        }
+        let Ok(degrees) = from_digits[..digits_len].parse::<u32>() else {
+            continue;
+        };
+        let unit_text = after.trim_start();
+        let unit_text = unit_text
+            .strip_prefix('°')
+            .or_else(|| unit_text.strip_prefix("º"))
+            .or_else(|| {
+                ["degrees", "degree", "deg"].iter().find_map(|word| {
+                    let head = unit_text.get(..word.len())?;
+                    head.eq_ignore_ascii_case(word)
+                        .then(|| &unit_text[word.len()..])
+                })
+            })
+            .map(str::trim_start)
+            .unwrap_or(unit_text);
+        let unit = match unit_text.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => TemperatureUnit::Celsius,
+            Some('F') => TemperatureUnit::Fahrenheit,
+            _ => continue,
+        };
+        let word = unit_text
+            .split(|c: char| !c.is_alphabetic())
+            .next()
+            .unwrap_or_default();
+        let is_unit = word.len() == 1
+            || word.eq_ignore_ascii_case("celsius")
+            || word.eq_ignore_ascii_case("fahrenheit");
+        let temperature = Temperature { degrees, unit };
+        if is_unit && temperature.is_oven_temperature() {
+            return Some(temperature);
+        }
+    }
+    None
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Someone who can sign in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    /// Admins can add accounts and replace the whole library.
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// Signs in, or creates an account.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// What signing in or refreshing gives. The access token is sent as a bearer
+/// token until it expires, then the refresh token gets a new pair.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Seconds until the access token expires.
+    pub expires_in: i64,
+    pub user: User,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
@@ -0,0 +1,180 @@
+use r_ecipe_s_model::component::{
+    check_components, expand_components, Component, ComponentError, Portion,
+};
+use r_ecipe_s_model::{Ingredient, Quantity, Recipe};
+use std::collections::HashMap;
+
+fn ingredient(name: &str, grams: f64) -> Ingredient {
+    Ingredient {
+        name: name.to_string(),
+        quantity: Quantity::Gram(grams),
+        ..Ingredient::default()
+    }
+}
+
+fn component(recipe_id: i64, portion: Portion) -> Ingredient {
+    Ingredient {
+        name: format!("recipe {recipe_id}"),
+        quantity: Quantity::Count(0.0),
+        component: Some(Component { recipe_id, portion }),
+        ..Ingredient::default()
+    }
+}
+
+fn recipe(servings: Option<u32>, ingredients: Vec<Ingredient>) -> Recipe {
+    Recipe {
+        servings,
+        ingredients,
+        ..Recipe::default()
+    }
+}
+
+fn amounts(ingredients: &[Ingredient]) -> Vec<(&str, Quantity)> {
+    ingredients
+        .iter()
+        .map(|ingredient| (ingredient.name.as_str(), ingredient.quantity.clone()))
+        .collect()
+}
+
+#[test]
+fn rejects_a_recipe_using_itself() {
+    let ingredients = vec![component(1, Portion::Fraction(1.0))];
+    let recipes = HashMap::from([(1, recipe(None, ingredients.clone()))]);
+    assert_eq!(
+        check_components(Some(1), &ingredients, &recipes),
+        Err(ComponentError::Cycle(vec![1, 1]))
+    );
+}
+
+#[test]
+fn rejects_recipes_using_each_other_in_a_cycle() {
+    let ingredients = vec![
+        ingredient("flour", 100.0),
+        component(2, Portion::Fraction(1.0)),
+    ];
+    let recipes = HashMap::from([
+        (1, recipe(None, ingredients.clone())),
+        (2, recipe(None, vec![component(3, Portion::Fraction(1.0))])),
+        (3, recipe(None, vec![component(1, Portion::Fraction(0.5))])),
+    ]);
+    assert_eq!(
+        check_components(Some(1), &ingredients, &recipes),
+        Err(ComponentError::Cycle(vec![1, 2, 3, 1]))
+    );
+    // A new recipe using the cycle is rejected, and expanding it stops.
+    let new = vec![component(2, Portion::Fraction(1.0))];
+    assert_eq!(
+        check_components(None, &new, &recipes),
+        Err(ComponentError::Cycle(vec![2, 3, 1, 2]))
+    );
+    assert!(expand_components(&new, &recipes).is_err());
+}
+
+#[test]
+fn accepts_a_component_used_through_two_others() {
+    let ingredients = vec![
+        component(2, Portion::Fraction(1.0)),
+        component(3, Portion::Fraction(1.0)),
+    ];
+    let recipes = HashMap::from([
+        (
+            2,
+            recipe(
+                None,
+                vec![
+                    ingredient("tomato", 300.0),
+                    component(4, Portion::Fraction(0.5)),
+                ],
+            ),
+        ),
+        (
+            3,
+            recipe(
+                None,
+                vec![
+                    ingredient("cheese", 200.0),
+                    component(4, Portion::Fraction(0.25)),
+                ],
+            ),
+        ),
+        (4, recipe(None, vec![ingredient("flour", 400.0)])),
+    ]);
+    assert_eq!(check_components(Some(1), &ingredients, &recipes), Ok(()));
+    let expanded = expand_components(&ingredients, &recipes).expect("no cycle");
+    assert_eq!(
+        amounts(&expanded),
+        vec![
+            ("tomato", Quantity::Gram(300.0)),
+            ("flour", Quantity::Gram(200.0)),
+            ("cheese", Quantity::Gram(200.0)),
+            ("flour", Quantity::Gram(100.0)),
+        ]
+    );
+}
+
+#[test]
+fn scales_components_of_components() {
+    // Two of the dough's four servings, and the dough uses half the starter.
+    let ingredients = vec![
+        ingredient("tomato", 150.0),
+        component(2, Portion::Servings(2.0)),
+    ];
+    let recipes = HashMap::from([
+        (
+            2,
+            recipe(
+                Some(4),
+                vec![
+                    ingredient("flour", 400.0),
+                    component(3, Portion::Fraction(0.5)),
+                ],
+            ),
+        ),
+        (
+            3,
+            recipe(
+                Some(1),
+                vec![ingredient("flour", 200.0), ingredient("water", 100.0)],
+            ),
+        ),
+    ]);
+    assert_eq!(check_components(None, &ingredients, &recipes), Ok(()));
+    let expanded = expand_components(&ingredients, &recipes).expect("no cycle");
+    assert_eq!(
+        amounts(&expanded),
+        vec![
+            ("tomato", Quantity::Gram(150.0)),
+            ("flour", Quantity::Gram(200.0)),
+            ("flour", Quantity::Gram(50.0)),
+            ("water", Quantity::Gram(25.0)),
+        ]
+    );
+}
+
+#[test]
+fn rejects_missing_components() {
+    let ingredients = vec![component(9, Portion::Fraction(1.0))];
+    assert_eq!(
+        check_components(None, &ingredients, &HashMap::new()),
+        Err(ComponentError::Missing(9))
+    );
+}
+
+#[test]
+fn rejects_portions_that_are_not_positive() {
+    let recipes = HashMap::from([(2, recipe(Some(4), vec![ingredient("flour", 400.0)]))]);
+    for portion in [
+        Portion::Fraction(-0.5),
+        Portion::Servings(0.0),
+        Portion::Servings(-2.0),
+        Portion::Fraction(f64::NAN),
+        Portion::Servings(f64::INFINITY),
+    ] {
+        let ingredients = vec![component(2, portion)];
+        assert_eq!(
+            check_components(None, &ingredients, &recipes),
+            Err(ComponentError::InvalidPortion(2)),
+            "{portion:?}"
+        );
+    }
+}
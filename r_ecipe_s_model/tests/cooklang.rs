@@ -0,0 +1,28 @@
+use r_ecipe_s_model::cooklang::{parse_cooklang, to_cooklang};
+use r_ecipe_s_model::Recipe;
+
+fn round_trip(source: &str) -> (Recipe, Recipe) {
+    let parsed = parse_cooklang(source, None).expect("source should parse");
+    let written = to_cooklang(&parsed);
+    let reparsed = parse_cooklang(&written, None)
+        .unwrap_or_else(|err| panic!("failed to parse written recipe {written:?}: {err}"));
+    (parsed, reparsed)
+}
+
+#[test]
+fn keeps_amounts_exact_through_a_round_trip() {
+    let source = ">> title: Scones\nMix @flour{1/3%cup} with @eggs{2/3} and @salt{}.";
+    let (parsed, reparsed) = round_trip(source);
+    assert_eq!(parsed.ingredients.len(), 3);
+    assert_eq!(reparsed.ingredients, parsed.ingredients);
+    assert_eq!(parsed.ingredients[0].quantity.value(), 1.0 / 3.0);
+    assert_eq!(parsed.ingredients[1].quantity.value(), 2.0 / 3.0);
+}
+
+#[test]
+fn keeps_amounts_as_text_through_a_round_trip() {
+    let source = ">> title: Stew\nAdd @stock{2-3%cups} and @thyme{a sprig}.";
+    let (parsed, reparsed) = round_trip(source);
+    assert_eq!(parsed.ingredients.len(), 2);
+    assert_eq!(reparsed.ingredients, parsed.ingredients);
+}
@@ -0,0 +1,78 @@
+use r_ecipe_s_model::ingredient_parser::{parse_ingredient, parse_ingredients, ParseError};
+use r_ecipe_s_model::{serde_json, Ingredient};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Case {
+    line: String,
+    expected: Ingredient,
+}
+
+#[derive(Deserialize)]
+struct InvalidCase {
+    line: String,
+    error: String,
+}
+
+const CASES: &str = include_str!("fixtures/ingredient_lines.json");
+const INVALID_CASES: &str = include_str!("fixtures/invalid_ingredient_lines.json");
+
+fn assert_same(line: &str, parsed: &Ingredient, expected: &Ingredient) {
+    assert_eq!(parsed.name, expected.name, "name for line {line:?}");
+    assert_eq!(
+        parsed.preparation, expected.preparation,
+        "preparation for line {line:?}"
+    );
+    assert_eq!(
+        parsed.quantity.label(),
+        expected.quantity.label(),
+        "unit for line {line:?}"
+    );
+    assert!(
+        (parsed.quantity.value() - expected.quantity.value()).abs() < 1e-6,
+        "amount for line {line:?}: {} != {}",
+        parsed.quantity.value(),
+        expected.quantity.value()
+    );
+}
+
+#[test]
+fn parses_fixture_lines() {
+    let cases: Vec<Case> = serde_json::from_str(CASES).expect("invalid fixture file");
+    for Case { line, expected } in &cases {
+        let parsed =
+            parse_ingredient(line).unwrap_or_else(|err| panic!("failed to parse {line:?}: {err}"));
+        assert_same(line, &parsed, expected);
+    }
+}
+
+#[test]
+fn rejects_invalid_fixture_lines() {
+    let cases: Vec<InvalidCase> =
+        serde_json::from_str(INVALID_CASES).expect("invalid fixture file");
+    for InvalidCase { line, error } in &cases {
+        let err = parse_ingredient(line).expect_err(line);
+        let kind = match err {
+            ParseError::Empty => "Empty",
+            ParseError::MissingName(_) => "MissingName",
+        };
+        assert_eq!(kind, error, "error for line {line:?}");
+    }
+}
+
+#[test]
+fn parses_pasted_list_skipping_blank_lines() {
+    let cases: Vec<Case> = serde_json::from_str(CASES).expect("invalid fixture file");
+    let pasted = cases
+        .iter()
+        .map(|case| case.line.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let parsed = parse_ingredients(&pasted);
+    assert_eq!(parsed.len(), cases.len());
+    for ((line, result), case) in parsed.iter().zip(&cases) {
+        assert_eq!(*line, case.line);
+        let ingredient = result.as_ref().expect("fixture line should parse");
+        assert_same(line, ingredient, &case.expected);
+    }
+}
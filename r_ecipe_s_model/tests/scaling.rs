@@ -0,0 +1,110 @@
+use r_ecipe_s_model::scaling::scale_factor;
+use r_ecipe_s_model::{Ingredient, Quantity, Recipe};
+
+fn other(amount: &str, unit: &str) -> Quantity {
+    Quantity::Other {
+        amount: amount.to_string(),
+        unit: unit.to_string(),
+    }
+}
+
+#[test]
+fn promotes_to_the_largest_readable_unit() {
+    assert_eq!(Quantity::Tsp(6.0).promote(), Quantity::Tbsp(2.0));
+    assert_eq!(Quantity::Tbsp(32.0).promote(), Quantity::Cup(2.0));
+    assert_eq!(Quantity::Gram(1500.0).promote(), Quantity::Kg(1.5));
+    assert_eq!(Quantity::Ml(2000.0).promote(), Quantity::L(2.0));
+}
+
+#[test]
+fn demotes_small_amounts() {
+    assert_eq!(Quantity::Tbsp(0.5).promote(), Quantity::Tsp(1.5));
+    assert_eq!(Quantity::Cup(0.25).promote(), Quantity::Tbsp(4.0));
+    assert_eq!(Quantity::Kg(0.25).promote(), Quantity::Gram(250.0));
+    assert_eq!(Quantity::L(0.5).promote(), Quantity::Ml(500.0));
+}
+
+#[test]
+fn leaves_counts_and_free_text_as_they_are() {
+    assert_eq!(Quantity::Count(2000.0).promote(), Quantity::Count(2000.0));
+    assert_eq!(other("1000", "pinch").promote(), other("1000", "pinch"));
+}
+
+#[test]
+fn scales_and_promotes() {
+    assert_eq!(Quantity::Tsp(16.0).scale(3.0), Quantity::Cup(1.0));
+    assert_eq!(Quantity::Kg(1.0).scale(0.5), Quantity::Gram(500.0));
+    assert_eq!(other("2", "pinch").scale(1.5), other("3", "pinch"));
+    assert_eq!(other("a few", "").scale(2.0), other("a few", ""));
+}
+
+#[test]
+fn adds_compatible_units() {
+    assert_eq!(
+        Quantity::Cup(1.0).add(&Quantity::Tbsp(8.0)),
+        Some(Quantity::Cup(1.5))
+    );
+    assert_eq!(
+        Quantity::Gram(600.0).add(&Quantity::Kg(0.4)),
+        Some(Quantity::Kg(1.0))
+    );
+    assert_eq!(
+        other("1", "Pinch").add(&other("2", "pinch")),
+        Some(other("3", "Pinch"))
+    );
+}
+
+#[test]
+fn keeps_incompatible_units_apart() {
+    assert_eq!(Quantity::Cup(1.0).add(&Quantity::Gram(100.0)), None);
+    assert_eq!(Quantity::Ml(100.0).add(&Quantity::Tsp(1.0)), None);
+    assert_eq!(Quantity::Count(1.0).add(&Quantity::Gram(1.0)), None);
+    assert_eq!(other("1", "pinch").add(&other("1", "sprig")), None);
+    assert_eq!(other("some", "pinch").add(&other("1", "pinch")), None);
+}
+
+#[test]
+fn subtracts_down_to_zero() {
+    assert_eq!(
+        Quantity::Kg(1.0).subtract(&Quantity::Gram(250.0)),
+        Some(Quantity::Gram(750.0))
+    );
+    assert_eq!(
+        Quantity::Tbsp(1.0).subtract(&Quantity::Cup(1.0)),
+        Some(Quantity::Tsp(0.0))
+    );
+    assert_eq!(Quantity::L(1.0).subtract(&Quantity::Gram(1.0)), None);
+}
+
+#[test]
+fn ratio_of_quantities() {
+    assert_eq!(Quantity::Cup(1.0).ratio(&Quantity::Tbsp(4.0)), Some(4.0));
+    assert_eq!(Quantity::Kg(1.0).ratio(&Quantity::Gram(250.0)), Some(4.0));
+    assert_eq!(other("3", "pinch").ratio(&other("1.5", "Pinch")), Some(2.0));
+    assert_eq!(Quantity::Cup(1.0).ratio(&Quantity::Tsp(0.0)), None);
+    assert_eq!(Quantity::Cup(1.0).ratio(&Quantity::Gram(1.0)), None);
+    assert_eq!(other("1", "pinch").ratio(&Quantity::Count(1.0)), None);
+}
+
+#[test]
+fn scale_factor_assumes_one_serving_when_unknown() {
+    assert_eq!(scale_factor(Some(4), 6), 1.5);
+    assert_eq!(scale_factor(None, 3), 3.0);
+    assert_eq!(scale_factor(Some(0), 2), 2.0);
+}
+
+#[test]
+fn scales_recipes_to_servings() {
+    let recipe = Recipe {
+        servings: Some(2),
+        ingredients: vec![Ingredient {
+            name: "flour".to_string(),
+            quantity: Quantity::Gram(250.0),
+            ..Ingredient::default()
+        }],
+        ..Recipe::default()
+    };
+    let scaled = recipe.scaled(8);
+    assert_eq!(scaled.servings, Some(8));
+    assert_eq!(scaled.ingredients[0].quantity, Quantity::Kg(1.0));
+}
@@ -0,0 +1,26 @@
+use r_ecipe_s_model::schema_org::parse_duration;
+
+#[test]
+fn parses_durations_into_minutes() {
+    assert_eq!(parse_duration("PT1H30M"), Some(90));
+    assert_eq!(parse_duration("P1DT2H"), Some(26 * 60));
+    assert_eq!(parse_duration("pt0.5h"), Some(30));
+    assert_eq!(parse_duration("PT90S"), Some(2));
+}
+
+#[test]
+fn rejects_malformed_durations() {
+    assert_eq!(parse_duration("1H30M"), None);
+    assert_eq!(parse_duration("PT1H30"), None);
+    assert_eq!(parse_duration("P1H"), None);
+    assert_eq!(parse_duration("PT1X"), None);
+}
+
+#[test]
+fn rejects_durations_that_overflow() {
+    assert_eq!(
+        parse_duration("P99999999999999999999DT99999999999999999999H"),
+        None
+    );
+    assert_eq!(parse_duration("P9999999999W"), None);
+}
@@ -0,0 +1,109 @@
+use r_ecipe_s_model::shopping::{aisle_of, ingredient_key, shopping_items, OTHER_AISLE};
+use r_ecipe_s_model::{Ingredient, Quantity};
+
+fn ingredient(name: &str, quantity: Quantity) -> Ingredient {
+    Ingredient {
+        name: name.to_string(),
+        quantity,
+        ..Ingredient::default()
+    }
+}
+
+#[test]
+fn places_ingredients_in_aisles() {
+    assert_eq!(aisle_of("Red Onions"), "produce");
+    assert_eq!(aisle_of("eggs"), "dairy & eggs");
+    assert_eq!(aisle_of("plain flour"), "pantry");
+    assert_eq!(aisle_of("dragon fruit"), OTHER_AISLE);
+}
+
+#[test]
+fn last_mentioned_word_decides_the_aisle() {
+    assert_eq!(aisle_of("chicken stock"), "pantry");
+    assert_eq!(aisle_of("stock cubes for chicken"), "meat & fish");
+}
+
+#[test]
+fn longer_keywords_win_ties() {
+    assert_eq!(aisle_of("black pepper"), "spices");
+    assert_eq!(aisle_of("green pepper"), "produce");
+    assert_eq!(aisle_of("peanut butter"), "pantry");
+}
+
+#[test]
+fn only_whole_words_match() {
+    assert_eq!(aisle_of("mixed nuts"), "pantry");
+    assert_eq!(aisle_of("walnuts"), OTHER_AISLE);
+    assert_eq!(aisle_of("teaspoon"), OTHER_AISLE);
+}
+
+#[test]
+fn anything_frozen_goes_to_the_freezer() {
+    assert_eq!(aisle_of("frozen spinach"), "frozen");
+    assert_eq!(aisle_of("peas"), "frozen");
+}
+
+#[test]
+fn keys_ignore_case_and_spacing() {
+    assert_eq!(ingredient_key("  Spring   Onions "), "spring onions");
+}
+
+#[test]
+fn merges_ingredients_with_the_same_name() {
+    let items = shopping_items([
+        ingredient("Onions ", Quantity::Count(2.0)),
+        ingredient("onions", Quantity::Count(1.0)),
+        ingredient("flour", Quantity::Gram(500.0)),
+        ingredient("Flour", Quantity::Kg(1.0)),
+    ]);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].name, "Onions");
+    assert_eq!(items[0].quantities, vec![Quantity::Count(3.0)]);
+    assert_eq!(items[1].name, "flour");
+    assert_eq!(items[1].quantities, vec![Quantity::Kg(1.5)]);
+}
+
+#[test]
+fn keeps_incompatible_amounts_apart() {
+    let items = shopping_items([
+        ingredient("milk", Quantity::Cup(1.0)),
+        ingredient("milk", Quantity::Ml(100.0)),
+        ingredient("milk", Quantity::Tbsp(8.0)),
+    ]);
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].quantities,
+        vec![Quantity::Cup(1.5), Quantity::Ml(100.0)]
+    );
+}
+
+#[test]
+fn leaves_out_missing_amounts_and_names() {
+    let items = shopping_items([
+        ingredient("salt", Quantity::Count(0.0)),
+        ingredient("  ", Quantity::Count(1.0)),
+    ]);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "salt");
+    assert!(items[0].quantities.is_empty());
+}
+
+#[test]
+fn groups_items_by_aisle_in_shop_order() {
+    let items = shopping_items([
+        ingredient("wine", Quantity::Count(1.0)),
+        ingredient("rice", Quantity::Count(1.0)),
+        ingredient("milk", Quantity::Count(1.0)),
+        ingredient("basil", Quantity::Count(1.0)),
+        ingredient("apples", Quantity::Count(1.0)),
+        ingredient("dragon fruit", Quantity::Count(1.0)),
+    ]);
+    let names = items
+        .iter()
+        .map(|item| item.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["apples", "basil", "milk", "rice", "wine", "dragon fruit"]
+    );
+}
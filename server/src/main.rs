@@ -1,19 +1,36 @@
 //use actix_web::{dev::*, http::header, middleware::Logger, web::Data, *};
 use axum::http::StatusCode;
-use axum::Router;
+use axum::{Extension, Router};
 use futures::future::abortable;
 use futures::FutureExt;
 use log::info;
 use qdrant_client::prelude::{QdrantClient, QdrantClientConfig};
-use r_ecipe_s_backend::auth::BearerValidation;
+use r_ecipe_s_backend::auth::{JwtKeys, MIN_SECRET_LENGTH};
 use std::net::{AddrParseError, SocketAddr};
 use std::sync::Arc;
 use tracing::warn;
 
 use axum::routing::get_service;
 use r_ecipe_s_backend::app_config;
+use r_ecipe_s_backend::catalogue_service::{CatalogueAccess, CatalogueService};
+use r_ecipe_s_backend::collection_service::{CollectionAccess, CollectionService};
+use r_ecipe_s_backend::cook_log_service::{CookLogAccess, CookLogService};
+use r_ecipe_s_backend::cookbook_service::CookbookService;
+use r_ecipe_s_backend::equipment_service::{EquipmentAccess, EquipmentService};
+use r_ecipe_s_backend::export_service::ExportService;
+use r_ecipe_s_backend::image_service::{ImageAccess, ImageService};
+use r_ecipe_s_backend::image_store::{ImageStore, LocalImageStore};
+use r_ecipe_s_backend::import_service::ImportService;
+use r_ecipe_s_backend::meal_plan_service::{MealPlanAccess, MealPlanService};
+use r_ecipe_s_backend::nutrition_service::{NutritionAccess, NutritionService};
+use r_ecipe_s_backend::pantry_service::{PantryAccess, PantryService};
+use r_ecipe_s_backend::price_service::{PriceAccess, PriceService};
 use r_ecipe_s_backend::recipe_service::{RecipeAccess, RecipeService};
-use r_ecipe_s_backend::{db, search_indexer};
+use r_ecipe_s_backend::shopping_service::{ShoppingAccess, ShoppingService};
+use r_ecipe_s_backend::substitution_service::{SubstitutionAccess, SubstitutionService};
+use r_ecipe_s_backend::tag_service::{TagAccess, TagService};
+use r_ecipe_s_backend::user_service::{self, UserAccess, UserService};
+use r_ecipe_s_backend::{cook_log_service, db, recipe_service, search_indexer};
 use std::env;
 use thiserror::Error as ThisError;
 use tower_http::{
@@ -31,6 +48,12 @@ enum Error {
     Confg(#[from] config::ConfigError),
     #[error("r_ecipe_s database error {0}")]
     DB(#[from] db::Error),
+    #[error("r_ecipe_s recipe error {0}")]
+    Recipe(#[from] recipe_service::Error),
+    #[error("r_ecipe_s user error {0}")]
+    User(#[from] user_service::Error),
+    #[error("r_ecipe_s cook log error {0}")]
+    CookLog(#[from] cook_log_service::Error),
     #[error("r_ecipe_s search indexing error {0}")]
     SearchIndexer(#[from] search_indexer::ContextError),
     #[error("Failed to parse address from connection config: {0}")]
@@ -62,18 +85,51 @@ async fn main() -> Result<()> {
         db_config,
         search_config,
         vector_search_config,
+        image_config,
     }: app_config::AppConfig = app_config::AppConfig::load("config/config.toml")?;
     info!("Running migrations: {db_config:?}");
     let db_access = Arc::new(db::DbMigrator::new(&db_config).await?.migrate().await?);
     info!("Migrations successfully run!");
+    let image_store: Arc<dyn ImageStore> = Arc::new(LocalImageStore::new(&image_config.path)?);
     env::set_current_dir("../frontend_ls")?;
     info!("set directory");
 
     std::env::set_var("RUST_LOG", "axum=info,sqlx=warn");
-    let api_key = std::env::var("API_KEY").expect("API_KEY  environment variable is not set");
-    let bearer_validation = Arc::new(BearerValidation::new(&api_key));
+    let jwt_secret = std::env::var("R_ECIPE_S_JWT_SECRET")
+        .expect("R_ECIPE_S_JWT_SECRET environment variable is not set");
+    if jwt_secret.len() < MIN_SECRET_LENGTH {
+        return Err(Error::Message(format!(
+            "R_ECIPE_S_JWT_SECRET must be at least {MIN_SECRET_LENGTH} bytes long"
+        )));
+    }
+    let jwt_keys = Arc::new(JwtKeys::new(&jwt_secret));
     let host_port = http_config.connection_string();
     let recipe_access = Arc::new(RecipeAccess::new(&db_access));
+    let classified = recipe_access.classify_all().await?;
+    info!("Updated dietary flags of {classified} recipes");
+    let tag_access = Arc::new(TagAccess::new(&db_access));
+    let equipment_access = Arc::new(EquipmentAccess::new(&db_access));
+    let collection_access = Arc::new(CollectionAccess::new(&db_access));
+    let meal_plan_access = Arc::new(MealPlanAccess::new(&db_access));
+    let shopping_access = Arc::new(ShoppingAccess::new(&db_access));
+    let pantry_access = Arc::new(PantryAccess::new(&db_access));
+    let nutrition_access = Arc::new(NutritionAccess::new(&db_access));
+    let substitution_access = Arc::new(SubstitutionAccess::new(&db_access));
+    let catalogue_access = Arc::new(CatalogueAccess::new(&db_access));
+    let price_access = Arc::new(PriceAccess::new(&db_access));
+    let cook_log_access = Arc::new(CookLogAccess::new(&db_access));
+    let moved = cook_log_access.move_photos(image_store.as_ref()).await?;
+    info!("Moved {moved} cook log photos to the image store");
+    let image_access = Arc::new(ImageAccess::new(&db_access));
+    let user_access = Arc::new(UserAccess::new(&db_access));
+    if let (Ok(username), Ok(password)) = (
+        std::env::var("R_ECIPE_S_ADMIN_USERNAME"),
+        std::env::var("R_ECIPE_S_ADMIN_PASSWORD"),
+    ) {
+        if user_access.ensure_user(username, password).await? {
+            info!("Created the first user from the environment");
+        }
+    }
 
     let vector_client = Arc::new(
         QdrantClient::new(Some(QdrantClientConfig::from_url(&format!(
@@ -88,12 +144,35 @@ async fn main() -> Result<()> {
     let app = Router::new()
         .nest(
             "/api/v1",
-            Router::new().bind_recipe_routes(
-                &recipe_access,
-                &search_config,
-                &vector_client,
-                &bearer_validation,
-            ),
+            Router::new()
+                .bind_user_routes(&user_access, &jwt_keys)
+                .bind_recipe_routes(&recipe_access, &image_store, &search_config, &vector_client)
+                .bind_import_routes(&recipe_access, &image_store)
+                .bind_export_routes(&recipe_access)
+                .bind_cookbook_routes(&recipe_access, &collection_access)
+                .bind_tag_routes(&tag_access)
+                .bind_equipment_routes(&equipment_access)
+                .bind_collection_routes(&collection_access, &recipe_access)
+                .bind_meal_plan_routes(
+                    &meal_plan_access,
+                    &recipe_access,
+                    &user_access,
+                    &jwt_keys,
+                    &http_config,
+                )
+                .bind_shopping_routes(&shopping_access, &meal_plan_access, &recipe_access)
+                .bind_pantry_routes(
+                    &pantry_access,
+                    &recipe_access,
+                    &search_config,
+                    &vector_client,
+                )
+                .bind_nutrition_routes(&nutrition_access, &recipe_access)
+                .bind_substitution_routes(&substitution_access)
+                .bind_catalogue_routes(&catalogue_access, &recipe_access, &search_config)
+                .bind_price_routes(&price_access, &recipe_access)
+                .bind_cook_log_routes(&cook_log_access, &recipe_access, &image_store)
+                .bind_image_routes(&image_access, &image_store, &recipe_access),
         )
         .nest(
             "/static",
@@ -124,6 +203,7 @@ async fn main() -> Result<()> {
                 )
             }),
         ))
+        .layer(Extension(jwt_keys))
         .layer(TraceLayer::new_for_http());
     tracing::info!("Successfully bound server to {}", host_port);
     let http_server = axum::Server::bind(&sock_addr).serve(app.into_make_service());